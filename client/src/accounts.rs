@@ -0,0 +1,67 @@
+use anchor_lang::{error::ErrorCode, prelude::Pubkey, AccountDeserialize, Discriminator};
+use escrow::{Escrow, OrderBook, OrderBookPage};
+use solana_rpc_client::rpc_client::RpcClient;
+
+use crate::{order_book_address, order_book_page_address, Error, Result, ID};
+
+/// Decodes an escrow from its account data, checking its discriminator and layout
+/// Escrows still in an older layout fail to decode until migrated with `migrate_escrow`
+pub fn deserialize_escrow(data: &[u8]) -> Result<Escrow> {
+    if data.len() < 8 || data[..8] != Escrow::DISCRIMINATOR {
+        return Err(
+            anchor_lang::error::Error::from(ErrorCode::AccountDiscriminatorMismatch).into(),
+        );
+    }
+    if data.len() != Escrow::SPACE {
+        return Err(anchor_lang::error::Error::from(ErrorCode::AccountDidNotDeserialize).into());
+    }
+    // RPC account data isn't guaranteed to be aligned for the zero-copy layout
+    Ok(bytemuck::pod_read_unaligned(&data[8..]))
+}
+
+/// Decodes any of the program's Borsh accounts (config, basket, swap, auction, ...) from its account data
+pub fn deserialize_account<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    Ok(T::try_deserialize(&mut &data[..])?)
+}
+
+/// Fetches the data of `address`, which must be owned by the escrow program
+pub fn fetch_data(client: &RpcClient, address: &Pubkey) -> Result<Vec<u8>> {
+    let account = client
+        .get_account_with_commitment(address, client.commitment())?
+        .value
+        .ok_or(Error::AccountNotFound(*address))?;
+    if account.owner != ID {
+        return Err(Error::WrongOwner(*address));
+    }
+    Ok(account.data)
+}
+
+/// Fetches and decodes the escrow at `address`
+pub fn fetch_escrow(client: &RpcClient, address: &Pubkey) -> Result<Escrow> {
+    deserialize_escrow(&fetch_data(client, address)?)
+}
+
+/// Fetches and decodes any of the program's Borsh accounts at `address`
+pub fn fetch_account<T: AccountDeserialize>(client: &RpcClient, address: &Pubkey) -> Result<T> {
+    deserialize_account(&fetch_data(client, address)?)
+}
+
+/// Fetches the escrows listed in the order book of the pair of `mint_a` offered for `mint_b`, walking its pages in order
+/// A pair without an order book lists none, and listings of escrows settled without delisting them last until pruned
+pub fn fetch_order_book(
+    client: &RpcClient,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+) -> Result<Vec<Pubkey>> {
+    let book: OrderBook = match fetch_account(client, &order_book_address(mint_a, mint_b)) {
+        Err(Error::AccountNotFound(_)) => return Ok(Vec::new()),
+        book => book?,
+    };
+    let mut escrows = Vec::new();
+    for index in 0..book.pages {
+        let page: OrderBookPage =
+            fetch_account(client, &order_book_page_address(mint_a, mint_b, index))?;
+        escrows.extend(page.escrows);
+    }
+    Ok(escrows)
+}
@@ -0,0 +1,97 @@
+use std::mem::offset_of;
+
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use escrow::{Escrow, EscrowStatus};
+use solana_account_decoder::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::{Memcmp, RpcFilterType},
+};
+
+use crate::{deserialize_escrow, Result, ID};
+
+/// Offset of `field` in an escrow's account data, past the discriminator
+macro_rules! escrow_offset {
+    ($field:ident) => {
+        8 + offset_of!(Escrow, $field)
+    };
+}
+
+/// Filters matching every escrow in the current layout
+/// Combine with the other filters below to narrow down a `getProgramAccounts` query
+pub fn escrow_filters() -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize(Escrow::SPACE as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &Escrow::DISCRIMINATOR)),
+    ]
+}
+
+/// Matches escrows made by `maker`
+pub fn maker_filter(maker: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(maker),
+        maker.as_ref(),
+    ))
+}
+
+/// Matches escrows whose position is held by `owner`
+pub fn owner_filter(owner: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(owner),
+        owner.as_ref(),
+    ))
+}
+
+/// Matches escrows depositing `mint_a`
+pub fn mint_a_filter(mint_a: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(mint_a),
+        mint_a.as_ref(),
+    ))
+}
+
+/// Matches escrows asking for `mint_b`
+pub fn mint_b_filter(mint_b: &Pubkey) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(mint_b),
+        mint_b.as_ref(),
+    ))
+}
+
+/// Matches escrows at lifecycle stage `status`, e.g. the open ones a taker can fill
+pub fn status_filter(status: EscrowStatus) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(status),
+        &[status as u8],
+    ))
+}
+
+/// Matches escrows filed by their maker under `category`
+pub fn category_filter(category: u16) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        escrow_offset!(category),
+        &category.to_le_bytes(),
+    ))
+}
+
+/// Fetches every escrow matching `filters` on top of `escrow_filters`, with its address
+pub fn fetch_escrows(
+    client: &RpcClient,
+    filters: impl IntoIterator<Item = RpcFilterType>,
+) -> Result<Vec<(Pubkey, Escrow)>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(escrow_filters().into_iter().chain(filters).collect()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            commitment: Some(client.commitment()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    client
+        .get_program_accounts_with_config(&ID, config)?
+        .into_iter()
+        .map(|(address, account)| Ok((address, deserialize_escrow(&account.data)?)))
+        .collect()
+}
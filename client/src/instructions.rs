@@ -0,0 +1,1472 @@
+use anchor_lang::{
+    prelude::{AccountMeta, Pubkey},
+    solana_program::instruction::Instruction,
+    system_program, InstructionData, ToAccountMetas,
+};
+use anchor_spl::associated_token::{
+    self, spl_associated_token_account::instruction::create_associated_token_account_idempotent,
+};
+use escrow::{
+    accounts, helpers::is_native_mint, instruction, Escrow, FeeCurrency, FillAmount, Gate,
+    PaymentLeg, RentDestination, Schedule, Vesting,
+};
+
+use crate::{
+    allowlist_pass_address, ata_address, auto_refund_address, cancel_authority_address,
+    config_address, escrow_address, escrow_address_from_id, event_log_address,
+    fee_override_address, fill_receipt_address, maker_profile_address, order_book_address,
+    order_book_page_address, pair_index_address, payment_options_address, pda_vault_address,
+    reservation_address, trader_stats_address, ID,
+};
+
+/// Fixed keys of an escrow, from which every account its instructions need is derived
+#[derive(Clone, Copy, Debug)]
+pub struct EscrowKeys {
+    pub maker: Pubkey,                 // maker of the escrow
+    pub address_maker: Option<Pubkey>, // maker its address is derived from instead, once moved with `transfer_maker`
+    pub seed: u64,                     // seed the escrow was made with
+    pub seed_id: Option<[u8; 32]>, // seed its address is derived from instead, see `seed_from_id`
+    pub mint_a: Pubkey,            // token deposited by the maker
+    pub mint_b: Pubkey,            // token the maker expects to receive
+    pub token_program_a: Pubkey,   // token program owning mint_a
+    pub token_program_b: Pubkey,   // token program owning mint_b
+    pub pda_vault: bool,           // vault made at the escrow's PDA rather than as its ATA
+    pub indexed: bool,             // counted in its mint pair's index
+}
+
+impl EscrowKeys {
+    /// Address of the escrow
+    pub fn escrow(&self) -> Pubkey {
+        let maker = self.address_maker.unwrap_or(self.maker);
+        match &self.seed_id {
+            Some(seed_id) => escrow_address_from_id(&maker, seed_id),
+            None => escrow_address(&maker, self.seed),
+        }
+    }
+
+    /// Keys of the escrow once `transfer_maker` made `new_maker` its maker, its address staying the same
+    pub fn transferred(&self, new_maker: Pubkey) -> EscrowKeys {
+        EscrowKeys {
+            maker: new_maker,
+            address_maker: Some(self.address_maker.unwrap_or(self.maker)),
+            ..*self
+        }
+    }
+
+    /// Address of the vault holding the escrow's deposit
+    pub fn vault(&self) -> Pubkey {
+        if self.pda_vault {
+            return pda_vault_address(&self.escrow());
+        }
+        ata_address(&self.escrow(), &self.mint_a, &self.token_program_a)
+    }
+
+    /// Index of the escrow's mint pair, `None` unless the escrow is indexed
+    fn pair_index(&self) -> Option<Pubkey> {
+        self.indexed
+            .then(|| pair_index_address(&self.mint_a, &self.mint_b))
+    }
+
+    /// `owner`'s token account for mint_a, `None` for wrapped SOL which is paid in lamports
+    fn ata_a(&self, owner: &Pubkey) -> Option<Pubkey> {
+        (!is_native_mint(&self.mint_a))
+            .then(|| ata_address(owner, &self.mint_a, &self.token_program_a))
+    }
+
+    /// `owner`'s token account for mint_b, `None` for wrapped SOL which is paid in lamports
+    fn ata_b(&self, owner: &Pubkey) -> Option<Pubkey> {
+        (!is_native_mint(&self.mint_b))
+            .then(|| ata_address(owner, &self.mint_b, &self.token_program_b))
+    }
+}
+
+/// Optional terms of a new escrow, none of them set by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MakeOptions {
+    pub expiry: i64,                        // deadline to take the escrow, 0 for none
+    pub taker: Option<Pubkey>,              // only account allowed to take the escrow
+    pub arbiter: Option<Pubkey>,            // settles disputes over the vault
+    pub hashlock: Option<[u8; 32]>,         // SHA-256 hash of the preimage a taker must reveal
+    pub vesting: Option<Vesting>,           // schedule the taker claims the deposit on
+    pub schedule: Option<Schedule>,         // recurring installments the escrow is taken in
+    pub keep_receipt: bool,                 // keep the escrow as a receipt once settled
+    pub rent_destination: RentDestination,  // receives the rent once closed, split by default
+    pub label: [u8; 32],                    // tag shown by UIs, see `label`, none if zeroed
+    pub maker_profile: bool,                // seeded by the maker profile's offer counter
+    pub payer: Option<Pubkey>,              // pays the rent instead of the maker
+    pub event_log: bool,                    // records the escrow in the program's event log
+    pub template: Option<Pubkey>,           // template whose terms the escrow adopts
+    pub gate: Option<Gate>,                 // holding the taker must prove to fill the escrow
+    pub gatekeeper_network: Option<Pubkey>, // network whose identity pass the taker must hold
+    pub order_book_page: Option<u32>,       // index of the order book page listing the escrow
+    pub category: u16,                      // kind of offer clients filter escrows by, 0 for none
+}
+
+/// Encodes `text` as an escrow label for `MakeOptions`, zero-padded and cut at 32 bytes on a character boundary
+pub fn label(text: &str) -> [u8; 32] {
+    let mut len = text.len().min(32);
+    while !text.is_char_boundary(len) {
+        len -= 1;
+    }
+    let mut label = [0; 32];
+    label[..len].copy_from_slice(&text.as_bytes()[..len]);
+    label
+}
+
+/// Optional accounts of a fill, none of them passed by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TakeOptions {
+    pub referrer: Option<Pubkey>, // receives the referral share of the fee
+    pub membership: Option<(Pubkey, Pubkey)>, // member NFT token account and its metadata
+    pub rent_treasury: Option<Pubkey>, // treasury the escrow sends its rent to, if any
+    pub fill_receipt: bool,       // records the fill in the taker's fill receipt
+    pub event_log: bool,          // records the fill in the program's event log
+    pub fee_override: Option<Pubkey>, // escrow or maker whose fee override is charged
+    pub fee_currency: FeeCurrency, // the config's, for the fee accounts it needs
+    pub trader_stats: bool,       // charges the taker's fee tier and adds the fill to their volume
+    pub reservation: bool, // settles the taker's reservation, required while it holds the escrow
+    pub template: Option<Pubkey>, // template the escrow was made from, required if it was
+    pub allowlisted: bool, // passes the taker's allowlist pass, required by restricted templates
+    pub gate_account: Option<Pubkey>, // taker's token account of the gate mint, required if gated
+    pub gateway_token: Option<Pubkey>, // taker's identity pass, required if the escrow names a gatekeeper network
+    pub chained_seed: Option<u64>, // seed of the escrow chained to it, whose vault receives the proceeds, required if chained
+    pub payment_options: bool, // pays in one of the escrow's payment options, the `mint_b` of the keys being its mint
+    pub payout: Option<Pubkey>, // token account the owner designated for the proceeds, required if they did
+    pub receiver: Option<Pubkey>, // mint_a token account the taker is paid into, their associated token account if none
+    pub source: Option<Pubkey>, // mint_b token account the taker owns or is the delegate of and pays from, their associated token account if none
+    pub order_book_page: Option<u32>, // index of the order book page listing the escrow, required to complete it if listed
+}
+
+/// Appends `remaining` to `instruction`, e.g. the extra accounts of Token-2022 transfer hooks
+pub fn with_remaining_accounts(
+    mut instruction: Instruction,
+    remaining: impl IntoIterator<Item = AccountMeta>,
+) -> Instruction {
+    instruction.accounts.extend(remaining);
+    instruction
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Creates the maker's profile, whose offer counter then seeds their escrows
+pub fn create_profile(maker: &Pubkey) -> Instruction {
+    build(
+        accounts::CreateProfile {
+            maker: *maker,
+            maker_profile: maker_profile_address(maker),
+            system_program: system_program::ID,
+        },
+        instruction::CreateProfile {},
+    )
+}
+
+fn make_accounts(keys: &EscrowKeys, options: MakeOptions) -> accounts::Make {
+    accounts::Make {
+        maker: keys.maker,
+        maker_authority: None,
+        maker_program: None,
+        payer: options.payer.unwrap_or(keys.maker),
+        mint_a: keys.mint_a,
+        mint_b: keys.mint_b,
+        maker_ata_a: keys.ata_a(&keys.maker),
+        config: config_address(),
+        maker_profile: options
+            .maker_profile
+            .then(|| maker_profile_address(&keys.maker)),
+        escrow: keys.escrow(),
+        vault: (!keys.pda_vault).then(|| keys.vault()),
+        pda_vault: keys.pda_vault.then(|| keys.vault()),
+        template: options.template,
+        event_log: options.event_log.then(event_log_address),
+        order_book_page: options
+            .order_book_page
+            .map(|index| order_book_page_address(&keys.mint_a, &keys.mint_b, index)),
+        pair_index: keys.pair_index(),
+        associated_token_program: associated_token::ID,
+        token_program_a: keys.token_program_a,
+        token_program_b: keys.token_program_b,
+        system_program: system_program::ID,
+    }
+}
+
+/// Builds a `Make` instruction signed by the maker
+/// `maker` is unchecked so calling programs can pass their PDAs unsigned, so its meta isn't a signer unless marked as one
+fn build_make(keys: &EscrowKeys, options: MakeOptions, data: impl InstructionData) -> Instruction {
+    let mut instruction = build(make_accounts(keys, options), data);
+    for meta in &mut instruction.accounts {
+        if meta.pubkey == keys.maker {
+            meta.is_signer = true;
+        }
+    }
+    instruction
+}
+
+/// Makes the escrow of `keys`, depositing `deposit` of mint_a for `receive` of mint_b
+pub fn make(keys: &EscrowKeys, deposit: u64, receive: u64, options: MakeOptions) -> Instruction {
+    build_make(
+        keys,
+        options,
+        instruction::Make {
+            seed: keys.seed,
+            seed_id: keys.seed_id,
+            deposit,
+            receive,
+            expiry: options.expiry,
+            taker: options.taker,
+            arbiter: options.arbiter,
+            hashlock: options.hashlock,
+            vesting: options.vesting,
+            schedule: options.schedule,
+            keep_receipt: options.keep_receipt,
+            label: options.label,
+            rent_destination: options.rent_destination,
+            gate: options.gate,
+            gatekeeper_network: options.gatekeeper_network,
+            category: options.category,
+        },
+    )
+}
+
+/// Makes a private escrow, depositing `deposit` of mint_a for terms only known by their `Escrow::terms_hash`
+/// `keys.mint_b` is a placeholder here, e.g. a widely held mint, and the real one when taking it with `take_private`
+/// Only the expiry and taker of `options` apply, along with its accounts
+pub fn make_private(
+    keys: &EscrowKeys,
+    deposit: u64,
+    terms_hash: [u8; 32],
+    options: MakeOptions,
+) -> Instruction {
+    build_make(
+        keys,
+        options,
+        instruction::MakePrivate {
+            seed: keys.seed,
+            seed_id: keys.seed_id,
+            deposit,
+            expiry: options.expiry,
+            taker: options.taker,
+            terms_hash,
+        },
+    )
+}
+
+/// Chains the escrow of `chained` to the open escrow of `keys`, whose `owner` pays for it, asking `receive` of its mint_b
+/// for what the escrow of `keys` asks, until `expiry`
+/// `chained` trades the mint_b of `keys` as its mint_a, under the same maker and with an associated token account vault,
+/// fills of `keys` then pass `TakeOptions::chained_seed`
+pub fn make_chained(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    chained: &EscrowKeys,
+    receive: u64,
+    expiry: i64,
+) -> Instruction {
+    build(
+        accounts::MakeChained {
+            owner: *owner,
+            escrow: keys.escrow(),
+            mint_b: keys.mint_b,
+            chained_mint_b: chained.mint_b,
+            config: config_address(),
+            chained_escrow: chained.escrow(),
+            chained_vault: chained.vault(),
+            associated_token_program: associated_token::ID,
+            token_program_b: keys.token_program_b,
+            chained_token_program: chained.token_program_b,
+            system_program: system_program::ID,
+        },
+        instruction::MakeChained {
+            seed: chained.seed,
+            receive,
+            expiry,
+        },
+    )
+}
+
+/// Creates the associated token account vault of the escrow of `keys` ahead of `make`, paid for by `payer`
+/// Lets a multisig's proposal to make the escrow await its approvals without anyone being able to block it
+pub fn create_vault(keys: &EscrowKeys, payer: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(
+        payer,
+        &keys.escrow(),
+        &keys.mint_a,
+        &keys.token_program_a,
+    )
+}
+
+/// Prices a full fill of the escrow of `keys`, the `Quote` is returned in the simulation's return data
+/// `fee_override` is the escrow or maker whose fee override is quoted, if any
+/// `trader` is the taker whose fee tier is quoted, their trader stats must exist
+/// `template` is the template the escrow was made from, required if it was
+pub fn quote(
+    keys: &EscrowKeys,
+    fee_override: Option<Pubkey>,
+    trader: Option<Pubkey>,
+    template: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::GetQuote {
+            escrow: keys.escrow(),
+            mint_a: keys.mint_a,
+            mint_b: keys.mint_b,
+            config: config_address(),
+            fee_override: fee_override.as_ref().map(fee_override_address),
+            trader_stats: trader.map(|trader| trader_stats_address(&trader, &keys.mint_b)),
+            template,
+        },
+        instruction::Quote {},
+    )
+}
+
+/// Creates the token accounts a fill of the escrow of `keys` by `taker` pays into, paid for by `payer`
+/// `fee_currency` is the config's, the fee vault for mint_a is only created when fees are charged in it
+pub fn prepare_take(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    payer: &Pubkey,
+    fee_currency: FeeCurrency,
+) -> Instruction {
+    let config = config_address();
+    build(
+        accounts::PrepareTake {
+            payer: *payer,
+            taker: *taker,
+            owner: *owner,
+            mint_a: keys.mint_a,
+            mint_b: keys.mint_b,
+            taker_ata_a: ata_address(taker, &keys.mint_a, &keys.token_program_a),
+            owner_ata_b: keys.ata_b(owner),
+            config,
+            fee_vault: ata_address(&config, &keys.mint_b, &keys.token_program_b),
+            fee_vault_a: (fee_currency == FeeCurrency::MintA)
+                .then(|| ata_address(&config, &keys.mint_a, &keys.token_program_a)),
+            escrow: keys.escrow(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            token_program_b: keys.token_program_b,
+            system_program: system_program::ID,
+        },
+        instruction::PrepareTake {},
+    )
+}
+
+fn take_accounts(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    options: TakeOptions,
+) -> accounts::Take {
+    let config = config_address();
+    accounts::Take {
+        taker: *taker,
+        maker: keys.maker,
+        owner: *owner,
+        mint_a: keys.mint_a,
+        mint_b: keys.mint_b,
+        taker_ata_a: options
+            .receiver
+            .unwrap_or_else(|| ata_address(taker, &keys.mint_a, &keys.token_program_a)),
+        owner_ata_a: keys.ata_a(owner),
+        taker_ata_b: options.source.or_else(|| keys.ata_b(taker)),
+        owner_ata_b: options.payout.or_else(|| keys.ata_b(owner)),
+        referrer: options.referrer,
+        referrer_ata_b: options.referrer.and_then(|referrer| keys.ata_b(&referrer)),
+        member_nft: options.membership.map(|(nft, _)| nft),
+        member_metadata: options.membership.map(|(_, metadata)| metadata),
+        rent_treasury: options.rent_treasury,
+        config,
+        fee_vault: ata_address(&config, &keys.mint_b, &keys.token_program_b),
+        fee_vault_a: (options.fee_currency == FeeCurrency::MintA)
+            .then(|| ata_address(&config, &keys.mint_a, &keys.token_program_a)),
+        fee_treasury: options.fee_currency.treasury(),
+        escrow: keys.escrow(),
+        vault: keys.vault(),
+        fill_receipt: options
+            .fill_receipt
+            .then(|| fill_receipt_address(&keys.escrow(), taker)),
+        fee_override: options.fee_override.as_ref().map(fee_override_address),
+        trader_stats: options
+            .trader_stats
+            .then(|| trader_stats_address(taker, &keys.mint_b)),
+        reservation: options
+            .reservation
+            .then(|| reservation_address(&keys.escrow())),
+        template: options.template,
+        allowlist_pass: options
+            .template
+            .filter(|_| options.allowlisted)
+            .map(|template| allowlist_pass_address(&template, taker)),
+        gate_account: options.gate_account,
+        gateway_token: options.gateway_token,
+        chained_escrow: options
+            .chained_seed
+            .map(|seed| escrow_address(&keys.maker, seed)),
+        chained_vault: options.chained_seed.map(|seed| {
+            ata_address(
+                &escrow_address(&keys.maker, seed),
+                &keys.mint_b,
+                &keys.token_program_b,
+            )
+        }),
+        payment_options: options
+            .payment_options
+            .then(|| payment_options_address(&keys.escrow())),
+        order_book_page: options
+            .order_book_page
+            .map(|index| order_book_page_address(&keys.mint_a, &keys.mint_b, index)),
+        pair_index: keys.pair_index(),
+        event_log: options.event_log.then(event_log_address),
+        token_program_a: keys.token_program_a,
+        token_program_b: keys.token_program_b,
+        system_program: system_program::ID,
+    }
+}
+
+/// Fills the whole escrow of `keys`, whose position is held by `owner`
+/// Fails if its deposit or price no longer match `expected_deposit` and `expected_receive`
+pub fn take(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::Take {
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Fills the whole escrow of `keys` like `take`, keeping it as a receipt of the trade instead of closing it
+pub fn take_keep_receipt(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::TakeKeepReceipt {
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Fills the whole private escrow of `keys`, revealing its terms: `keys.mint_b`, `receive` and the `salt` they were hashed with
+/// Fails if its deposit no longer matches `expected_deposit`
+pub fn take_private(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    receive: u64,
+    salt: [u8; 32],
+    expected_deposit: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::TakePrivate {
+            receive,
+            salt,
+            expected_deposit,
+        },
+    )
+}
+
+/// Reserves the escrow of `keys` for `taker`, who commits to take it with `Escrow::commitment` of their secret `salt`
+pub fn commit_take(keys: &EscrowKeys, taker: &Pubkey, salt: &[u8; 32]) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::CommitTake {
+            taker: *taker,
+            escrow,
+        },
+        instruction::CommitTake {
+            hash: Escrow::commitment(&escrow, taker, salt),
+        },
+    )
+}
+
+/// Proves `taker` is on the allowlist of `template` with the merkle `proof` of their key
+/// Fill the template's escrows with `TakeOptions::allowlisted` set to pass the resulting pass
+pub fn join_allowlist(template: &Pubkey, taker: &Pubkey, proof: Vec<[u8; 32]>) -> Instruction {
+    build(
+        accounts::JoinAllowlist {
+            taker: *taker,
+            template: *template,
+            allowlist_pass: allowlist_pass_address(template, taker),
+            system_program: system_program::ID,
+        },
+        instruction::JoinAllowlist { proof },
+    )
+}
+
+/// Holds the escrow of `keys` for `taker` for `slots` slots, locking `bond` lamports
+/// Fill it with `TakeOptions::reservation` set to get the bond back
+pub fn reserve(keys: &EscrowKeys, taker: &Pubkey, slots: u64, bond: u64) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::Reserve {
+            taker: *taker,
+            config: config_address(),
+            escrow,
+            reservation: reservation_address(&escrow),
+            system_program: system_program::ID,
+        },
+        instruction::Reserve { slots, bond },
+    )
+}
+
+/// Holds the escrow of `keys` for `taker` for `slots` slots like `reserve`, for up to `Reservation::MAX_FILL_SLOTS`
+/// Settle it with `settle_fill` once mint_b is sourced
+pub fn reserve_fill(keys: &EscrowKeys, taker: &Pubkey, slots: u64, bond: u64) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::Reserve {
+            taker: *taker,
+            config: config_address(),
+            escrow,
+            reservation: reservation_address(&escrow),
+            system_program: system_program::ID,
+        },
+        instruction::ReserveFill { slots, bond },
+    )
+}
+
+/// Fills the whole escrow of `keys` like `take`, settling `taker`'s reservation of it
+pub fn settle_fill(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(
+            keys,
+            owner,
+            taker,
+            TakeOptions {
+                reservation: true,
+                ..options
+            },
+        ),
+        instruction::SettleFill {
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Closes the lapsed reservation of `taker` on the escrow of `keys`, its bond going to `owner` if the escrow is still open
+pub fn forfeit_reservation(keys: &EscrowKeys, owner: &Pubkey, taker: &Pubkey) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::ForfeitReservation {
+            owner: *owner,
+            taker: *taker,
+            escrow,
+            reservation: reservation_address(&escrow),
+        },
+        instruction::ForfeitReservation {},
+    )
+}
+
+/// Fills the whole escrow of `keys` like `take`, revealing the `salt` `taker` committed to it with
+pub fn reveal_take(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    salt: [u8; 32],
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::RevealTake {
+            salt,
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Fills `amount` of the deposit of the escrow of `keys`, whose position is held by `owner`
+/// Fails if its deposit or price no longer match `expected_deposit` and `expected_receive`
+pub fn take_partial(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    amount: u64,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::TakePartial {
+            amount,
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Fills the escrow of `keys` for an exact `amount` of either leg, the other one bounded by `limit`
+#[allow(clippy::too_many_arguments)]
+pub fn take_exact(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    amount: FillAmount,
+    limit: u64,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::TakeExact {
+            amount,
+            limit,
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Takes the next installment of the scheduled escrow of `keys`, whose position is held by `owner`
+pub fn take_installment(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    taker: &Pubkey,
+    expected_deposit: u64,
+    expected_receive: u64,
+    options: TakeOptions,
+) -> Instruction {
+    build(
+        take_accounts(keys, owner, taker, options),
+        instruction::TakeInstallment {
+            expected_deposit,
+            expected_receive,
+        },
+    )
+}
+
+/// Closes `taker`'s receipt of their fills of `escrow`, returning its rent to them
+pub fn close_fill_receipt(escrow: &Pubkey, taker: &Pubkey) -> Instruction {
+    build(
+        accounts::CloseFillReceipt {
+            taker: *taker,
+            fill_receipt: fill_receipt_address(escrow, taker),
+        },
+        instruction::CloseFillReceipt {},
+    )
+}
+
+/// Refunds the escrow of `keys` to `owner`, the holder of its position
+/// `rent_treasury` must be passed when the escrow sends its rent to a treasury
+pub fn refund(keys: &EscrowKeys, owner: &Pubkey, rent_treasury: Option<Pubkey>) -> Instruction {
+    refund_with(
+        keys,
+        owner,
+        owner,
+        None,
+        keys.ata_a(owner),
+        rent_treasury,
+        None,
+        false,
+        None,
+    )
+}
+
+/// Refunds the escrow of `keys` to `owner` like `refund` while `reserver` holds a reservation of it
+/// Pays them the config's cancellation penalty out of the deposit, into their associated token account for mint_a
+pub fn refund_reserved(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    reserver: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    refund_with(
+        keys,
+        owner,
+        owner,
+        None,
+        keys.ata_a(owner),
+        rent_treasury,
+        Some(*reserver),
+        false,
+        None,
+    )
+}
+
+/// Refunds the escrow of `keys` to `owner` like `refund`, recording it in the program's event log
+pub fn logged_refund(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    refund_with(
+        keys,
+        owner,
+        owner,
+        None,
+        keys.ata_a(owner),
+        rent_treasury,
+        None,
+        true,
+        None,
+    )
+}
+
+/// Refunds the escrow of `keys` into `token_account`, any token account of `owner` for mint_a rather than their associated one
+pub fn refund_to(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    token_account: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    refund_with(
+        keys,
+        owner,
+        owner,
+        None,
+        Some(*token_account),
+        rent_treasury,
+        None,
+        false,
+        None,
+    )
+}
+
+/// Refunds the escrow of `keys` to `owner`, signed by the cancel `authority` they delegated to
+pub fn delegated_refund(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    authority: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    let cancel_authority = Some(cancel_authority_address(owner));
+    refund_with(
+        keys,
+        owner,
+        authority,
+        cancel_authority,
+        keys.ata_a(owner),
+        rent_treasury,
+        None,
+        false,
+        None,
+    )
+}
+
+/// Refunds the listed escrow of `keys` to `owner` like `refund`, removing it from the order book page at `index`
+pub fn refund_listed(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    index: u32,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    refund_with(
+        keys,
+        owner,
+        owner,
+        None,
+        keys.ata_a(owner),
+        rent_treasury,
+        None,
+        false,
+        Some(index),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn refund_with(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    authority: &Pubkey,
+    cancel_authority: Option<Pubkey>,
+    owner_ata_a: Option<Pubkey>,
+    rent_treasury: Option<Pubkey>,
+    reserver: Option<Pubkey>,
+    event_log: bool,
+    order_book_page: Option<u32>,
+) -> Instruction {
+    build(
+        accounts::Refund {
+            authority: *authority,
+            owner: *owner,
+            cancel_authority,
+            maker: keys.maker,
+            mint_a: keys.mint_a,
+            owner_ata_a,
+            rent_treasury,
+            config: config_address(),
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            reservation: reserver.map(|_| reservation_address(&keys.escrow())),
+            reserver_ata_a: reserver
+                .map(|reserver| ata_address(&reserver, &keys.mint_a, &keys.token_program_a)),
+            event_log: event_log.then(event_log_address),
+            order_book_page: order_book_page
+                .map(|index| order_book_page_address(&keys.mint_a, &keys.mint_b, index)),
+            pair_index: keys.pair_index(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::Refund {},
+    )
+}
+
+/// Lets `authority` refund the escrows whose position `owner` holds, replacing any previous cancel authority
+pub fn set_cancel_authority(owner: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::SetCancelAuthority {
+            owner: *owner,
+            cancel_authority: cancel_authority_address(owner),
+            system_program: system_program::ID,
+        },
+        instruction::SetCancelAuthority {
+            authority: *authority,
+        },
+    )
+}
+
+/// Revokes `owner`'s cancel authority
+pub fn revoke_cancel_authority(owner: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeCancelAuthority {
+            owner: *owner,
+            cancel_authority: cancel_authority_address(owner),
+        },
+        instruction::RevokeCancelAuthority {},
+    )
+}
+
+/// Refunds the expired escrow of `keys` to `owner`, paid for by anyone as `payer`
+/// `rent_treasury` must be passed when the escrow sends its rent to a treasury
+pub fn refund_expired(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    payer: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::RefundExpired {
+            payer: *payer,
+            maker: keys.maker,
+            owner: *owner,
+            mint_a: keys.mint_a,
+            owner_ata_a: keys.ata_a(owner),
+            rent_treasury,
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            pair_index: keys.pair_index(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::RefundExpired {},
+    )
+}
+
+/// Schedules the refund of the escrow of `keys` from `execute_after` (0 for its expiry), tipping the keeper `tip` lamports
+pub fn schedule_auto_refund(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    execute_after: i64,
+    tip: u64,
+) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::ScheduleAutoRefund {
+            owner: *owner,
+            escrow,
+            auto_refund: auto_refund_address(&escrow),
+            system_program: system_program::ID,
+        },
+        instruction::ScheduleAutoRefund { execute_after, tip },
+    )
+}
+
+/// Cancels the scheduled refund of `escrow`, returning its tip and rent to `owner`
+pub fn cancel_auto_refund(escrow: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(
+        accounts::CancelAutoRefund {
+            owner: *owner,
+            auto_refund: auto_refund_address(escrow),
+        },
+        instruction::CancelAutoRefund {},
+    )
+}
+
+/// Executes the due scheduled refund of the escrow of `keys` to `owner`, tipping `keeper`
+/// `rent_treasury` must be passed when the escrow sends its rent to a treasury, and `order_book_page`,
+/// the index of the page listing it, when it is listed
+pub fn execute_auto_refund(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    keeper: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+    event_log: bool,
+    order_book_page: Option<u32>,
+) -> Instruction {
+    let escrow = keys.escrow();
+    build(
+        accounts::ExecuteAutoRefund {
+            keeper: *keeper,
+            maker: keys.maker,
+            owner: *owner,
+            mint_a: keys.mint_a,
+            owner_ata_a: keys.ata_a(owner),
+            rent_treasury,
+            escrow,
+            vault: keys.vault(),
+            auto_refund: auto_refund_address(&escrow),
+            event_log: event_log.then(event_log_address),
+            order_book_page: order_book_page
+                .map(|index| order_book_page_address(&keys.mint_a, &keys.mint_b, index)),
+            pair_index: keys.pair_index(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::ExecuteAutoRefund {},
+    )
+}
+
+/// Reprices the escrow of `keys` to `receive`, with a new `expiry` (0 for none)
+pub fn update_escrow(keys: &EscrowKeys, owner: &Pubkey, receive: u64, expiry: i64) -> Instruction {
+    build(
+        accounts::Update {
+            owner: *owner,
+            escrow: keys.escrow(),
+        },
+        instruction::UpdateEscrow { receive, expiry },
+    )
+}
+
+/// Accepts each of `legs` as payment for the escrow of `keys` besides its mint_b, each asking its `receive` for the whole deposit
+pub fn set_payment_options(
+    keys: &EscrowKeys,
+    owner: &Pubkey,
+    legs: Vec<PaymentLeg>,
+) -> Instruction {
+    build(
+        accounts::SetPaymentOptions {
+            owner: *owner,
+            escrow: keys.escrow(),
+            config: config_address(),
+            payment_options: payment_options_address(&keys.escrow()),
+            system_program: system_program::ID,
+        },
+        instruction::SetPaymentOptions { legs },
+    )
+}
+
+/// Closes the payment options of `escrow`, returning their rent to `owner`, who set them
+pub fn close_payment_options(escrow: &Pubkey, owner: &Pubkey) -> Instruction {
+    build(
+        accounts::ClosePaymentOptions {
+            owner: *owner,
+            payment_options: payment_options_address(escrow),
+        },
+        instruction::ClosePaymentOptions {},
+    )
+}
+
+/// Deposits `amount` more of mint_a into the escrow of `keys`, at its current price
+pub fn top_up(keys: &EscrowKeys, owner: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::TopUp {
+            owner: *owner,
+            mint_a: keys.mint_a,
+            owner_ata_a: keys.ata_a(owner),
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            pair_index: keys.pair_index(),
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::TopUp { amount },
+    )
+}
+
+/// Withdraws `amount` of the deposit of the escrow of `keys` back to `owner`, at its current price
+pub fn reduce_deposit(keys: &EscrowKeys, owner: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::ReduceDeposit {
+            owner: *owner,
+            mint_a: keys.mint_a,
+            owner_ata_a: ata_address(owner, &keys.mint_a, &keys.token_program_a),
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            pair_index: keys.pair_index(),
+            token_program_a: keys.token_program_a,
+        },
+        instruction::ReduceDeposit { amount },
+    )
+}
+
+/// Returns anything donated to the vault of the escrow of `keys` on top of its deposit to `owner`
+pub fn sweep_surplus(keys: &EscrowKeys, owner: &Pubkey) -> Instruction {
+    build(
+        accounts::SweepSurplus {
+            owner: *owner,
+            mint_a: keys.mint_a,
+            owner_ata_a: ata_address(owner, &keys.mint_a, &keys.token_program_a),
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::SweepSurplus {},
+    )
+}
+
+/// Moves `amount` of the deposit of the escrow of `keys` into a new escrow made with `seed`, on the same terms
+/// The maker signs along with `owner`, as the new escrow is derived from their key and paid for by them
+pub fn split_escrow(keys: &EscrowKeys, owner: &Pubkey, seed: u64, amount: u64) -> Instruction {
+    // The new vault is always the new escrow's associated token account
+    let new_keys = EscrowKeys {
+        address_maker: None,
+        seed,
+        seed_id: None,
+        pda_vault: false,
+        ..*keys
+    };
+    build(
+        accounts::SplitEscrow {
+            maker: keys.maker,
+            owner: *owner,
+            mint_a: keys.mint_a,
+            config: config_address(),
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            new_escrow: new_keys.escrow(),
+            new_vault: new_keys.vault(),
+            pair_index: keys.pair_index(),
+            associated_token_program: associated_token::ID,
+            token_program_a: keys.token_program_a,
+            system_program: system_program::ID,
+        },
+        instruction::SplitEscrow { seed, amount },
+    )
+}
+
+/// Folds the escrow of `merged` into the escrow of `keys`, both made by the same maker on the same terms
+/// `rent_treasury` must be passed when the escrows send their rent to a treasury
+pub fn merge_escrows(
+    keys: &EscrowKeys,
+    merged: &EscrowKeys,
+    owner: &Pubkey,
+    rent_treasury: Option<Pubkey>,
+) -> Instruction {
+    build(
+        accounts::MergeEscrows {
+            owner: *owner,
+            maker: keys.maker,
+            mint_a: keys.mint_a,
+            rent_treasury,
+            escrow: keys.escrow(),
+            vault: keys.vault(),
+            merged: merged.escrow(),
+            merged_vault: merged.vault(),
+            pair_index: keys.pair_index(),
+            token_program_a: keys.token_program_a,
+        },
+        instruction::MergeEscrows {},
+    )
+}
+
+/// Hands the position in the escrow of `keys` from `owner` to `new_owner`
+pub fn transfer_position(keys: &EscrowKeys, owner: &Pubkey, new_owner: &Pubkey) -> Instruction {
+    build(
+        accounts::TransferPosition {
+            owner: *owner,
+            escrow: keys.escrow(),
+        },
+        instruction::TransferPosition {
+            new_owner: *new_owner,
+        },
+    )
+}
+
+/// Makes `new_maker` the maker of the escrow of `keys`, whose keys are then `keys.transferred(new_maker)`
+pub fn transfer_maker(keys: &EscrowKeys, new_maker: &Pubkey) -> Instruction {
+    build(
+        accounts::TransferMaker {
+            owner: keys.maker,
+            escrow: keys.escrow(),
+        },
+        instruction::TransferMaker {
+            new_maker: *new_maker,
+        },
+    )
+}
+
+/// Routes the proceeds of the escrow of `keys` to `payout`, a token account of its mint_b, or back to `owner`'s with `None`
+pub fn set_payout(keys: &EscrowKeys, owner: &Pubkey, payout: Option<Pubkey>) -> Instruction {
+    build(
+        accounts::SetPayout {
+            owner: *owner,
+            escrow: keys.escrow(),
+            payout,
+        },
+        instruction::SetPayout {},
+    )
+}
+
+/// Adds the page at `index`, the order book's current page count, to the order book of the pair of `mint_a` offered for `mint_b`
+/// `payer` pays for the page, and for the order book when `index` is 0
+pub fn add_order_book_page(
+    payer: &Pubkey,
+    mint_a: &Pubkey,
+    mint_b: &Pubkey,
+    index: u32,
+) -> Instruction {
+    build(
+        accounts::AddOrderBookPage {
+            payer: *payer,
+            mint_a: *mint_a,
+            mint_b: *mint_b,
+            order_book: order_book_address(mint_a, mint_b),
+            order_book_page: order_book_page_address(mint_a, mint_b, index),
+            system_program: system_program::ID,
+        },
+        instruction::AddOrderBookPage {},
+    )
+}
+
+/// Creates the index of the pair of `mint_a` offered for `mint_b`, paid for by `payer`
+pub fn create_pair_index(payer: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey) -> Instruction {
+    build(
+        accounts::CreatePairIndex {
+            payer: *payer,
+            mint_a: *mint_a,
+            mint_b: *mint_b,
+            pair_index: pair_index_address(mint_a, mint_b),
+            system_program: system_program::ID,
+        },
+        instruction::CreatePairIndex {},
+    )
+}
+
+/// Removes `escrow`, once no longer open, from the order book page at `page`
+pub fn prune_order_book(page: &Pubkey, escrow: &Pubkey) -> Instruction {
+    build(
+        accounts::PruneOrderBook {
+            order_book_page: *page,
+            escrow: *escrow,
+        },
+        instruction::PruneOrderBook {},
+    )
+}
+
+/// Closes the escrow of `keys`, kept as a receipt once settled
+pub fn close_receipt(keys: &EscrowKeys) -> Instruction {
+    build(
+        accounts::CloseReceipt {
+            maker: keys.maker,
+            escrow: keys.escrow(),
+        },
+        instruction::CloseReceipt {},
+    )
+}
+
+/// Rewrites the escrow at `escrow` made in an older layout in the current one, `payer` paying for the added rent
+/// Baseline escrows also need their `vault`, whose balance becomes their deposit
+pub fn migrate_escrow(escrow: &Pubkey, payer: &Pubkey, vault: Option<Pubkey>) -> Instruction {
+    build(
+        accounts::MigrateEscrow {
+            payer: *payer,
+            escrow: *escrow,
+            vault,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateEscrow {},
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use anchor_lang::{
+        prelude::{AccountInfo, CpiContext},
+        solana_program::{
+            entrypoint::ProgramResult,
+            program_stubs::{self, SyscallStubs},
+        },
+        AnchorDeserialize, Discriminator,
+    };
+    use anchor_spl::{token, token::spl_token::native_mint, token_2022};
+
+    use super::*;
+
+    fn keys() -> EscrowKeys {
+        EscrowKeys {
+            maker: Pubkey::new_unique(),
+            address_maker: None,
+            seed: 42,
+            seed_id: None,
+            mint_a: Pubkey::new_unique(),
+            mint_b: Pubkey::new_unique(),
+            token_program_a: token_2022::ID,
+            token_program_b: token::ID,
+            pda_vault: false,
+            indexed: false,
+        }
+    }
+
+    /// First meta of `key` in `instruction`
+    fn meta(instruction: &Instruction, key: &Pubkey) -> AccountMeta {
+        instruction
+            .accounts
+            .iter()
+            .find(|meta| meta.pubkey == *key)
+            .cloned()
+            .unwrap_or_else(|| panic!("{key} isn't passed"))
+    }
+
+    #[test]
+    fn escrow_keys_derive_the_program_addresses() {
+        let keys = keys();
+        let (escrow, _) = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                keys.maker.as_ref(),
+                &Escrow::address_seed_of(keys.seed, None),
+            ],
+            &ID,
+        );
+        assert_eq!(keys.escrow(), escrow);
+        assert_eq!(
+            keys.vault(),
+            ata_address(&escrow, &keys.mint_a, &token_2022::ID)
+        );
+
+        let with_id = EscrowKeys {
+            seed_id: Some([7; 32]),
+            ..keys
+        };
+        assert_eq!(
+            with_id.escrow(),
+            escrow_address_from_id(&keys.maker, &[7; 32])
+        );
+
+        let with_pda_vault = EscrowKeys {
+            pda_vault: true,
+            ..keys
+        };
+        assert_eq!(with_pda_vault.vault(), pda_vault_address(&escrow));
+    }
+
+    #[test]
+    fn transferred_keys_keep_the_escrow_address() {
+        let keys = keys();
+        let new_maker = Pubkey::new_unique();
+        let transferred = keys.transferred(new_maker);
+        assert_eq!(transferred.maker, new_maker);
+        assert_eq!(transferred.escrow(), keys.escrow());
+        assert_eq!(transferred.vault(), keys.vault());
+        // Transferred again, the address is still derived from the maker who made it
+        let again = transferred.transferred(Pubkey::new_unique());
+        assert_eq!(again.escrow(), keys.escrow());
+
+        // Only the current maker can hand the escrow on
+        let instruction = transfer_maker(&transferred, &Pubkey::new_unique());
+        assert!(meta(&instruction, &new_maker).is_signer);
+        assert!(meta(&instruction, &keys.escrow()).is_writable);
+    }
+
+    #[test]
+    fn make_encodes_its_terms_after_the_discriminator() {
+        let keys = EscrowKeys {
+            seed_id: Some([7; 32]),
+            ..keys()
+        };
+        let options = MakeOptions {
+            expiry: 100,
+            label: label("OTC desk"),
+            category: 2,
+            ..Default::default()
+        };
+        let instruction = make(&keys, 10, 20, options);
+        assert_eq!(instruction.program_id, ID);
+        assert_eq!(instruction.data[..8], instruction::Make::DISCRIMINATOR);
+
+        let args = instruction::Make::deserialize(&mut &instruction.data[8..]).unwrap();
+        assert_eq!((args.seed, args.seed_id), (42, Some([7; 32])));
+        assert_eq!((args.deposit, args.receive, args.expiry), (10, 20, 100));
+        assert_eq!((args.label, args.category), (label("OTC desk"), 2));
+        assert_eq!(args.taker, None);
+
+        assert!(meta(&instruction, &keys.escrow()).is_writable);
+        assert!(meta(&instruction, &keys.vault()).is_writable);
+        // The maker approves the terms even when a sponsor pays the rent
+        let payer = Pubkey::new_unique();
+        let sponsored = make(
+            &keys,
+            10,
+            20,
+            MakeOptions {
+                payer: Some(payer),
+                ..options
+            },
+        );
+        assert!(meta(&sponsored, &keys.maker).is_signer);
+        assert!(meta(&sponsored, &payer).is_signer);
+        assert!(
+            meta(
+                &instruction,
+                &ata_address(&keys.maker, &keys.mint_a, &token_2022::ID)
+            )
+            .is_writable
+        );
+        // The profile and event log are only passed when asked for
+        assert!(!instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == maker_profile_address(&keys.maker)
+                || meta.pubkey == event_log_address()));
+    }
+
+    #[test]
+    fn take_pays_between_the_associated_token_accounts() {
+        let keys = keys();
+        let (owner, taker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = take(&keys, &owner, &taker, 10, 20, TakeOptions::default());
+        assert_eq!(instruction.data[..8], instruction::Take::DISCRIMINATOR);
+        let args = instruction::Take::deserialize(&mut &instruction.data[8..]).unwrap();
+        assert_eq!((args.expected_deposit, args.expected_receive), (10, 20));
+
+        assert!(meta(&instruction, &taker).is_signer);
+        for account in [
+            ata_address(&taker, &keys.mint_a, &token_2022::ID),
+            ata_address(&taker, &keys.mint_b, &token::ID),
+            ata_address(&owner, &keys.mint_b, &token::ID),
+            ata_address(&config_address(), &keys.mint_b, &token::ID),
+            keys.escrow(),
+            keys.vault(),
+        ] {
+            assert!(meta(&instruction, &account).is_writable);
+        }
+        // A receiving account replaces the taker's associated token account for mint_a
+        let receiver = Pubkey::new_unique();
+        let options = TakeOptions {
+            receiver: Some(receiver),
+            ..Default::default()
+        };
+        let instruction = take(&keys, &owner, &taker, 10, 20, options);
+        assert!(meta(&instruction, &receiver).is_writable);
+        assert!(!instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == ata_address(&taker, &keys.mint_a, &token_2022::ID)));
+    }
+
+    #[test]
+    fn wrapped_sol_legs_pass_no_token_accounts() {
+        let keys = EscrowKeys {
+            mint_b: native_mint::ID,
+            ..keys()
+        };
+        let (owner, taker) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let instruction = take(&keys, &owner, &taker, 10, 20, TakeOptions::default());
+        for account in [
+            ata_address(&taker, &native_mint::ID, &token::ID),
+            ata_address(&owner, &native_mint::ID, &token::ID),
+        ] {
+            assert!(!instruction
+                .accounts
+                .iter()
+                .any(|meta| meta.pubkey == account));
+        }
+    }
+
+    /// Records the instructions a program invokes instead of running them
+    struct RecordInvokes(Arc<Mutex<Vec<Instruction>>>);
+
+    impl SyscallStubs for RecordInvokes {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            _account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            self.0.lock().unwrap().push(instruction.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cpi_wrappers_invoke_what_the_client_builds() {
+        let keys = keys();
+        let (owner, escrow) = (Pubkey::new_unique(), keys.escrow());
+        let (mut owner_lamports, mut escrow_lamports, mut program_lamports) = (0, 0, 0);
+        let (mut owner_data, mut escrow_data, mut program_data) = (vec![], vec![], vec![]);
+        let owner_info = AccountInfo::new(
+            &owner,
+            true,
+            false,
+            &mut owner_lamports,
+            &mut owner_data,
+            &system_program::ID,
+            false,
+            0,
+        );
+        let escrow_info = AccountInfo::new(
+            &escrow,
+            false,
+            true,
+            &mut escrow_lamports,
+            &mut escrow_data,
+            &ID,
+            false,
+            0,
+        );
+        let program_info = AccountInfo::new(
+            &ID,
+            false,
+            false,
+            &mut program_lamports,
+            &mut program_data,
+            &system_program::ID,
+            true,
+            0,
+        );
+
+        // A calling program, like a marketplace repricing its escrows, goes through `escrow::cpi`
+        let invoked = Arc::new(Mutex::new(Vec::new()));
+        program_stubs::set_syscall_stubs(Box::new(RecordInvokes(invoked.clone())));
+        let cpi_accounts = escrow::cpi::accounts::Update {
+            owner: owner_info,
+            escrow: escrow_info,
+        };
+        escrow::cpi::update_escrow(CpiContext::new(program_info, cpi_accounts), 20, 100).unwrap();
+        assert_eq!(
+            *invoked.lock().unwrap(),
+            [update_escrow(&keys, &owner, 20, 100)]
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Off-chain client for the escrow program
+//!
+//! Builds its instructions, derives its PDAs, and fetches and filters its accounts,
+//! so Rust bots and backends don't have to hand-roll Anchor discriminators and layouts.
+
+pub mod accounts;
+pub mod filters;
+pub mod instructions;
+pub mod pda;
+
+pub use accounts::*;
+pub use filters::*;
+pub use instructions::*;
+pub use pda::*;
+
+pub use escrow::{self as program, ID};
+
+/// Errors returned by the client
+/// The RPC and Anchor errors are boxed, they would otherwise make every `Result` of the client hundreds of bytes large
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("RPC request failed: {0}")]
+    Rpc(Box<solana_rpc_client_api::client_error::Error>),
+    #[error("Account {0} doesn't exist")]
+    AccountNotFound(anchor_lang::prelude::Pubkey),
+    #[error("Account {0} isn't owned by the escrow program")]
+    WrongOwner(anchor_lang::prelude::Pubkey),
+    #[error("Account data doesn't match the expected type: {0}")]
+    InvalidData(Box<anchor_lang::error::Error>),
+}
+
+impl From<solana_rpc_client_api::client_error::Error> for Error {
+    fn from(error: solana_rpc_client_api::client_error::Error) -> Self {
+        Error::Rpc(Box::new(error))
+    }
+}
+
+impl From<anchor_lang::error::Error> for Error {
+    fn from(error: anchor_lang::error::Error) -> Self {
+        Error::InvalidData(Box::new(error))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
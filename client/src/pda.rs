@@ -0,0 +1,268 @@
+use anchor_lang::{prelude::Pubkey, solana_program::hash::hash};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::ID;
+
+/// Address of the program's global config
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &ID).0
+}
+
+/// Address of the program's event log
+pub fn event_log_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"event_log"], &ID).0
+}
+
+/// Address of the fee override of `subject`, an escrow or a maker
+pub fn fee_override_address(subject: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fee_override", subject.as_ref()], &ID).0
+}
+
+/// Address of the stats of `trader`'s fills paid in `mint_b`, whose volume sets their fee tier
+pub fn trader_stats_address(trader: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"trader_stats", trader.as_ref(), mint_b.as_ref()], &ID).0
+}
+
+/// Address of the template of escrow terms the admin created with `seed`
+pub fn template_address(seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"template", &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the pass proving `taker` is on the allowlist of `template`
+pub fn allowlist_pass_address(template: &Pubkey, taker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"allowlist_pass", template.as_ref(), taker.as_ref()], &ID).0
+}
+
+/// Address of the escrow made by `maker` with `seed`
+pub fn escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the escrow made by `maker` with `seed_id`, derived from it instead of its `seed`
+pub fn escrow_address_from_id(maker: &Pubkey, seed_id: &[u8; 32]) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), seed_id], &ID).0
+}
+
+/// Seed id of the escrow identified by `id`, e.g. an off-chain order id, so integrators can derive escrows from their own identifiers
+/// `id` is hashed with SHA-256, so identifiers of any length fit the 32 bytes of `EscrowKeys::seed_id`
+pub fn seed_from_id(id: &[u8]) -> [u8; 32] {
+    hash(id).to_bytes()
+}
+
+/// Address of the vault of `escrow` when made as a token account at its PDA rather than as its associated token account
+pub fn pda_vault_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &ID).0
+}
+
+/// Address of `maker`'s profile, whose offer counter seeds the escrows made with it
+pub fn maker_profile_address(maker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"profile", maker.as_ref()], &ID).0
+}
+
+/// Address of `owner`'s delegation of their refunds to a cancel authority
+pub fn cancel_authority_address(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"cancel_authority", owner.as_ref()], &ID).0
+}
+
+/// Address of the scheduled refund of `escrow`
+pub fn auto_refund_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"auto_refund", escrow.as_ref()], &ID).0
+}
+
+/// Address of the reservation holding `escrow`
+pub fn reservation_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"reservation", escrow.as_ref()], &ID).0
+}
+
+/// Address of the other mints accepted as payment for `escrow`
+pub fn payment_options_address(escrow: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"payment_options", escrow.as_ref()], &ID).0
+}
+
+/// Address of the counter-offer made by `taker` on `escrow`
+pub fn counter_offer_address(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"counter", escrow.as_ref(), taker.as_ref()], &ID).0
+}
+
+/// Address of the receipt of `taker`'s fills of `escrow`
+pub fn fill_receipt_address(escrow: &Pubkey, taker: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"fill_receipt", escrow.as_ref(), taker.as_ref()], &ID).0
+}
+
+/// Address of the basket made by `maker` with `seed`
+pub fn basket_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"basket", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the two-sided swap proposed by `maker` with `seed`
+pub fn swap_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"swap", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the auction opened by `maker` with `seed`
+pub fn auction_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"auction", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of `bidder`'s sealed bid on `auction`
+pub fn sealed_bid_address(auction: &Pubkey, bidder: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"sealed_bid", auction.as_ref(), bidder.as_ref()], &ID).0
+}
+
+/// Address of the milestone escrow funded by `maker` with `seed`
+pub fn milestone_escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"milestones", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the loan opened by `maker` with `seed`
+pub fn loan_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"loan", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the compressed NFT escrow made by `maker` with `seed`
+pub fn compressed_escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"compressed", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the edition-print escrow made by `maker` with `seed`
+pub fn edition_escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"edition", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the stake account escrow made by `maker` with `seed`
+pub fn stake_escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"stake", maker.as_ref(), &seed.to_le_bytes()], &ID).0
+}
+
+/// Address of the order book of the pair of `mint_a` offered for `mint_b`
+pub fn order_book_address(mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"order_book", mint_a.as_ref(), mint_b.as_ref()], &ID).0
+}
+
+/// Address of the index of the pair of `mint_a` offered for `mint_b`
+pub fn pair_index_address(mint_a: &Pubkey, mint_b: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pair_index", mint_a.as_ref(), mint_b.as_ref()], &ID).0
+}
+
+/// Address of the page at `index` of the order book of the pair of `mint_a` offered for `mint_b`
+pub fn order_book_page_address(mint_a: &Pubkey, mint_b: &Pubkey, index: u32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"order_book_page",
+            mint_a.as_ref(),
+            mint_b.as_ref(),
+            &index.to_le_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Address of `owner`'s associated token account for `mint`, under `token_program`
+/// Vaults are the associated token accounts of the escrow, basket, swap, auction, milestone escrow, loan or edition-print escrow holding them
+pub fn ata_address(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(owner, mint, token_program)
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_spl::associated_token;
+    use escrow::Escrow;
+
+    use super::*;
+
+    #[test]
+    fn escrow_addresses_follow_the_program_seeds() {
+        let maker = Pubkey::new_unique();
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                maker.as_ref(),
+                &Escrow::address_seed_of(42, None),
+            ],
+            &ID,
+        );
+        assert_eq!(escrow_address(&maker, 42), address);
+
+        let seed_id = seed_from_id(b"order-42");
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"escrow",
+                maker.as_ref(),
+                &Escrow::address_seed_of(42, Some(seed_id)),
+            ],
+            &ID,
+        );
+        assert_eq!(escrow_address_from_id(&maker, &seed_id), address);
+        assert_ne!(
+            escrow_address_from_id(&maker, &seed_id),
+            escrow_address(&maker, 42)
+        );
+    }
+
+    #[test]
+    fn seed_ids_are_the_sha256_of_the_id() {
+        assert_eq!(seed_from_id(b"order-42"), hash(b"order-42").to_bytes());
+        assert_ne!(seed_from_id(b"order-42"), seed_from_id(b"order-43"));
+    }
+
+    #[test]
+    fn addresses_differ_by_maker_and_seed() {
+        let (maker, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_ne!(escrow_address(&maker, 1), escrow_address(&maker, 2));
+        assert_ne!(escrow_address(&maker, 1), escrow_address(&other, 1));
+        // Every kind of escrow lives under its own prefix, so the same maker and seed never collide
+        let addresses = [
+            escrow_address(&maker, 1),
+            basket_address(&maker, 1),
+            swap_address(&maker, 1),
+            auction_address(&maker, 1),
+            milestone_escrow_address(&maker, 1),
+            loan_address(&maker, 1),
+            compressed_escrow_address(&maker, 1),
+            edition_escrow_address(&maker, 1),
+            stake_escrow_address(&maker, 1),
+        ];
+        for (i, address) in addresses.iter().enumerate() {
+            assert!(!addresses[i + 1..].contains(address));
+        }
+    }
+
+    #[test]
+    fn vaults_are_associated_token_accounts_or_pdas_of_the_escrow() {
+        let (escrow, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(
+            ata_address(&escrow, &mint, &anchor_spl::token_2022::ID),
+            associated_token::get_associated_token_address_with_program_id(
+                &escrow,
+                &mint,
+                &anchor_spl::token_2022::ID
+            )
+        );
+        assert_ne!(
+            ata_address(&escrow, &mint, &anchor_spl::token::ID),
+            ata_address(&escrow, &mint, &anchor_spl::token_2022::ID)
+        );
+        let (address, _) = Pubkey::find_program_address(&[b"vault", escrow.as_ref()], &ID);
+        assert_eq!(pda_vault_address(&escrow), address);
+    }
+
+    #[test]
+    fn order_book_pages_are_derived_from_their_pair_and_index() {
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (address, _) = Pubkey::find_program_address(
+            &[
+                b"order_book_page",
+                mint_a.as_ref(),
+                mint_b.as_ref(),
+                &7u32.to_le_bytes(),
+            ],
+            &ID,
+        );
+        assert_eq!(order_book_page_address(&mint_a, &mint_b, 7), address);
+        assert_ne!(
+            order_book_page_address(&mint_a, &mint_b, 7),
+            order_book_page_address(&mint_b, &mint_a, 7)
+        );
+    }
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{CounterOffer, CounterOfferEvent, Escrow, EscrowError, EscrowStatus};
+
+/// Defines the accounts needed for the `accept_counter` instruction, repricing the escrow for the counter-offer's taker
+#[derive(Accounts)]
+pub struct AcceptCounter<'info> {
+    /// The owner of the maker's position, the only one allowed to change the escrow's terms
+    pub owner: Signer<'info>,
+
+    /// The taker who made the counter-offer, receives its rent back
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    /// The open escrow being repriced
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The accepted counter-offer, closed once its terms are applied to the escrow
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow,
+        has_one = taker,
+        seeds = [b"counter", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = counter_offer.bump
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+}
+
+impl<'info> AcceptCounter<'info> {
+    /// Reprices the escrow at the counter-offer and reserves it for the taker who made it
+    /// The taker then fills it with `take` as usual
+    pub fn accept_counter(&mut self) -> Result<()> {
+        let receive = self.counter_offer.receive;
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.set_receive(receive);
+        escrow.set_taker(Some(self.taker.key()));
+
+        emit!(CounterOfferEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            receive,
+            accepted: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
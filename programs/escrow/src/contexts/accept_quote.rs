@@ -0,0 +1,352 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, AssociatedToken},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{reindex, release_vault, rent_recipients, transfer_checked_with_hook},
+    Config, Escrow, EscrowError, EscrowStatus, PairIndex, RfqQuote, RfqQuoteEvent, TakeEvent,
+};
+
+/// Defines the accounts needed for the `accept_quote` instruction, settling an escrow against the best quote posted on it
+/// Losing quotes are refunded through the remaining accounts, in groups of `[quote, quote_vault, taker, taker_ata_b]`
+/// Transfer hooks aren't supported, as the remaining accounts are taken by the losing quotes
+#[derive(Accounts)]
+pub struct AcceptQuote<'info> {
+    /// The owner of the maker's position, choosing the quote and receiving its mint_b
+    /// Pays for the token accounts created for the settlement
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The original maker of the escrow, receives its rent once it is closed
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The taker whose quote is accepted, receives the deposit and the quote's rent
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    /// The mint of the token deposited by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of the quoted token
+    /// Writable so Token-2022 transfer fees withheld in the quote vaults can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_b
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the taker receiving the deposit
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Associated token account of the owner receiving the quoted mint_b
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_b,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_b
+    )]
+    pub owner_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Receives the rent of the escrow and its vault when the maker chose a treasury for it, checked against the escrow's in `rent_recipients`
+    #[account(mut)]
+    pub rent_treasury: Option<UncheckedAccount<'info>>,
+
+    /// The program's global config, which must not be paused for fills
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees in mint_b
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The open escrow being settled
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault for the assets deposited by the maker
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The accepted quote, closed to its taker
+    #[account(
+        mut,
+        close = taker,
+        has_one = escrow @ EscrowError::InvalidQuote,
+        has_one = taker @ EscrowError::InvalidQuote,
+        seeds = [b"rfq", escrow.key().as_ref(), taker.key().as_ref()],
+        bump = quote.bump
+    )]
+    pub quote: Box<Account<'info, RfqQuote>>,
+
+    /// Vault holding the accepted quote's mint_b
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = quote,
+        associated_token::token_program = token_program_b
+    )]
+    pub quote_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The index of the escrow's mints, which the settlement removes it from
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AcceptQuote<'info> {
+    /// Number of remaining accounts passed per losing quote
+    pub const ACCOUNTS_PER_QUOTE: usize = 4;
+
+    /// Ensures the escrow can still be filled by the quote's taker
+    pub fn check_fillable(&self) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!escrow.is_expired(now), EscrowError::EscrowExpired);
+        require!(!escrow.is_committed(now), EscrowError::CommitmentPending);
+        require!(
+            !escrow.is_reserved(Clock::get()?.slot),
+            EscrowError::EscrowReserved
+        );
+        require!(escrow.template().is_none(), EscrowError::TemplatedEscrow);
+        require!(
+            escrow.gate().is_none() && escrow.gatekeeper_network().is_none(),
+            EscrowError::GatedEscrow
+        );
+        require!(escrow.chained_seed().is_none(), EscrowError::ChainedEscrow);
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        Ok(())
+    }
+
+    /// Refunds and closes every losing quote, each of which must offer at most the accepted amount
+    pub fn refund_losers(&self, quote_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let groups = quote_accounts.chunks_exact(Self::ACCOUNTS_PER_QUOTE);
+        require!(groups.remainder().is_empty(), EscrowError::InvalidQuote);
+
+        for accounts in groups {
+            let [quote, quote_vault, taker, taker_ata_b] = accounts else {
+                return err!(EscrowError::InvalidQuote);
+            };
+            self.refund_one(quote, quote_vault, taker, taker_ata_b)?;
+        }
+        Ok(())
+    }
+
+    /// Returns a single losing quote's mint_b to its taker, then closes its vault and the quote to them
+    fn refund_one(
+        &self,
+        quote_info: &'info AccountInfo<'info>,
+        quote_vault: &AccountInfo<'info>,
+        taker: &AccountInfo<'info>,
+        taker_ata_b: &AccountInfo<'info>,
+    ) -> Result<()> {
+        // Checks it is owned by this program with the quote discriminator, so it can only be a genuine quote
+        let quote = Account::<RfqQuote>::try_from(quote_info)?;
+        require_keys_eq!(quote.escrow, self.escrow.key(), EscrowError::InvalidQuote);
+        require_keys_eq!(quote.taker, taker.key(), EscrowError::InvalidQuote);
+        require!(quote.amount <= self.quote.amount, EscrowError::QuoteNotBest);
+        require_keys_eq!(
+            quote_vault.key(),
+            get_associated_token_address_with_program_id(
+                quote_info.key,
+                &self.mint_b.key(),
+                self.token_program_b.key
+            ),
+            EscrowError::VaultMismatch
+        );
+        require_keys_eq!(
+            taker_ata_b.key(),
+            get_associated_token_address_with_program_id(
+                taker.key,
+                &self.mint_b.key(),
+                self.token_program_b.key
+            ),
+            EscrowError::InvalidQuote
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"rfq",
+            quote.escrow.as_ref(),
+            quote.taker.as_ref(),
+            &[quote.bump],
+        ]];
+        release_vault(
+            &self.token_program_b.to_account_info(),
+            &self.mint_b.to_account_info(),
+            quote_vault,
+            taker_ata_b,
+            taker,
+            quote_info,
+            &signer_seeds,
+            &[],
+        )?;
+        quote.close(taker.clone())
+    }
+
+    /// Pays the accepted quote to the owner, minus the protocol fee, then releases the whole vault to its taker
+    /// Any Token-2022 transfer fee on the way out of the quote's vault is borne by the owner
+    pub fn settle(&mut self) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let amount = self.quote.amount;
+
+        let quote_seeds: [&[&[u8]]; 1] = [&[
+            b"rfq",
+            self.quote.escrow.as_ref(),
+            self.quote.taker.as_ref(),
+            &[self.quote.bump],
+        ]];
+        let fee = self.config.fee(amount)?;
+        if fee > 0 {
+            let accounts = TransferChecked {
+                from: self.quote_vault.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.quote.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_b.to_account_info(),
+                accounts,
+                &quote_seeds,
+            );
+            transfer_checked_with_hook(ctx, fee, self.mint_b.decimals, &[])?;
+        }
+        release_vault(
+            &self.token_program_b.to_account_info(),
+            &self.mint_b.to_account_info(),
+            &self.quote_vault.to_account_info(),
+            &self.owner_ata_b.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.quote.to_account_info(),
+            &quote_seeds,
+            &[],
+        )?;
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let escrow_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+        let (_, vault_rent_to) = self.rent_recipients()?;
+        let deposit = release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.taker_ata_a.to_account_info(),
+            &vault_rent_to,
+            &self.escrow.to_account_info(),
+            &escrow_seeds,
+            &[],
+        )?;
+        // Released vaults must have held at least the recorded deposit the quote was made for
+        require!(deposit >= escrow.deposit, EscrowError::VaultDrained);
+
+        let now = Clock::get()?.unix_timestamp;
+        emit!(RfqQuoteEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount,
+            accepted: true,
+            timestamp: now,
+        });
+        emit!(TakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            receive: amount,
+            remaining: 0,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Closes the escrow account once settled, sending its rent where the maker chose, back to them by default
+    /// Escrows kept as receipts are marked as filled instead, until the maker closes them with `close_receipt`
+    pub fn close_escrow(&mut self) -> Result<()> {
+        reindex(
+            &mut *self.escrow.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+        if self.escrow.load()?.keep_receipt() {
+            self.escrow.load_mut()?.set_status(EscrowStatus::Filled);
+            return Ok(());
+        }
+        let (escrow_rent_to, _) = self.rent_recipients()?;
+        self.escrow.close(escrow_rent_to)
+    }
+
+    /// Accounts receiving the rent of the escrow and of its vault once closed, following the maker's choice
+    fn rent_recipients(&self) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+        let taker = self.taker.to_account_info();
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        rent_recipients(
+            self.escrow.load()?.rent_destination(),
+            &self.mint_a.key(),
+            &self.maker.to_account_info(),
+            &taker,
+            Some(&taker),
+            rent_treasury.as_ref(),
+        )
+    }
+}
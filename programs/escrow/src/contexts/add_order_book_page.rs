@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{EscrowError, OrderBook, OrderBookPage, OrderBookPageEvent};
+
+/// Defines the accounts needed for the permissionless `add_order_book_page` instruction, growing the order book of a mint pair by a page
+#[derive(Accounts)]
+pub struct AddOrderBookPage<'info> {
+    /// Pays the rent of the page, and of the order book when it is the pair's first page
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The mint the escrows listed in the order book offer
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint the escrows listed in the order book ask for
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The order book of the mint pair, created along with its first page
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + OrderBook::INIT_SPACE,
+        seeds = [b"order_book", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub order_book: Account<'info, OrderBook>,
+
+    /// The new page, following the order book's last one
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + OrderBookPage::INIT_SPACE,
+        seeds = [
+            b"order_book_page",
+            mint_a.key().as_ref(),
+            mint_b.key().as_ref(),
+            order_book.pages.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub order_book_page: Account<'info, OrderBookPage>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddOrderBookPage<'info> {
+    /// Appends an empty page to the order book of the mint pair, creating the order book if it has none yet
+    pub fn add_page(&mut self, bumps: &AddOrderBookPageBumps) -> Result<()> {
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+
+        let index = self.order_book.pages;
+        self.order_book.set_inner(OrderBook {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            pages: index.checked_add(1).ok_or(EscrowError::MathOverflow)?,
+            bump: bumps.order_book,
+        });
+        self.order_book_page.set_inner(OrderBookPage {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            index,
+            escrows: Vec::new(),
+            bump: bumps.order_book_page,
+        });
+
+        emit!(OrderBookPageEvent {
+            page: self.order_book_page.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            index,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+use crate::{EscrowError, MilestoneEscrow, MilestoneEvent, MilestoneStatus};
+
+/// Defines the accounts needed for the `approve_milestone` instruction, where the maker signs off a delivered milestone
+#[derive(Accounts)]
+pub struct ApproveMilestone<'info> {
+    /// The maker of the milestone escrow, the only one allowed to approve its milestones
+    pub maker: Signer<'info>,
+
+    /// The milestone escrow holding the milestone being approved
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        seeds = [b"milestones", maker.key().as_ref(), milestone_escrow.seed.to_le_bytes().as_ref()],
+        bump = milestone_escrow.bump
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+}
+
+impl<'info> ApproveMilestone<'info> {
+    /// Approves the pending milestone at `index`, letting the contractor claim its amount
+    pub fn approve(&mut self, index: u8) -> Result<()> {
+        let milestone = self
+            .milestone_escrow
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(EscrowError::InvalidMilestones)?;
+        require!(
+            milestone.status == MilestoneStatus::Pending,
+            EscrowError::MilestoneNotPending
+        );
+        milestone.status = MilestoneStatus::Approved;
+        let amount = milestone.amount;
+
+        emit!(MilestoneEvent {
+            milestone_escrow: self.milestone_escrow.key(),
+            index,
+            amount,
+            status: MilestoneStatus::Approved,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    helpers::release_vault, ApprovalEvent, ArbiterCommittee, DisputeResolution, Escrow,
+    EscrowError, EscrowStatus, ResolveEvent,
+};
+
+/// Defines the accounts needed for the `approve_resolution` instruction, a committee member's vote on a dispute
+/// Every approval passes the accounts the release needs, as the one reaching the committee's threshold settles the dispute
+#[derive(Accounts)]
+pub struct ApproveResolution<'info> {
+    /// The committee member approving, paying for the approvals record and the recipient's token account if needed
+    #[account(mut)]
+    pub member: Signer<'info>,
+
+    /// The maker of the escrow, receives the rent of the vault and of the escrow
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The side the member approves releasing the vault to, the owner of the maker's position or the designated taker
+    pub recipient: SystemAccount<'info>,
+
+    /// The mint of the token held in the vault
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The recipient's token account receiving the vault
+    #[account(
+        init_if_needed,
+        payer = member,
+        associated_token::mint = mint_a,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program_a
+    )]
+    pub recipient_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The committee the escrow was made with as its arbiter
+    #[account(
+        seeds = [b"committee", committee.creator.as_ref(), committee.seed.to_le_bytes().as_ref()],
+        bump = committee.bump
+    )]
+    pub committee: Box<Account<'info, ArbiterCommittee>>,
+
+    /// The approvals collected on the dispute, created by the first one
+    #[account(
+        init_if_needed,
+        payer = member,
+        space = 8 + DisputeResolution::INIT_SPACE,
+        seeds = [b"resolution", escrow.key().as_ref()],
+        bump
+    )]
+    pub resolution: Box<Account<'info, DisputeResolution>>,
+
+    /// The disputed escrow, which only its committee can resolve
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Disputed) @ EscrowError::EscrowNotDisputed,
+        constraint = escrow.load()?.arbiter() == Some(committee.key()) @ EscrowError::UnauthorizedArbiter,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the maker's deposit
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveResolution<'info> {
+    /// Records the member's approval of releasing the vault to the taker if `to_taker`, to the owner of the maker's position otherwise
+    /// Once `threshold` members approved the same side, the vault is released to it and the escrow closed like `resolve_to_*`
+    /// The approvals record is then closed, its rent going to the member whose approval settled the dispute
+    pub fn approve(
+        &mut self,
+        to_taker: bool,
+        bumps: &ApproveResolutionBumps,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let recipient = match to_taker {
+            true => escrow.taker().ok_or(EscrowError::UnauthorizedTaker)?,
+            false => escrow.owner,
+        };
+        require_keys_eq!(
+            self.recipient.key(),
+            recipient,
+            EscrowError::RecipientMismatch
+        );
+        let index = self
+            .committee
+            .member_index(&self.member.key())
+            .ok_or(EscrowError::NotCommitteeMember)?;
+
+        // A fresh record is zeroed, the first approval sets which escrow it is for
+        if self.resolution.escrow == Pubkey::default() {
+            self.resolution.escrow = self.escrow.key();
+            self.resolution.bump = bumps.resolution;
+        }
+        let approvals = self.resolution.approve(index, to_taker)?;
+        let now = Clock::get()?.unix_timestamp;
+
+        emit!(ApprovalEvent {
+            escrow: self.escrow.key(),
+            member: self.member.key(),
+            recipient,
+            approvals,
+            threshold: self.committee.threshold,
+            timestamp: now,
+        });
+        if approvals < self.committee.threshold as u32 {
+            return Ok(());
+        }
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+        let amount = release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.recipient_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+        self.resolution.close(self.member.to_account_info())?;
+
+        emit!(ResolveEvent {
+            escrow: self.escrow.key(),
+            arbiter: self.committee.key(),
+            recipient,
+            amount,
+            timestamp: now,
+        });
+
+        // Escrows kept as receipts are marked with the final status instead of being closed
+        if escrow.keep_receipt() {
+            self.escrow.load_mut()?.set_status(match to_taker {
+                true => EscrowStatus::Filled,
+                false => EscrowStatus::Refunded,
+            });
+            return Ok(());
+        }
+        self.escrow.close(self.maker.to_account_info())
+    }
+}
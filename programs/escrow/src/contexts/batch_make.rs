@@ -0,0 +1,214 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{create_account, CreateAccount},
+};
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{
+        check_mint_policy, flag_permanent_delegates, gross_up_transfer_fee,
+        transfer_checked_with_hook,
+    },
+    Config, Escrow, EscrowError, EscrowStatus, MakeEvent,
+};
+
+/// Terms of one of the escrows opened by `batch_make`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct BatchOffer {
+    pub seed: u64,    // seed of the escrow account
+    pub deposit: u64, // amount of mint_a deposited into its vault
+    pub receive: u64, // amount of mint_b the maker expects in return
+    pub expiry: i64,  // unix timestamp after which it can't be taken, 0 if none
+}
+
+/// Defines the accounts needed for the `batch_make` instruction, opening several escrows between the same mints
+/// Each escrow is passed as remaining accounts, in pairs of `[escrow, vault]` in the order of the offers
+#[derive(Accounts)]
+pub struct BatchMake<'info> {
+    /// The maker of every escrow, depositing into and paying for all of them
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint deposited into every escrow
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint expected in return by every escrow
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The maker's token account for mint_a, funding every vault
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Used to create the vault of each escrow
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BatchMake<'info> {
+    /// Number of remaining accounts passed per offer
+    pub const ACCOUNTS_PER_OFFER: usize = 2;
+
+    /// Opens one escrow per offer, with the same rules as `make`
+    pub fn make_all(
+        &mut self,
+        offers: &[BatchOffer],
+        escrow_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        require!(
+            !offers.is_empty() && escrow_accounts.len() == offers.len() * Self::ACCOUNTS_PER_OFFER,
+            EscrowError::InvalidBatch
+        );
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        require!(
+            self.config.is_mint_permitted(&self.mint_a.key())
+                && self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+        check_mint_policy(&self.config, &self.mint_a.to_account_info())?;
+        check_mint_policy(&self.config, &self.mint_b.to_account_info())?;
+
+        for (offer, accounts) in offers
+            .iter()
+            .zip(escrow_accounts.chunks(Self::ACCOUNTS_PER_OFFER))
+        {
+            let [escrow, vault] = accounts else {
+                return err!(EscrowError::InvalidBatch);
+            };
+            self.make_one(offer, escrow, vault)?;
+        }
+        Ok(())
+    }
+
+    /// Creates the escrow account and vault of a single offer and deposits into it
+    fn make_one(
+        &self,
+        offer: &BatchOffer,
+        escrow: &'info AccountInfo<'info>,
+        vault: &AccountInfo<'info>,
+    ) -> Result<()> {
+        require!(offer.deposit > 0, EscrowError::ZeroDeposit);
+        require!(offer.receive > 0, EscrowError::ZeroReceive);
+        let now = Clock::get()?.unix_timestamp;
+        if offer.expiry != 0 {
+            require!(offer.expiry > now, EscrowError::InvalidExpiry);
+        }
+
+        // Same address as `make` would use, so the escrow is taken and refunded like any other
+        let maker = self.maker.key();
+        let seed = offer.seed.to_le_bytes();
+        let (address, bump) =
+            Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed], &crate::ID);
+        require_keys_eq!(escrow.key(), address, EscrowError::InvalidBatch);
+
+        let space = Escrow::SPACE;
+        let signer_seeds: [&[&[u8]]; 1] = [&[b"escrow", maker.as_ref(), &seed, &[bump]]];
+        let create_accounts = CreateAccount {
+            from: self.maker.to_account_info(),
+            to: escrow.clone(),
+        };
+        create_account(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                create_accounts,
+                &signer_seeds,
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            &crate::ID,
+        )?;
+
+        // Initialized like Anchor's `init` constraint does, the discriminator is written on exit
+        let loader = AccountLoader::<Escrow>::try_from_unchecked(&crate::ID, escrow)?;
+        *loader.load_init()? = Escrow {
+            version: Escrow::VERSION,
+            seed: offer.seed,
+            maker,
+            owner: maker,
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit: offer.deposit,
+            receive: offer.receive,
+            price_a: offer.deposit,
+            price_b: offer.receive,
+            expiry: offer.expiry,
+            status: EscrowStatus::Open as u8,
+            // Only reached with mints that have a permanent delegate if the config flags rather than rejects them
+            permanent_delegate: flag_permanent_delegates(
+                escrow.key(),
+                [
+                    &self.mint_a.to_account_info(),
+                    &self.mint_b.to_account_info(),
+                ],
+                now,
+            )
+            .into(),
+            bump,
+            ..Default::default()
+        };
+        loader.exit(&crate::ID)?;
+
+        // The associated token program checks that `vault` is the escrow's account for mint_a
+        // A vault created beforehand is left as is, so nobody can block the escrow by creating it first
+        let create_accounts = Create {
+            payer: self.maker.to_account_info(),
+            associated_token: vault.clone(),
+            authority: escrow.clone(),
+            mint: self.mint_a.to_account_info(),
+            system_program: self.system_program.to_account_info(),
+            token_program: self.token_program_a.to_account_info(),
+        };
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            create_accounts,
+        ))?;
+
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: vault.clone(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+        // With a Token-2022 transfer fee, the maker sends enough for the vault to receive exactly `deposit`
+        let amount = gross_up_transfer_fee(&self.mint_a.to_account_info(), offer.deposit)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals, &[])?;
+
+        emit!(MakeEvent {
+            escrow: escrow.key(),
+            maker,
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit: offer.deposit,
+            receive: offer.receive,
+            expiry: offer.expiry,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
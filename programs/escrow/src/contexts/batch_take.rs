@@ -0,0 +1,425 @@
+use anchor_lang::{prelude::*, system_program};
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{
+        delist, gross_up_transfer_fee, is_native_mint, load_token_account, reindex, release_vault,
+        rent_recipients, transfer_checked_with_hook,
+    },
+    Config, Escrow, EscrowError, EscrowStatus, EventLog, FeeCurrency, FeeOverride, LogKind,
+    OrderBookPage, PairIndex, TakeEvent, TraderStats,
+};
+
+/// Defines the accounts needed for the `batch_take` instruction, filling several escrows between the same mints
+/// Each escrow is passed as remaining accounts, in groups of `[escrow, maker, owner, vault, owner_ata_b, owner_ata_a]`
+/// `owner_ata_a` is the program's ID when not passed, as for Anchor's optional accounts
+#[derive(Accounts)]
+pub struct BatchTake<'info> {
+    /// The taker filling every escrow
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The mint deposited in every escrow
+    /// Writable so Token-2022 transfer fees withheld in the vaults can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint paid to every maker
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the taker receiving mint_a from every vault
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Associated token account of the taker paying mint_b to every maker
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payments
+    /// Batches are only paid in mint_b, so configs charging the fee in another currency only fill through `take`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused,
+        constraint = config.fee_currency == FeeCurrency::MintB @ EscrowError::BatchFeeCurrency
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// A fee the admin negotiated for one of the escrows or its maker, charged on the escrows it applies to
+    /// Optional, the other escrows are charged the config's fee, or the taker's fee tier
+    #[account(
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Option<Box<Account<'info, FeeOverride>>>,
+
+    /// The taker's cumulative volume in mint_b, created on their first tracked fill and paid for by them
+    /// Optional, each fill is charged the rate of the fee tier reached before it when passed, and adds to it
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + TraderStats::INIT_SPACE,
+        seeds = [b"trader_stats", taker.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub trader_stats: Option<Box<Account<'info, TraderStats>>>,
+
+    /// CHECK: Receives the rent of the escrows and their vaults whose maker chose a treasury for it, checked against each escrow's in `rent_recipients`
+    /// Escrows sending their rent to different treasuries can't be filled in the same batch
+    #[account(mut)]
+    pub rent_treasury: Option<UncheckedAccount<'info>>,
+
+    /// The order book page listing the escrows, which every filled escrow is removed from
+    /// Required when any of the escrows is listed, escrows listed in different pages are filled in separate batches
+    #[account(mut)]
+    pub order_book_page: Option<Box<Account<'info, OrderBookPage>>>,
+
+    /// The index of the pair, which every filled escrow is removed from
+    /// Required when any of the escrows is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// The program's event log, recording each fill when passed
+    #[account(
+        mut,
+        seeds = [b"event_log"],
+        bump
+    )]
+    pub event_log: Option<AccountLoader<'info, EventLog>>,
+
+    /// Used to create the position owners' token accounts for mint_b if needed
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> BatchTake<'info> {
+    /// Number of remaining accounts passed per escrow
+    pub const ACCOUNTS_PER_ESCROW: usize = 6;
+
+    /// Fills every escrow in full, as `take` would
+    pub fn take_all(&mut self, escrow_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let groups = escrow_accounts.chunks_exact(Self::ACCOUNTS_PER_ESCROW);
+        require!(
+            !escrow_accounts.is_empty() && groups.remainder().is_empty(),
+            EscrowError::InvalidBatch
+        );
+
+        for accounts in groups {
+            let [escrow, maker, owner, vault, owner_ata_b, owner_ata_a] = accounts else {
+                return err!(EscrowError::InvalidBatch);
+            };
+            let owner_ata_a = (owner_ata_a.key() != crate::ID).then_some(owner_ata_a);
+            self.take_one(escrow, maker, owner, vault, owner_ata_b, owner_ata_a)?;
+        }
+        Ok(())
+    }
+
+    /// Pays the owner of a single escrow's maker position, releases its vault to the taker and closes it
+    fn take_one(
+        &mut self,
+        escrow_info: &'info AccountInfo<'info>,
+        maker: &AccountInfo<'info>,
+        owner: &AccountInfo<'info>,
+        vault: &AccountInfo<'info>,
+        owner_ata_b: &AccountInfo<'info>,
+        owner_ata_a: Option<&AccountInfo<'info>>,
+    ) -> Result<()> {
+        // Checks it is owned by this program with the escrow discriminator, so it can only be a genuine escrow
+        let loader = AccountLoader::<Escrow>::try_from(escrow_info)?;
+        // Escrows still in an older layout are shorter than `Escrow` and can't be loaded until migrated
+        require!(
+            escrow_info.data_len() == Escrow::SPACE,
+            EscrowError::EscrowNotMigrated
+        );
+        let escrow = *loader.load()?;
+
+        require_keys_eq!(escrow.maker, maker.key(), EscrowError::MakerMismatch);
+        require_keys_neq!(escrow.maker, self.taker.key(), EscrowError::SelfTake);
+        require_keys_eq!(escrow.owner, owner.key(), EscrowError::NotPositionOwner);
+        require_keys_eq!(escrow.mint_a, self.mint_a.key(), EscrowError::MintMismatch);
+        require_keys_eq!(escrow.mint_b, self.mint_b.key(), EscrowError::MintMismatch);
+        require!(
+            escrow.has_status(EscrowStatus::Open),
+            EscrowError::EscrowNotOpen
+        );
+        let now = Clock::get()?.unix_timestamp;
+        require!(!escrow.is_expired(now), EscrowError::EscrowExpired);
+        // Escrows reserved by a commitment can only be taken by revealing it
+        require!(!escrow.is_committed(now), EscrowError::CommitmentPending);
+        // Escrows held by a reservation can only be taken by its taker, through `take`
+        require!(
+            !escrow.is_reserved(Clock::get()?.slot),
+            EscrowError::EscrowReserved
+        );
+        // As do escrows made from a template or gated, whose conditions only `take` enforces
+        require!(escrow.template().is_none(), EscrowError::TemplatedEscrow);
+        require!(
+            escrow.gate().is_none() && escrow.gatekeeper_network().is_none(),
+            EscrowError::GatedEscrow
+        );
+        require!(escrow.chained_seed().is_none(), EscrowError::ChainedEscrow);
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        // Hashlocked, vesting and scheduled escrows need their own take flow
+        escrow.check_preimage(None)?;
+        require!(
+            escrow.vesting().is_none() && escrow.schedule().is_none(),
+            EscrowError::InvalidBatch
+        );
+        require!(
+            !escrow.collection_offer(),
+            EscrowError::CollectionOfferRequiresNft
+        );
+        require_keys_eq!(
+            vault.key(),
+            escrow.vault_address(escrow_info.key, self.token_program_a.key)?,
+            EscrowError::VaultMismatch
+        );
+
+        // Proceeds go to the payout account the owner designated, which already exists, or else to their associated token account
+        match escrow.payout() {
+            Some(payout) => {
+                require_keys_eq!(owner_ata_b.key(), payout, EscrowError::PayoutMismatch)
+            }
+            None => {
+                let create_accounts = Create {
+                    payer: self.taker.to_account_info(),
+                    associated_token: owner_ata_b.clone(),
+                    authority: owner.clone(),
+                    mint: self.mint_b.to_account_info(),
+                    system_program: self.system_program.to_account_info(),
+                    token_program: self.token_program_b.to_account_info(),
+                };
+                create_idempotent(CpiContext::new(
+                    self.associated_token_program.to_account_info(),
+                    create_accounts,
+                ))?;
+            }
+        }
+
+        let fee = self.fee(escrow_info.key, &escrow)?;
+        if fee > 0 {
+            self.transfer_b(self.fee_vault.to_account_info(), fee)?;
+        }
+        let proceeds = escrow
+            .receive
+            .checked_sub(fee)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.transfer_b(owner_ata_b.clone(), proceeds)?;
+
+        // The rent of the escrow and its vault follows the maker's choice, as on a `take`
+        let taker = self.taker.to_account_info();
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        let (escrow_rent_to, vault_rent_to) = rent_recipients(
+            escrow.rent_destination(),
+            &self.mint_a.key(),
+            maker,
+            &taker,
+            Some(&taker),
+            rent_treasury.as_ref(),
+        )?;
+
+        let (address_maker, seed) = (escrow.address_maker(), escrow.address_seed());
+        let signer_seeds: [&[&[u8]]; 1] =
+            [&[b"escrow", address_maker.as_ref(), &seed, &[escrow.bump]]];
+        // As on a `take`, a surplus donated to the vault goes back to the owner when their mint_a account is passed,
+        // and along with the deposit to the taker otherwise, or when a wrapped SOL vault unwraps it
+        if let Some(owner_ata_a) = owner_ata_a.filter(|_| !is_native_mint(&self.mint_a.key())) {
+            self.return_surplus(
+                &escrow,
+                escrow_info,
+                owner,
+                vault,
+                owner_ata_a,
+                &signer_seeds,
+            )?;
+        }
+        let released = release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            vault,
+            &self.taker_ata_a.to_account_info(),
+            &vault_rent_to,
+            escrow_info,
+            &signer_seeds,
+            &[],
+        )?;
+        // Released vaults must have held at least the recorded deposit the maker is being paid for
+        require!(released >= escrow.deposit, EscrowError::VaultDrained);
+
+        self.track_volume(escrow.receive);
+        if let Some(event_log) = &self.event_log {
+            event_log.load_mut()?.record(
+                LogKind::Take,
+                escrow_info.key(),
+                self.taker.key(),
+                escrow.deposit,
+                now,
+            )?;
+        }
+        emit!(TakeEvent {
+            escrow: escrow_info.key(),
+            maker: maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit: escrow.deposit,
+            receive: escrow.receive,
+            remaining: 0,
+            timestamp: now,
+        });
+
+        delist(&loader, self.order_book_page.as_deref_mut())?;
+        reindex(
+            &mut *loader.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+
+        // Escrows kept as receipts are marked as filled instead, until the maker closes them with `close_receipt`
+        if escrow.keep_receipt() {
+            loader.load_mut()?.set_status(EscrowStatus::Filled);
+            return Ok(());
+        }
+
+        // Closes the escrow the way Anchor's `close` constraint does, its rent going where the maker chose
+        let lamports = escrow_rent_to
+            .lamports()
+            .checked_add(escrow_info.lamports())
+            .ok_or(EscrowError::MathOverflow)?;
+        **escrow_rent_to.try_borrow_mut_lamports()? = lamports;
+        **escrow_info.try_borrow_mut_lamports()? = 0;
+        escrow_info.assign(&system_program::ID);
+        escrow_info.realloc(0, false).map_err(Into::into)
+    }
+
+    /// Transfers what `vault` holds past the deposit of `escrow` to `owner_ata_a`, which must be the owner's associated token account
+    fn return_surplus(
+        &self,
+        escrow: &Escrow,
+        escrow_info: &AccountInfo<'info>,
+        owner: &AccountInfo<'info>,
+        vault: &AccountInfo<'info>,
+        owner_ata_a: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        require_keys_eq!(
+            owner_ata_a.key(),
+            get_associated_token_address_with_program_id(
+                owner.key,
+                &self.mint_a.key(),
+                self.token_program_a.key
+            ),
+            ErrorCode::ConstraintAssociated
+        );
+        let surplus = load_token_account(vault, self.token_program_a.key)?
+            .amount
+            .checked_sub(escrow.deposit)
+            .ok_or(EscrowError::VaultDrained)?;
+        if surplus == 0 {
+            return Ok(());
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: vault.clone(),
+            mint: self.mint_a.to_account_info(),
+            to: owner_ata_a.clone(),
+            authority: escrow_info.clone(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            transfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked_with_hook(cpi_ctx, surplus, self.mint_a.decimals, &[])
+    }
+
+    /// Protocol fee owed on filling `escrow` at `address`, as `take` charges it
+    /// The fee override if it applies to the escrow or its maker, or else the fee tier of the taker's volume so far
+    fn fee(&self, address: &Pubkey, escrow: &Escrow) -> Result<u64> {
+        let fee_bps = match (&self.fee_override, &self.trader_stats) {
+            (Some(fee_override), _)
+                if fee_override.subject == *address || fee_override.subject == escrow.maker =>
+            {
+                fee_override.fee_bps
+            }
+            (_, Some(trader_stats)) => self.config.tier_fee_bps(trader_stats.volume),
+            _ => self.config.fee_bps,
+        };
+        self.config.fee_at(escrow.receive, fee_bps)
+    }
+
+    /// Adds a fill paying `receive` to the taker's stats if passed
+    fn track_volume(&mut self, receive: u64) {
+        let (taker, mint_b) = (self.taker.key(), self.mint_b.key());
+        if let Some(trader_stats) = &mut self.trader_stats {
+            // Fresh stats are zeroed, the first tracked fill sets who and what they track
+            if trader_stats.fills == 0 {
+                trader_stats.trader = taker;
+                trader_stats.mint = mint_b;
+                trader_stats.bump = Pubkey::find_program_address(
+                    &[b"trader_stats", taker.as_ref(), mint_b.as_ref()],
+                    &crate::ID,
+                )
+                .1;
+            }
+            trader_stats.volume = trader_stats.volume.saturating_add(receive);
+            trader_stats.fills = trader_stats.fills.saturating_add(1);
+        }
+    }
+
+    /// Transfers `amount` of mint_b from the taker to the `to` token account
+    /// With a Token-2022 transfer fee, the taker sends enough for `to` to receive exactly `amount`
+    fn transfer_b(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, &[])
+    }
+}
@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Auction, BidEvent, Config, EscrowError, SealedBid, SealedBidSettledEvent,
+};
+
+/// Defines the accounts needed for the `bid` instruction, outbidding the current highest bidder of an auction
+/// Also used by `reveal_bid`, which reveals a sealed bid and outbids the current highest bidder if it beats them
+#[derive(Accounts)]
+pub struct Bid<'info> {
+    /// The bidder, locking their bid of mint_b in the auction's bid vault, and refunded their sealed bid's bond when revealing it
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// The mint bids are made in
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The bidder's token account for mint_b, funding the bid
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::authority = bidder,
+        token::token_program = token_program_b
+    )]
+    pub bidder_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// A token account of the previous highest bidder, getting their bid back
+    /// Only needed once the auction has a bid
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+        constraint = Some(previous_bidder_ata_b.owner) == auction.bidder @ EscrowError::RecipientMismatch
+    )]
+    pub previous_bidder_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, which must not be paused for bids to be placed
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The auction being bid on
+    #[account(
+        mut,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"auction", auction.maker.as_ref(), auction.seed.to_le_bytes().as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// The bidder's sealed bid, closed to them with its bond once revealed
+    /// Only needed by `reveal_bid`
+    #[account(
+        mut,
+        close = bidder,
+        has_one = auction @ EscrowError::InvalidSealedBid,
+        has_one = bidder @ EscrowError::InvalidSealedBid,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = sealed_bid.bump
+    )]
+    pub sealed_bid: Option<Account<'info, SealedBid>>,
+
+    /// Vault holding the highest bid, controlled by the auction PDA
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program_b
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+impl<'info> Bid<'info> {
+    /// Refunds the previous highest bid, if any, and locks `amount` in its place
+    /// The bid is grossed up so the vault holds exactly `amount` with a Token-2022 transfer fee, the refund's fee is borne by its recipient
+    pub fn bid(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(!self.auction.is_sealed(), EscrowError::SealedAuction);
+        require!(
+            !self.auction.has_ended(Clock::get()?.unix_timestamp),
+            EscrowError::AuctionEnded
+        );
+        require!(self.auction.is_winning_bid(amount), EscrowError::BidTooLow);
+
+        self.outbid(amount, hook_accounts)
+    }
+
+    /// Reveals the bidder's sealed bid of `amount`, committed with `salt`, returning its bond
+    /// A bid beating the current highest one outbids it, any other is simply closed, so reveals never fail for being low
+    pub fn reveal(
+        &mut self,
+        amount: u64,
+        salt: [u8; 32],
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.auction.is_revealing(now), EscrowError::NotRevealing);
+        let Some(sealed_bid) = &self.sealed_bid else {
+            return err!(EscrowError::InvalidSealedBid);
+        };
+        require!(
+            sealed_bid.hash
+                == Auction::bid_commitment(&self.auction.key(), &self.bidder.key(), amount, &salt),
+            EscrowError::InvalidSealedBid
+        );
+
+        emit!(SealedBidSettledEvent {
+            auction: self.auction.key(),
+            bidder: self.bidder.key(),
+            bond: sealed_bid.bond,
+            forfeited: false,
+            timestamp: now,
+        });
+
+        if self.auction.is_winning_bid(amount) {
+            self.outbid(amount, hook_accounts)?;
+        }
+        Ok(())
+    }
+
+    /// Refunds the previous highest bid, if any, and locks `amount` in its place as the highest bid
+    fn outbid(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if self.auction.bidder.is_some() {
+            let Some(previous_bidder_ata_b) = &self.previous_bidder_ata_b else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+
+            let signer_seeds: [&[&[u8]]; 1] = [&[
+                b"auction",
+                self.auction.maker.as_ref(),
+                &self.auction.seed.to_le_bytes()[..],
+                &[self.auction.bump],
+            ]];
+            let refund_accounts = TransferChecked {
+                from: self.bid_vault.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+                to: previous_bidder_ata_b.to_account_info(),
+                authority: self.auction.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                self.token_program_b.to_account_info(),
+                refund_accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(
+                cpi_ctx,
+                self.auction.bid,
+                self.mint_b.decimals,
+                hook_accounts,
+            )?;
+        }
+
+        let transfer_accounts = TransferChecked {
+            from: self.bidder_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.bid_vault.to_account_info(),
+            authority: self.bidder.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+        let gross = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, gross, self.mint_b.decimals, hook_accounts)?;
+
+        self.auction.bidder = Some(self.bidder.key());
+        self.auction.bid = amount;
+
+        emit!(BidEvent {
+            auction: self.auction.key(),
+            bidder: self.bidder.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
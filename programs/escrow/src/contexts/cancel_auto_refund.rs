@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{AutoRefund, AutoRefundEvent};
+
+/// Defines the accounts needed for the `cancel_auto_refund` instruction, dropping an escrow's scheduled refund
+#[derive(Accounts)]
+pub struct CancelAutoRefund<'info> {
+    /// The owner who scheduled the refund, receiving the tip and the schedule's rent back
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The escrow's schedule, closed to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"auto_refund", auto_refund.escrow.as_ref()],
+        bump = auto_refund.bump
+    )]
+    pub auto_refund: Account<'info, AutoRefund>,
+}
+
+impl<'info> CancelAutoRefund<'info> {
+    /// Leaves the escrow open until the owner refunds it, or it is taken
+    pub fn cancel_auto_refund(&mut self) -> Result<()> {
+        emit!(AutoRefundEvent {
+            escrow: self.auto_refund.escrow,
+            owner: self.owner.key(),
+            keeper: None,
+            execute_after: 0,
+            tip: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
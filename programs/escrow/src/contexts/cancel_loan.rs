@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{helpers::release_vault, EscrowError, Loan, SettleLoanEvent};
+
+/// Defines the accounts needed for the `cancel_loan` instruction, where the maker withdraws a loan no lender has taken
+#[derive(Accounts)]
+pub struct CancelLoan<'info> {
+    /// The maker of the loan, receives the collateral and the rent of the loan and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the collateral
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// Associated token account of the maker receiving the collateral back
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The loan being cancelled, which must not have a lender yet, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = loan.lender.is_none() @ EscrowError::LoanAlreadyTaken,
+        seeds = [b"loan", maker.key().as_ref(), loan.seed.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    /// Vault holding the collateral
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_a`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelLoan<'info> {
+    /// Releases the collateral back to the maker and closes the vault
+    pub fn cancel(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"loan",
+            self.maker.to_account_info().key.as_ref(),
+            &self.loan.seed.to_le_bytes()[..],
+            &[self.loan.bump],
+        ]];
+        release_vault(
+            &self.token_program.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.maker_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.loan.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(SettleLoanEvent {
+            loan: self.loan.key(),
+            maker: self.maker.key(),
+            lender: None,
+            repayment: 0,
+            liquidated: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
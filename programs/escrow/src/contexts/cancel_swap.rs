@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{helpers::release_vault, CancelSwapEvent, EscrowError, Swap};
+
+/// Defines the accounts needed for the `cancel_swap` instruction, letting either side back out before settlement
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    /// Either party of the swap, paying for any token account that has to be recreated
+    #[account(
+        mut,
+        constraint = swap.is_party(signer.key) @ EscrowError::NotSwapParty
+    )]
+    pub signer: Signer<'info>,
+
+    /// The maker of the swap, gets mint_a back along with the swap's and `vault_a`'s rent
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The taker of the swap, gets mint_b back along with `vault_b`'s rent if they funded it
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    /// The mint locked by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint locked by the taker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_b
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The maker's token account getting mint_a back
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The taker's token account getting mint_b back, only needed once the taker has funded the swap
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The swap being cancelled, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = taker @ EscrowError::UnauthorizedTaker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"swap", maker.key().as_ref(), swap.seed.to_le_bytes().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Box<Account<'info, Swap>>,
+
+    /// Vault holding the maker's side of the swap
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault holding the taker's side of the swap, only needed once the taker has funded the swap
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelSwap<'info> {
+    /// Returns each locked side to the party that locked it and closes the vaults
+    pub fn cancel(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"swap",
+            self.maker.to_account_info().key.as_ref(),
+            &self.swap.seed.to_le_bytes()[..],
+            &[self.swap.bump],
+        ]];
+
+        release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault_a.to_account_info(),
+            &self.maker_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.swap.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        if self.swap.funded {
+            let (Some(vault_b), Some(taker_ata_b)) = (&self.vault_b, &self.taker_ata_b) else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+            release_vault(
+                &self.token_program_b.to_account_info(),
+                &self.mint_b.to_account_info(),
+                &vault_b.to_account_info(),
+                &taker_ata_b.to_account_info(),
+                &self.taker.to_account_info(),
+                &self.swap.to_account_info(),
+                &signer_seeds,
+                hook_accounts,
+            )?;
+        }
+
+        emit!(CancelSwapEvent {
+            swap: self.swap.key(),
+            cancelled_by: self.signer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
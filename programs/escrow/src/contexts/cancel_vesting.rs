@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    helpers::{is_native_mint, release_vault, transfer_checked_with_hook},
+    CancelVestingEvent, Escrow, EscrowError, EscrowStatus,
+};
+
+/// Defines the accounts needed for the `cancel_vesting` instruction, ending a vesting escrow early with the consent of both sides
+#[derive(Accounts)]
+pub struct CancelVesting<'info> {
+    /// The owner of the maker's position, receiving the part of the deposit that hasn't vested yet
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The taker who filled the escrow, receiving what has vested and not been claimed yet
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the escrow, receives the escrow's rent once it is closed
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the vesting deposit
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// Associated token account of the taker receiving the vested mint_a
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// Associated token account of the owner receiving the unvested mint_a
+    /// Not needed when mint_a is wrapped SOL, closing the vault then unwraps it straight to the owner
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The escrow whose deposit is vesting to the taker
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Vesting) @ EscrowError::EscrowNotOpen,
+        constraint = escrow.load()?.taker() == Some(taker.key()) @ EscrowError::UnauthorizedTaker,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the part of the deposit not claimed yet
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CancelVesting<'info> {
+    /// Pays the taker what has vested but not been claimed, then returns the rest of the vault to the owner and closes it
+    pub fn cancel(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let vested = escrow.vested(Clock::get()?.unix_timestamp)?;
+        let claimable = vested
+            .checked_sub(escrow.claimed)
+            .ok_or(EscrowError::MathOverflow)?;
+        // Anything donated to the vault is returned along with the unvested deposit
+        let refunded = self
+            .vault
+            .amount
+            .checked_sub(claimable)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        if claimable > 0 {
+            let accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: self.taker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, claimable, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        // A wrapped SOL vault can be closed with its balance, which unwraps it into the owner's lamports
+        if is_native_mint(&self.mint_a.key()) {
+            let accounts = CloseAccount {
+                account: self.vault.to_account_info(),
+                destination: self.owner.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            close_account(ctx)?;
+        } else {
+            let owner_ata_a = self
+                .owner_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            release_vault(
+                &self.token_program_a.to_account_info(),
+                &self.mint_a.to_account_info(),
+                &self.vault.to_account_info(),
+                &owner_ata_a.to_account_info(),
+                &self.owner.to_account_info(),
+                &self.escrow.to_account_info(),
+                &signer_seeds,
+                hook_accounts,
+            )?;
+        }
+        self.escrow.load_mut()?.claimed = vested;
+
+        emit!(CancelVestingEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            claimed: claimable,
+            refunded,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        // Escrows kept as receipts are marked as refunded instead, until the maker closes them with `close_receipt`
+        if escrow.keep_receipt() {
+            self.escrow.load_mut()?.set_status(EscrowStatus::Refunded);
+            return Ok(());
+        }
+        self.escrow.close(self.maker.to_account_info())
+    }
+}
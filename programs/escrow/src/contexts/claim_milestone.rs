@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{release_vault, transfer_checked_with_hook},
+    EscrowError, MilestoneEscrow, MilestoneEvent, MilestoneStatus,
+};
+
+/// Defines the accounts needed for the `claim_milestone` instruction, paying an approved milestone to the contractor
+#[derive(Accounts)]
+pub struct ClaimMilestone<'info> {
+    /// The contractor of the milestone escrow, the only one allowed to claim its milestones
+    #[account(mut)]
+    pub contractor: Signer<'info>,
+
+    /// The maker of the milestone escrow, receives the rent of the escrow and its vault once every milestone is claimed
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint the milestones are paid in
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Associated token account of the contractor receiving the milestone's amount
+    #[account(
+        init_if_needed,
+        payer = contractor,
+        associated_token::mint = mint,
+        associated_token::authority = contractor,
+        associated_token::token_program = token_program
+    )]
+    pub contractor_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The milestone escrow holding the approved milestone
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = contractor @ EscrowError::UnauthorizedTaker,
+        has_one = mint @ EscrowError::MintMismatch,
+        seeds = [b"milestones", maker.key().as_ref(), milestone_escrow.seed.to_le_bytes().as_ref()],
+        bump = milestone_escrow.bump
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+
+    /// Vault holding the deposit for every milestone not claimed yet
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimMilestone<'info> {
+    /// Transfers the amount of the approved milestone at `index` to the contractor
+    /// Once every milestone is claimed, the whole vault is released to the contractor and the escrow closed to the maker
+    /// Any Token-2022 transfer fee on the release is borne by the contractor
+    pub fn claim(&mut self, index: u8, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let milestone = self
+            .milestone_escrow
+            .milestones
+            .get_mut(index as usize)
+            .ok_or(EscrowError::InvalidMilestones)?;
+        require!(
+            milestone.status == MilestoneStatus::Approved,
+            EscrowError::MilestoneNotApproved
+        );
+        milestone.status = MilestoneStatus::Claimed;
+        let amount = milestone.amount;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"milestones",
+            self.maker.to_account_info().key.as_ref(),
+            &self.milestone_escrow.seed.to_le_bytes()[..],
+            &[self.milestone_escrow.bump],
+        ]];
+
+        let complete = self.milestone_escrow.is_complete();
+        if complete {
+            // Anything donated to the vault goes along, it must be empty to be closed
+            release_vault(
+                &self.token_program.to_account_info(),
+                &self.mint.to_account_info(),
+                &self.vault.to_account_info(),
+                &self.contractor_ata.to_account_info(),
+                &self.maker.to_account_info(),
+                &self.milestone_escrow.to_account_info(),
+                &signer_seeds,
+                hook_accounts,
+            )?;
+        } else {
+            let accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.contractor_ata.to_account_info(),
+                authority: self.milestone_escrow.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, amount, self.mint.decimals, hook_accounts)?;
+        }
+
+        emit!(MilestoneEvent {
+            milestone_escrow: self.milestone_escrow.key(),
+            index,
+            amount,
+            status: MilestoneStatus::Claimed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        if complete {
+            self.milestone_escrow.close(self.maker.to_account_info())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{release_vault, transfer_checked_with_hook},
+    ClaimEvent, Escrow, EscrowError, EscrowStatus,
+};
+
+/// Defines the accounts needed for the `claim_vested` instruction, releasing the vested part of the deposit to the taker
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    /// The taker who filled the escrow, the only one allowed to claim its deposit
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the escrow, receives the escrow's rent once the whole deposit has been claimed
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the vesting deposit
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// Associated token account of the taker receiving the vested mint_a
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The escrow whose deposit is vesting to the taker
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Vesting) @ EscrowError::EscrowNotOpen,
+        constraint = escrow.load()?.taker() == Some(taker.key()) @ EscrowError::UnauthorizedTaker,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the part of the deposit not claimed yet
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ClaimVested<'info> {
+    /// Transfers what has vested since the last claim to the taker
+    /// Once fully vested, the whole vault is released and closed to the taker, and the escrow is closed
+    pub fn claim(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let vested = escrow.vested(Clock::get()?.unix_timestamp)?;
+        let amount = vested
+            .checked_sub(escrow.claimed)
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(amount > 0, EscrowError::NothingToClaim);
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        if vested == escrow.deposit {
+            // Anything donated to the vault goes along, it must be empty to be closed
+            release_vault(
+                &self.token_program_a.to_account_info(),
+                &self.mint_a.to_account_info(),
+                &self.vault.to_account_info(),
+                &self.taker_ata_a.to_account_info(),
+                &self.taker.to_account_info(),
+                &self.escrow.to_account_info(),
+                &signer_seeds,
+                hook_accounts,
+            )?;
+        } else {
+            let accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: self.taker_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)?;
+        }
+        self.escrow.load_mut()?.claimed = vested;
+
+        emit!(ClaimEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount,
+            claimed: vested,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        if vested < escrow.deposit {
+            return Ok(());
+        }
+        // Escrows kept as receipts are marked as filled instead, until the maker closes them with `close_receipt`
+        if escrow.keep_receipt() {
+            self.escrow.load_mut()?.set_status(EscrowStatus::Filled);
+            return Ok(());
+        }
+        self.escrow.close(self.maker.to_account_info())
+    }
+}
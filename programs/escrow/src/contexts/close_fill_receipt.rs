@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::FillReceipt;
+
+/// Defines the accounts needed for the `close_fill_receipt` instruction, letting a taker reclaim the rent of their fill receipt
+#[derive(Accounts)]
+pub struct CloseFillReceipt<'info> {
+    /// The taker the receipt records fills of, receives its rent back
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The fill receipt, closed to the taker
+    /// Works whether or not the escrow is still open
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker,
+        seeds = [b"fill_receipt", fill_receipt.escrow.as_ref(), taker.key().as_ref()],
+        bump = fill_receipt.bump
+    )]
+    pub fill_receipt: Account<'info, FillReceipt>,
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::{EscrowError, PaymentOptions, PaymentOptionsEvent};
+
+/// Defines the accounts needed for the `close_payment_options` instruction, accepting only the escrow's mint_b again
+/// Also reclaims the rent of options left behind once their escrow is settled
+#[derive(Accounts)]
+pub struct ClosePaymentOptions<'info> {
+    /// The owner who set the options, receives their rent back
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The payment options, closed to the owner
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        seeds = [b"payment_options", payment_options.escrow.as_ref()],
+        bump = payment_options.bump
+    )]
+    pub payment_options: Account<'info, PaymentOptions>,
+}
+
+impl<'info> ClosePaymentOptions<'info> {
+    /// Stops accepting the other mints as payment
+    pub fn close_payment_options(&self) -> Result<()> {
+        emit!(PaymentOptionsEvent {
+            escrow: self.payment_options.escrow,
+            owner: self.owner.key(),
+            legs: vec![],
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError};
+
+/// Defines the accounts needed for the `close_receipt` instruction, closing an escrow kept as a receipt once settled
+#[derive(Accounts)]
+pub struct CloseReceipt<'info> {
+    /// The maker of the escrow, receiving the rent of the receipt
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The settled escrow kept as a receipt, its vault has already been closed
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        constraint = escrow.load()?.is_settled() @ EscrowError::EscrowStillOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
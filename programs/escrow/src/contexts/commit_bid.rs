@@ -0,0 +1,77 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{Auction, CommitBidEvent, Config, EscrowError, SealedBid};
+
+/// Defines the accounts needed for the `commit_bid` instruction, placing a sealed bid on an auction
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    /// The bidder committing to a bid, locking the bond and paying the sealed bid's rent
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+
+    /// The program's global config, which must not be paused for bids to be placed
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The sealed-bid auction being bid on
+    #[account(
+        constraint = auction.is_sealed() @ EscrowError::InvalidSealedBid,
+        seeds = [b"auction", auction.maker.as_ref(), auction.seed.to_le_bytes().as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// The sealed bid holding the commitment and bond, one per bidder and auction
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + SealedBid::INIT_SPACE,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CommitBid<'info> {
+    /// Commits to a bid hashing to `hash`, see `Auction::bid_commitment`, locking `bond` lamports until it is revealed
+    /// Bids never revealed forfeit the bond to the maker, so committing to bids with no intention of paying costs the bidder
+    pub fn commit_bid(&mut self, hash: [u8; 32], bond: u64, bumps: &CommitBidBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(!self.auction.has_ended(now), EscrowError::AuctionEnded);
+        require!(bond >= SealedBid::MIN_BOND, EscrowError::InvalidSealedBid);
+
+        let transfer_accounts = Transfer {
+            from: self.bidder.to_account_info(),
+            to: self.sealed_bid.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, bond)?;
+
+        self.sealed_bid.set_inner(SealedBid {
+            auction: self.auction.key(),
+            maker: self.auction.maker,
+            bidder: self.bidder.key(),
+            hash,
+            bond,
+            bump: bumps.sealed_bid,
+        });
+
+        emit!(CommitBidEvent {
+            auction: self.auction.key(),
+            bidder: self.bidder.key(),
+            bond,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
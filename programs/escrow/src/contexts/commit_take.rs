@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+use crate::{CommitEvent, Escrow, EscrowError, EscrowStatus};
+
+/// Defines the accounts needed for the `commit_take` instruction, reserving an escrow for the taker before they take it
+#[derive(Accounts)]
+pub struct CommitTake<'info> {
+    /// The taker committing to take the escrow
+    pub taker: Signer<'info>,
+
+    /// The open escrow being reserved
+    #[account(
+        mut,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> CommitTake<'info> {
+    /// Records `hash` as the escrow's commitment, unless another one is still pending
+    pub fn commit(&mut self, hash: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let mut escrow = self.escrow.load_mut()?;
+        require!(!escrow.is_expired(now), EscrowError::EscrowExpired);
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        require!(!escrow.is_committed(now), EscrowError::CommitmentPending);
+
+        let deadline = now
+            .checked_add(Escrow::COMMIT_WINDOW)
+            .ok_or(EscrowError::MathOverflow)?;
+        escrow.commit_hash = hash;
+        escrow.commit_deadline = deadline;
+
+        emit!(CommitEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            deadline,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
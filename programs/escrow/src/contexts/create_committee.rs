@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::{ArbiterCommittee, CommitteeEvent, EscrowError};
+
+/// Defines the accounts needed for the `create_committee` instruction, setting up a panel of arbiters
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateCommittee<'info> {
+    /// The account creating the committee and paying for it, typically the maker about to name it arbiter
+    #[account(mut)]
+    pub creator: Signer<'info>,
+
+    /// The committee, whose address escrows are made with as their arbiter
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + ArbiterCommittee::INIT_SPACE,
+        seeds = [b"committee", creator.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub committee: Account<'info, ArbiterCommittee>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateCommittee<'info> {
+    /// Creates a committee of distinct `members`, `threshold` of whom must approve the outcome of a dispute
+    pub fn create(
+        &mut self,
+        seed: u64,
+        threshold: u8,
+        members: Vec<Pubkey>,
+        bumps: &CreateCommitteeBumps,
+    ) -> Result<()> {
+        require!(
+            threshold > 0
+                && threshold as usize <= members.len()
+                && members.len() <= ArbiterCommittee::MAX_MEMBERS
+                && members.iter().enumerate().all(|(i, member)| {
+                    *member != Pubkey::default() && !members[..i].contains(member)
+                }),
+            EscrowError::InvalidCommittee
+        );
+
+        self.committee.set_inner(ArbiterCommittee {
+            creator: self.creator.key(),
+            seed,
+            threshold,
+            members: members.clone(),
+            bump: bumps.committee,
+        });
+
+        emit!(CommitteeEvent {
+            committee: self.committee.key(),
+            creator: self.creator.key(),
+            threshold,
+            members,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
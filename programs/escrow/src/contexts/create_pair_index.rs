@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{EscrowError, PairIndex};
+
+/// Defines the accounts needed for the permissionless `create_pair_index` instruction, creating the index of a mint pair
+#[derive(Accounts)]
+pub struct CreatePairIndex<'info> {
+    /// Pays the rent of the index
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The mint the indexed escrows offer
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint the indexed escrows ask for
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The index of the mint pair, empty until escrows are made with it
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PairIndex::INIT_SPACE,
+        seeds = [b"pair_index", mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub pair_index: Account<'info, PairIndex>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreatePairIndex<'info> {
+    pub fn create_pair_index(&mut self, bumps: &CreatePairIndexBumps) -> Result<()> {
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        self.pair_index.set_inner(PairIndex {
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            open_escrows: 0,
+            total_deposit: 0,
+            bump: bumps.pair_index,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::MakerProfile;
+
+/// Defines the accounts needed for the `create_profile` instruction, opening a maker's offer counter
+#[derive(Accounts)]
+pub struct CreateProfile<'info> {
+    /// The maker the profile belongs to, paying for it
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The maker's profile, one per maker
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + MakerProfile::INIT_SPACE,
+        seeds = [b"profile", maker.key().as_ref()],
+        bump
+    )]
+    pub maker_profile: Account<'info, MakerProfile>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateProfile<'info> {
+    /// Starts the maker's offer counter at 0
+    pub fn create_profile(&mut self, bumps: &CreateProfileBumps) -> Result<()> {
+        self.maker_profile.set_inner(MakerProfile {
+            maker: self.maker.key(),
+            offers: 0,
+            bump: bumps.maker_profile,
+        });
+        Ok(())
+    }
+}
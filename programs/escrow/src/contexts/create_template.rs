@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, Template, TemplateEvent};
+
+/// Defines the accounts needed for the `create_template` instruction, letting the admin publish a set of escrow terms
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct CreateTemplate<'info> {
+    /// The admin of the config, paying for the template
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The program's global config, naming the admin
+    #[account(
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The template, whose address makers reference in `make`
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Template::INIT_SPACE,
+        seeds = [b"template", seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub template: Account<'info, Template>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateTemplate<'info> {
+    /// Creates a template whose escrows pay `fee_bps` on fills and expire within `max_duration` seconds of being made
+    /// Fills are restricted to takers on the allowlist with `allowlist_root` if given, and disputes go to `arbiter` if given
+    pub fn create(
+        &mut self,
+        seed: u64,
+        fee_bps: u16,
+        max_duration: i64,
+        allowlist_root: Option<[u8; 32]>,
+        arbiter: Option<Pubkey>,
+        bumps: &CreateTemplateBumps,
+    ) -> Result<()> {
+        require!(
+            fee_bps <= Config::MAX_FEE_BPS && max_duration >= 0,
+            EscrowError::InvalidTemplate
+        );
+        let allowlist_root = allowlist_root.unwrap_or_default();
+        let arbiter = arbiter.unwrap_or_default();
+
+        self.template.set_inner(Template {
+            seed,
+            fee_bps,
+            max_duration,
+            allowlist_root,
+            arbiter,
+            bump: bumps.template,
+        });
+
+        emit!(TemplateEvent {
+            template: self.template.key(),
+            admin: self.admin.key(),
+            fee_bps,
+            max_duration,
+            allowlist_root,
+            arbiter,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
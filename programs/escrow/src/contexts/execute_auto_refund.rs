@@ -0,0 +1,255 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    helpers::{
+        delist, harvest_withheld_fees, is_native_mint, reindex, rent_recipients,
+        transfer_checked_with_hook,
+    },
+    AutoRefund, AutoRefundEvent, Escrow, EscrowError, EscrowStatus, EventLog, LogKind,
+    OrderBookPage, PairIndex, RefundEvent,
+};
+
+/// Defines the accounts needed for the `execute_auto_refund` instruction, letting any keeper run a due scheduled refund
+/// Like `refund_expired`, funds can only ever go back to the owner of the maker's position, and rent where the maker chose
+#[derive(Accounts)]
+pub struct ExecuteAutoRefund<'info> {
+    /// Whoever executes the due refund, e.g. an automation thread, tipped for it
+    /// Pays for the owner's token account if it has to be recreated
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    /// The maker of the escrow, receiving the rent of the escrow account
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The owner of the maker's position, receiving the refund, the vault's rent and the schedule's
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    /// The mint of the token that was initially deposited into the escrow by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The owner's associated token account for Mint A, where tokens will be refunded to
+    /// Not needed when Mint A is wrapped SOL, closing the vault then unwraps the refund straight to the owner
+    #[account(
+        init_if_needed,
+        payer = keeper,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Receives the rent of the escrow and its vault when the maker chose a treasury for it, checked against the escrow's in `rent_recipients`
+    #[account(mut)]
+    pub rent_treasury: Option<UncheckedAccount<'info>>,
+
+    /// The scheduled escrow, closed with its rent going back to the maker unless kept as a receipt
+    #[account(
+        mut,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The vault holding the maker's deposit, emptied back to the owner and closed
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The escrow's schedule, paying the tip to the keeper and closed to the owner who scheduled it
+    /// A schedule set before the position changed hands no longer matches its owner, and can only be cancelled
+    #[account(
+        mut,
+        close = owner,
+        has_one = escrow,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        seeds = [b"auto_refund", escrow.key().as_ref()],
+        bump = auto_refund.bump
+    )]
+    pub auto_refund: Account<'info, AutoRefund>,
+
+    /// The program's event log, recording this refund when passed
+    #[account(
+        mut,
+        seeds = [b"event_log"],
+        bump
+    )]
+    pub event_log: Option<AccountLoader<'info, EventLog>>,
+
+    /// The order book page listing the escrow, which the refund removes it from
+    /// Required when the escrow is listed
+    #[account(mut)]
+    pub order_book_page: Option<Box<Account<'info, OrderBookPage>>>,
+
+    /// The index of the escrow's mints, which the refund removes it from
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    // SPL Associated Token Program used for handling operations on associated token accounts
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    // Token Program owning Mint A, used for tokens transfers and other token operations
+    pub token_program_a: Interface<'info, TokenInterface>,
+    // Solana System Program used for account creation, lamports transfer, etc.
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ExecuteAutoRefund<'info> {
+    /// Returns the vault to the owner and closes it once the schedule is due, then tips the keeper
+    /// Waits for any reservation of the escrow to lapse, so a schedule can't dodge the cancellation penalty
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn execute_auto_refund(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            now >= self.auto_refund.execute_after,
+            EscrowError::AutoRefundNotDue
+        );
+        // Scheduling doesn't lift a hashlock, the taker holding the preimage keeps their window until expiry
+        let escrow = *self.escrow.load()?;
+        if escrow.hashlock().is_some() {
+            require!(escrow.is_expired(now), EscrowError::HashlockNotExpired);
+        }
+        // Nor a reservation, which only `refund` can cut short by paying the reserving taker their penalty
+        require!(
+            !escrow.is_reserved(Clock::get()?.slot),
+            EscrowError::EscrowReserved
+        );
+
+        // Prepare the signer seeds for authorizig operations with the escrow's PDA
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        let amount = self.vault.amount;
+
+        // A wrapped SOL vault can be closed with its balance, which unwraps it into the owner's lamports
+        if !is_native_mint(&self.mint_a.key()) {
+            let owner_ata_a = self
+                .owner_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+
+            let xfer_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: owner_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                xfer_accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        harvest_withheld_fees(
+            self.token_program_a.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
+        // The vault's rent goes where the maker chose, the owner by default, never to the keeper
+        let (_, vault_rent_to) = self.rent_recipients()?;
+        let close_accounts = CloseAccount {
+            account: self.vault.to_account_info(),
+            destination: vault_rent_to,
+            authority: self.escrow.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            close_accounts,
+            &signer_seeds,
+        );
+        close_account(ctx)?;
+
+        // The tip was set aside in the schedule, whose remaining rent goes back to the owner once it is closed
+        let tip = self.auto_refund.tip;
+        self.auto_refund.sub_lamports(tip)?;
+        self.keeper.add_lamports(tip)?;
+
+        if let Some(event_log) = &self.event_log {
+            event_log.load_mut()?.record(
+                LogKind::Refund,
+                self.escrow.key(),
+                self.keeper.key(),
+                amount,
+                now,
+            )?;
+        }
+
+        emit!(RefundEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            amount,
+            timestamp: now,
+        });
+        emit!(AutoRefundEvent {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            keeper: Some(self.keeper.key()),
+            execute_after: self.auto_refund.execute_after,
+            tip,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Closes the escrow account, sending its rent where the maker chose, back to them by default
+    /// Escrows kept as receipts are marked as refunded instead, until the maker closes them with `close_receipt`
+    pub fn close_escrow(&mut self) -> Result<()> {
+        delist(&self.escrow, self.order_book_page.as_deref_mut())?;
+        reindex(
+            &mut *self.escrow.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+        if self.escrow.load()?.keep_receipt() {
+            self.escrow.load_mut()?.set_status(EscrowStatus::Refunded);
+            return Ok(());
+        }
+        let (escrow_rent_to, _) = self.rent_recipients()?;
+        self.escrow.close(escrow_rent_to)
+    }
+
+    /// Accounts receiving the rent of the escrow and of its vault once closed, following the maker's choice
+    fn rent_recipients(&self) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        rent_recipients(
+            self.escrow.load()?.rent_destination(),
+            &self.mint_a.key(),
+            &self.maker.to_account_info(),
+            &self.owner.to_account_info(),
+            None,
+            rent_treasury.as_ref(),
+        )
+    }
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{Auction, EscrowError, SealedBid, SealedBidSettledEvent};
+
+/// Defines the accounts needed for the permissionless `forfeit_bid` instruction, closing a sealed bid never revealed
+#[derive(Accounts)]
+pub struct ForfeitBid<'info> {
+    /// The maker of the auction, receiving the bond of the unrevealed bid
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The bidder who committed the bid, refunded its rent
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+
+    /// CHECK: The auction the bid was committed on, which may have been settled and closed since
+    /// Its reveal window is read from the account while it still exists
+    #[account(address = sealed_bid.auction @ EscrowError::InvalidSealedBid)]
+    pub auction: UncheckedAccount<'info>,
+
+    /// The unrevealed sealed bid, closed to the bidder
+    #[account(
+        mut,
+        close = bidder,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = bidder @ EscrowError::InvalidSealedBid,
+        seeds = [b"sealed_bid", sealed_bid.auction.as_ref(), bidder.key().as_ref()],
+        bump = sealed_bid.bump
+    )]
+    pub sealed_bid: Account<'info, SealedBid>,
+}
+
+impl<'info> ForfeitBid<'info> {
+    /// Pays the bond of a bid left unrevealed to the maker, once the reveal window has closed
+    /// Auctions can only be settled and closed after their reveal window, so a closed auction means the window has passed
+    pub fn forfeit(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if *self.auction.owner == crate::ID {
+            let auction = Auction::try_deserialize(&mut &self.auction.try_borrow_data()?[..])?;
+            require!(auction.is_settleable(now), EscrowError::NotRevealing);
+        }
+
+        let bond = self.sealed_bid.bond;
+        self.sealed_bid.sub_lamports(bond)?;
+        self.maker.add_lamports(bond)?;
+
+        emit!(SealedBidSettledEvent {
+            auction: self.sealed_bid.auction,
+            bidder: self.bidder.key(),
+            bond,
+            forfeited: true,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,76 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{Escrow, EscrowError, EscrowStatus, Reservation, ReservationSettledEvent};
+
+/// Defines the accounts needed for the permissionless `forfeit_reservation` instruction, closing a lapsed reservation
+#[derive(Accounts)]
+pub struct ForfeitReservation<'info> {
+    /// The owner of the maker's position in the reserved escrow, receiving the bond if the taker abandoned it
+    /// Checked against the escrow's current owner, as the position may have been transferred since the reservation
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
+    /// The taker who held the reservation, refunded its rent, and its bond if the escrow can no longer be filled
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    /// CHECK: The escrow the reservation held, which may have been closed since, only read if it is still an escrow
+    #[account(address = reservation.escrow @ EscrowError::ReservationMismatch)]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// The lapsed reservation, closed to the taker
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker @ EscrowError::ReservationMismatch,
+        seeds = [b"reservation", reservation.escrow.as_ref()],
+        bump = reservation.bump
+    )]
+    pub reservation: Account<'info, Reservation>,
+}
+
+impl<'info> ForfeitReservation<'info> {
+    /// Pays the bond to the escrow's owner if it is still fillable, as the taker abandoned it, and back to the taker otherwise
+    /// An escrow refunded, expired or settled through another path meanwhile wasn't the taker's to fill, so their bond is returned
+    pub fn forfeit(&mut self) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(
+            clock.slot >= self.reservation.expires,
+            EscrowError::ReservationActive
+        );
+
+        // The escrow may have been closed, in which case it is no longer owned by the program
+        let fillable = {
+            let data = self.escrow.try_borrow_data()?;
+            (*self.escrow.owner == crate::ID
+                && data.len() == Escrow::SPACE
+                && data[..8] == Escrow::DISCRIMINATOR)
+                .then(|| bytemuck::pod_read_unaligned::<Escrow>(&data[8..]))
+                .filter(|escrow| {
+                    escrow.has_status(EscrowStatus::Open)
+                        && !escrow.is_expired(clock.unix_timestamp)
+                })
+        };
+
+        let bond = self.reservation.bond;
+        let forfeited = fillable.is_some();
+        if let Some(escrow) = fillable {
+            require_keys_eq!(
+                self.owner.key(),
+                escrow.owner,
+                EscrowError::NotPositionOwner
+            );
+            self.reservation.sub_lamports(bond)?;
+            self.owner.add_lamports(bond)?;
+        }
+
+        emit!(ReservationSettledEvent {
+            escrow: self.reservation.escrow,
+            taker: self.taker.key(),
+            bond,
+            forfeited,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
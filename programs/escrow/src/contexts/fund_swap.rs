@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    EscrowError, FundSwapEvent, Swap,
+};
+
+/// Defines the accounts needed for the `fund_swap` instruction, where the taker locks their side of a two-sided swap
+#[derive(Accounts)]
+pub struct FundSwap<'info> {
+    /// The counterparty designated by the maker, locking `amount_b` of mint_b
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the swap, used to derive its address
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the token locked by the taker
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The taker's token account for mint_b, funding `vault_b`
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// The swap being funded, which can't have been funded already
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = taker @ EscrowError::UnauthorizedTaker,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = !swap.funded @ EscrowError::SwapAlreadyFunded,
+        seeds = [b"swap", maker.key().as_ref(), swap.seed.to_le_bytes().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Account<'info, Swap>,
+
+    /// Vault holding the taker's side of the swap, controlled by the swap PDA
+    /// Created if needed, as anyone can open the swap's associated token account ahead of the taker
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> FundSwap<'info> {
+    /// Locks `amount_b` of mint_b in `vault_b`, grossed up so the vault holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.vault_b.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), self.swap.amount_b)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, hook_accounts)?;
+
+        self.swap.funded = true;
+
+        emit!(FundSwapEvent {
+            swap: self.swap.key(),
+            taker: self.taker.key(),
+            amount_b: self.swap.amount_b,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    program::Escrow as EscrowProgram, Config, EscrowError, FeeCurrency, PermanentDelegatePolicy,
+};
+
+/// Defines the accounts needed to create the program's global `Config`
+/// Only the program's upgrade authority may do so, which keeps anyone else from claiming the admin role first
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    /// The program's upgrade authority, who becomes the config admin and pays for the account
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The global config PDA, there is only one per deployment
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + Config::INIT_SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The escrow program itself, used to locate its program data account
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, EscrowProgram>,
+
+    /// The program data account, holding the upgrade authority the admin is checked against
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    /// Stores the admin and the protocol fee charged on fills
+    pub fn initialize_config(&mut self, fee_bps: u16, bumps: &InitializeConfigBumps) -> Result<()> {
+        require!(fee_bps <= Config::MAX_FEE_BPS, EscrowError::InvalidFee);
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            fee_bps,
+            referral_bps: 0,
+            membership_collection: None,
+            member_fee_bps: 0,
+            paused: false,
+            allowed_mints: Vec::new(),
+            banned_mints: Vec::new(),
+            allow_non_transferable: false,
+            permanent_delegate_policy: PermanentDelegatePolicy::Reject,
+            fee_currency: FeeCurrency::MintB,
+            fee_tiers: vec![],
+            cancel_penalty_bps: 0,
+            bump: bumps.config,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, EventLog};
+
+/// Defines the accounts needed for the `initialize_event_log` instruction, creating the program's on-chain event log
+#[derive(Accounts)]
+pub struct InitializeEventLog<'info> {
+    /// The admin of the config, paying for the log
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The program's global config, whose admin decides whether the deployment keeps a log
+    #[account(
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The event log PDA, there is only one per deployment
+    #[account(
+        init,
+        payer = admin,
+        space = EventLog::SPACE,
+        seeds = [b"event_log"],
+        bump
+    )]
+    pub event_log: AccountLoader<'info, EventLog>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeEventLog<'info> {
+    /// Creates the log empty, its first event being numbered 1
+    pub fn initialize_event_log(&mut self) -> Result<()> {
+        self.event_log.load_init()?;
+        Ok(())
+    }
+}
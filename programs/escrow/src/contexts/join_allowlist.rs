@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+use crate::{AllowlistEvent, AllowlistPass, EscrowError, Template};
+
+/// Defines the accounts needed for the `join_allowlist` instruction, letting a taker prove they may fill a template's escrows
+#[derive(Accounts)]
+pub struct JoinAllowlist<'info> {
+    /// The taker on the allowlist, paying for their pass
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The template restricting who fills its escrows
+    #[account(
+        seeds = [b"template", template.seed.to_le_bytes().as_ref()],
+        bump = template.bump
+    )]
+    pub template: Account<'info, Template>,
+
+    /// The taker's pass, checked by `take` in place of the proof
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + AllowlistPass::INIT_SPACE,
+        seeds = [b"allowlist_pass", template.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub allowlist_pass: Account<'info, AllowlistPass>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> JoinAllowlist<'info> {
+    /// Checks `proof` places the taker under the template's allowlist root and records their pass
+    pub fn join(&mut self, proof: Vec<[u8; 32]>, bumps: &JoinAllowlistBumps) -> Result<()> {
+        require!(
+            self.template.is_restricted()
+                && self.template.is_allowlisted(&self.taker.key(), &proof),
+            EscrowError::NotAllowlisted
+        );
+
+        self.allowlist_pass.set_inner(AllowlistPass {
+            template: self.template.key(),
+            taker: self.taker.key(),
+            bump: bumps.allowlist_pass,
+        });
+
+        emit!(AllowlistEvent {
+            template: self.template.key(),
+            taker: self.taker.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{helpers::release_vault, EscrowError, Loan, SettleLoanEvent};
+
+/// Defines the accounts needed for the `liquidate_after_deadline` instruction, where the lender claims the collateral of a defaulted loan
+#[derive(Accounts)]
+pub struct LiquidateAfterDeadline<'info> {
+    /// The lender who took the loan, receives the collateral
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    /// The maker who defaulted on the loan, receives the rent of the loan and its vault
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the collateral
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// Associated token account of the lender receiving the collateral
+    #[account(
+        init_if_needed,
+        payer = lender,
+        associated_token::mint = mint_a,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program
+    )]
+    pub lender_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The defaulted loan, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = loan.lender == Some(lender.key()) @ EscrowError::LoanNotTaken,
+        seeds = [b"loan", maker.key().as_ref(), loan.seed.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    /// Vault holding the collateral
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_a`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> LiquidateAfterDeadline<'info> {
+    /// Releases the whole collateral to the lender once the repayment deadline has passed, and closes the vault
+    /// Any Token-2022 transfer fee on the release is borne by the lender
+    pub fn liquidate(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(self.loan.is_defaulted(now), EscrowError::LoanNotDefaulted);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"loan",
+            self.maker.to_account_info().key.as_ref(),
+            &self.loan.seed.to_le_bytes()[..],
+            &[self.loan.bump],
+        ]];
+        release_vault(
+            &self.token_program.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.lender_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.loan.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(SettleLoanEvent {
+            loan: self.loan.key(),
+            maker: self.maker.key(),
+            lender: self.loan.lender,
+            repayment: 0,
+            liquidated: true,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
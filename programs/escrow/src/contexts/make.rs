@@ -1,105 +1,456 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked},
+    token_interface::{
+        sync_native, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked,
+    },
 };
 
-use crate::Escrow;
+use crate::{
+    helpers::{
+        check_mint_policy, flag_permanent_delegates, gross_up_transfer_fee, is_native_mint,
+        transfer_checked_with_hook,
+    },
+    Config, Escrow, EscrowError, EscrowStatus, EventLog, Gate, ListingEvent, LogKind, MakeEvent,
+    MakerProfile, OrderBookPage, PairIndex, RentDestination, Schedule, Template, Vesting,
+};
 
 /// Defines the accounts needed to execute the `make` instruction, including the maker, token mints, token acocunts and system programs
 #[derive(Accounts)]
-#[instruction(seed: u64)]
+#[instruction(seed: u64, seed_id: Option<[u8; 32]>)]
 pub struct Make<'info> {
-    /// The user initiating the escrow who signs the transaction
-    /// The signer must be this user, approving the transaction's terms and authorizing the transfer of funds
+    /// CHECK: The user initiating the escrow, who signs the transaction to approve its terms and authorize the transfer of funds
+    /// A PDA of a calling program, e.g. a DAO treasury or a vault, can be the maker without signing,
+    /// vouched for by `maker_authority` instead, see `check_maker`
+    /// Writable as a wrapped SOL deposit is funded from its lamports
     #[account(mut)]
-    pub maker: Signer<'info>,
+    pub maker: UncheckedAccount<'info>,
+
+    /// The `["maker_authority", maker]` PDA of `maker_program`, authorizing the escrow for a maker that doesn't sign
+    /// The calling program signs for it with `invoke_signed`, and it moves the deposit out of `maker_ata_a` as its delegate
+    pub maker_authority: Option<Signer<'info>>,
+
+    /// CHECK: The program `maker_authority` is a PDA of, only used to derive it, required with `maker_authority`
+    pub maker_program: Option<UncheckedAccount<'info>>,
+
+    /// Pays the rent of the escrow and its vault, the maker or a sponsor covering it for them
+    #[account(mut)]
+    pub payer: Signer<'info>,
 
     /// Represents the token type (mint) that the maker will deposit into the escrow
     /// This account stores information about the specific token type, such as total supply and minting authority
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_a
     )]
     pub mint_a: InterfaceAccount<'info, Mint>,
 
     /// Represents the token type that the maker expects to receive from the escrow
     /// This is used to verify the type of tokens the escrow will handle in the transaction opposite to `mint_a`
     #[account(
-        mint::token_program = token_program
+        mint::token_program = token_program_b
     )]
     pub mint_b: InterfaceAccount<'info, Mint>,
 
     /// The maker's token account for `mint_a`
     /// This is where the tokens that will be deposited into the escrow are initially held
+    /// Optional when `mint_a` is wrapped SOL, the deposit is then wrapped straight from the maker's lamports
+    /// Makers holding data, such as program-owned PDAs, can't send lamports and deposit wrapped SOL from it instead
     #[account(
         mut, // This account's balance can be modified- decremented
         associated_token::mint = mint_a, // Links this account to the `mint_a` token type
         associated_token::authority = maker, // Confirms the maker controls this account
-        associated_token::token_program = token_program // Specifies the token management program
+        associated_token::token_program = token_program_a // Specifies the token management program
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The maker's profile, if any, whose offer counter must be used as the escrow's seed
+    /// Lets clients derive the maker's escrows from the counter instead of tracking random seeds
+    #[account(
+        mut,
+        has_one = maker,
+        seeds = [b"profile", maker.key().as_ref()],
+        bump = maker_profile.bump
+    )]
+    pub maker_profile: Option<Account<'info, MakerProfile>>,
+
+    /// The template whose terms the escrow adopts, if any, enforced by `take` on every fill
+    #[account(
+        seeds = [b"template", template.seed.to_le_bytes().as_ref()],
+        bump = template.bump
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub template: Option<Box<Account<'info, Template>>>,
 
     /// The actual escrow account that will hold the state of the escrow transaction, including data like the seed, amounts to be sent/received, and ownership details
     #[account(
         init, // Indicates this account will be created with this transaction if it doesn't already exist
-        payer = maker, // Specifies that the maker will pay for the account creation
-        space = 8 + Escrow::INIT_SPACE, // Defines how much data storage space is needed
+        payer = payer, // Specifies who pays for the account creation
+        space = Escrow::SPACE, // Defines how much data storage space is needed
 
         // seeds and bump provide a mechanism for creating a predictable, yet secure, address for this account using a derived address
-        seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        // derived from `seed_id` instead of `seed` when given, see `Escrow::address_seed_of`
+        seeds = [b"escrow", maker.key().as_ref(), Escrow::address_seed_of(seed, seed_id).as_ref()],
         bump
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub escrow: AccountLoader<'info, Escrow>,
 
     /// A special token account created to hold the `mint_a` tokens deposited by the maker. This account is controlled by the escrow, and acts as the lockbox for the assets until conditions are met
+    /// Not passed when the vault is created as `pda_vault` instead
+    /// May be created beforehand, e.g. while a multisig's proposal to make the escrow awaits its approvals,
+    /// so nobody can block the escrow by creating its vault first
     #[account(
-        init, // To create the account during this transaction
-        payer = maker, // Indicates the maker is paying for the setup
+        init_if_needed, // To create the account during this transaction, unless it already exists
+        payer = payer, // Indicates who is paying for the setup
         associated_token::mint = mint_a, // Ensures this vault can only hold the type of tokens specified by the `mint_a`
         associated_token::authority = escrow, // Transfer control of this account to the escrow program, meaning only the escrow can authorize transactions from it
-        associated_token::token_program = token_program // Specifies the token management program 
+        associated_token::token_program = token_program_a // Specifies the token management program
+    )]
+    pub vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The vault created as a token account at the escrow's `["vault", escrow]` PDA, instead of its associated token account
+    /// Cheaper to derive for indexers and CPI callers, and skips the Associated Token program CPI
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"vault", escrow.key().as_ref()],
+        bump,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a
+    )]
+    pub pda_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The program's event log, recording this escrow when passed
+    #[account(
+        mut,
+        seeds = [b"event_log"],
+        bump
+    )]
+    pub event_log: Option<AccountLoader<'info, EventLog>>,
+
+    /// The page of the order book of the escrow's mints listing it, when passed
+    /// Any page of the pair with room works, see `add_order_book_page`
+    #[account(
+        mut,
+        constraint = order_book_page.mint_a == mint_a.key() && order_book_page.mint_b == mint_b.key() @ EscrowError::OrderBookMismatch
+    )]
+    pub order_book_page: Option<Box<Account<'info, OrderBookPage>>>,
+
+    /// The index of the escrow's mints, counting it while it is open when passed
+    /// Every later change to the escrow's deposit or status must then be passed it too
+    #[account(
+        mut,
+        constraint = pair_index.mint_a == mint_a.key() && pair_index.mint_b == mint_b.key() @ EscrowError::IndexMismatch
     )]
-    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
 
     /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
     pub associated_token_program: Program<'info, AssociatedToken>,
 
-    /// The SPL Token program used for handling all token operations like transfers, minting, and burning within this transaction
-    pub token_program: Interface<'info, TokenInterface>,
+    /// The token program owning `mint_a`, used for the deposit into the vault
+    /// Either the SPL Token or the Token-2022 program, independently of `token_program_b`
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning `mint_b`, which lets a Token-2022 asset be traded against a legacy SPL token
+    pub token_program_b: Interface<'info, TokenInterface>,
 
     /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
+    /// Rejects nonsense escrows upfront, before anything is deposited
+    pub fn validate_terms(&self, deposit: u64, receive: u64) -> Result<()> {
+        self.check_maker()?;
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        require!(deposit > 0, EscrowError::ZeroDeposit);
+        require!(receive > 0, EscrowError::ZeroReceive);
+        require!(
+            self.config.is_mint_permitted(&self.mint_a.key())
+                && self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+        check_mint_policy(&self.config, &self.mint_a.to_account_info())?;
+        check_mint_policy(&self.config, &self.mint_b.to_account_info())?;
+        Ok(())
+    }
+
+    /// A maker that doesn't sign must be vouched for by `maker_authority`, the `["maker_authority", maker]` PDA of `maker_program`
+    /// Only that program can sign for the authority, through `invoke_signed`, so only it can make escrows for the maker
+    /// Such a maker can't send lamports either, so it deposits from `maker_ata_a`, delegated to the authority
+    fn check_maker(&self) -> Result<()> {
+        if self.maker.is_signer {
+            return Ok(());
+        }
+        let (Some(maker_authority), Some(maker_program)) =
+            (&self.maker_authority, &self.maker_program)
+        else {
+            return err!(EscrowError::UnauthorizedMaker);
+        };
+        let (authority, _) = Pubkey::find_program_address(
+            &[b"maker_authority", self.maker.key().as_ref()],
+            &maker_program.key(),
+        );
+        require_keys_eq!(
+            maker_authority.key(),
+            authority,
+            EscrowError::UnauthorizedMaker
+        );
+        require!(self.maker_ata_a.is_some(), EscrowError::MissingTokenAccount);
+        Ok(())
+    }
+
     /// This function is designed to initialize or update the escrow account with necessary parameters to establish the conditions under which the escrow operates
-    pub fn save_escrow(&mut self, seed: u64, receive: u64, bumps: &MakeBumps) -> Result<()> {
-        // Sets the inner state of the `escrow` accpimt with the new `Escrow` struct, passing in values such as the unique `seed`, identifies of the token types (`mint_a`, `mint_b`), and the amount the maker expects to receive (`receive`)
-        self.escrow.set_inner(Escrow {
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        seed_id: Option<[u8; 32]>,
+        deposit: u64,
+        receive: u64,
+        expiry: i64,
+        taker: Option<Pubkey>,
+        arbiter: Option<Pubkey>,
+        hashlock: Option<[u8; 32]>,
+        vesting: Option<Vesting>,
+        schedule: Option<Schedule>,
+        keep_receipt: bool,
+        rent_destination: RentDestination,
+        label: [u8; 32],
+        gate: Option<Gate>,
+        gatekeeper_network: Option<Pubkey>,
+        category: u16,
+        bumps: &MakeBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+
+        if let Some(maker_profile) = &mut self.maker_profile {
+            require!(seed == maker_profile.offers, EscrowError::SeedMismatch);
+            maker_profile.offers = maker_profile
+                .offers
+                .checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
+
+        // A zeroed seed id would read as none, leaving the escrow at an address its `seed` doesn't derive
+        require!(seed_id != Some([0; 32]), EscrowError::InvalidSeedId);
+        // An expiry of 0 means the offer never expires, any other deadline must still be in the future
+        if expiry != 0 {
+            require!(expiry > now, EscrowError::InvalidExpiry);
+        }
+        // A hashlocked escrow needs a timeout, after which the maker can get the deposit back
+        if hashlock.is_some() {
+            require!(expiry != 0, EscrowError::InvalidExpiry);
+        }
+        require!(
+            rent_destination != RentDestination::Treasury(Pubkey::default()),
+            EscrowError::InvalidRentDestination
+        );
+        if let Some(vesting) = vesting {
+            require!(
+                vesting.duration > 0 && (0..=vesting.duration).contains(&vesting.cliff),
+                EscrowError::InvalidVesting
+            );
+        }
+        // The template's arbiter replaces the maker's, and the maker's expiry must follow its policy
+        let arbiter = match &self.template {
+            Some(template) => {
+                require!(
+                    arbiter.is_none() || arbiter == template.arbiter(),
+                    EscrowError::TemplateMismatch
+                );
+                if template.max_duration > 0 {
+                    let deadline = now
+                        .checked_add(template.max_duration)
+                        .ok_or(EscrowError::MathOverflow)?;
+                    require!(
+                        expiry != 0 && expiry <= deadline,
+                        EscrowError::InvalidExpiry
+                    );
+                }
+                template.arbiter()
+            }
+            None => arbiter,
+        };
+        if let Some(gate) = gate {
+            require!(
+                gate.mint != Pubkey::default() && gate.amount > 0,
+                EscrowError::InvalidGate
+            );
+        }
+        if let Some(schedule) = schedule {
+            require!(
+                (1..=deposit).contains(&schedule.installment)
+                    && schedule.interval > 0
+                    && vesting.is_none()
+                    && hashlock.is_none(),
+                EscrowError::InvalidSchedule
+            );
+        }
+
+        // Sets the inner state of the `escrow` accpimt with the new `Escrow` struct, passing in values such as the unique `seed`, identifies of the token types (`mint_a`, `mint_b`), the amount deposited (`deposit`) and the amount the maker expects to receive (`receive`)
+        let mut escrow = Escrow {
+            version: Escrow::VERSION,
             seed,
+            seed_id: seed_id.unwrap_or_default(),
             maker: self.maker.key(),
+            owner: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            deposit, // Recorded so settlement doesn't depend on the vault balance, which anyone can add to
             receive,
+            price_a: deposit, // The price every fill is settled at, see `Escrow::price`
+            price_b: receive,
+            expiry,
+            taker: taker.unwrap_or_default(), // Optional fields are zeroed when unset, see `Escrow`
+            arbiter: arbiter.unwrap_or_default(),
+            hashlock: hashlock.unwrap_or_default(),
+            vesting: vesting.unwrap_or_default(),
+            schedule: schedule.unwrap_or_default(),
+            gate: gate.unwrap_or_default(),
+            gatekeeper_network: gatekeeper_network.unwrap_or_default(),
+            status: EscrowStatus::Open as u8,
+            keep_receipt: keep_receipt.into(),
+            label,
+            category,
+            template: self.template.as_ref().map(|t| t.key()).unwrap_or_default(),
             bump: bumps.escrow, // The bump seed is included to ensure that the address of the escrow account is derived securely and predictably using the provided seeds
+            ..Default::default()
+        };
+        escrow.set_rent_destination(rent_destination);
+        // Only reached with mints that have a permanent delegate if the config flags rather than rejects them
+        escrow.permanent_delegate = flag_permanent_delegates(
+            self.escrow.key(),
+            [
+                &self.mint_a.to_account_info(),
+                &self.mint_b.to_account_info(),
+            ],
+            now,
+        )
+        .into();
+        // Exactly one of the two vaults must be created
+        match (&self.vault, bumps.pda_vault) {
+            (Some(_), None) => {}
+            (None, Some(vault_bump)) => {
+                escrow.pda_vault = 1;
+                escrow.vault_bump = vault_bump;
+            }
+            _ => return err!(EscrowError::VaultMismatch),
+        }
+        if let Some(order_book_page) = &mut self.order_book_page {
+            order_book_page.insert(self.escrow.key())?;
+            escrow.order_book_page = (order_book_page.index as u64)
+                .checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+            emit!(ListingEvent {
+                escrow: self.escrow.key(),
+                page: order_book_page.key(),
+                listed: true,
+                timestamp: now,
+            });
+        }
+        if let Some(pair_index) = &mut self.pair_index {
+            pair_index.open_escrows = pair_index
+                .open_escrows
+                .checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+            pair_index.total_deposit = pair_index
+                .total_deposit
+                .checked_add(deposit)
+                .ok_or(EscrowError::MathOverflow)?;
+            escrow.indexed_deposit = deposit;
+        }
+        *self.escrow.load_init()? = escrow;
+
+        if let Some(event_log) = &self.event_log {
+            event_log.load_mut()?.record(
+                LogKind::Make,
+                self.escrow.key(),
+                self.maker.key(),
+                deposit,
+                now,
+            )?;
+        }
+
+        emit!(MakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            receive,
+            expiry,
+            timestamp: now,
         });
         Ok(())
     }
 
     /// This function handles the acutal transfer of tokens fom the maker's account to the escrow's vault. It ensures that the tokens are safely locked until the escrow conditions are met
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn deposit(&mut self, deposit: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        if is_native_mint(&self.mint_a.key()) && self.maker_ata_a.is_none() {
+            return self.wrap_deposit(deposit);
+        }
+
+        let maker_ata_a = self
+            .maker_ata_a
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
         // TranferChecked is created specifying the accounts involved in the transfer- from the maker's ata to the escrow's vault
         let transfer_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
+            from: maker_ata_a.to_account_info(),
             mint: self.mint_a.to_account_info(), // Uses the token mint information to ensure the transfer respects the token's properties (e.g. decimals)
-            to: self.vault.to_account_info(),
-            authority: self.maker.to_account_info(),
+            to: self.vault()?,
+            // A maker that doesn't sign has delegated the deposit to its authority, checked in `check_maker`
+            authority: match (self.maker.is_signer, &self.maker_authority) {
+                (false, Some(maker_authority)) => maker_authority.to_account_info(),
+                _ => self.maker.to_account_info(),
+            },
         };
 
-        // Context is set up with the `token_program`, allowing the escrow program to call the SPL Token program's `transfer_checked` function securely
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        // Context is set up with the `token_program_a`, allowing the escrow program to call the SPL Token program's `transfer_checked` function securely
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
 
         // The `transfer_checked` function is invoked to move `deposit` amount of tokens, validated by the token's decimal specification to ensure accuracy and correctness
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)
+        // With a Token-2022 transfer fee, the maker sends enough for the vault to receive exactly `deposit`
+        let amount = gross_up_transfer_fee(&self.mint_a.to_account_info(), deposit)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals, hook_accounts)
+    }
+
+    /// The vault created for the escrow, whichever of its two kinds was passed
+    fn vault(&self) -> Result<AccountInfo<'info>> {
+        self.vault
+            .as_ref()
+            .or(self.pda_vault.as_ref())
+            .map(|vault| vault.to_account_info())
+            .ok_or(EscrowError::VaultMismatch.into())
+    }
+
+    /// Funds a wrapped SOL vault directly with the maker's lamports, so SOL doesn't need to be wrapped beforehand
+    fn wrap_deposit(&mut self, deposit: u64) -> Result<()> {
+        // Lamports sent to a native token account only count towards its token balance once synced
+        let transfer_accounts = Transfer {
+            from: self.maker.to_account_info(),
+            to: self.vault()?,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, deposit)?;
+
+        let sync_accounts = SyncNative {
+            account: self.vault()?,
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
     }
 }
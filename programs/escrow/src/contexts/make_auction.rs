@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Auction, Config, EscrowError, MakeAuctionEvent,
+};
+
+/// Defines the accounts needed for the `make_auction` instruction, where the maker locks a deposit to be auctioned
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeAuction<'info> {
+    /// The maker opening the auction, locking `deposit` of mint_a and paying for both vaults
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the token being auctioned
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token bids are made in
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The maker's token account for mint_a, funding `vault_a`
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The auction account, holding its terms and the highest bid until it is settled
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Auction::INIT_SPACE,
+        seeds = [b"auction", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub auction: Account<'info, Auction>,
+
+    /// Vault holding the auctioned deposit, controlled by the auction PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// Vault holding the highest bid, controlled by the auction PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program_b
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeAuction<'info> {
+    /// Records the terms of the auction, which takes bids of at least `reserve` until `end`
+    /// Bids are sealed if `reveal_end` isn't 0, and must then be revealed by `reveal_end`
+    pub fn save_auction(
+        &mut self,
+        seed: u64,
+        deposit: u64,
+        reserve: u64,
+        end: i64,
+        reveal_end: i64,
+        bumps: &MakeAuctionBumps,
+    ) -> Result<()> {
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        require!(deposit > 0, EscrowError::ZeroDeposit);
+        require!(reserve > 0, EscrowError::ZeroReceive);
+        require!(
+            end > Clock::get()?.unix_timestamp,
+            EscrowError::InvalidAuctionEnd
+        );
+        require!(
+            reveal_end == 0 || reveal_end > end,
+            EscrowError::InvalidAuctionEnd
+        );
+        require!(
+            self.config.is_mint_permitted(&self.mint_a.key())
+                && self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        self.auction.set_inner(Auction {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            reserve,
+            end,
+            reveal_end,
+            bidder: None,
+            bid: 0,
+            bump: bumps.auction,
+        });
+
+        emit!(MakeAuctionEvent {
+            auction: self.auction.key(),
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            reserve,
+            end,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Locks `deposit` of mint_a in `vault_a`, grossed up so the vault holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault_a.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_a.to_account_info(), self.auction.deposit)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals, hook_accounts)
+    }
+}
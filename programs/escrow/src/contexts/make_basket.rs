@@ -0,0 +1,160 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{create, AssociatedToken, Create},
+    token_interface::{Mint, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, load_mint, transfer_checked_with_hook},
+    Basket, BasketLeg, Config, EscrowError, MakeBasketEvent,
+};
+
+/// Defines the accounts needed for the `make_basket` instruction
+/// Each leg is passed as remaining accounts, in groups of `[mint, maker_ata, vault]`
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBasket<'info> {
+    /// The maker of the basket, depositing every leg and paying for the vaults
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the token expected to be received by the maker for the whole basket
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The basket account, holding the legs and terms of the trade
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Basket::INIT_SPACE,
+        seeds = [b"basket", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub basket: Account<'info, Basket>,
+
+    /// Used to create the associated token account of the basket for each leg
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning every leg's mint
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBasket<'info> {
+    /// Creates a vault for each leg and deposits `amounts[i]` of the leg's mint into it
+    /// With a Token-2022 transfer fee, the maker sends enough for the vault to receive exactly the leg's amount
+    pub fn deposit_legs(
+        &mut self,
+        amounts: &[u64],
+        leg_accounts: &[AccountInfo<'info>],
+    ) -> Result<Vec<BasketLeg>> {
+        require!(
+            !amounts.is_empty()
+                && amounts.len() <= Basket::MAX_LEGS
+                && leg_accounts.len() == amounts.len() * Basket::ACCOUNTS_PER_LEG,
+            EscrowError::InvalidLegs
+        );
+
+        let mut legs: Vec<BasketLeg> = Vec::with_capacity(amounts.len());
+        for (&amount, accounts) in amounts
+            .iter()
+            .zip(leg_accounts.chunks(Basket::ACCOUNTS_PER_LEG))
+        {
+            let [mint, maker_ata, vault] = accounts else {
+                return err!(EscrowError::InvalidLegs);
+            };
+
+            require!(amount > 0, EscrowError::ZeroDeposit);
+            require_keys_neq!(mint.key(), self.mint_b.key(), EscrowError::SameMint);
+            require!(
+                legs.iter().all(|leg| leg.mint != mint.key()),
+                EscrowError::InvalidLegs
+            );
+            require!(
+                self.config.is_mint_permitted(mint.key),
+                EscrowError::MintNotPermitted
+            );
+            let decimals = load_mint(mint, self.token_program_a.key)?.decimals;
+
+            // The associated token program checks that `vault` is the basket's account for this mint
+            let create_accounts = Create {
+                payer: self.maker.to_account_info(),
+                associated_token: vault.clone(),
+                authority: self.basket.to_account_info(),
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program_a.to_account_info(),
+            };
+            create(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                create_accounts,
+            ))?;
+
+            let transfer_accounts = TransferChecked {
+                from: maker_ata.clone(),
+                mint: mint.clone(),
+                to: vault.clone(),
+                authority: self.maker.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+            let gross = gross_up_transfer_fee(mint, amount)?;
+            transfer_checked_with_hook(cpi_ctx, gross, decimals, &[])?;
+
+            legs.push(BasketLeg {
+                mint: mint.key(),
+                amount,
+            });
+        }
+
+        Ok(legs)
+    }
+
+    /// Initializes the basket with the deposited legs and the amount of mint_b expected for all of them
+    pub fn save_basket(
+        &mut self,
+        seed: u64,
+        legs: Vec<BasketLeg>,
+        receive: u64,
+        bumps: &MakeBasketBumps,
+    ) -> Result<()> {
+        require!(receive > 0, EscrowError::ZeroReceive);
+        require!(
+            self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        self.basket.set_inner(Basket {
+            seed,
+            maker: self.maker.key(),
+            mint_b: self.mint_b.key(),
+            receive,
+            legs,
+            bump: bumps.basket,
+        });
+
+        emit!(MakeBasketEvent {
+            basket: self.basket.key(),
+            maker: self.maker.key(),
+            legs: self.basket.legs.clone(),
+            mint_b: self.mint_b.key(),
+            receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{
+    helpers::{check_mint_policy, flag_permanent_delegates, is_native_mint},
+    ChainEvent, Config, Escrow, EscrowError, EscrowStatus,
+};
+
+/// Defines the accounts needed for the `make_chained` instruction, pre-declaring the escrow the proceeds of another fund
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeChained<'info> {
+    /// The owner of the maker's position, whose proceeds fund the chained escrow, paying for it and its vault
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The open escrow whose proceeds fund the chained escrow
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The mint the escrow is paid in, deposited into the chained escrow
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The mint the chained escrow asks for
+    #[account(mint::token_program = chained_token_program)]
+    pub chained_mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The chained escrow, made under the same maker with `seed`, and unfillable until funded
+    #[account(
+        init,
+        payer = owner,
+        space = Escrow::SPACE,
+        seeds = [b"escrow", escrow.load()?.maker.as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub chained_escrow: AccountLoader<'info, Escrow>,
+
+    /// The chained escrow's vault, receiving the proceeds of the escrow's fills
+    #[account(
+        init,
+        payer = owner,
+        associated_token::mint = mint_b,
+        associated_token::authority = chained_escrow,
+        associated_token::token_program = token_program_b
+    )]
+    pub chained_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The token program owning the chained escrow's mint_b
+    pub chained_token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeChained<'info> {
+    /// Makes the chained escrow, selling the escrow's mint_b for `receive` of `chained_mint_b` per `receive` of the escrow,
+    /// until `expiry`, 0 for never
+    /// Each fill of the escrow then deposits its proceeds into the chained escrow, opening it, in the same instruction
+    pub fn make_chained(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+        bumps: &MakeChainedBumps,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let escrow = *self.escrow.load()?;
+
+        // Proceeds in wrapped SOL are paid in lamports, and collection offers in NFTs, neither of which a vault can hold
+        require!(
+            seed != 0
+                && escrow.chained_seed().is_none()
+                && escrow.payout().is_none()
+                && !escrow.is_private()
+                && !escrow.collection_offer()
+                && !is_native_mint(&self.mint_b.key()),
+            EscrowError::InvalidChain
+        );
+        require_keys_neq!(
+            self.mint_b.key(),
+            self.chained_mint_b.key(),
+            EscrowError::SameMint
+        );
+        require!(receive > 0, EscrowError::ZeroReceive);
+        if expiry != 0 {
+            require!(expiry > now, EscrowError::InvalidExpiry);
+        }
+        require!(
+            self.config.is_mint_permitted(&self.chained_mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+        check_mint_policy(&self.config, &self.chained_mint_b.to_account_info())?;
+
+        *self.chained_escrow.load_init()? = Escrow {
+            version: Escrow::VERSION,
+            seed,
+            maker: escrow.maker,
+            owner: escrow.owner,
+            mint_a: escrow.mint_b,
+            mint_b: self.chained_mint_b.key(),
+            // Funded by the escrow's fills, at the price declared now
+            price_a: escrow.receive,
+            price_b: receive,
+            expiry,
+            status: EscrowStatus::Chained as u8,
+            permanent_delegate: flag_permanent_delegates(
+                self.chained_escrow.key(),
+                [
+                    &self.mint_b.to_account_info(),
+                    &self.chained_mint_b.to_account_info(),
+                ],
+                now,
+            )
+            .into(),
+            bump: bumps.chained_escrow,
+            ..Default::default()
+        };
+        self.escrow.load_mut()?.chained_seed = seed;
+
+        emit!(ChainEvent {
+            escrow: self.escrow.key(),
+            chained_escrow: self.chained_escrow.key(),
+            mint_b: self.chained_mint_b.key(),
+            receive,
+            expiry,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::Mint;
+use mpl_bubblegum::{
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    utils::get_asset_id,
+};
+
+use crate::{
+    helpers::transfer_compressed, CompressedEscrow, Config, EscrowError, LeafProof,
+    MakeCompressedEvent,
+};
+
+/// Defines the accounts needed for the `make_compressed` instruction, where the maker escrows a compressed NFT
+/// The proof nodes of the NFT's leaf are passed as remaining accounts
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeCompressed<'info> {
+    /// The maker of the escrow, owner of the compressed NFT and payer for the escrow account
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the token the maker expects to receive
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The escrow account, becoming the compressed NFT's leaf owner until it is taken or refunded
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + CompressedEscrow::INIT_SPACE,
+        seeds = [b"compressed", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, CompressedEscrow>,
+
+    /// CHECK: Bubblegum config of `merkle_tree`, checked by Bubblegum
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Merkle tree holding the compressed NFT, checked by the account compression program
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: The Bubblegum program, checked by address
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Noop program Bubblegum logs leaves with, checked by address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Account Compression program owning the tree, checked by address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeCompressed<'info> {
+    /// Records the terms of the escrow for the compressed NFT `proof` points to
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+        proof: &LeafProof,
+        bumps: &MakeCompressedBumps,
+    ) -> Result<()> {
+        require!(receive > 0, EscrowError::ZeroReceive);
+        if expiry != 0 {
+            require!(
+                expiry > Clock::get()?.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+        require!(
+            self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        let asset_id = get_asset_id(self.merkle_tree.key, proof.nonce);
+        self.escrow.set_inner(CompressedEscrow {
+            seed,
+            maker: self.maker.key(),
+            merkle_tree: self.merkle_tree.key(),
+            asset_id,
+            mint_b: self.mint_b.key(),
+            receive,
+            expiry,
+            bump: bumps.escrow,
+        });
+
+        emit!(MakeCompressedEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            merkle_tree: self.merkle_tree.key(),
+            asset_id,
+            mint_b: self.mint_b.key(),
+            receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Transfers the compressed NFT from the maker to the escrow PDA
+    pub fn deposit(&self, proof: &LeafProof, proof_nodes: &[AccountInfo<'info>]) -> Result<()> {
+        transfer_compressed(
+            &self.bubblegum_program.to_account_info(),
+            &self.tree_config.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &self.merkle_tree.to_account_info(),
+            &self.log_wrapper.to_account_info(),
+            &self.compression_program.to_account_info(),
+            &self.system_program.to_account_info(),
+            proof,
+            proof_nodes,
+            &[],
+        )
+    }
+}
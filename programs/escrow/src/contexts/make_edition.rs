@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{master_edition_supply, token_metadata, transfer_checked_with_hook},
+    Config, EditionEscrow, EscrowError, MakeEditionEvent,
+};
+
+/// Defines the accounts needed for the `make_edition` instruction, where the maker escrows a master edition to sell prints of it
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeEdition<'info> {
+    /// The maker of the escrow, holder of the master edition's token and payer for the escrow and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the master edition
+    #[account(mint::token_program = token_program)]
+    pub master_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of the token the maker expects to receive for each print
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The maker's token account holding the master edition's token
+    #[account(
+        mut,
+        associated_token::mint = master_mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The escrow account, printing editions of the master edition until it is refunded
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + EditionEscrow::INIT_SPACE,
+        seeds = [b"edition", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub escrow: Box<Account<'info, EditionEscrow>>,
+
+    /// Vault holding the master edition's token, controlled by the escrow PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = master_mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: The master edition account of `master_mint`, checked by seeds and deserialized in `save_escrow`
+    #[account(
+        seeds = [b"metadata", token_metadata::ID.as_ref(), master_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata::ID,
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning the master edition's mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeEdition<'info> {
+    /// Records the price of each print, failing unless the master edition can still print editions
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+        bumps: &MakeEditionBumps,
+    ) -> Result<()> {
+        require!(receive > 0, EscrowError::ZeroReceive);
+        if expiry != 0 {
+            require!(
+                expiry > Clock::get()?.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+        require!(
+            self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        let (supply, max_supply) = master_edition_supply(&self.master_edition)?;
+        require!(
+            max_supply != Some(supply),
+            EscrowError::InvalidMasterEdition
+        );
+
+        self.escrow.set_inner(EditionEscrow {
+            seed,
+            maker: self.maker.key(),
+            master_mint: self.master_mint.key(),
+            mint_b: self.mint_b.key(),
+            receive,
+            expiry,
+            printed: 0,
+            bump: bumps.escrow,
+        });
+
+        emit!(MakeEditionEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            master_mint: self.master_mint.key(),
+            mint_b: self.mint_b.key(),
+            receive,
+            max_supply,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Moves the master edition's token into the vault, making the escrow PDA the owner Token Metadata prints through
+    pub fn deposit(&self) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata.to_account_info(),
+            mint: self.master_mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        transfer_checked_with_hook(cpi_ctx, 1, self.master_mint.decimals, &[])
+    }
+}
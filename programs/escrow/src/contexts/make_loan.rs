@@ -0,0 +1,142 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Config, EscrowError, Loan, MakeLoanEvent,
+};
+
+/// Defines the accounts needed for the `make_loan` instruction, where the maker locks collateral to borrow against
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeLoan<'info> {
+    /// The maker borrowing against their collateral and paying for the loan and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the collateral locked by the maker
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the principal the maker borrows
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The maker's token account for `mint_a`, funding the vault
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The loan, holding its terms until it is repaid, liquidated or cancelled
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Loan::INIT_SPACE,
+        seeds = [b"loan", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    /// Vault holding the collateral, controlled by the loan PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_a`
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning `mint_b`
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeLoan<'info> {
+    /// Records the terms of the loan, open to any lender until taken
+    pub fn save_loan(
+        &mut self,
+        seed: u64,
+        collateral: u64,
+        principal: u64,
+        repayment: u64,
+        duration: i64,
+        bumps: &MakeLoanBumps,
+    ) -> Result<()> {
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        require!(
+            collateral > 0 && principal > 0 && repayment >= principal && duration > 0,
+            EscrowError::InvalidLoanTerms
+        );
+        require!(
+            self.config.is_mint_permitted(&self.mint_a.key())
+                && self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        self.loan.set_inner(Loan {
+            seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            collateral,
+            principal,
+            repayment,
+            duration,
+            lender: None,
+            deadline: 0,
+            bump: bumps.loan,
+        });
+
+        emit!(MakeLoanEvent {
+            loan: self.loan.key(),
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            collateral,
+            principal,
+            repayment,
+            duration,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Locks `collateral` of mint_a in the vault, grossed up so the vault holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, collateral: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_a.to_account_info(), collateral)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals, hook_accounts)
+    }
+}
@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Config, EscrowError, MakeMilestonesEvent, Milestone, MilestoneEscrow, MilestoneStatus,
+    MilestoneTerms,
+};
+
+/// Defines the accounts needed for the `make_milestones` instruction, where the maker funds every milestone of a contract upfront
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeMilestones<'info> {
+    /// The maker funding the milestones and paying for the escrow and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The contractor the milestones are released to
+    pub contractor: SystemAccount<'info>,
+
+    /// The mint of the token the milestones are paid in
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// The maker's token account for `mint`, funding the vault
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The milestone escrow, holding the milestones and their progress
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + MilestoneEscrow::INIT_SPACE,
+        seeds = [b"milestones", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub milestone_escrow: Account<'info, MilestoneEscrow>,
+
+    /// Vault holding the deposit for every milestone not claimed yet, controlled by the milestone escrow PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint,
+        associated_token::authority = milestone_escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeMilestones<'info> {
+    /// Records the milestones, all pending until the maker approves them, and returns the total to deposit
+    pub fn save_milestones(
+        &mut self,
+        seed: u64,
+        terms: Vec<MilestoneTerms>,
+        bumps: &MakeMilestonesBumps,
+    ) -> Result<u64> {
+        require!(
+            (1..=MilestoneEscrow::MAX_MILESTONES).contains(&terms.len())
+                && terms.iter().all(|milestone| milestone.amount > 0),
+            EscrowError::InvalidMilestones
+        );
+        require!(
+            self.config.is_mint_permitted(&self.mint.key()),
+            EscrowError::MintNotPermitted
+        );
+        let total = terms
+            .iter()
+            .try_fold(0u64, |total, milestone| total.checked_add(milestone.amount))
+            .ok_or(EscrowError::MathOverflow)?;
+
+        let milestones = terms
+            .iter()
+            .map(|milestone| Milestone {
+                amount: milestone.amount,
+                description_hash: milestone.description_hash,
+                status: MilestoneStatus::Pending,
+            })
+            .collect();
+        self.milestone_escrow.set_inner(MilestoneEscrow {
+            seed,
+            maker: self.maker.key(),
+            contractor: self.contractor.key(),
+            mint: self.mint.key(),
+            milestones,
+            bump: bumps.milestone_escrow,
+        });
+
+        emit!(MakeMilestonesEvent {
+            milestone_escrow: self.milestone_escrow.key(),
+            maker: self.maker.key(),
+            contractor: self.contractor.key(),
+            mint: self.mint.key(),
+            total,
+            milestones: terms.len() as u8,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(total)
+    }
+
+    /// Locks `total` of `mint` in the vault, grossed up so the vault holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, total: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint.to_account_info(), total)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint.decimals, hook_accounts)
+    }
+}
@@ -0,0 +1,117 @@
+use anchor_lang::{prelude::*, solana_program::stake};
+
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    helpers::{authorize_stake, stake_meta},
+    Config, EscrowError, MakeStakeEvent, StakeEscrow,
+};
+
+/// Defines the accounts needed for the `make_stake` instruction, where the maker escrows a native stake account
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeStake<'info> {
+    /// The maker of the escrow, staker and withdrawer of the stake account and payer for the escrow account
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the token the maker expects to receive
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The escrow account, becoming the stake account's staker and withdrawer until it is taken or refunded
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + StakeEscrow::INIT_SPACE,
+        seeds = [b"stake", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub escrow: Account<'info, StakeEscrow>,
+
+    /// CHECK: The stake account escrowed, its owner, state and authorities are checked in `save_escrow`
+    #[account(mut)]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// The clock sysvar, which the stake program's `Authorize` instruction reads
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: The native stake program, checked by address
+    #[account(address = stake::program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeStake<'info> {
+    /// Records the terms of the escrow for the stake account, which the maker must fully control
+    /// Accounts under a lockup are rejected, as their withdrawer can't be changed without the custodian
+    pub fn save_escrow(
+        &mut self,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+        bumps: &MakeStakeBumps,
+    ) -> Result<()> {
+        require!(receive > 0, EscrowError::ZeroReceive);
+        if expiry != 0 {
+            require!(
+                expiry > self.clock.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+        require!(
+            self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        let meta = stake_meta(&self.stake_account)?;
+        require!(
+            meta.authorized.staker == self.maker.key()
+                && meta.authorized.withdrawer == self.maker.key()
+                && !meta.lockup.is_in_force(&self.clock, None),
+            EscrowError::InvalidStakeAccount
+        );
+
+        self.escrow.set_inner(StakeEscrow {
+            seed,
+            maker: self.maker.key(),
+            stake_account: self.stake_account.key(),
+            mint_b: self.mint_b.key(),
+            receive,
+            expiry,
+            bump: bumps.escrow,
+        });
+
+        emit!(MakeStakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            stake_account: self.stake_account.key(),
+            lamports: self.stake_account.lamports(),
+            mint_b: self.mint_b.key(),
+            receive,
+            timestamp: self.clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Hands the stake account's authorities from the maker to the escrow PDA
+    pub fn deposit(&self) -> Result<()> {
+        authorize_stake(
+            &self.stake_program.to_account_info(),
+            &self.stake_account.to_account_info(),
+            &self.clock.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.key(),
+            &[],
+        )
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, MintList, MintListEvent};
+
+/// Defines the accounts needed for the `add_mint` and `remove_mint` instructions, managing the mints new escrows may use
+#[derive(Accounts)]
+pub struct ManageMints<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the mint lists
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> ManageMints<'info> {
+    /// Adds `mint` to `list`, escrows already open are not affected
+    pub fn add_mint(&mut self, mint: Pubkey, list: MintList) -> Result<()> {
+        let mints = self.config.mints_mut(list);
+        require!(!mints.contains(&mint), EscrowError::MintAlreadyListed);
+        require!(
+            mints.len() < Config::MAX_LISTED_MINTS,
+            EscrowError::MintListFull
+        );
+        mints.push(mint);
+
+        self.emit_change(mint, list, true)
+    }
+
+    /// Removes `mint` from `list`
+    pub fn remove_mint(&mut self, mint: Pubkey, list: MintList) -> Result<()> {
+        let mints = self.config.mints_mut(list);
+        let index = mints
+            .iter()
+            .position(|listed| *listed == mint)
+            .ok_or(EscrowError::MintNotListed)?;
+        mints.swap_remove(index);
+
+        self.emit_change(mint, list, false)
+    }
+
+    /// Emits a `MintListEvent` for `mint` being added to or removed from `list`
+    fn emit_change(&self, mint: Pubkey, list: MintList, listed: bool) -> Result<()> {
+        emit!(MintListEvent {
+            admin: self.admin.key(),
+            mint,
+            list,
+            listed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
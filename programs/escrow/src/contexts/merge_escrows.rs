@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{
+    helpers::{reindex, release_vault, rent_recipients},
+    Escrow, EscrowError, EscrowStatus, MergeEvent, PairIndex,
+};
+
+/// Defines the accounts needed for the `merge_escrows` instruction, folding an open escrow into another one on the same terms
+#[derive(Accounts)]
+pub struct MergeEscrows<'info> {
+    /// The owner of the maker's position in both escrows, receiving the rent of the merged vault unless the maker chose otherwise
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The maker of both escrows, receiving the rent of the merged escrow unless they chose otherwise
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the token deposited in both escrows
+    /// Writable so Token-2022 transfer fees withheld in the merged vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Receives the rent of the merged escrow and its vault when the maker chose a treasury for it, checked against the escrow's in `rent_recipients`
+    #[account(mut)]
+    pub rent_treasury: Option<UncheckedAccount<'info>>,
+
+    /// The open escrow receiving the deposit of `merged`
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault of `escrow`, receiving the deposit of `merged`
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The open escrow folded into `escrow`, closed along with its vault
+    #[account(
+        mut,
+        constraint = merged.key() != escrow.key() @ EscrowError::TermsMismatch,
+        constraint = merged.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = merged.load()?.has_same_terms(&*escrow.load()?) @ EscrowError::TermsMismatch,
+        seeds = [b"escrow", merged.load()?.address_maker().as_ref(), merged.load()?.address_seed().as_ref()],
+        bump = merged.load()?.bump
+    )]
+    pub merged: AccountLoader<'info, Escrow>,
+
+    /// Vault of `merged`, emptied into `vault` and closed
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = merged,
+        token::token_program = token_program_a,
+        address = merged.load()?.vault_address(&merged.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub merged_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The index of the escrows' mints, counting the merged deposit in `escrow` alone
+    /// Required when either escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+}
+
+impl<'info> MergeEscrows<'info> {
+    /// Moves the whole vault of `merged` into `escrow`'s, adding what it received to the deposit at the shared price
+    /// `merged` and its vault are then closed, even if kept as a receipt, as nothing was settled through them
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn merge(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let merged = *self.merged.load()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        // A taker who committed to either escrow relies on its deposit until their commitment lapses
+        require!(
+            !merged.is_committed(now) && !self.escrow.load()?.is_committed(now),
+            EscrowError::CommitmentPending
+        );
+        // As does a taker holding a reservation of either
+        let slot = Clock::get()?.slot;
+        require!(
+            !merged.is_reserved(slot) && !self.escrow.load()?.is_reserved(slot),
+            EscrowError::EscrowReserved
+        );
+
+        let address_maker = merged.address_maker();
+        let address_seed = merged.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[merged.bump],
+        ]];
+
+        // The vault's balance is moved rather than `merged.deposit`, so anything donated to it isn't stranded
+        let balance = self.vault.amount;
+        let (escrow_rent_to, vault_rent_to) = self.rent_recipients()?;
+        release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.merged_vault.to_account_info(),
+            &self.vault.to_account_info(),
+            &vault_rent_to,
+            &self.merged.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+        reindex(
+            &mut *self.merged.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+        self.merged.close(escrow_rent_to)?;
+
+        // Any Token-2022 transfer fee is borne by the merged deposit, which only adds what the vault received
+        self.vault.reload()?;
+        let mut escrow = self.escrow.load_mut()?;
+        let deposit = self
+            .vault
+            .amount
+            .checked_sub(balance)
+            .and_then(|received| escrow.deposit.checked_add(received))
+            .ok_or(EscrowError::MathOverflow)?;
+        let receive = escrow.receive_for(deposit)?;
+        escrow.deposit = deposit;
+        escrow.receive = receive;
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+
+        emit!(MergeEvent {
+            escrow: self.escrow.key(),
+            merged: self.merged.key(),
+            maker: escrow.maker,
+            deposit,
+            receive,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Accounts receiving the rent of the merged escrow and of its vault, following the maker's choice as on a refund
+    fn rent_recipients(&self) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        rent_recipients(
+            self.merged.load()?.rent_destination(),
+            &self.mint_a.key(),
+            &self.maker.to_account_info(),
+            &self.owner.to_account_info(),
+            None,
+            rent_treasury.as_ref(),
+        )
+    }
+}
@@ -0,0 +1,100 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{Escrow, EscrowError, EscrowV0, EscrowV1, MigrateEvent};
+
+/// Defines the accounts needed for the `migrate_escrow` instruction, rewriting an escrow made in an older layout in the current one
+/// Permissionless, as the escrow's terms are carried over unchanged
+#[derive(Accounts)]
+pub struct MigrateEscrow<'info> {
+    /// Pays the rent for the space added to the escrow account
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: An escrow in an older layout, which can't be loaded as the current `Escrow` until migrated
+    /// Its owner is checked here, its discriminator, size and address in `migrate`
+    #[account(mut, owner = crate::ID)]
+    pub escrow: UncheckedAccount<'info>,
+
+    /// The vault of a baseline escrow, its associated token account, whose balance was its deposit
+    /// Only needed to migrate baseline escrows, the escrow's later layouts record their deposit
+    #[account(token::authority = escrow)]
+    pub vault: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateEscrow<'info> {
+    /// Reallocs the escrow to `Escrow::SPACE` and rewrites its baseline to V14 terms in the current layout
+    pub fn migrate(&mut self) -> Result<()> {
+        let escrow_info = self.escrow.to_account_info();
+
+        let escrow = {
+            let data = escrow_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == Escrow::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            // Every layout shared the `Escrow` discriminator, so they are told apart by their size
+            match data.len() {
+                EscrowV0::SPACE => self.carry_over_v0(EscrowV0::deserialize(&mut &data[8..])?)?,
+                EscrowV1::SPACE => Escrow::from(EscrowV1::deserialize(&mut &data[8..])?),
+                Escrow::V6_SPACE => Escrow::from_v6(&data[8..]),
+                Escrow::V9_SPACE => Escrow::from_v9(&data[8..]),
+                Escrow::V14_SPACE => Escrow::from_v14(&data[8..]),
+                _ => return err!(EscrowError::AlreadyMigrated),
+            }
+        };
+
+        // Same address `make` gave it, so only genuine escrows get rewritten
+        let address = Pubkey::create_program_address(
+            &[
+                b"escrow",
+                escrow.address_maker().as_ref(),
+                &escrow.address_seed(),
+                &[escrow.bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| ErrorCode::ConstraintSeeds)?;
+        require_keys_eq!(escrow_info.key(), address, ErrorCode::ConstraintSeeds);
+
+        let rent = Rent::get()?.minimum_balance(Escrow::SPACE);
+        let top_up = rent.saturating_sub(escrow_info.lamports());
+        if top_up > 0 {
+            let transfer_accounts = Transfer {
+                from: self.payer.to_account_info(),
+                to: escrow_info.clone(),
+            };
+            let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+            transfer(cpi_ctx, top_up)?;
+        }
+        escrow_info.realloc(Escrow::SPACE, true)?;
+
+        escrow_info.try_borrow_mut_data()?[8..].copy_from_slice(bytemuck::bytes_of(&escrow));
+
+        emit!(MigrateEvent {
+            escrow: escrow_info.key(),
+            maker: escrow.maker,
+            version: escrow.version,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Carries a baseline escrow over with its vault's balance as its deposit
+    fn carry_over_v0(&self, v0: EscrowV0) -> Result<Escrow> {
+        let vault = self.vault.as_ref().ok_or(EscrowError::VaultMismatch)?;
+        let escrow = v0.into_escrow(vault.amount);
+        // Its associated token account under the token program owning it, so the balance is the escrow's deposit
+        let vault_address =
+            escrow.vault_address(&self.escrow.key(), vault.to_account_info().owner)?;
+        require_keys_eq!(vault.key(), vault_address, EscrowError::VaultMismatch);
+        Ok(escrow)
+    }
+}
@@ -1,8 +1,275 @@
+pub mod accept_counter;
+pub use accept_counter::*;
+
+pub mod accept_quote;
+pub use accept_quote::*;
+
+pub mod add_order_book_page;
+pub use add_order_book_page::*;
+
+pub mod approve_resolution;
+pub use approve_resolution::*;
+
+pub mod approve_milestone;
+pub use approve_milestone::*;
+
+pub mod batch_make;
+pub use batch_make::*;
+
+pub mod batch_take;
+pub use batch_take::*;
+
+pub mod bid;
+pub use bid::*;
+
+pub mod cancel_swap;
+pub use cancel_swap::*;
+
+pub mod cancel_auto_refund;
+pub use cancel_auto_refund::*;
+
+pub mod cancel_loan;
+pub use cancel_loan::*;
+
+pub mod cancel_vesting;
+pub use cancel_vesting::*;
+
+pub mod claim_vested;
+pub use claim_vested::*;
+
+pub mod claim_milestone;
+pub use claim_milestone::*;
+
+pub mod close_fill_receipt;
+pub use close_fill_receipt::*;
+
+pub mod close_payment_options;
+pub use close_payment_options::*;
+
+pub mod close_receipt;
+pub use close_receipt::*;
+
+pub mod commit_take;
+pub use commit_take::*;
+
+pub mod create_committee;
+pub use create_committee::*;
+
+pub mod create_pair_index;
+pub use create_pair_index::*;
+
+pub mod create_profile;
+pub use create_profile::*;
+
+pub mod commit_bid;
+pub use commit_bid::*;
+
+pub mod create_template;
+pub use create_template::*;
+
+pub mod execute_auto_refund;
+pub use execute_auto_refund::*;
+
+pub mod forfeit_bid;
+pub use forfeit_bid::*;
+
+pub mod forfeit_reservation;
+pub use forfeit_reservation::*;
+
+pub mod fund_swap;
+pub use fund_swap::*;
+
+pub mod initialize_config;
+pub use initialize_config::*;
+
+pub mod initialize_event_log;
+pub use initialize_event_log::*;
+
+pub mod join_allowlist;
+pub use join_allowlist::*;
+
+pub mod liquidate_after_deadline;
+pub use liquidate_after_deadline::*;
+
 pub mod make;
 pub use make::*;
 
+pub mod make_auction;
+pub use make_auction::*;
+
+pub mod make_basket;
+pub use make_basket::*;
+
+pub mod make_chained;
+pub use make_chained::*;
+
+pub mod make_compressed;
+pub use make_compressed::*;
+
+pub mod make_edition;
+pub use make_edition::*;
+
+pub mod make_loan;
+pub use make_loan::*;
+
+pub mod make_milestones;
+pub use make_milestones::*;
+
+pub mod make_stake;
+pub use make_stake::*;
+
+pub mod manage_mints;
+pub use manage_mints::*;
+
+pub mod merge_escrows;
+pub use merge_escrows::*;
+
+pub mod migrate_escrow;
+pub use migrate_escrow::*;
+
+pub mod open_dispute;
+pub use open_dispute::*;
+
+pub mod post_quote;
+pub use post_quote::*;
+
 pub mod take;
 pub use take::*;
 
+pub mod take_basket;
+pub use take_basket::*;
+
+pub mod take_compressed;
+pub use take_compressed::*;
+
+pub mod take_edition;
+pub use take_edition::*;
+
+pub mod take_loan;
+pub use take_loan::*;
+
+pub mod take_nft;
+pub use take_nft::*;
+
+pub mod take_stake;
+pub use take_stake::*;
+
+pub mod quote;
+pub use quote::*;
+
+pub mod reduce_deposit;
+pub use reduce_deposit::*;
+
 pub mod refund;
-pub use refund::*;
\ No newline at end of file
+pub use refund::*;
+
+pub mod refund_basket;
+pub use refund_basket::*;
+
+pub mod refund_compressed;
+pub use refund_compressed::*;
+
+pub mod refund_edition;
+pub use refund_edition::*;
+
+pub mod refund_expired;
+pub use refund_expired::*;
+
+pub mod refund_stake;
+pub use refund_stake::*;
+
+pub mod remove_fee_override;
+pub use remove_fee_override::*;
+
+pub mod repay;
+pub use repay::*;
+
+pub mod reserve;
+pub use reserve::*;
+
+pub mod resolve_dispute;
+pub use resolve_dispute::*;
+
+pub mod revoke_cancel_authority;
+pub use revoke_cancel_authority::*;
+
+pub mod schedule_auto_refund;
+pub use schedule_auto_refund::*;
+
+pub mod prepare_take;
+pub use prepare_take::*;
+
+pub mod propose_counter;
+pub use propose_counter::*;
+
+pub mod propose_swap;
+pub use propose_swap::*;
+
+pub mod prune_order_book;
+pub use prune_order_book::*;
+
+pub mod set_cancel_authority;
+pub use set_cancel_authority::*;
+
+pub mod set_cancel_penalty;
+pub use set_cancel_penalty::*;
+
+pub mod set_fee_currency;
+pub use set_fee_currency::*;
+
+pub mod set_fee_override;
+pub use set_fee_override::*;
+
+pub mod set_fee_tiers;
+pub use set_fee_tiers::*;
+
+pub mod set_membership;
+pub use set_membership::*;
+
+pub mod set_mint_policy;
+pub use set_mint_policy::*;
+
+pub mod set_paused;
+pub use set_paused::*;
+
+pub mod set_payment_options;
+pub use set_payment_options::*;
+
+pub mod set_payout;
+pub use set_payout::*;
+
+pub mod set_referral_bps;
+pub use set_referral_bps::*;
+
+pub mod settle_auction;
+pub use settle_auction::*;
+
+pub mod settle_swap;
+pub use settle_swap::*;
+
+pub mod split_escrow;
+pub use split_escrow::*;
+
+pub mod sweep_surplus;
+pub use sweep_surplus::*;
+
+pub mod top_up;
+pub use top_up::*;
+
+pub mod transfer_maker;
+pub use transfer_maker::*;
+
+pub mod transfer_position;
+pub use transfer_position::*;
+
+pub mod update;
+pub use update::*;
+
+pub mod withdraw_counter;
+pub use withdraw_counter::*;
+
+pub mod withdraw_fees;
+pub use withdraw_fees::*;
+
+pub mod withdraw_quote;
+pub use withdraw_quote::*;
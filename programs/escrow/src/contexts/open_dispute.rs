@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{helpers::reindex, DisputeEvent, Escrow, EscrowError, EscrowStatus, PairIndex};
+
+/// Defines the accounts needed for the `open_dispute` instruction, contesting an escrow with its arbiter
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    /// The owner of the maker's position or the designated taker of the escrow
+    #[account(
+        constraint = signer.key() == escrow.load()?.owner
+            || Some(signer.key()) == escrow.load()?.taker() @ EscrowError::NotEscrowParty
+    )]
+    pub signer: Signer<'info>,
+
+    /// The escrow being contested, frozen until the arbiter resolves it
+    #[account(
+        mut,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = escrow.load()?.arbiter().is_some() @ EscrowError::NoArbiter,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The index of the escrow's mints, which no longer counts the escrow once disputed
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+}
+
+impl<'info> OpenDispute<'info> {
+    /// Marks the escrow as disputed, so it can't be taken, updated or refunded until the arbiter resolves it
+    pub fn open_dispute(&mut self) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.set_status(EscrowStatus::Disputed);
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+        drop(escrow);
+
+        emit!(DisputeEvent {
+            escrow: self.escrow.key(),
+            opened_by: self.signer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Escrow, EscrowError, EscrowStatus, RfqQuote, RfqQuoteEvent,
+};
+
+/// Defines the accounts needed for the `post_quote` instruction, where a prospective taker bids for the whole deposit of an escrow
+#[derive(Accounts)]
+pub struct PostQuote<'info> {
+    /// The prospective taker, funding the quote and paying for its accounts
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The mint of the token the escrow is paid in
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The taker's token account for `mint_b`, funding the quote's vault
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// The open escrow the quote is posted on, which must be open to this taker
+    #[account(
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        constraint = escrow.load()?.is_taker_allowed(&taker.key()) @ EscrowError::UnauthorizedTaker,
+        constraint = !escrow.load()?.collection_offer() @ EscrowError::CollectionOfferRequiresNft,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The quote, one per escrow and taker
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + RfqQuote::INIT_SPACE,
+        seeds = [b"rfq", escrow.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub quote: Account<'info, RfqQuote>,
+
+    /// Vault holding the quoted mint_b until the quote is accepted, refunded or withdrawn
+    #[account(
+        init,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = quote,
+        associated_token::token_program = token_program
+    )]
+    pub quote_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_b`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PostQuote<'info> {
+    /// Records the taker's quote of `amount` mint_b for the whole deposit
+    /// Hashlocked, vesting and scheduled escrows are settled through their own take flow, so they can't be quoted
+    pub fn post_quote(&mut self, amount: u64, bumps: &PostQuoteBumps) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        require!(
+            amount > 0
+                && escrow.hashlock().is_none()
+                && escrow.vesting().is_none()
+                && escrow.schedule().is_none(),
+            EscrowError::InvalidQuote
+        );
+        drop(escrow);
+
+        self.quote.set_inner(RfqQuote {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            mint_b: self.mint_b.key(),
+            amount,
+            bump: bumps.quote,
+        });
+
+        emit!(RfqQuoteEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            amount,
+            accepted: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Locks `amount` of mint_b in the quote's vault, grossed up so it holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.quote_vault.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, hook_accounts)
+    }
+}
@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, AssociatedToken, Create},
+    token_interface::{Mint, TokenInterface},
+};
+
+use crate::{helpers::is_native_mint, Config, Escrow, EscrowError, EscrowStatus, FeeCurrency};
+
+/// Defines the accounts needed for the `prepare_take` instruction, creating the token accounts `take` expects to exist
+/// Kept out of `take` so fills don't pay for account creation, and so a fee payer can create them ahead of time
+#[derive(Accounts)]
+pub struct PrepareTake<'info> {
+    /// Pays the rent of the created accounts, the taker or anyone sponsoring them
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The taker the escrow will be filled by, authority of `taker_ata_a`
+    pub taker: SystemAccount<'info>,
+
+    /// The owner of the maker's position, authority of `owner_ata_b`
+    pub owner: SystemAccount<'info>,
+
+    /// The mint of the token deposited by the maker
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token expected to be received by the owner
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// CHECK: Associated token account of the taker for mint_a, its address is checked by the associated token program
+    #[account(mut)]
+    pub taker_ata_a: UncheckedAccount<'info>,
+
+    /// CHECK: Associated token account of the owner for mint_b, its address is checked by the associated token program
+    /// Not needed when mint_b is wrapped SOL, the owner then receives lamports
+    #[account(mut)]
+    pub owner_ata_b: Option<UncheckedAccount<'info>>,
+
+    /// The program's global config, authority of the fee vault
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// CHECK: Token account of the config PDA collecting the protocol fees in mint_b, its address is checked by the associated token program
+    #[account(mut)]
+    pub fee_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Token account of the config PDA collecting the protocol fees in mint_a, its address is checked by the associated token program
+    /// Only needed when the config charges fees in mint_a
+    #[account(mut)]
+    pub fee_vault_a: Option<UncheckedAccount<'info>>,
+
+    /// The open escrow about to be taken, tying the owner and mints to its terms
+    #[account(
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PrepareTake<'info> {
+    /// Creates the taker's mint_a account, the owner's mint_b account and the fee vaults, skipping any that already exist
+    pub fn prepare(&self) -> Result<()> {
+        self.create_ata(
+            self.taker_ata_a.to_account_info(),
+            self.taker.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.token_program_a.to_account_info(),
+        )?;
+
+        if !is_native_mint(&self.mint_b.key()) {
+            let owner_ata_b = self
+                .owner_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            self.create_ata(
+                owner_ata_b.to_account_info(),
+                self.owner.to_account_info(),
+                self.mint_b.to_account_info(),
+                self.token_program_b.to_account_info(),
+            )?;
+        }
+
+        if self.config.fee_currency == FeeCurrency::MintA {
+            let fee_vault_a = self
+                .fee_vault_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            self.create_ata(
+                fee_vault_a.to_account_info(),
+                self.config.to_account_info(),
+                self.mint_a.to_account_info(),
+                self.token_program_a.to_account_info(),
+            )?;
+        }
+        self.create_ata(
+            self.fee_vault.to_account_info(),
+            self.config.to_account_info(),
+            self.mint_b.to_account_info(),
+            self.token_program_b.to_account_info(),
+        )
+    }
+
+    /// Creates the associated token account of `authority` for `mint` unless it already exists
+    fn create_ata(
+        &self,
+        associated_token: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        mint: AccountInfo<'info>,
+        token_program: AccountInfo<'info>,
+    ) -> Result<()> {
+        let create_accounts = Create {
+            payer: self.payer.to_account_info(),
+            associated_token,
+            authority,
+            mint,
+            system_program: self.system_program.to_account_info(),
+            token_program,
+        };
+        create_idempotent(CpiContext::new(
+            self.associated_token_program.to_account_info(),
+            create_accounts,
+        ))
+    }
+}
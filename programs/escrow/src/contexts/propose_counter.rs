@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{CounterOffer, CounterOfferEvent, Escrow, EscrowError, EscrowStatus};
+
+/// Defines the accounts needed for the `propose_counter` instruction, letting a prospective taker suggest another price
+#[derive(Accounts)]
+pub struct ProposeCounter<'info> {
+    /// The prospective taker, paying for the counter-offer account
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The open escrow the counter-offer is made on, which must be open to this taker
+    #[account(
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        constraint = escrow.load()?.is_taker_allowed(&taker.key()) @ EscrowError::UnauthorizedTaker,
+        constraint = !escrow.load()?.collection_offer() @ EscrowError::CollectionOfferRequiresNft,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The counter-offer, one per escrow and taker
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + CounterOffer::INIT_SPACE,
+        seeds = [b"counter", escrow.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProposeCounter<'info> {
+    /// Records the taker's offer of `receive` mint_b for the whole deposit
+    pub fn propose_counter(&mut self, receive: u64, bumps: &ProposeCounterBumps) -> Result<()> {
+        require!(receive > 0, EscrowError::ZeroReceive);
+
+        self.counter_offer.set_inner(CounterOffer {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            receive,
+            bump: bumps.counter_offer,
+        });
+
+        emit!(CounterOfferEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            receive,
+            accepted: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
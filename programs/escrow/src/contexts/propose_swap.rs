@@ -0,0 +1,136 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Config, EscrowError, ProposeSwapEvent, Swap,
+};
+
+/// Defines the accounts needed for the `propose_swap` instruction, where the maker locks their side of a two-sided swap
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct ProposeSwap<'info> {
+    /// The maker proposing the swap, locking `amount_a` of mint_a
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the token locked by the maker
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token the taker has to lock
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The maker's token account for mint_a, funding `vault_a`
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The swap account, holding both sides' terms until it is settled or cancelled
+    #[account(
+        init,
+        payer = maker,
+        space = 8 + Swap::INIT_SPACE,
+        seeds = [b"swap", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub swap: Account<'info, Swap>,
+
+    /// Vault holding the maker's side of the swap, controlled by the swap PDA
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProposeSwap<'info> {
+    /// Records the terms of the swap with `taker` as the only counterparty
+    pub fn save_swap(
+        &mut self,
+        seed: u64,
+        amount_a: u64,
+        amount_b: u64,
+        taker: Pubkey,
+        bumps: &ProposeSwapBumps,
+    ) -> Result<()> {
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+        require!(amount_a > 0, EscrowError::ZeroDeposit);
+        require!(amount_b > 0, EscrowError::ZeroReceive);
+        require!(
+            self.config.is_mint_permitted(&self.mint_a.key())
+                && self.config.is_mint_permitted(&self.mint_b.key()),
+            EscrowError::MintNotPermitted
+        );
+
+        self.swap.set_inner(Swap {
+            seed,
+            maker: self.maker.key(),
+            taker,
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b,
+            funded: false,
+            bump: bumps.swap,
+        });
+
+        emit!(ProposeSwapEvent {
+            swap: self.swap.key(),
+            maker: self.maker.key(),
+            taker,
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Locks `amount_a` of mint_a in `vault_a`, grossed up so the vault holds exactly that amount with a Token-2022 transfer fee
+    pub fn deposit(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_a.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.vault_a.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_a.to_account_info(), self.swap.amount_a)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals, hook_accounts)
+    }
+}
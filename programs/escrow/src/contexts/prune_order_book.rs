@@ -0,0 +1,42 @@
+use anchor_lang::{prelude::*, Discriminator};
+
+use crate::{Escrow, EscrowError, EscrowStatus, ListingEvent, OrderBookPage};
+
+/// Defines the accounts needed for the permissionless `prune_order_book` instruction, removing a stale listing from an order book page
+#[derive(Accounts)]
+pub struct PruneOrderBook<'info> {
+    /// The page listing the escrow
+    #[account(mut)]
+    pub order_book_page: Account<'info, OrderBookPage>,
+
+    /// CHECK: The listed escrow, which may have been closed since, only read if it is still an escrow
+    pub escrow: UncheckedAccount<'info>,
+}
+
+impl<'info> PruneOrderBook<'info> {
+    /// Removes the escrow from the page once it is no longer open
+    /// Covers escrows settled without passing their page, e.g. refunded once expired, taken in a batch or merged
+    pub fn prune(&mut self) -> Result<()> {
+        // The escrow may have been closed, in which case it is no longer owned by the program
+        let open = {
+            let data = self.escrow.try_borrow_data()?;
+            *self.escrow.owner == crate::ID
+                && data.len() == Escrow::SPACE
+                && data[..8] == Escrow::DISCRIMINATOR
+                && bytemuck::pod_read_unaligned::<Escrow>(&data[8..]).has_status(EscrowStatus::Open)
+        };
+        require!(!open, EscrowError::EscrowStillOpen);
+        require!(
+            self.order_book_page.remove(&self.escrow.key()),
+            EscrowError::OrderBookMismatch
+        );
+
+        emit!(ListingEvent {
+            escrow: self.escrow.key(),
+            page: self.order_book_page.key(),
+            listed: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
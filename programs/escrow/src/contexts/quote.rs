@@ -0,0 +1,140 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::Mint;
+
+use crate::{
+    helpers::{gross_up_transfer_fee, is_native_mint, ui_amount},
+    Config, Escrow, EscrowError, EscrowStatus, FeeCurrency, FeeOverride, Template, TraderStats,
+};
+
+/// Settlement of a full fill of an escrow, as `take` would execute it now
+/// Returned by `quote` through return data, for UIs to simulate before sending `take`
+/// Amounts are raw, as settled, their UI equivalents include the interest accrued by interest-bearing mints
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct Quote {
+    pub deposit: u64, // amount of mint_a released from the vault, the fee included when charged in mint_a
+    pub receive: u64, // price of the deposit in mint_b
+    pub deposit_ui: String, // `deposit` in UI units of mint_a at the time of the quote
+    pub receive_ui: String, // `receive` in UI units of mint_b at the time of the quote
+    pub fee_currency: FeeCurrency, // currency `fee` is charged in
+    pub fee: u64,     // protocol fee at the regular, tiered or overridden rate, in `fee_currency`
+    pub proceeds: u64, // amount of mint_b the position's owner receives
+    pub taker_pays: u64, // amount of mint_b the taker sends, Token-2022 transfer fees included
+    pub expiry: i64,  // deadline to take the escrow, 0 if none
+    pub expired: bool, // whether the deadline has passed
+    pub fillable: bool, // whether `take` would accept the escrow's status and deadline
+}
+
+/// Defines the accounts needed for the read-only `quote` instruction
+#[derive(Accounts)]
+pub struct GetQuote<'info> {
+    /// The escrow being quoted
+    #[account(
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The mint of the token deposited by the maker, whose UI amount is quoted
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The mint of the token expected by the maker, whose transfer fee the taker bears
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payment
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// A fee the admin negotiated for this escrow or its maker, quoted instead of the config's fee
+    /// Optional, the config's fee applies when it isn't passed
+    #[account(
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump,
+        constraint = fee_override.subject == escrow.key()
+            || fee_override.subject == escrow.load()?.maker @ EscrowError::FeeOverrideMismatch
+    )]
+    pub fee_override: Option<Account<'info, FeeOverride>>,
+
+    /// The volume in mint_b of the taker the quote is for, whose fee tier is quoted when no override is passed
+    #[account(
+        seeds = [b"trader_stats", trader_stats.trader.as_ref(), mint_b.key().as_ref()],
+        bump = trader_stats.bump
+    )]
+    pub trader_stats: Option<Account<'info, TraderStats>>,
+
+    /// The template the escrow was made from, whose fee is quoted when no override is passed
+    /// Required to quote escrows made from a template, and only accepted for those
+    #[account(
+        address = escrow.load()?.template @ EscrowError::TemplateMismatch
+    )]
+    pub template: Option<Account<'info, Template>>,
+}
+
+impl<'info> GetQuote<'info> {
+    /// Prices a full fill of the escrow, without changing any account
+    pub fn quote(&self) -> Result<Quote> {
+        let escrow = self.escrow.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        let expired = escrow.is_expired(now);
+        require!(
+            escrow.template().is_none() || self.template.is_some(),
+            EscrowError::TemplateMismatch
+        );
+        let fee_bps = match (&self.fee_override, &self.template, &self.trader_stats) {
+            (Some(fee_override), _, _) => fee_override.fee_bps,
+            (None, Some(template), _) => template.fee_bps,
+            (None, None, Some(trader_stats)) => self.config.tier_fee_bps(trader_stats.volume),
+            (None, None, None) => self.config.fee_bps,
+        };
+        let fee_currency = self.config.fee_currency;
+        let fee = match fee_currency {
+            FeeCurrency::MintB => self.config.fee_at(escrow.receive, fee_bps)?,
+            FeeCurrency::MintA => self.config.fee_at(escrow.deposit, fee_bps)?,
+            FeeCurrency::Lamports { lamports, .. } => lamports,
+        };
+        // Only a fee in mint_b is taken out of the owner's proceeds
+        let fee_b = match fee_currency {
+            FeeCurrency::MintB => fee,
+            _ => 0,
+        };
+        let proceeds = escrow
+            .receive
+            .checked_sub(fee_b)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        // Like in `take`, the fee and the proceeds are sent separately, each grossed up for the transfer fee
+        // Wrapped SOL is paid in lamports, which carry no transfer fee
+        let taker_pays = if is_native_mint(&self.mint_b.key()) {
+            escrow.receive
+        } else {
+            let mint_b = self.mint_b.to_account_info();
+            let fee = match fee_b {
+                0 => 0,
+                fee => gross_up_transfer_fee(&mint_b, fee)?,
+            };
+            fee.checked_add(gross_up_transfer_fee(&mint_b, proceeds)?)
+                .ok_or(EscrowError::MathOverflow)?
+        };
+
+        Ok(Quote {
+            deposit: escrow.deposit,
+            receive: escrow.receive,
+            deposit_ui: ui_amount(&self.mint_a, escrow.deposit, now)?,
+            receive_ui: ui_amount(&self.mint_b, escrow.receive, now)?,
+            fee_currency,
+            fee,
+            proceeds,
+            taker_pays,
+            expiry: escrow.expiry,
+            expired,
+            fillable: escrow.has_status(EscrowStatus::Open)
+                && !expired
+                && !self.config.paused
+                && !escrow.collection_offer(),
+        })
+    }
+}
@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{
+    helpers::{reindex, transfer_checked_with_hook},
+    Escrow, EscrowError, EscrowStatus, PairIndex, ResizeEvent,
+};
+
+/// Defines the accounts needed for the `reduce_deposit` instruction, letting the maker pull part of the deposit back
+#[derive(Accounts)]
+pub struct ReduceDeposit<'info> {
+    /// The owner of the maker's position, the only one allowed to reduce its deposit
+    pub owner: Signer<'info>,
+
+    /// The mint of the token deposited in the escrow
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The owner's token account for mint_a, receiving the withdrawn tokens
+    /// Needed for wrapped SOL too, as the vault can only be unwrapped by closing it
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The open escrow whose deposit is reduced
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        constraint = !escrow.load()?.is_reserved(Clock::get()?.slot) @ EscrowError::EscrowReserved,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the deposit
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The index of the escrow's mints, no longer counting the withdrawn deposit
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+}
+
+impl<'info> ReduceDeposit<'info> {
+    /// Withdraws `amount` of mint_a back to the owner and lowers `deposit` and `receive` at the escrow's price
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn reduce_deposit(
+        &mut self,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+
+        // The escrow must keep something to fill, withdrawing everything goes through `refund`
+        require!(
+            amount > 0 && amount < escrow.deposit,
+            EscrowError::InvalidAmount
+        );
+
+        // Like a refund, a hashlocked deposit stays claimable with the preimage until its timeout
+        if escrow.hashlock().is_some() {
+            require!(
+                escrow.is_expired(Clock::get()?.unix_timestamp),
+                EscrowError::HashlockNotExpired
+            );
+        }
+
+        let deposit = escrow
+            .deposit
+            .checked_sub(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        let receive = escrow.receive_for(deposit)?;
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.owner_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            accounts,
+            &signer_seeds,
+        );
+        // Any Token-2022 transfer fee on the way back is borne by the owner
+        transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)?;
+
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.deposit = deposit;
+        escrow.receive = receive;
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+
+        emit!(ResizeEvent {
+            escrow: self.escrow.key(),
+            maker: escrow.maker,
+            deposit,
+            receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
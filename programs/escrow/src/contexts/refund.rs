@@ -3,101 +3,319 @@ use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
-        TransferChecked,
+        close_account, CloseAccount, Mint, TokenAccount, TokenInterface, TransferChecked,
     },
 };
 
-use crate::Escrow;
+use crate::{
+    helpers::{
+        delist, harvest_withheld_fees, is_native_mint, reindex, rent_recipients,
+        transfer_checked_with_hook,
+    },
+    CancelAuthority, Config, Escrow, EscrowError, EscrowStatus, EventLog, LogKind, OrderBookPage,
+    PairIndex, PenaltyPaidEvent, RefundEvent, Reservation,
+};
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
-    /// The acount of the maker who initiates the refund
+    /// Initiates the refund: the owner, or the cancel authority they delegated it to
     /// Must a signer to authorize the refund
+    authority: Signer<'info>,
+
+    /// The owner of the maker's position, the maker unless the position was transferred
+    /// Receives the refund whoever initiates it
     #[account(mut)]
-    maker: Signer<'info>,
+    owner: SystemAccount<'info>,
+
+    /// The owner's delegation, needed when `authority` isn't the owner
+    #[account(
+        has_one = owner,
+        has_one = authority @ EscrowError::UnauthorizedCanceller,
+        seeds = [b"cancel_authority", owner.key().as_ref()],
+        bump = cancel_authority.bump
+    )]
+    cancel_authority: Option<Account<'info, CancelAuthority>>,
+
+    /// The maker of the escrow, receiving the rent of the escrow account
+    #[account(mut)]
+    maker: SystemAccount<'info>,
 
     /// The mint of the token that was initially deposited into the escrow by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
     mint_a: InterfaceAccount<'info, Mint>,
 
-    /// The maker's associated token account for Mint A, where tokens will be refunded to
+    /// The owner's token account for Mint A, where tokens will be refunded to
+    /// Usually their associated token account, but any account they own works, e.g. the custody account of an exchange
+    /// Not needed when Mint A is wrapped SOL, closing the vault then unwraps the refund straight to the owner
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = maker,
-        associated_token::token_program = token_program
+        token::mint = mint_a,
+        token::authority = owner,
+        token::token_program = token_program_a
+    )]
+    owner_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: Receives the rent of the escrow and its vault when the maker chose a treasury for it, checked against the escrow's in `rent_recipients`
+    #[account(mut)]
+    rent_treasury: Option<UncheckedAccount<'info>>,
+
+    /// The program's global config, holding the penalty paid to the taker reserving the escrow
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
     )]
-    maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    config: Box<Account<'info, Config>>,
 
     /// The escrow account holding the state and terms of the escrow, including the seed and associated tokens
-    /// This account will be closed, and its remaining balance will be refunded to the maker
+    /// This account will be closed, and its remaining balance will be refunded to the maker, unless kept as a receipt
+    /// Chained escrows can be refunded before they are funded, closing them with their empty vault
     #[account(
         mut,
-        close = maker, 
-        has_one = mint_a,
-        has_one = maker,
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()], 
-        bump = escrow.bump
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) || escrow.load()?.has_status(EscrowStatus::Chained) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
     )]
-    escrow: Account<'info, Escrow>,
+    escrow: AccountLoader<'info, Escrow>,
 
     /// The vault account where the tokens from the maker were deposited and held during the escrow
     /// Tokens will be transferred back to the maker and account closed
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = escrow,
-        associated_token::token_program = token_program
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
 
+    /// The reservation holding the escrow, left to be closed with `forfeit_reservation`, which returns its bond once the escrow is gone
+    /// Only needed while it holds the escrow and the config charges a cancellation penalty
+    #[account(
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump = reservation.bump
+    )]
+    reservation: Option<Box<Account<'info, Reservation>>>,
+
+    /// The reserving taker's token account for mint_a, receiving the cancellation penalty
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::token_program = token_program_a
+    )]
+    reserver_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's event log, recording this refund when passed
+    #[account(
+        mut,
+        seeds = [b"event_log"],
+        bump
+    )]
+    event_log: Option<AccountLoader<'info, EventLog>>,
+
+    /// The order book page listing the escrow, which the refund removes it from
+    /// Required to refund a listed escrow
+    #[account(mut)]
+    order_book_page: Option<Box<Account<'info, OrderBookPage>>>,
+
+    /// The index of the escrow's mints, which the refund removes it from
+    /// Required to refund an indexed escrow
+    #[account(mut)]
+    pair_index: Option<Box<Account<'info, PairIndex>>>,
+
     // SPL Associated Token Program used for handling operations on associated token accounts
     associated_token_program: Program<'info, AssociatedToken>,
-    // SPL Token Program used for tokens transfers and other token operations
-    token_program: Interface<'info, TokenInterface>,
+    // Token Program owning Mint A, used for tokens transfers and other token operations
+    token_program_a: Interface<'info, TokenInterface>,
     // Solana System Program used for account creation, lamports transfer, etc.
     system_program: Program<'info, System>,
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
+    /// Only the owner, or their cancel authority, can refund an escrow
+    /// A hashlocked escrow stays claimable by whoever knows the preimage until its timeout
+    pub fn check_refundable(&self) -> Result<()> {
+        require!(
+            self.authority.key() == self.owner.key() || self.cancel_authority.is_some(),
+            EscrowError::UnauthorizedCanceller
+        );
+        let escrow = self.escrow.load()?;
+        if escrow.hashlock().is_some() {
+            require!(
+                escrow.is_expired(Clock::get()?.unix_timestamp),
+                EscrowError::HashlockNotExpired
+            );
+        }
+        Ok(())
+    }
+
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn refund_and_close_vault(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
         // Prepare the signer seeds for authorizig operations with the escrow's PDA
+        let escrow = *self.escrow.load()?;
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
-            self.maker.to_account_info().key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump],
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
         ]];
 
-        // Set up the transfer checked call to move tokens from the vault back to the maker's ATA
-        let xfer_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+        let penalty = self.pay_cancel_penalty(&signer_seeds, hook_accounts)?;
+        let amount = self
+            .vault
+            .amount
+            .checked_sub(penalty)
+            .ok_or(EscrowError::MathOverflow)?;
 
-        // Execute the transfer checked operaiton, transfrering any remaining SOL to the maker
-        let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            xfer_accounts,
-            &signer_seeds,
-        );
-        transfer_checked(ctx, self.vault.amount, self.mint_a.decimals)?;
+        // A wrapped SOL vault can be closed with its balance, which unwraps it into the owner's lamports
+        if !is_native_mint(&self.mint_a.key()) {
+            let owner_ata_a = self
+                .owner_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+
+            // Set up the transfer checked call to move tokens from the vault back to the owner's ATA
+            let xfer_accounts = TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: owner_ata_a.to_account_info(),
+                authority: self.escrow.to_account_info(),
+            };
+
+            // Execute the transfer checked operaiton, transfrering any remaining SOL to the maker
+            // The whole balance is returned: the remaining recorded deposit plus anything donated to the vault, which must be empty to be closed
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_a.to_account_info(),
+                xfer_accounts,
+                &signer_seeds,
+            );
+            // Any Token-2022 transfer fee on the way back is borne by the owner
+            transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        harvest_withheld_fees(
+            self.token_program_a.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
 
-        // Set up the closing of the vault account, transferring any remaining SOL to the maker
+        // Set up the closing of the vault account, transferring its rent where the maker chose, the owner by default
+        let (_, vault_rent_to) = self.rent_recipients()?;
         let close_accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
+            destination: vault_rent_to,
             authority: self.escrow.to_account_info(),
         };
 
         // Execute the account closure using the signer seeds
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            self.token_program_a.to_account_info(),
             close_accounts,
             &signer_seeds,
         );
-        close_account(ctx)
+        close_account(ctx)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(event_log) = &self.event_log {
+            event_log.load_mut()?.record(
+                LogKind::Refund,
+                self.escrow.key(),
+                self.authority.key(),
+                amount,
+                now,
+            )?;
+        }
+
+        emit!(RefundEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            amount,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Pays the taker reserving the escrow the config's cancellation penalty out of the vault, returning it
+    /// Makers can't yank an offer from under a taker who reserved it for free, escrows not reserved owe nothing
+    fn pay_cancel_penalty(
+        &self,
+        signer_seeds: &[&[&[u8]]; 1],
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<u64> {
+        // Computed from the recorded deposit, so donations to the vault can't inflate it
+        let escrow = *self.escrow.load()?;
+        let penalty = self.config.cancel_penalty(escrow.deposit)?;
+        if penalty == 0 || !escrow.is_reserved(Clock::get()?.slot) {
+            return Ok(0);
+        }
+        let (Some(reservation), Some(reserver_ata_a)) = (&self.reservation, &self.reserver_ata_a)
+        else {
+            return err!(EscrowError::PenaltyOwed);
+        };
+        require_keys_eq!(
+            reserver_ata_a.owner,
+            reservation.taker,
+            EscrowError::ReservationMismatch
+        );
+
+        let xfer_accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: reserver_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            xfer_accounts,
+            signer_seeds,
+        );
+        transfer_checked_with_hook(ctx, penalty, self.mint_a.decimals, hook_accounts)?;
+
+        emit!(PenaltyPaidEvent {
+            escrow: self.escrow.key(),
+            taker: reservation.taker,
+            amount: penalty,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(penalty)
+    }
+
+    /// Closes the escrow account, sending its rent where the maker chose, back to them by default
+    /// Escrows kept as receipts are marked as refunded instead, until the maker closes them with `close_receipt`
+    pub fn close_escrow(&mut self) -> Result<()> {
+        delist(&self.escrow, self.order_book_page.as_deref_mut())?;
+        reindex(
+            &mut *self.escrow.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+        if self.escrow.load()?.keep_receipt() {
+            self.escrow.load_mut()?.set_status(EscrowStatus::Refunded);
+            return Ok(());
+        }
+        let (escrow_rent_to, _) = self.rent_recipients()?;
+        self.escrow.close(escrow_rent_to)
+    }
+
+    /// Accounts receiving the rent of the escrow and of its vault once closed, following the maker's choice
+    fn rent_recipients(&self) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        rent_recipients(
+            self.escrow.load()?.rent_destination(),
+            &self.mint_a.key(),
+            &self.maker.to_account_info(),
+            &self.owner.to_account_info(),
+            None,
+            rent_treasury.as_ref(),
+        )
     }
 }
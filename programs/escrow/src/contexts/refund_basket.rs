@@ -0,0 +1,108 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::TokenInterface,
+};
+
+use crate::{helpers::release_vault, Basket, EscrowError, RefundBasketEvent};
+
+/// Defines the accounts needed for the `refund_basket` instruction
+/// Each leg is passed as remaining accounts, in groups of `[mint, vault, maker_ata]`, in the basket's order
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+    /// The maker of the basket, the only one allowed to take its legs back
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The basket being refunded, closed to the maker once every leg has been returned
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump
+    )]
+    pub basket: Account<'info, Basket>,
+
+    /// Used to recreate the maker's associated token account for a leg if it was closed in the meantime
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning every leg's mint
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBasket<'info> {
+    /// Returns every leg's vault to the maker and closes it, the vaults' rent goes back to the maker
+    pub fn refund_legs(&mut self, leg_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            leg_accounts.len() == self.basket.legs.len() * Basket::ACCOUNTS_PER_LEG,
+            EscrowError::InvalidLegs
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.to_account_info().key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for (leg, accounts) in self
+            .basket
+            .legs
+            .iter()
+            .zip(leg_accounts.chunks(Basket::ACCOUNTS_PER_LEG))
+        {
+            let [mint, vault, maker_ata] = accounts else {
+                return err!(EscrowError::InvalidLegs);
+            };
+
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::MintMismatch);
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(
+                    &self.basket.key(),
+                    &leg.mint,
+                    self.token_program_a.key
+                ),
+                EscrowError::VaultMismatch
+            );
+
+            let create_accounts = Create {
+                payer: self.maker.to_account_info(),
+                associated_token: maker_ata.clone(),
+                authority: self.maker.to_account_info(),
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program_a.to_account_info(),
+            };
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                create_accounts,
+            ))?;
+
+            release_vault(
+                &self.token_program_a.to_account_info(),
+                mint,
+                vault,
+                maker_ata,
+                &self.maker.to_account_info(),
+                &self.basket.to_account_info(),
+                &signer_seeds,
+                &[],
+            )?;
+        }
+
+        emit!(RefundBasketEvent {
+            basket: self.basket.key(),
+            maker: self.maker.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use mpl_bubblegum::{
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    utils::get_asset_id,
+};
+
+use crate::{
+    helpers::transfer_compressed, CompressedEscrow, EscrowError, LeafProof, RefundCompressedEvent,
+};
+
+/// Defines the accounts needed for the `refund_compressed` instruction, returning a compressed NFT to its maker
+/// The proof nodes of the NFT's leaf are passed as remaining accounts
+#[derive(Accounts)]
+pub struct RefundCompressed<'info> {
+    /// The maker of the escrow, gets the compressed NFT back along with the escrow's rent
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The escrow being refunded, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = merkle_tree @ EscrowError::AssetMismatch,
+        seeds = [b"compressed", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, CompressedEscrow>,
+
+    /// CHECK: Bubblegum config of `merkle_tree`, checked by Bubblegum
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Merkle tree holding the compressed NFT, checked against the escrow
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: The Bubblegum program, checked by address
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Noop program Bubblegum logs leaves with, checked by address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Account Compression program owning the tree, checked by address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundCompressed<'info> {
+    /// Transfers the compressed NFT from the escrow PDA back to the maker
+    pub fn refund(&self, proof: &LeafProof, proof_nodes: &[AccountInfo<'info>]) -> Result<()> {
+        require_keys_eq!(
+            get_asset_id(self.merkle_tree.key, proof.nonce),
+            self.escrow.asset_id,
+            EscrowError::AssetMismatch
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"compressed",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        transfer_compressed(
+            &self.bubblegum_program.to_account_info(),
+            &self.tree_config.to_account_info(),
+            &self.escrow.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.merkle_tree.to_account_info(),
+            &self.log_wrapper.to_account_info(),
+            &self.compression_program.to_account_info(),
+            &self.system_program.to_account_info(),
+            proof,
+            proof_nodes,
+            &signer_seeds,
+        )?;
+
+        emit!(RefundCompressedEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            asset_id: self.escrow.asset_id,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
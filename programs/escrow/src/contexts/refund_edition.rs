@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{helpers::release_vault, EditionEscrow, EscrowError, RefundEditionEvent};
+
+/// Defines the accounts needed for the `refund_edition` instruction, returning a master edition to its maker
+#[derive(Accounts)]
+pub struct RefundEdition<'info> {
+    /// The maker of the escrow, gets the master edition's token back along with the rent of the escrow and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The mint of the master edition
+    #[account(mint::token_program = token_program)]
+    pub master_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The maker's token account receiving the master edition's token
+    #[account(
+        mut,
+        token::mint = master_mint,
+        token::authority = maker,
+        token::token_program = token_program
+    )]
+    pub maker_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The escrow being refunded, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = master_mint @ EscrowError::MintMismatch,
+        seeds = [b"edition", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Box<Account<'info, EditionEscrow>>,
+
+    /// Vault holding the master edition's token, closed to the maker
+    #[account(
+        mut,
+        associated_token::mint = master_mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The token program owning the master edition's mint
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RefundEdition<'info> {
+    /// Returns the master edition's token to the maker and closes the vault
+    pub fn refund(&self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"edition",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        release_vault(
+            &self.token_program.to_account_info(),
+            &self.master_mint.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.maker_ata.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+            &[],
+        )?;
+
+        emit!(RefundEditionEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            master_mint: self.master_mint.key(),
+            printed: self.escrow.printed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use anchor_lang::{prelude::*, solana_program::stake};
+
+use crate::{helpers::authorize_stake, EscrowError, RefundStakeEvent, StakeEscrow};
+
+/// Defines the accounts needed for the `refund_stake` instruction, returning a stake account to its maker
+#[derive(Accounts)]
+pub struct RefundStake<'info> {
+    /// The maker of the escrow, gets the stake account's authorities back along with the escrow's rent
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The escrow being refunded, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = stake_account @ EscrowError::InvalidStakeAccount,
+        seeds = [b"stake", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, StakeEscrow>,
+
+    /// CHECK: The escrowed stake account, checked against the escrow
+    #[account(mut)]
+    pub stake_account: UncheckedAccount<'info>,
+
+    /// The clock sysvar, which the stake program's `Authorize` instruction reads
+    pub clock: Sysvar<'info, Clock>,
+
+    /// CHECK: The native stake program, checked by address
+    #[account(address = stake::program::ID)]
+    pub stake_program: UncheckedAccount<'info>,
+}
+
+impl<'info> RefundStake<'info> {
+    /// Hands the stake account's authorities from the escrow PDA back to the maker
+    pub fn refund(&self) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"stake",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        authorize_stake(
+            &self.stake_program.to_account_info(),
+            &self.stake_account.to_account_info(),
+            &self.clock.to_account_info(),
+            &self.escrow.to_account_info(),
+            &self.maker.key(),
+            &signer_seeds,
+        )?;
+
+        emit!(RefundStakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            stake_account: self.stake_account.key(),
+            timestamp: self.clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, FeeOverride, FeeOverrideEvent};
+
+/// Defines the accounts needed for the `remove_fee_override` instruction, ending a negotiated fee
+#[derive(Accounts)]
+pub struct RemoveFeeOverride<'info> {
+    /// The admin of the config, receives the override's rent back
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The program's global config
+    #[account(
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The override, closed to the admin
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+}
+
+impl<'info> RemoveFeeOverride<'info> {
+    /// Charges the override's subject the config's fee again
+    pub fn remove_fee_override(&mut self) -> Result<()> {
+        emit!(FeeOverrideEvent {
+            admin: self.admin.key(),
+            subject: self.fee_override.subject,
+            fee_bps: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
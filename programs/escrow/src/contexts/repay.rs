@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, release_vault, transfer_checked_with_hook},
+    EscrowError, Loan, SettleLoanEvent,
+};
+
+/// Defines the accounts needed for the `repay` instruction, where the maker pays back a loan to get their collateral back
+#[derive(Accounts)]
+pub struct Repay<'info> {
+    /// The maker repaying the loan, receives the collateral and the rent of the loan and its vault
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The lender who took the loan, receives the repayment
+    pub lender: SystemAccount<'info>,
+
+    /// The mint of the collateral
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of the principal, the loan is repaid in
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the maker receiving the collateral back
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a
+    )]
+    pub maker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The maker's token account for `mint_b`, paying the repayment
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Associated token account of the lender receiving the repayment
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program_b
+    )]
+    pub lender_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The loan being repaid, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = loan.lender == Some(lender.key()) @ EscrowError::LoanNotTaken,
+        seeds = [b"loan", maker.key().as_ref(), loan.seed.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    /// Vault holding the collateral
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = loan,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_a`
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning `mint_b`
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Repay<'info> {
+    /// Pays the repayment to the lender, then releases the collateral back to the maker and closes the vault
+    /// Still possible past the deadline, as long as the lender hasn't liquidated the loan
+    /// With a Token-2022 transfer fee, the maker sends enough for the lender to receive exactly the repayment
+    pub fn repay(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.maker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.lender_ata_b.to_account_info(),
+            authority: self.maker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), self.loan.repayment)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, hook_accounts)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"loan",
+            self.maker.to_account_info().key.as_ref(),
+            &self.loan.seed.to_le_bytes()[..],
+            &[self.loan.bump],
+        ]];
+        // Anything donated to the vault goes along, it must be empty to be closed
+        release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.maker_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.loan.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(SettleLoanEvent {
+            loan: self.loan.key(),
+            maker: self.maker.key(),
+            lender: self.loan.lender,
+            repayment: self.loan.repayment,
+            liquidated: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
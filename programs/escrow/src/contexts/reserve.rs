@@ -0,0 +1,111 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{Config, Escrow, EscrowError, EscrowStatus, Reservation, ReserveEvent};
+
+/// Defines the accounts needed for the `reserve` instruction, letting a taker hold an escrow against a bond
+#[derive(Accounts)]
+pub struct Reserve<'info> {
+    /// The taker reserving the escrow, locking the bond and paying the reservation's rent
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The program's global config, which must not be paused for escrows to be reserved, as they couldn't be filled
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The open escrow being reserved
+    #[account(
+        mut,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The reservation holding the bond, one per escrow, so a lapsed one must be forfeited before the escrow is reserved again
+    #[account(
+        init,
+        payer = taker,
+        space = 8 + Reservation::INIT_SPACE,
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump
+    )]
+    pub reservation: Account<'info, Reservation>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Reserve<'info> {
+    /// Holds the escrow for the taker for `slots` slots, at most `max_slots`, locking `bond` lamports in the reservation
+    /// Filling the escrow with `take` returns the bond, it is forfeited to the escrow's owner if the reservation lapses while the escrow is still open
+    pub fn reserve(
+        &mut self,
+        slots: u64,
+        bond: u64,
+        max_slots: u64,
+        bumps: &ReserveBumps,
+    ) -> Result<()> {
+        let clock = Clock::get()?;
+        let mut escrow = self.escrow.load_mut()?;
+        require!(
+            (1..=max_slots).contains(&slots) && bond >= Reservation::min_bond(slots),
+            EscrowError::InvalidReservation
+        );
+        require!(
+            !escrow.is_expired(clock.unix_timestamp),
+            EscrowError::EscrowExpired
+        );
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        // Collection offers are only filled by `take_nft`, which has no reservation to settle
+        require!(
+            !escrow.collection_offer(),
+            EscrowError::CollectionOfferRequiresNft
+        );
+        require!(
+            !escrow.is_committed(clock.unix_timestamp),
+            EscrowError::CommitmentPending
+        );
+
+        let expires = clock
+            .slot
+            .checked_add(slots)
+            .ok_or(EscrowError::MathOverflow)?;
+        escrow.reserved_until = expires;
+
+        let transfer_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to: self.reservation.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, bond)?;
+
+        self.reservation.set_inner(Reservation {
+            escrow: self.escrow.key(),
+            maker: escrow.maker,
+            taker: self.taker.key(),
+            bond,
+            expires,
+            bump: bumps.reservation,
+        });
+
+        emit!(ReserveEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            bond,
+            expires,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface},
+};
+
+use crate::{helpers::release_vault, Escrow, EscrowError, EscrowStatus, ResolveEvent};
+
+/// Defines the accounts needed for the `resolve_to_maker` and `resolve_to_taker` instructions
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    /// The arbiter chosen by the maker, paying for the recipient's token account if needed
+    #[account(mut)]
+    pub arbiter: Signer<'info>,
+
+    /// The maker of the escrow, receives the rent of the vault and of the escrow
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The side the dispute is resolved to, the owner of the maker's position or the designated taker
+    pub recipient: SystemAccount<'info>,
+
+    /// The mint of the token held in the vault
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The recipient's token account receiving the vault
+    #[account(
+        init_if_needed,
+        payer = arbiter,
+        associated_token::mint = mint_a,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program_a
+    )]
+    pub recipient_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The disputed escrow, which only its arbiter can resolve
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Disputed) @ EscrowError::EscrowNotDisputed,
+        constraint = escrow.load()?.arbiter() == Some(arbiter.key()) @ EscrowError::UnauthorizedArbiter,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the maker's deposit
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ResolveDispute<'info> {
+    /// Releases the whole vault to `recipient` and closes it, sending its rent back to the maker
+    /// Wrapped SOL is released as tokens, the recipient unwraps it themselves
+    pub fn resolve(
+        &mut self,
+        recipient: Pubkey,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        require_keys_eq!(
+            self.recipient.key(),
+            recipient,
+            EscrowError::RecipientMismatch
+        );
+
+        let escrow = *self.escrow.load()?;
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        let amount = release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.recipient_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(ResolveEvent {
+            escrow: self.escrow.key(),
+            arbiter: self.arbiter.key(),
+            recipient,
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Closes the escrow account, sending its rent back to the maker
+    /// Escrows kept as receipts are marked with the final `status` instead, until the maker closes them with `close_receipt`
+    pub fn close_escrow(&mut self, status: EscrowStatus) -> Result<()> {
+        if self.escrow.load()?.keep_receipt() {
+            self.escrow.load_mut()?.set_status(status);
+            return Ok(());
+        }
+        self.escrow.close(self.maker.to_account_info())
+    }
+}
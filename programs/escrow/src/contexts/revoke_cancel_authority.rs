@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{CancelAuthority, CancelAuthorityEvent};
+
+/// Defines the accounts needed for the `revoke_cancel_authority` instruction, ending an owner's delegation
+#[derive(Accounts)]
+pub struct RevokeCancelAuthority<'info> {
+    /// The owner of the maker positions, receives the delegation's rent back
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The owner's delegation, closed to them
+    #[account(
+        mut,
+        close = owner,
+        has_one = owner,
+        seeds = [b"cancel_authority", owner.key().as_ref()],
+        bump = cancel_authority.bump
+    )]
+    pub cancel_authority: Account<'info, CancelAuthority>,
+}
+
+impl<'info> RevokeCancelAuthority<'info> {
+    /// Leaves the refund of the owner's escrows to the owner alone
+    pub fn revoke_cancel_authority(&mut self) -> Result<()> {
+        emit!(CancelAuthorityEvent {
+            owner: self.owner.key(),
+            authority: None,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use crate::{AutoRefund, AutoRefundEvent, Escrow, EscrowError, EscrowStatus};
+
+/// Defines the accounts needed for the `schedule_auto_refund` instruction, letting keepers unwind an escrow once due
+#[derive(Accounts)]
+pub struct ScheduleAutoRefund<'info> {
+    /// The owner of the maker's position, paying for the schedule and the keeper's tip
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The open escrow refunded once the schedule is due
+    #[account(
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The escrow's schedule, one per escrow, cancelled with `cancel_auto_refund` before setting another
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + AutoRefund::INIT_SPACE,
+        seeds = [b"auto_refund", escrow.key().as_ref()],
+        bump
+    )]
+    pub auto_refund: Account<'info, AutoRefund>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ScheduleAutoRefund<'info> {
+    /// Schedules the refund from `execute_after`, the escrow's expiry when 0, setting `tip` lamports aside for the keeper
+    pub fn schedule_auto_refund(
+        &mut self,
+        execute_after: i64,
+        tip: u64,
+        bumps: &ScheduleAutoRefundBumps,
+    ) -> Result<()> {
+        let execute_after = match execute_after {
+            0 => self.escrow.load()?.expiry,
+            execute_after => execute_after,
+        };
+        require!(execute_after > 0, EscrowError::InvalidAutoRefund);
+
+        self.auto_refund.set_inner(AutoRefund {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            execute_after,
+            tip,
+            bump: bumps.auto_refund,
+        });
+
+        // The tip sits in the schedule on top of its rent until a keeper executes it, or the owner cancels it
+        if tip > 0 {
+            let accounts = Transfer {
+                from: self.owner.to_account_info(),
+                to: self.auto_refund.to_account_info(),
+            };
+            let ctx = CpiContext::new(self.system_program.to_account_info(), accounts);
+            transfer(ctx, tip)?;
+        }
+
+        emit!(AutoRefundEvent {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            keeper: None,
+            execute_after,
+            tip,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
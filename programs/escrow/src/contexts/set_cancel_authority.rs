@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+use crate::{CancelAuthority, CancelAuthorityEvent};
+
+/// Defines the accounts needed for the `set_cancel_authority` instruction, delegating the refund of an owner's escrows
+#[derive(Accounts)]
+pub struct SetCancelAuthority<'info> {
+    /// The owner of the maker positions, paying for the delegation
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The owner's delegation, one per owner, replaced whenever it is set again
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + CancelAuthority::INIT_SPACE,
+        seeds = [b"cancel_authority", owner.key().as_ref()],
+        bump
+    )]
+    pub cancel_authority: Account<'info, CancelAuthority>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetCancelAuthority<'info> {
+    /// Lets `authority` refund any escrow whose position the owner holds
+    pub fn set_cancel_authority(
+        &mut self,
+        authority: Pubkey,
+        bumps: &SetCancelAuthorityBumps,
+    ) -> Result<()> {
+        self.cancel_authority.set_inner(CancelAuthority {
+            owner: self.owner.key(),
+            authority,
+            bump: bumps.cancel_authority,
+        });
+
+        emit!(CancelAuthorityEvent {
+            owner: self.owner.key(),
+            authority: Some(authority),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
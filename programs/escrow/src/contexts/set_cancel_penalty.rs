@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{CancelPenaltyEvent, Config, EscrowError};
+
+/// Defines the accounts needed for the `set_cancel_penalty` instruction, letting the admin compensate takers whose reserved escrows are refunded
+#[derive(Accounts)]
+pub struct SetCancelPenalty<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the cancellation penalty
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetCancelPenalty<'info> {
+    /// Sets the share of the deposit refunds pay the taker reserving the escrow, 0 letting makers cancel under them for free
+    pub fn set_cancel_penalty(&mut self, cancel_penalty_bps: u16) -> Result<()> {
+        require!(
+            cancel_penalty_bps <= Config::MAX_FEE_BPS,
+            EscrowError::InvalidFee
+        );
+        self.config.cancel_penalty_bps = cancel_penalty_bps;
+
+        emit!(CancelPenaltyEvent {
+            admin: self.admin.key(),
+            cancel_penalty_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, FeeCurrency, FeeCurrencyEvent};
+
+/// Defines the accounts needed for the `set_fee_currency` instruction, letting the admin choose what fees are taken in
+#[derive(Accounts)]
+pub struct SetFeeCurrency<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the fee currency
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetFeeCurrency<'info> {
+    /// Makes `take` charge the protocol fee in `fee_currency` from the next fill on
+    pub fn set_fee_currency(&mut self, fee_currency: FeeCurrency) -> Result<()> {
+        require!(
+            fee_currency.treasury() != Some(Pubkey::default()),
+            EscrowError::InvalidFeeCurrency
+        );
+        self.config.fee_currency = fee_currency;
+
+        emit!(FeeCurrencyEvent {
+            admin: self.admin.key(),
+            fee_currency,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
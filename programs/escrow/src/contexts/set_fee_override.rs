@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, FeeOverride, FeeOverrideEvent};
+
+/// Defines the accounts needed for the `set_fee_override` instruction, letting the admin negotiate a fee for an escrow or maker
+#[derive(Accounts)]
+#[instruction(subject: Pubkey)]
+pub struct SetFeeOverride<'info> {
+    /// The admin of the config, paying for the override
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// The program's global config
+    #[account(
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The override of `subject`, replaced whenever it is set again
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = 8 + FeeOverride::INIT_SPACE,
+        seeds = [b"fee_override", subject.as_ref()],
+        bump
+    )]
+    pub fee_override: Account<'info, FeeOverride>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetFeeOverride<'info> {
+    /// Charges `fee_bps` on the fills of `subject`, an escrow or a maker, instead of the config's fee
+    pub fn set_fee_override(
+        &mut self,
+        subject: Pubkey,
+        fee_bps: u16,
+        bumps: &SetFeeOverrideBumps,
+    ) -> Result<()> {
+        require!(fee_bps <= Config::MAX_FEE_BPS, EscrowError::InvalidFee);
+        self.fee_override.set_inner(FeeOverride {
+            subject,
+            fee_bps,
+            bump: bumps.fee_override,
+        });
+
+        emit!(FeeOverrideEvent {
+            admin: self.admin.key(),
+            subject,
+            fee_bps: Some(fee_bps),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, FeeTier, FeeTiersEvent};
+
+/// Defines the accounts needed for the `set_fee_tiers` instruction, letting the admin discount high-volume takers
+#[derive(Accounts)]
+pub struct SetFeeTiers<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the fee tiers
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetFeeTiers<'info> {
+    /// Replaces the fee tiers with `fee_tiers`, which must be sorted by strictly increasing volume, an empty table disabling tiering
+    pub fn set_fee_tiers(&mut self, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        require!(
+            fee_tiers.len() <= Config::MAX_FEE_TIERS
+                && fee_tiers
+                    .iter()
+                    .all(|tier| tier.fee_bps <= Config::MAX_FEE_BPS)
+                && fee_tiers
+                    .windows(2)
+                    .all(|tiers| tiers[0].min_volume < tiers[1].min_volume),
+            EscrowError::InvalidFeeTiers
+        );
+        self.config.fee_tiers = fee_tiers.clone();
+
+        emit!(FeeTiersEvent {
+            admin: self.admin.key(),
+            fee_tiers,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, MembershipEvent};
+
+/// Defines the accounts needed for the `set_membership` instruction, letting the admin reward holders of an NFT collection
+#[derive(Accounts)]
+pub struct SetMembership<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the membership collection and its fee
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetMembership<'info> {
+    /// Sets the collection whose holders pay `member_fee_bps` on fills, `None` ending the discount
+    pub fn set_membership(
+        &mut self,
+        collection: Option<Pubkey>,
+        member_fee_bps: u16,
+    ) -> Result<()> {
+        require!(
+            member_fee_bps <= Config::MAX_FEE_BPS,
+            EscrowError::InvalidFee
+        );
+        self.config.membership_collection = collection;
+        self.config.member_fee_bps = member_fee_bps;
+
+        emit!(MembershipEvent {
+            admin: self.admin.key(),
+            collection,
+            member_fee_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
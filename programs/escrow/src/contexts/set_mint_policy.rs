@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, MintPolicyEvent, PermanentDelegatePolicy};
+
+/// Defines the accounts needed for the `set_mint_policy` instruction, letting the admin permit mints with restrictive Token-2022 extensions
+#[derive(Accounts)]
+pub struct SetMintPolicy<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the mint policy
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetMintPolicy<'info> {
+    /// Permits or rejects NonTransferable mints in new escrows, and rejects or flags mints with a permanent delegate
+    /// Escrows already made are left as they are
+    pub fn set_mint_policy(
+        &mut self,
+        allow_non_transferable: bool,
+        permanent_delegate_policy: PermanentDelegatePolicy,
+    ) -> Result<()> {
+        self.config.allow_non_transferable = allow_non_transferable;
+        self.config.permanent_delegate_policy = permanent_delegate_policy;
+
+        emit!(MintPolicyEvent {
+            admin: self.admin.key(),
+            allow_non_transferable,
+            permanent_delegate_policy,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
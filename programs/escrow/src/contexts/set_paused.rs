@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, PauseEvent};
+
+/// Defines the accounts needed for the `set_paused` instruction, letting the admin halt new activity during an incident
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the pause switch
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetPaused<'info> {
+    /// Pauses or resumes `make` and `take`, refunds are never paused
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.config.paused = paused;
+
+        emit!(PauseEvent {
+            admin: self.admin.key(),
+            paused,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    Config, Escrow, EscrowError, EscrowStatus, PaymentLeg, PaymentOptions, PaymentOptionsEvent,
+};
+
+/// Defines the accounts needed for the `set_payment_options` instruction, listing other mints an escrow can be paid in
+#[derive(Accounts)]
+pub struct SetPaymentOptions<'info> {
+    /// The owner of the maker's position, the only one allowed to set its terms, paying for the options
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The escrow whose payment options are set
+    #[account(
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The program's global config, whose mint lists the accepted mints must pass
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The escrow's payment options, replaced whenever they are set again
+    #[account(
+        init_if_needed,
+        payer = owner,
+        space = 8 + PaymentOptions::INIT_SPACE,
+        seeds = [b"payment_options", escrow.key().as_ref()],
+        bump
+    )]
+    pub payment_options: Account<'info, PaymentOptions>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetPaymentOptions<'info> {
+    /// Accepts each of `legs` as payment besides mint_b, each `receive` being asked for the escrow's whole current deposit
+    /// Collection offers, chained and indexed escrows can only be paid in their mint_b
+    pub fn set_payment_options(
+        &mut self,
+        legs: Vec<PaymentLeg>,
+        bumps: &SetPaymentOptionsBumps,
+    ) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        require!(
+            !escrow.collection_offer() && escrow.chained_seed().is_none() && !escrow.is_indexed(),
+            EscrowError::InvalidPaymentOptions
+        );
+        require!(
+            !legs.is_empty() && legs.len() <= PaymentOptions::MAX_LEGS,
+            EscrowError::InvalidPaymentOptions
+        );
+        for (i, leg) in legs.iter().enumerate() {
+            require!(leg.receive > 0, EscrowError::ZeroReceive);
+            require!(
+                leg.mint != escrow.mint_a
+                    && leg.mint != escrow.mint_b
+                    && legs[..i].iter().all(|other| other.mint != leg.mint),
+                EscrowError::InvalidPaymentOptions
+            );
+            require!(
+                self.config.is_mint_permitted(&leg.mint),
+                EscrowError::MintNotPermitted
+            );
+        }
+
+        self.payment_options.set_inner(PaymentOptions {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            price_a: escrow.deposit,
+            legs: legs.clone(),
+            bump: bumps.payment_options,
+        });
+
+        emit!(PaymentOptionsEvent {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            legs,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{helpers::is_native_mint, Escrow, EscrowError, EscrowStatus, PayoutEvent};
+
+/// Defines the accounts needed for the `set_payout` instruction, routing an escrow's proceeds to a token account of the owner's choosing
+#[derive(Accounts)]
+pub struct SetPayout<'info> {
+    /// The owner of the maker's position, the only one allowed to choose where its proceeds go
+    pub owner: Signer<'info>,
+
+    /// The open escrow whose proceeds are routed
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// The token account fills pay the proceeds to, any account of the escrow's mint_b, e.g. of a custody wallet
+    /// Not passed to pay them to the owner's associated token account again
+    #[account(
+        constraint = payout.mint == escrow.load()?.mint_b @ EscrowError::InvalidPayout
+    )]
+    pub payout: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+}
+
+impl<'info> SetPayout<'info> {
+    /// Pays the escrow's proceeds to `payout`, or to the owner's associated token account if it isn't passed
+    /// Proceeds in wrapped SOL are paid in lamports and those of chained escrows fund the escrow chained to them, so neither can be routed
+    pub fn set_payout(&mut self) -> Result<()> {
+        let payout = self.payout.as_ref().map(|payout| payout.key());
+        let mut escrow = self.escrow.load_mut()?;
+        if payout.is_some() {
+            require!(
+                !is_native_mint(&escrow.mint_b)
+                    && escrow.chained_seed().is_none()
+                    && !escrow.collection_offer(),
+                EscrowError::InvalidPayout
+            );
+        }
+        escrow.payout = payout.unwrap_or_default();
+
+        emit!(PayoutEvent {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            payout,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::{Config, EscrowError, ReferralEvent};
+
+/// Defines the accounts needed for the `set_referral_bps` instruction, letting the admin reward frontends routing fills
+#[derive(Accounts)]
+pub struct SetReferralBps<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config holding the referral share
+    #[account(
+        mut,
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> SetReferralBps<'info> {
+    /// Sets the share of the protocol fee paid to referrers, 0 keeping the whole fee for the protocol
+    pub fn set_referral_bps(&mut self, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= Config::MAX_FEE_BPS, EscrowError::InvalidFee);
+        self.config.referral_bps = referral_bps;
+
+        emit!(ReferralEvent {
+            admin: self.admin.key(),
+            referral_bps,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
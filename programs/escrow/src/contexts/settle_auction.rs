@@ -0,0 +1,189 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{release_vault, transfer_checked_with_hook},
+    Auction, Config, EscrowError, SettleAuctionEvent,
+};
+
+/// Defines the accounts needed for the `settle_auction` instruction, callable by anyone once bidding has ended
+#[derive(Accounts)]
+pub struct SettleAuction<'info> {
+    /// Whoever settles the auction, paying for any token account that has to be created
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The maker of the auction, receives the winning bid along with the rent of the auction and its vaults
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The winning bidder, or the maker if the auction got no bids, receives the auctioned deposit
+    #[account(
+        constraint = recipient.key() == auction.bidder.unwrap_or(auction.maker) @ EscrowError::RecipientMismatch
+    )]
+    pub recipient: SystemAccount<'info>,
+
+    /// The mint being auctioned
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint bids are made in
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_b
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The recipient's token account receiving the auctioned deposit
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program_a
+    )]
+    pub recipient_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The maker's token account receiving the winning bid
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, holding the protocol fee taken from the winning bid
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The auction being settled, closed to the maker
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        seeds = [b"auction", maker.key().as_ref(), auction.seed.to_le_bytes().as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Box<Account<'info, Auction>>,
+
+    /// Vault holding the auctioned deposit
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault holding the winning bid
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = auction,
+        associated_token::token_program = token_program_b
+    )]
+    pub bid_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleAuction<'info> {
+    /// Releases the deposit to the winner and the winning bid to the maker, minus the protocol fee, closing both vaults
+    /// Without any bid the deposit goes back to the maker
+    pub fn settle(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            self.auction.is_settleable(Clock::get()?.unix_timestamp),
+            EscrowError::AuctionNotEnded
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"auction",
+            self.maker.to_account_info().key.as_ref(),
+            &self.auction.seed.to_le_bytes()[..],
+            &[self.auction.bump],
+        ]];
+
+        let fee = self.config.fee(self.auction.bid)?;
+        if fee > 0 {
+            let accounts = TransferChecked {
+                from: self.bid_vault.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.auction.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_b.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, fee, self.mint_b.decimals, hook_accounts)?;
+        }
+
+        release_vault(
+            &self.token_program_b.to_account_info(),
+            &self.mint_b.to_account_info(),
+            &self.bid_vault.to_account_info(),
+            &self.maker_ata_b.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.auction.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+        release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault_a.to_account_info(),
+            &self.recipient_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.auction.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(SettleAuctionEvent {
+            auction: self.auction.key(),
+            maker: self.maker.key(),
+            winner: self.auction.bidder,
+            deposit: self.auction.deposit,
+            bid: self.auction.bid,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{release_vault, transfer_checked_with_hook},
+    Config, EscrowError, SettleSwapEvent, Swap,
+};
+
+/// Defines the accounts needed for the `settle_swap` instruction, atomically releasing each vault to the other side
+#[derive(Accounts)]
+pub struct SettleSwap<'info> {
+    /// Either party of the swap, paying for any token account that has to be created
+    #[account(
+        mut,
+        constraint = swap.is_party(signer.key) @ EscrowError::NotSwapParty
+    )]
+    pub signer: Signer<'info>,
+
+    /// The maker of the swap, receives mint_b along with the swap's and `vault_a`'s rent
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The taker of the swap, receives mint_a along with `vault_b`'s rent
+    #[account(mut)]
+    pub taker: SystemAccount<'info>,
+
+    /// The mint locked by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint locked by the taker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_b
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The maker's token account receiving mint_b
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The taker's token account receiving mint_a
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's side
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = signer,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The swap being settled, which both parties must have funded
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = taker @ EscrowError::UnauthorizedTaker,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = swap.funded @ EscrowError::SwapNotFunded,
+        seeds = [b"swap", maker.key().as_ref(), swap.seed.to_le_bytes().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Box<Account<'info, Swap>>,
+
+    /// Vault holding the maker's side of the swap
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Vault holding the taker's side of the swap
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = swap,
+        associated_token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SettleSwap<'info> {
+    /// Releases `vault_a` to the taker and `vault_b` to the maker, minus the protocol fee, closing both vaults
+    /// Any Token-2022 transfer fee on the way out of a vault is borne by the side receiving it
+    pub fn settle(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Both vaults must still hold what each side agreed to lock
+        require!(
+            self.vault_a.amount >= self.swap.amount_a && self.vault_b.amount >= self.swap.amount_b,
+            EscrowError::VaultDrained
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"swap",
+            self.maker.to_account_info().key.as_ref(),
+            &self.swap.seed.to_le_bytes()[..],
+            &[self.swap.bump],
+        ]];
+
+        let fee = self.config.fee(self.swap.amount_b)?;
+        if fee > 0 {
+            let accounts = TransferChecked {
+                from: self.vault_b.to_account_info(),
+                mint: self.mint_b.to_account_info(),
+                to: self.fee_vault.to_account_info(),
+                authority: self.swap.to_account_info(),
+            };
+            let ctx = CpiContext::new_with_signer(
+                self.token_program_b.to_account_info(),
+                accounts,
+                &signer_seeds,
+            );
+            transfer_checked_with_hook(ctx, fee, self.mint_b.decimals, hook_accounts)?;
+        }
+
+        release_vault(
+            &self.token_program_b.to_account_info(),
+            &self.mint_b.to_account_info(),
+            &self.vault_b.to_account_info(),
+            &self.maker_ata_b.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.swap.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+        release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault_a.to_account_info(),
+            &self.taker_ata_a.to_account_info(),
+            &self.maker.to_account_info(),
+            &self.swap.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(SettleSwapEvent {
+            swap: self.swap.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            amount_a: self.swap.amount_a,
+            amount_b: self.swap.amount_b,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
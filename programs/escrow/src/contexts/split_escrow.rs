@@ -0,0 +1,211 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{reindex, transfer_checked_with_hook},
+    Config, Escrow, EscrowError, EscrowStatus, MakeEvent, PairIndex, ResizeEvent,
+};
+
+/// Defines the accounts needed for the `split_escrow` instruction, moving part of an open escrow into a new one on the same terms
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct SplitEscrow<'info> {
+    /// The maker of the escrow, paying for the new escrow and its vault, which are derived from their key
+    #[account(mut)]
+    pub maker: Signer<'info>,
+
+    /// The owner of the maker's position, the only one allowed to split its deposit
+    pub owner: Signer<'info>,
+
+    /// The mint of the token deposited in the escrow
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The program's global config, which must not be paused for new escrows to be made
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The open escrow part of the deposit is taken from
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the deposit of the escrow being split
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The escrow made with the part of the deposit split off
+    #[account(
+        init,
+        payer = maker,
+        space = Escrow::SPACE,
+        seeds = [b"escrow", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub new_escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault of the new escrow, receiving the part of the deposit split off
+    #[account(
+        init,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = new_escrow,
+        associated_token::token_program = token_program_a
+    )]
+    pub new_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The index of the escrow's mints, counting the new escrow along with the split one
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SplitEscrow<'info> {
+    /// Moves `amount` of the deposit into the new escrow, which inherits every term of the original one
+    /// Both escrows keep the original price, so `receive` is split in proportion to the deposit
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn split(
+        &mut self,
+        seed: u64,
+        amount: u64,
+        bumps: &SplitEscrowBumps,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let now = Clock::get()?.unix_timestamp;
+
+        // Both escrows must keep something to fill, moving everything would just rename the escrow
+        require!(
+            amount > 0 && amount < escrow.deposit,
+            EscrowError::InvalidAmount
+        );
+        // A taker who committed to the escrow relies on its deposit until their commitment lapses
+        require!(!escrow.is_committed(now), EscrowError::CommitmentPending);
+        // As does a taker holding a reservation of it
+        require!(
+            !escrow.is_reserved(Clock::get()?.slot),
+            EscrowError::EscrowReserved
+        );
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.new_vault.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            accounts,
+            &signer_seeds,
+        );
+        transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)?;
+
+        // Any Token-2022 transfer fee is borne by the new escrow, which only holds what its vault received
+        self.new_vault.reload()?;
+        let new_deposit = self.new_vault.amount;
+        let new_receive = escrow.receive_for(new_deposit)?;
+        let deposit = escrow
+            .deposit
+            .checked_sub(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        let receive = escrow.receive_for(deposit)?;
+        require!(new_receive > 0 && receive > 0, EscrowError::ZeroReceive);
+
+        *self.new_escrow.load_init()? = Escrow {
+            seed,
+            deposit: new_deposit,
+            receive: new_receive,
+            commit_hash: [0; 32],
+            commit_deadline: 0,
+            reserved_until: 0,
+            // Listing the new escrow is up to the maker, only the split one stays listed
+            order_book_page: 0,
+            // The new escrow is indexed along with the split one, see below
+            indexed_deposit: match escrow.is_indexed() {
+                true => new_deposit,
+                false => 0,
+            },
+            // The new vault is always the new escrow's associated token account
+            pda_vault: 0,
+            vault_bump: 0,
+            bump: bumps.new_escrow,
+            ..escrow
+        };
+        {
+            let mut escrow = self.escrow.load_mut()?;
+            escrow.deposit = deposit;
+            escrow.receive = receive;
+            reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+        }
+        // Passed if the escrow is indexed, the pair's total deposit is back to what it was with the new escrow counted
+        if let (true, Some(pair_index)) = (escrow.is_indexed(), &mut self.pair_index) {
+            pair_index.open_escrows = pair_index
+                .open_escrows
+                .checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+            pair_index.total_deposit = pair_index
+                .total_deposit
+                .checked_add(new_deposit)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
+
+        emit!(ResizeEvent {
+            escrow: self.escrow.key(),
+            maker: escrow.maker,
+            deposit,
+            receive,
+            timestamp: now,
+        });
+        emit!(MakeEvent {
+            escrow: self.new_escrow.key(),
+            maker: escrow.maker,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            deposit: new_deposit,
+            receive: new_receive,
+            expiry: escrow.expiry,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{helpers::transfer_checked_with_hook, Escrow, EscrowError, EscrowStatus, SweepEvent};
+
+/// Defines the accounts needed for the `sweep_surplus` instruction, returning tokens donated to an escrow's vault
+#[derive(Accounts)]
+pub struct SweepSurplus<'info> {
+    /// The owner of the maker's position, receiving the surplus
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The mint of the token deposited in the escrow
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The owner's token account for mint_a, receiving the surplus
+    /// Needed for wrapped SOL too, as the vault can only be unwrapped by closing it
+    #[account(
+        init_if_needed,
+        payer = owner,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: InterfaceAccount<'info, TokenAccount>,
+
+    /// The open escrow whose vault holds more than its recorded deposit
+    #[account(
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the deposit, and the surplus on top of it
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SweepSurplus<'info> {
+    /// Transfers whatever the vault holds beyond the recorded deposit to the owner, leaving the deposit untouched
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn sweep(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let surplus = self.vault.amount.saturating_sub(escrow.deposit);
+        require!(surplus > 0, EscrowError::NoSurplus);
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        let accounts = TransferChecked {
+            from: self.vault.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+            to: self.owner_ata_a.to_account_info(),
+            authority: self.escrow.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program_a.to_account_info(),
+            accounts,
+            &signer_seeds,
+        );
+        // Any Token-2022 transfer fee on the way out is borne by the owner
+        transfer_checked_with_hook(ctx, surplus, self.mint_a.decimals, hook_accounts)?;
+
+        emit!(SweepEvent {
+            escrow: self.escrow.key(),
+            owner: self.owner.key(),
+            amount: surplus,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
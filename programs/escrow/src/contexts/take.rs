@@ -1,156 +1,1203 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+    Discriminator,
+};
 
 use anchor_spl::{
-    associated_token::AssociatedToken,
+    associated_token::get_associated_token_address_with_program_id,
     token_interface::{
-        close_account, transfer_checked, CloseAccount, Mint, TokenAccount, TokenInterface,
+        close_account, sync_native, CloseAccount, Mint, SyncNative, TokenAccount, TokenInterface,
         TransferChecked,
     },
 };
 
-use crate::Escrow;
-/// Defines the accounts needed for the `take` instruction, facilitating asset transfers and vault closure.
+use crate::{
+    helpers::{
+        check_gateway_token, check_mint_policy, delist, flag_permanent_delegates,
+        frozen_account_error, gross_up_transfer_fee, harvest_withheld_fees, is_native_mint,
+        reindex, rent_recipients, transfer_checked_with_hook, verified_collection,
+    },
+    AllowlistPass, ChainFundedEvent, Config, Escrow, EscrowError, EscrowStatus, EventLog,
+    FeeCurrency, FeeOverride, FillReceipt, LogKind, OrderBookPage, PairIndex, PaymentLeg,
+    PaymentOptions, PaymentSelectedEvent, Reservation, ReservationSettledEvent, TakeEvent,
+    Template, TermsRevealedEvent, TraderStats,
+};
+
+/// Leg of a fill whose amount the taker fixes, the other one is computed at the escrow's price
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillAmount {
+    /// Buys exactly `deposit` of mint_a, paying what the price asks for it
+    ExactOut { deposit: u64 },
+    /// Spends exactly `payment` of mint_b, buying as much mint_a as it pays for
+    ExactIn { payment: u64 },
+}
 
 /// Defines the accounts needed for the `take` instruction, facilitating assets transfers and vault closure
+/// The token accounts receiving assets must already exist, `prepare_take` creates them with its own rent payer
+/// None of the token accounts a fill moves tokens through may be frozen, the error then names the frozen one
+/// and tells accounts frozen on creation by their mint's DefaultAccountState, which its issuer must thaw, apart
 #[derive(Accounts)]
 pub struct Take<'info> {
     /// The participant initiating the `take` must be a signer
     #[account(mut)]
     pub taker: Signer<'info>,
 
-    /// The original maker of the escrow, holds the counter assets
+    /// The original maker of the escrow, receives its rent once it is closed
     #[account(mut)]
     pub maker: SystemAccount<'info>,
 
+    /// The owner of the maker's position, the maker unless it was transferred, holds the counter assets
+    /// Receives the taker's payment in lamports when mint_b is wrapped SOL
+    #[account(mut)]
+    pub owner: SystemAccount<'info>,
+
     // Case scenario: Trading an NFT or SPL Token for an amount of stablecoin
-    pub mint_a: InterfaceAccount<'info, Mint>,
     /// The mint of the token deposited by the maker into the escrow- e.g. an NFT or SPL Token
-    pub mint_b: InterfaceAccount<'info, Mint>,
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
     /// The mint of the token expected to be received by the maker- e.g. a stablecoin
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
 
-    /// Associated token account of the taker for receiving mint_a tokens
+    /// Token account receiving the taker's mint_a tokens, usually their associated token account
+    /// Any mint_a account the taker designates is accepted, e.g. cold storage, as their signature authorizes it
+    /// When mint_a is wrapped SOL, a full take unwraps the vault straight to the taker's lamports instead
     #[account(
-        init_if_needed,
-        payer = taker,
-        associated_token::mint = mint_a,
-        associated_token::authority = taker,
-        associated_token::token_program = token_program
+        mut,
+        constraint = !taker_ata_a.is_frozen() @ frozen_account_error(&mint_a.to_account_info()),
+        token::mint = mint_a,
+        token::token_program = token_program_a
     )]
     pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// Associated token account of the taker for depositing mint_b tokens to the maker
+    /// Associated token account of the position's owner, returned anything donated to the vault on top of the deposit
+    /// When it isn't passed, or mint_a is wrapped SOL, a full take releases such a surplus to the taker along with the deposit
+    #[account(
+        mut,
+        constraint = !owner_ata_a.is_frozen() @ frozen_account_error(&mint_a.to_account_info()),
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token account the taker pays mint_b from, usually their associated token account
+    /// Any mint_b account the taker owns or is the delegate of is accepted, e.g. exchange withdrawal or custody accounts
+    /// Not needed when mint_b is wrapped SOL, the taker then pays in lamports
     #[account(
         mut,
+        constraint = !taker_ata_b.is_frozen() @ frozen_account_error(&mint_b.to_account_info()),
+        constraint = taker_ata_b.owner == taker.key()
+            || Option::from(taker_ata_b.delegate) == Some(taker.key()) @ EscrowError::TakerAccountMismatch,
+        token::mint = mint_b,
+        token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Token account receiving mint_b tokens from the taker for the position's owner
+    /// Their associated token account, unless they designated a payout account with `set_payout`
+    /// Not needed when mint_b is wrapped SOL, the owner then receives lamports
+    #[account(
+        mut,
+        constraint = !owner_ata_b.is_frozen() @ frozen_account_error(&mint_b.to_account_info()),
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+        address = escrow.load()?.payout_address(&owner.key(), &mint_b.key(), &token_program_b.key()) @ EscrowError::PayoutMismatch
+    )]
+    pub owner_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The frontend or aggregator that routed the fill, paid a share of the protocol fee if the config sets one
+    /// Receives it in lamports when mint_b is wrapped SOL
+    #[account(mut)]
+    pub referrer: Option<SystemAccount<'info>>,
+
+    /// Associated token account of the referrer for receiving their share of the fee in mint_b
+    /// Only needed with a referrer, and not when mint_b is wrapped SOL
+    #[account(
+        mut,
+        constraint = !referrer_ata_b.is_frozen() @ frozen_account_error(&mint_b.to_account_info()),
         associated_token::mint = mint_b,
-        associated_token::authority = taker,
-        associated_token::token_program = token_program
+        associated_token::authority = referrer,
+        associated_token::token_program = token_program_b
+    )]
+    pub referrer_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// An NFT of the config's membership collection, held by the taker or the position's owner, for the discounted fee
+    pub member_nft: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Token Metadata account of `member_nft`, deserialized and checked against it when computing the fee
+    pub member_metadata: Option<UncheckedAccount<'info>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payment
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
     )]
-    pub taker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub config: Box<Account<'info, Config>>,
 
-    /// Associated token account of the maker for receiving mint_b tokens from the taker
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
     #[account(
-        init_if_needed,
-        payer = taker,
+        mut,
+        constraint = !fee_vault.is_frozen() @ frozen_account_error(&mint_b.to_account_info()),
         associated_token::mint = mint_b,
-        associated_token::authority = maker,
-        associated_token::token_program = token_program
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
     )]
-    pub maker_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    /// The escrow account itself, holding state, terms and seeds
+    /// Token account of the config PDA collecting the protocol fees taken out of mint_a
+    /// Only needed when the config charges the fee in mint_a
     #[account(
         mut,
-        close = maker, // Allows the escrow account to be closed, and its remaining balance to be sent to maker once the escrow isn't needed anymore
+        constraint = !fee_vault_a.is_frozen() @ frozen_account_error(&mint_a.to_account_info()),
+        associated_token::mint = mint_a,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_a
+    )]
+    pub fee_vault_a: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The treasury the config pays lamport fees to, only needed when it charges the fee in lamports
+    #[account(
+        mut,
+        constraint = config.fee_currency.treasury() == Some(fee_treasury.key()) @ EscrowError::FeeTreasuryMismatch
+    )]
+    pub fee_treasury: Option<SystemAccount<'info>>,
+
+    /// CHECK: Receives the rent of the escrow and its vault when the maker chose a treasury for it, checked against the escrow's in `rent_recipients`
+    #[account(mut)]
+    pub rent_treasury: Option<UncheckedAccount<'info>>,
 
+    /// The escrow account itself, holding state, terms and seeds
+    /// Closed to the maker once fully filled, kept open with the remaining terms after a partial fill
+    #[account(
+        mut,
         // Ensures the escrow account is linked to the specific maker, mint_a and mint_b
         // It ensures that the provided accounts match the ones specified on the creation of the escrow account
-        has_one = maker,
-        has_one = mint_a,
-        has_one = mint_b,
-        
-        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
-        bump = escrow.bump
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        // A private escrow's mint_b is only checked against its terms hash once revealed, see `reveal_terms`
+        // and one of the escrow's payment options once it is switched to, see `select_payment`
+        constraint = escrow.load()?.mint_b == mint_b.key()
+            || escrow.load()?.is_private()
+            || payment_options.is_some() @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        // Self-fills only pay fees to move tokens back to the maker, and would inflate any volume stats
+        constraint = taker.key() != maker.key() @ EscrowError::SelfTake,
+
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
     )]
-    pub escrow: Account<'info, Escrow>,
+    pub escrow: AccountLoader<'info, Escrow>,
 
     /// Vault for the assets deposited by the maker, controlled by the escrow logic
     #[account(
         mut,
-        associated_token::mint = mint_a,
-        associated_token::authority = escrow,
-        associated_token::token_program = token_program
+        constraint = !vault.is_frozen() @ frozen_account_error(&mint_a.to_account_info()),
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
 
-    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
-    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// The taker's receipt of their fills of this escrow, created on their first fill and paid for by them
+    /// Optional, fills are only recorded when it is passed
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + FillReceipt::INIT_SPACE,
+        seeds = [b"fill_receipt", escrow.key().as_ref(), taker.key().as_ref()],
+        bump
+    )]
+    pub fill_receipt: Option<Box<Account<'info, FillReceipt>>>,
+
+    /// A fee the admin negotiated for this escrow or its maker, charged instead of the config's
+    /// Optional, the config's fee applies when it isn't passed
+    #[account(
+        seeds = [b"fee_override", fee_override.subject.as_ref()],
+        bump = fee_override.bump,
+        constraint = fee_override.subject == escrow.key()
+            || fee_override.subject == maker.key() @ EscrowError::FeeOverrideMismatch
+    )]
+    pub fee_override: Option<Box<Account<'info, FeeOverride>>>,
+
+    /// The taker's cumulative volume in mint_b, created on their first tracked fill and paid for by them
+    /// Optional, the fill is charged the rate of the fee tier it reached when passed, and adds to it
+    #[account(
+        init_if_needed,
+        payer = taker,
+        space = 8 + TraderStats::INIT_SPACE,
+        seeds = [b"trader_stats", taker.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub trader_stats: Option<Box<Account<'info, TraderStats>>>,
+
+    /// The taker's reservation of the escrow, closed by the fill with its bond returned to them
+    /// Required to fill an escrow while it is reserved, only its taker can pass it
+    #[account(
+        mut,
+        has_one = taker @ EscrowError::ReservationMismatch,
+        seeds = [b"reservation", escrow.key().as_ref()],
+        bump = reservation.bump
+    )]
+    pub reservation: Option<Box<Account<'info, Reservation>>>,
+
+    /// The template the escrow was made from, whose terms the fill is held to
+    /// Required to fill escrows made from a template, and only accepted for those
+    #[account(
+        address = escrow.load()?.template @ EscrowError::TemplateMismatch
+    )]
+    pub template: Option<Box<Account<'info, Template>>>,
+
+    /// The taker's pass onto the template's allowlist, required by templates restricting who fills their escrows
+    #[account(
+        seeds = [b"allowlist_pass", allowlist_pass.template.as_ref(), taker.key().as_ref()],
+        bump = allowlist_pass.bump
+    )]
+    pub allowlist_pass: Option<Box<Account<'info, AllowlistPass>>>,
+
+    /// The taker's token account for the escrow's gate mint, proving their holding when the escrow is token-gated
+    #[account(
+        token::authority = taker
+    )]
+    pub gate_account: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: The taker's gateway token, proving their identity pass when the escrow requires one of a gatekeeper network
+    /// Its owner and contents are checked in `check_fillable`
+    pub gateway_token: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The escrow chained to this one with `make_chained`, funded by the fill's proceeds instead of the owner
+    /// Required when the escrow is chained, it may have been refunded, filled or closed since, which `chain_fundable` checks
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.load()?.maker.as_ref(), escrow.load()?.chained_seed.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub chained_escrow: Option<UncheckedAccount<'info>>,
+
+    /// CHECK: The chained escrow's vault, receiving the fill's proceeds, checked against its associated token account in `deposit`
+    /// Closed with the chained escrow, so only read while it can still be funded
+    #[account(mut)]
+    pub chained_vault: Option<UncheckedAccount<'info>>,
+
+    /// The other mints the escrow's owner accepts as payment, required to pay in one of them rather than its mint_b
+    #[account(
+        mut,
+        seeds = [b"payment_options", escrow.key().as_ref()],
+        bump = payment_options.bump
+    )]
+    pub payment_options: Option<Box<Account<'info, PaymentOptions>>>,
+
+    /// The order book page listing the escrow, which a fill completing it removes it from
+    /// Required to complete a listed escrow
+    #[account(mut)]
+    pub order_book_page: Option<Box<Account<'info, OrderBookPage>>>,
+
+    /// The index of the escrow's mints, updated with what the fill leaves of its deposit
+    /// Required to fill an indexed escrow
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// The program's event log, recording this fill when passed
+    #[account(
+        mut,
+        seeds = [b"event_log"],
+        bump
+    )]
+    pub event_log: Option<AccountLoader<'info, EventLog>>,
+
+    /// The token program owning mint_a, used to release the vault to the taker
+    pub token_program_a: Interface<'info, TokenInterface>,
 
-    /// The SPL Token program used for handling all token operations like transfers, minting, and burning within this transaction
-    pub token_program: Interface<'info, TokenInterface>,
+    /// The token program owning mint_b, used for the taker's payment, which may differ from `token_program_a`
+    pub token_program_b: Interface<'info, TokenInterface>,
 
     /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Take<'info> {
-    /// Transfers the expected receive amount of mint_b from taker to the maker
+    /// Ensures the escrow can still be filled by this taker
+    /// Once the deadline has passed it can only be refunded, and private deals can only be filled by the designated counterparty
+    pub fn check_fillable(&self) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        // Private terms must be revealed first, which only `take_private` does
+        require!(!escrow.is_private(), EscrowError::PrivateTerms);
+        require!(
+            !escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::EscrowExpired
+        );
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        require!(
+            !escrow.collection_offer(),
+            EscrowError::CollectionOfferRequiresNft
+        );
+        // Escrows made from a template are filled on its terms, only by takers on its allowlist if it has one
+        if escrow.template().is_some() {
+            let template = self
+                .template
+                .as_ref()
+                .ok_or(EscrowError::TemplateMismatch)?;
+            require!(
+                !template.is_restricted()
+                    || self
+                        .allowlist_pass
+                        .as_ref()
+                        .is_some_and(|pass| pass.template == template.key()),
+                EscrowError::NotAllowlisted
+            );
+        }
+        if let Some(gate) = escrow.gate() {
+            require!(
+                self.gate_account.as_ref().is_some_and(
+                    |account| account.mint == gate.mint && account.amount >= gate.amount
+                ),
+                EscrowError::GateNotMet
+            );
+        }
+        if let Some(gatekeeper_network) = escrow.gatekeeper_network() {
+            let gateway_token = self
+                .gateway_token
+                .as_ref()
+                .ok_or(EscrowError::InvalidGatewayToken)?;
+            check_gateway_token(
+                gateway_token,
+                &self.taker.key(),
+                &gatekeeper_network,
+                Clock::get()?.unix_timestamp,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reveals the private terms of the escrow, the mint_b passed and `receive`, checked with `salt` against its terms hash
+    /// They are written in place, with the mint checks `make_private` couldn't run on them, so the fill settles like a public one
+    pub fn reveal_terms(&mut self, receive: u64, salt: &[u8; 32]) -> Result<()> {
+        let mint_b = self.mint_b.key();
+        {
+            let escrow = self.escrow.load()?;
+            require!(
+                escrow.is_private()
+                    && escrow.terms_hash == Escrow::terms_hash(&mint_b, receive, salt),
+                EscrowError::TermsHashMismatch
+            );
+        }
+        require!(receive > 0, EscrowError::ZeroReceive);
+        require_keys_neq!(self.mint_a.key(), mint_b, EscrowError::SameMint);
+        require!(
+            self.config.is_mint_permitted(&mint_b),
+            EscrowError::MintNotPermitted
+        );
+        check_mint_policy(&self.config, &self.mint_b.to_account_info())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let permanent_delegate = flag_permanent_delegates(
+            self.escrow.key(),
+            [
+                &self.mint_a.to_account_info(),
+                &self.mint_b.to_account_info(),
+            ],
+            now,
+        );
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.mint_b = mint_b;
+        escrow.receive = receive;
+        // Private escrows can't be resized, so their deposit is still the one they were priced at
+        escrow.price_b = receive;
+        escrow.permanent_delegate = permanent_delegate.into();
+        escrow.terms_hash = [0; 32];
+
+        emit!(TermsRevealedEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            mint_b,
+            receive,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Switches the escrow to the payment option paying in the mint_b passed, when it isn't the escrow's mint_b already
+    /// The leg becomes the escrow's mint_b and price, with the mint checks `set_payment_options` couldn't run on it,
+    /// and the mint_b it replaces becomes a payment option in its place, so takers can still pay in it
+    pub fn select_payment(&mut self) -> Result<()> {
+        let mint_b = self.mint_b.key();
+        if self.escrow.load()?.mint_b == mint_b {
+            return Ok(());
+        }
+        let payment_options = self
+            .payment_options
+            .as_mut()
+            .ok_or(EscrowError::MintMismatch)?;
+        let index = payment_options
+            .leg(&mint_b)
+            .ok_or(EscrowError::MintMismatch)?;
+        require!(
+            self.config.is_mint_permitted(&mint_b),
+            EscrowError::MintNotPermitted
+        );
+        check_mint_policy(&self.config, &self.mint_b.to_account_info())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let permanent_delegate = flag_permanent_delegates(
+            self.escrow.key(),
+            [
+                &self.mint_a.to_account_info(),
+                &self.mint_b.to_account_info(),
+            ],
+            now,
+        );
+        let mut escrow = self.escrow.load_mut()?;
+        require!(
+            escrow.chained_seed().is_none(),
+            EscrowError::InvalidPaymentOptions
+        );
+        // A payout account only holds the mint_b it was designated for
+        require!(escrow.payout().is_none(), EscrowError::InvalidPayout);
+        let leg = payment_options.legs[index];
+        payment_options.legs[index] = PaymentLeg {
+            mint: escrow.mint_b,
+            receive: escrow.receive_for(payment_options.price_a)?,
+        };
+        escrow.mint_b = mint_b;
+        (escrow.price_a, escrow.price_b) = (payment_options.price_a, leg.receive);
+        escrow.receive = escrow.receive_for(escrow.deposit)?;
+        require!(escrow.receive > 0, EscrowError::ZeroReceive);
+        escrow.permanent_delegate = permanent_delegate.into();
+
+        emit!(PaymentSelectedEvent {
+            escrow: self.escrow.key(),
+            taker: self.taker.key(),
+            mint_b,
+            receive: escrow.receive,
+            timestamp: now,
+        });
+        Ok(())
+    }
+
+    /// Checks the fill settles the taker's reservation, as `settle_fill` requires
+    pub fn check_reservation(&self) -> Result<()> {
+        require!(self.reservation.is_some(), EscrowError::NotReserved);
+        Ok(())
+    }
+
+    /// Checks the take against any pending commitment, which only its taker can settle by revealing their `salt`
+    /// While a reservation holds the escrow, only its taker can fill it, by passing the reservation
+    pub fn check_commitment(&self, salt: Option<&[u8; 32]>) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        require!(
+            !escrow.is_reserved(Clock::get()?.slot) || self.reservation.is_some(),
+            EscrowError::EscrowReserved
+        );
+        escrow.check_commitment(
+            &self.escrow.key(),
+            &self.taker.key(),
+            salt,
+            Clock::get()?.unix_timestamp,
+        )
+    }
+
+    /// Guards the taker against the terms changing between building and landing the transaction
+    /// Fails if the owner updated, topped up or reduced the escrow since the taker read it
+    pub fn check_terms(&self, expected_deposit: u64, expected_receive: u64) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        require!(
+            escrow.deposit == expected_deposit && escrow.receive == expected_receive,
+            EscrowError::TermsChanged
+        );
+        Ok(())
+    }
+
+    /// Transfers `amount` of mint_b from taker to the owner of the maker's position, minus the protocol fee which goes to the fee vault
+    /// With a referrer, their share of the fee goes to them instead of the fee vault
     /// Represents the taker fulfilling their part of the escrow agreement
-    pub fn deposit(&mut self) -> Result<()> {
+    /// `hook_accounts` are forwarded to the transfers for mints with a Token-2022 TransferHook
+    pub fn deposit(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Lamport fees are paid on top of the payment, fees in mint_a are taken out of what the taker receives
+        let (total_fee, lamport_fee) = match self.config.fee_currency {
+            FeeCurrency::MintB => (self.fee(amount)?, 0),
+            FeeCurrency::MintA => (0, 0),
+            FeeCurrency::Lamports { lamports, .. } => (0, lamports),
+        };
+        let referral = match self.referrer {
+            Some(_) => self.config.referral_share(total_fee)?,
+            None => 0,
+        };
+        let fee = total_fee
+            .checked_sub(referral)
+            .ok_or(EscrowError::MathOverflow)?;
+        let proceeds = amount
+            .checked_sub(total_fee)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        self.check_funds(&[referral, fee, proceeds], lamport_fee)?;
+        if let FeeCurrency::Lamports { lamports, .. } = self.config.fee_currency {
+            let fee_treasury = self
+                .fee_treasury
+                .as_ref()
+                .ok_or(EscrowError::FeeTreasuryMismatch)?;
+            self.transfer_lamports(fee_treasury.to_account_info(), lamports)?;
+        }
+
+        // Wrapped SOL is paid in lamports, so neither side has to wrap or unwrap it themselves
+        if is_native_mint(&self.mint_b.key()) {
+            if referral > 0 {
+                let referrer = self
+                    .referrer
+                    .as_ref()
+                    .ok_or(EscrowError::MissingTokenAccount)?;
+                self.transfer_lamports(referrer.to_account_info(), referral)?;
+            }
+            if fee > 0 {
+                self.transfer_lamports(self.fee_vault.to_account_info(), fee)?;
+                self.sync_fee_vault()?;
+            }
+            return self.transfer_lamports(self.owner.to_account_info(), proceeds);
+        }
+
+        // A chained escrow's proceeds fund the escrow chained to it rather than reaching the owner, while it can be funded
+        let chained = self.escrow.load()?.chained_seed().is_some() && self.chain_fundable()?;
+        let owner_ata_b = match chained {
+            true => {
+                let chained_vault = self
+                    .chained_vault
+                    .as_ref()
+                    .ok_or(EscrowError::ChainedEscrow)?;
+                require_keys_eq!(
+                    chained_vault.key(),
+                    get_associated_token_address_with_program_id(
+                        &self
+                            .chained_escrow
+                            .as_ref()
+                            .ok_or(EscrowError::ChainedEscrow)?
+                            .key(),
+                        &self.mint_b.key(),
+                        &self.token_program_b.key()
+                    ),
+                    EscrowError::ChainedEscrow
+                );
+                chained_vault.to_account_info()
+            }
+            false => self
+                .owner_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?
+                .to_account_info(),
+        };
+
+        if referral > 0 {
+            let referrer_ata_b = self
+                .referrer_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?
+                .to_account_info();
+            self.transfer_b(referrer_ata_b, referral, hook_accounts)?;
+        }
+        if fee > 0 {
+            self.transfer_b(self.fee_vault.to_account_info(), fee, hook_accounts)?;
+        }
+        self.transfer_b(owner_ata_b, proceeds, hook_accounts)?;
+        if chained {
+            self.fund_chain(proceeds)?;
+        }
+        Ok(())
+    }
+
+    /// Ensures both sides can settle before any transfer, so an underfunded fill fails with a specific error
+    /// rather than with the token program's, midway through settlement
+    /// `payments` are the mint_b transfers the taker makes, each grossed up by any Token-2022 transfer fee,
+    /// and `lamport_fee` the fee they pay on top in lamports
+    fn check_funds(&self, payments: &[u64], lamport_fee: u64) -> Result<()> {
+        // The vault must still hold the recorded deposit the maker is being paid for
+        require!(
+            self.vault.amount >= self.escrow.load()?.deposit,
+            EscrowError::VaultDrained
+        );
+
+        // The taker's account must stay rent-exempt after paying in lamports
+        let rent = Rent::get()?.minimum_balance(self.taker.data_len());
+        let lamports = self.taker.lamports().saturating_sub(rent);
+        let mut lamports_owed = lamport_fee;
+
+        // Wrapped SOL is paid in lamports, a delegate can only pay what it was approved for
+        if is_native_mint(&self.mint_b.key()) {
+            for payment in payments {
+                lamports_owed = lamports_owed
+                    .checked_add(*payment)
+                    .ok_or(EscrowError::MathOverflow)?;
+            }
+        } else {
+            let taker_ata_b = self
+                .taker_ata_b
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+            let available = match taker_ata_b.owner == self.taker.key() {
+                true => taker_ata_b.amount,
+                false => taker_ata_b.amount.min(taker_ata_b.delegated_amount),
+            };
+            let mut owed: u64 = 0;
+            for payment in payments.iter().filter(|payment| **payment > 0) {
+                let gross = gross_up_transfer_fee(&self.mint_b.to_account_info(), *payment)?;
+                owed = owed.checked_add(gross).ok_or(EscrowError::MathOverflow)?;
+            }
+            require!(available >= owed, EscrowError::InsufficientTakerFunds);
+        }
+        require!(
+            lamports >= lamports_owed,
+            EscrowError::InsufficientTakerFunds
+        );
+        Ok(())
+    }
+
+    /// Whether the escrow chained to this one can still be funded, unchaining the escrow if not
+    /// A chained escrow refunded, filled or closed since can't be, the escrow's fills then pay its owner again
+    fn chain_fundable(&self) -> Result<bool> {
+        let chained_escrow = self
+            .chained_escrow
+            .as_ref()
+            .ok_or(EscrowError::ChainedEscrow)?;
+
+        // The chained escrow may have been closed, in which case it is no longer owned by the program
+        let fundable = {
+            let data = chained_escrow.try_borrow_data()?;
+            *chained_escrow.owner == crate::ID
+                && data.len() == Escrow::SPACE
+                && data[..8] == Escrow::DISCRIMINATOR
+                && {
+                    let chained = bytemuck::pod_read_unaligned::<Escrow>(&data[8..]);
+                    (chained.has_status(EscrowStatus::Chained)
+                        || chained.has_status(EscrowStatus::Open))
+                        && chained.mint_a == self.mint_b.key()
+                }
+        };
+        if !fundable {
+            self.escrow.load_mut()?.chained_seed = 0;
+        }
+        Ok(fundable)
+    }
+
+    /// Adds `proceeds` to the deposit of the chained escrow, repricing it at its declared price and opening it
+    fn fund_chain(&self, proceeds: u64) -> Result<()> {
+        let chained_escrow = self
+            .chained_escrow
+            .as_ref()
+            .ok_or(EscrowError::ChainedEscrow)?;
+        let mut data = chained_escrow.try_borrow_mut_data()?;
+        let mut chained = bytemuck::pod_read_unaligned::<Escrow>(&data[8..]);
+        chained.deposit = chained
+            .deposit
+            .checked_add(proceeds)
+            .ok_or(EscrowError::MathOverflow)?;
+        chained.receive = chained.receive_for(chained.deposit)?;
+        require!(chained.receive > 0, EscrowError::ZeroReceive);
+        chained.set_status(EscrowStatus::Open);
+        data[8..].copy_from_slice(bytemuck::bytes_of(&chained));
+
+        emit!(ChainFundedEvent {
+            escrow: self.escrow.key(),
+            chained_escrow: chained_escrow.key(),
+            amount: proceeds,
+            deposit: chained.deposit,
+            receive: chained.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Protocol fee owed on a payment of `amount`, at the member rate if `member_nft` proves membership
+    /// A fee override, or else the escrow's template fee, or else the fee tier of the taker's volume before this fill,
+    /// replaces the regular rate
+    /// Members still pay their rate if it is lower
+    /// A membership NFT that doesn't qualify fails the fill rather than silently charging the full fee
+    fn fee(&self, amount: u64) -> Result<u64> {
+        let fee_bps = match (&self.fee_override, &self.template, &self.trader_stats) {
+            (Some(fee_override), _, _) => fee_override.fee_bps,
+            (None, Some(template), _) => template.fee_bps,
+            (None, None, Some(trader_stats)) => self.config.tier_fee_bps(trader_stats.volume),
+            (None, None, None) => self.config.fee_bps,
+        };
+        let (Some(member_nft), Some(member_metadata)) = (&self.member_nft, &self.member_metadata)
+        else {
+            return self.config.fee_at(amount, fee_bps);
+        };
+
+        require!(
+            (member_nft.owner == self.taker.key() || member_nft.owner == self.owner.key())
+                && member_nft.amount == 1,
+            EscrowError::InvalidMembership
+        );
+        let collection = verified_collection(&member_metadata.to_account_info(), &member_nft.mint)?;
+        require!(
+            collection.is_some() && collection == self.config.membership_collection,
+            EscrowError::InvalidMembership
+        );
+
+        self.config.member_fee_at(amount, fee_bps)
+    }
+
+    /// Transfers `amount` lamports from the taker to `to`
+    fn transfer_lamports(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Syncs the wrapped SOL fee vault so lamports sent to it count towards its token balance
+    fn sync_fee_vault(&self) -> Result<()> {
+        let sync_accounts = SyncNative {
+            account: self.fee_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
+    }
+
+    /// Transfers `amount` of mint_b from the taker to the `to` token account
+    /// With a Token-2022 transfer fee, the taker sends enough for `to` to receive exactly `amount`
+    fn transfer_b(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let taker_ata_b = self
+            .taker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
         // Set up the acounts for transferring tokens with the SPL Token program
         let transfer_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
+            from: taker_ata_b.to_account_info(),
             mint: self.mint_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
+            to,
             authority: self.taker.to_account_info(),
         };
 
         // Creates a context for thhe Cross-Program Invocation (CPI) with the token program
-        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
 
         // Execute the transfer checked operation to move th specified amount of mint_b tokens, ensuring that the token decimals are correctly handled
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, hook_accounts)
     }
 
-    /// Withdraws the deposited mint_a tokens from the vault to the taker and closes the vault account
-    /// This action finalizes the escrow by returning control of the deposited assets to the taker and cleaning up state
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
+    /// Withdraws `amount` of the deposited mint_a tokens from the vault to the taker, less any protocol fee in mint_a
+    /// The vault only holds the deposit, so any Token-2022 transfer fee on mint_a is borne by the taker
+    pub fn withdraw(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let fee = self.collect_fee_a(amount, hook_accounts)?;
+        let taker_ata_a = self.taker_ata_a.to_account_info();
+        let amount = amount.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+        self.release_a(taker_ata_a, amount, hook_accounts)
+    }
+
+    /// Releases the protocol fee owed on `amount` of mint_a to the config's fee vault for it, returning the fee
+    /// Nothing is taken unless the config charges the fee in mint_a
+    fn collect_fee_a(&self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<u64> {
+        if self.config.fee_currency != FeeCurrency::MintA {
+            return Ok(0);
+        }
+        let fee = self.fee(amount)?;
+        if fee > 0 {
+            let fee_vault_a = self
+                .fee_vault_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?
+                .to_account_info();
+            self.release_a(fee_vault_a, fee, hook_accounts)?;
+        }
+        Ok(fee)
+    }
+
+    /// Transfers `amount` of mint_a from the vault to the `to` token account, signed by the escrow's PDA
+    fn release_a(
+        &self,
+        to: AccountInfo<'info>,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
         // Prepare the seeds for signing with the escrow's PDA
+        let escrow = *self.escrow.load()?;
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
         let signer_seeds: [&[&[u8]]; 1] = [&[
             b"escrow",
-            self.maker.to_account_info().key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump],
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
         ]];
 
-        // Set up tthe transfer of mint_a tokens from the vault back to the taker's ATA
+        // Set up the transfer of mint_a tokens from the vault
         let accounts = TransferChecked {
             from: self.vault.to_account_info(),
             mint: self.mint_a.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
+            to,
             authority: self.escrow.to_account_info(),
         };
 
         // Executes the transfer with signing authority from the PDA
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            self.token_program_a.to_account_info(),
             accounts,
             &signer_seeds,
         );
-        transfer_checked(ctx, self.vault.amount, self.mint_a.decimals)?;
+        transfer_checked_with_hook(ctx, amount, self.mint_a.decimals, hook_accounts)
+    }
+
+    /// Withdraws the deposited mint_a tokens from the vault to the taker and closes the vault account
+    /// This action finalizes the escrow by returning control of the deposited assets to the taker and cleaning up state
+    /// The taker is settled the recorded deposit, anything donated to the vault on top of it goes back to the owner if their token account is passed
+    pub fn withdraw_and_close_vault(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // The vault must still hold the recorded deposit the maker is being paid for
+        let deposit = self.escrow.load()?.deposit;
+        let surplus = self
+            .vault
+            .amount
+            .checked_sub(deposit)
+            .ok_or(EscrowError::VaultDrained)?;
+
+        // The vault has to be emptied to be closed, so a surplus without the owner's token account goes along to the taker
+        // A wrapped SOL vault can be closed with its balance instead, which unwraps it into the taker's lamports
+        let fee = self.collect_fee_a(deposit, hook_accounts)?;
+        if !is_native_mint(&self.mint_a.key()) {
+            let taker_ata_a = self.taker_ata_a.to_account_info();
+            match self.owner_ata_a.as_ref().map(|ata| ata.to_account_info()) {
+                Some(owner_ata_a) if surplus > 0 => {
+                    let amount = deposit.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+                    self.release_a(taker_ata_a, amount, hook_accounts)?;
+                    self.release_a(owner_ata_a, surplus, hook_accounts)?;
+                }
+                _ => {
+                    let amount = self
+                        .vault
+                        .amount
+                        .checked_sub(fee)
+                        .ok_or(EscrowError::MathOverflow)?;
+                    self.release_a(taker_ata_a, amount, hook_accounts)?
+                }
+            }
+        }
 
-        // Set up the closure of the vault account, transferring any remaining SOL balance to the taker
+        harvest_withheld_fees(
+            self.token_program_a.to_account_info(),
+            self.mint_a.to_account_info(),
+            self.vault.to_account_info(),
+        )?;
+
+        // Prepare the seeds for signing with the escrow's PDA
+        let escrow = *self.escrow.load()?;
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+
+        // Set up the closure of the vault account, transferring its rent where the maker chose, the taker by default
+        let (_, vault_rent_to) = self.rent_recipients()?;
         let accounts = CloseAccount {
             account: self.vault.to_account_info(),
-            destination: self.taker.to_account_info(),
+            destination: vault_rent_to,
             authority: self.escrow.to_account_info(),
         };
 
         // Executes the closure of the vault account
         let ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            self.token_program_a.to_account_info(),
             accounts,
             &signer_seeds,
         );
         close_account(ctx)
     }
+
+    /// Completes the escrow: pays the full `receive` to the maker, then releases the vault to the taker and closes it
+    /// With a vesting schedule the vault is kept instead, for the taker to claim with `claim_vested`
+    pub fn fill(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        require!(escrow.schedule().is_none(), EscrowError::ScheduledEscrow);
+        if escrow.vesting().is_some() {
+            return self.fill_vesting(hook_accounts);
+        }
+
+        let (deposit, receive) = (escrow.deposit, escrow.receive);
+
+        self.deposit(receive, hook_accounts)?;
+        self.withdraw_and_close_vault(hook_accounts)?;
+
+        self.emit_take(deposit, receive, 0)
+    }
+
+    /// Pays the full `receive` to the maker and starts vesting the deposit to the taker
+    fn fill_vesting(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let (deposit, receive) = (escrow.deposit, escrow.receive);
+        self.deposit(receive, hook_accounts)?;
+        // A fee in mint_a is taken upfront, the taker vesting what remains of the deposit
+        let fee = self.collect_fee_a(deposit, hook_accounts)?;
+
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.deposit = escrow
+            .deposit
+            .checked_sub(fee)
+            .ok_or(EscrowError::MathOverflow)?;
+        escrow.set_taker(Some(self.taker.key()));
+        escrow.vesting_start = Clock::get()?.unix_timestamp;
+        escrow.set_status(EscrowStatus::Vesting);
+        drop(escrow);
+
+        self.emit_take(deposit, receive, 0)
+    }
+
+    /// Marks the escrow to be kept as a receipt once fully filled, whatever the maker chose at `make`
+    pub fn keep_receipt(&mut self) -> Result<()> {
+        self.escrow.load_mut()?.keep_receipt = 1;
+        Ok(())
+    }
+
+    /// Closes the escrow account once it has been fully filled, sending its rent where the maker chose, back to them by default
+    /// Escrows kept as receipts are marked as filled instead, recording their taker and when they were settled,
+    /// until the maker closes them with `close_receipt`
+    /// Vesting escrows stay open until the taker has claimed the whole deposit
+    pub fn close_escrow(&mut self) -> Result<()> {
+        delist(&self.escrow, self.order_book_page.as_deref_mut())?;
+        let mut escrow = self.escrow.load_mut()?;
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), true)?;
+        if escrow.has_status(EscrowStatus::Vesting) {
+            return Ok(());
+        }
+        if escrow.keep_receipt() {
+            escrow.set_taker(Some(self.taker.key()));
+            escrow.last_fill_ts = Clock::get()?.unix_timestamp;
+            escrow.set_status(EscrowStatus::Filled);
+            return Ok(());
+        }
+        drop(escrow);
+        let (escrow_rent_to, _) = self.rent_recipients()?;
+        self.escrow.close(escrow_rent_to)
+    }
+
+    /// Accounts receiving the rent of the escrow and of its vault once closed, following the maker's choice
+    fn rent_recipients(&self) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+        let taker = self.taker.to_account_info();
+        let rent_treasury = self.rent_treasury.as_ref().map(|t| t.to_account_info());
+        rent_recipients(
+            self.escrow.load()?.rent_destination(),
+            &self.mint_a.key(),
+            &self.maker.to_account_info(),
+            &taker,
+            Some(&taker),
+            rent_treasury.as_ref(),
+        )
+    }
+
+    /// Fills `amount` of the deposited mint_a, paying the pro-rata share of `receive` to the maker
+    /// The escrow and vault stay open, with `deposit` and `receive` reduced to what remains of the offer
+    pub fn fill_partial(
+        &mut self,
+        amount: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        require!(escrow.vesting().is_none(), EscrowError::PartialVesting);
+        require!(escrow.schedule().is_none(), EscrowError::ScheduledEscrow);
+
+        // A partial fill must leave something behind, a complete fill goes through `take`
+        require!(
+            amount > 0 && amount < escrow.deposit,
+            EscrowError::InvalidAmount
+        );
+
+        // Priced at the escrow's price rather than the vault balance, so tokens donated to the vault can't skew the rate
+        // Rounded up so the maker never receives less than the agreed price for the filled portion
+        let payment = escrow.receive_for(amount)?;
+        self.settle_partial(amount, payment, hook_accounts)
+    }
+
+    /// Fills the escrow for an exact amount of either leg, computing the other one at the escrow's price
+    /// `limit` bounds the computed leg: the most mint_b paid with `ExactOut`, the least mint_a received with `ExactIn`
+    /// Filling the whole deposit completes the escrow like `take`, anything less keeps it open like `take_partial`
+    pub fn fill_exact(
+        &mut self,
+        amount: FillAmount,
+        limit: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // Vesting escrows are only taken in full, through `take`
+        let escrow = *self.escrow.load()?;
+        require!(escrow.vesting().is_none(), EscrowError::PartialVesting);
+        require!(escrow.schedule().is_none(), EscrowError::ScheduledEscrow);
+
+        let (deposit, payment) = match amount {
+            FillAmount::ExactOut { deposit } => {
+                let payment = escrow.receive_for(deposit)?;
+                require!(payment <= limit, EscrowError::SlippageExceeded);
+                (deposit, payment)
+            }
+            FillAmount::ExactIn { payment } => {
+                let deposit = escrow.deposit_for(payment)?;
+                require!(deposit >= limit, EscrowError::SlippageExceeded);
+                (deposit, payment)
+            }
+        };
+        require!(
+            deposit > 0 && deposit <= escrow.deposit,
+            EscrowError::InvalidAmount
+        );
+        if deposit < escrow.deposit {
+            return self.settle_partial(deposit, payment, hook_accounts);
+        }
+
+        self.deposit(payment, hook_accounts)?;
+        self.withdraw_and_close_vault(hook_accounts)?;
+        self.emit_take(escrow.deposit, payment, 0)?;
+        self.close_escrow()
+    }
+
+    /// Takes the installment due, `Schedule::installment` of the deposit or what remains of it, at the escrow's price
+    /// The last installment pays the whole remaining `receive` and completes the escrow like `take`, as does one
+    /// whose payment rounds up to the whole `receive`, leaving nothing to pay for the rest of the deposit
+    pub fn fill_installment(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(
+            escrow.is_installment_due(now),
+            EscrowError::InstallmentNotDue
+        );
+        let installment = escrow.schedule.installment;
+        self.escrow.load_mut()?.last_fill_ts = now;
+
+        if installment < escrow.deposit {
+            let payment = escrow.receive_for(installment)?;
+            if payment < escrow.receive {
+                return self.settle_partial(installment, payment, hook_accounts);
+            }
+        }
+
+        let (deposit, receive) = (escrow.deposit, escrow.receive);
+        self.deposit(receive, hook_accounts)?;
+        self.withdraw_and_close_vault(hook_accounts)?;
+        self.emit_take(deposit, receive, 0)?;
+        self.close_escrow()
+    }
+
+    /// Pays `payment` to the maker for `amount` of the deposit, keeping the rest of the escrow open at the same price
+    fn settle_partial(
+        &mut self,
+        amount: u64,
+        payment: u64,
+        hook_accounts: &[AccountInfo<'info>],
+    ) -> Result<()> {
+        // Rounding must not consume the whole `receive`, or the rest of the vault would be free
+        require!(
+            payment < self.escrow.load()?.receive,
+            EscrowError::InvalidAmount
+        );
+
+        self.deposit(payment, hook_accounts)?;
+        self.withdraw(amount, hook_accounts)?;
+
+        let mut escrow = self.escrow.load_mut()?;
+        // Pins the price of escrows made before it was stored, before their deposit changes
+        (escrow.price_a, escrow.price_b) = escrow.price();
+        escrow.deposit = escrow
+            .deposit
+            .checked_sub(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        escrow.receive = escrow.receive_for(escrow.deposit)?;
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+        let remaining = escrow.deposit;
+        drop(escrow);
+
+        self.emit_take(amount, payment, remaining)
+    }
+
+    /// Emits a `TakeEvent` for a fill of `deposit` mint_a against `receive` mint_b, and adds it to the taker's fill receipt and stats if passed
+    /// A reservation passed is settled by the fill
+    fn emit_take(&mut self, deposit: u64, receive: u64, remaining: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(fill_receipt) = &mut self.fill_receipt {
+            // A fresh receipt is zeroed, its first fill sets who it belongs to
+            if fill_receipt.fills == 0 {
+                let (escrow, taker) = (self.escrow.key(), self.taker.key());
+                fill_receipt.escrow = escrow;
+                fill_receipt.taker = taker;
+                fill_receipt.first_fill = now;
+                fill_receipt.bump = Pubkey::find_program_address(
+                    &[b"fill_receipt", escrow.as_ref(), taker.as_ref()],
+                    &crate::ID,
+                )
+                .1;
+            }
+            fill_receipt.deposit = fill_receipt.deposit.saturating_add(deposit);
+            fill_receipt.receive = fill_receipt.receive.saturating_add(receive);
+            fill_receipt.fills = fill_receipt.fills.saturating_add(1);
+            fill_receipt.last_fill = now;
+        }
+
+        if let Some(reservation) = &self.reservation {
+            // Filling the escrow settles the reservation, returning its bond and rent to the taker
+            self.escrow.load_mut()?.reserved_until = 0;
+            reservation.close(self.taker.to_account_info())?;
+            emit!(ReservationSettledEvent {
+                escrow: self.escrow.key(),
+                taker: self.taker.key(),
+                bond: reservation.bond,
+                forfeited: false,
+                timestamp: now,
+            });
+        }
+
+        if let Some(trader_stats) = &mut self.trader_stats {
+            // Fresh stats are zeroed, the first tracked fill sets who and what they track
+            if trader_stats.fills == 0 {
+                let (taker, mint_b) = (self.taker.key(), self.mint_b.key());
+                trader_stats.trader = taker;
+                trader_stats.mint = mint_b;
+                trader_stats.bump = Pubkey::find_program_address(
+                    &[b"trader_stats", taker.as_ref(), mint_b.as_ref()],
+                    &crate::ID,
+                )
+                .1;
+            }
+            trader_stats.volume = trader_stats.volume.saturating_add(receive);
+            trader_stats.fills = trader_stats.fills.saturating_add(1);
+        }
+
+        if let Some(event_log) = &self.event_log {
+            event_log.load_mut()?.record(
+                LogKind::Take,
+                self.escrow.key(),
+                self.taker.key(),
+                deposit,
+                now,
+            )?;
+        }
+
+        emit!(TakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit,
+            receive,
+            remaining,
+            timestamp: now,
+        });
+        Ok(())
+    }
 }
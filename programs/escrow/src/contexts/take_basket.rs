@@ -0,0 +1,246 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use anchor_spl::{
+    associated_token::{
+        create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+    },
+    token_interface::{
+        sync_native, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    helpers::{
+        gross_up_transfer_fee, is_native_mint, load_token_account, release_vault,
+        transfer_checked_with_hook,
+    },
+    Basket, Config, EscrowError, TakeBasketEvent,
+};
+
+/// Defines the accounts needed for the `take_basket` instruction
+/// Each leg is passed as remaining accounts, in groups of `[mint, vault, taker_ata]`, in the basket's order
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+    /// The participant taking the whole basket must be a signer
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the basket, receives the payment and the basket's rent
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the token expected to be received by the maker
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the taker paying mint_b to the maker
+    /// Not needed when mint_b is wrapped SOL, the taker then pays in lamports
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Associated token account of the maker receiving mint_b from the taker
+    /// Not needed when mint_b is wrapped SOL, the maker then receives lamports
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payment
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The basket being taken, closed to the maker once every leg has been released
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = taker.key() != maker.key() @ EscrowError::SelfTake,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump
+    )]
+    pub basket: Box<Account<'info, Basket>>,
+
+    /// Used to create the taker's associated token account for each leg
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning every leg's mint
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBasket<'info> {
+    /// Transfers `receive` of mint_b from the taker to the maker, minus the protocol fee which goes to the fee vault
+    pub fn deposit(&mut self) -> Result<()> {
+        let amount = self.basket.receive;
+        let fee = self.config.fee(amount)?;
+        let proceeds = amount.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+
+        // Wrapped SOL is paid in lamports, so neither side has to wrap or unwrap it themselves
+        if is_native_mint(&self.mint_b.key()) {
+            if fee > 0 {
+                self.transfer_lamports(self.fee_vault.to_account_info(), fee)?;
+                self.sync_fee_vault()?;
+            }
+            return self.transfer_lamports(self.maker.to_account_info(), proceeds);
+        }
+
+        let maker_ata_b = self
+            .maker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?
+            .to_account_info();
+
+        if fee > 0 {
+            self.transfer_b(self.fee_vault.to_account_info(), fee)?;
+        }
+        self.transfer_b(maker_ata_b, proceeds)
+    }
+
+    /// Transfers `amount` lamports from the taker to `to`
+    fn transfer_lamports(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Syncs the wrapped SOL fee vault so lamports sent to it count towards its token balance
+    fn sync_fee_vault(&self) -> Result<()> {
+        let sync_accounts = SyncNative {
+            account: self.fee_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
+    }
+
+    /// Transfers `amount` of mint_b from the taker to the `to` token account
+    /// With a Token-2022 transfer fee, the taker sends enough for `to` to receive exactly `amount`
+    fn transfer_b(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let taker_ata_b = self
+            .taker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, &[])
+    }
+
+    /// Releases every leg's vault to the taker and closes it, the vaults' rent goes to the taker
+    /// Any Token-2022 transfer fee on a leg's mint is borne by the taker
+    pub fn withdraw_legs(&mut self, leg_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(
+            leg_accounts.len() == self.basket.legs.len() * Basket::ACCOUNTS_PER_LEG,
+            EscrowError::InvalidLegs
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.to_account_info().key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump],
+        ]];
+
+        for (leg, accounts) in self
+            .basket
+            .legs
+            .iter()
+            .zip(leg_accounts.chunks(Basket::ACCOUNTS_PER_LEG))
+        {
+            let [mint, vault, taker_ata] = accounts else {
+                return err!(EscrowError::InvalidLegs);
+            };
+
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::MintMismatch);
+            require_keys_eq!(
+                vault.key(),
+                get_associated_token_address_with_program_id(
+                    &self.basket.key(),
+                    &leg.mint,
+                    self.token_program_a.key
+                ),
+                EscrowError::VaultMismatch
+            );
+            // The vault must still hold the leg the maker is being paid for
+            require!(
+                load_token_account(vault, self.token_program_a.key)?.amount >= leg.amount,
+                EscrowError::VaultDrained
+            );
+
+            let create_accounts = Create {
+                payer: self.taker.to_account_info(),
+                associated_token: taker_ata.clone(),
+                authority: self.taker.to_account_info(),
+                mint: mint.clone(),
+                system_program: self.system_program.to_account_info(),
+                token_program: self.token_program_a.to_account_info(),
+            };
+            create_idempotent(CpiContext::new(
+                self.associated_token_program.to_account_info(),
+                create_accounts,
+            ))?;
+
+            release_vault(
+                &self.token_program_a.to_account_info(),
+                mint,
+                vault,
+                taker_ata,
+                &self.taker.to_account_info(),
+                &self.basket.to_account_info(),
+                &signer_seeds,
+                &[],
+            )?;
+        }
+
+        emit!(TakeBasketEvent {
+            basket: self.basket.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_b: self.mint_b.key(),
+            receive: self.basket.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
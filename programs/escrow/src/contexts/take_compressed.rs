@@ -0,0 +1,235 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        sync_native, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+use mpl_bubblegum::{
+    programs::{SPL_ACCOUNT_COMPRESSION_ID, SPL_NOOP_ID},
+    utils::get_asset_id,
+};
+
+use crate::{
+    helpers::{
+        gross_up_transfer_fee, is_native_mint, transfer_checked_with_hook, transfer_compressed,
+    },
+    CompressedEscrow, Config, EscrowError, LeafProof, TakeCompressedEvent,
+};
+
+/// Defines the accounts needed for the `take_compressed` instruction, paying for an escrowed compressed NFT
+/// The proof nodes of the NFT's leaf are passed as remaining accounts
+#[derive(Accounts)]
+pub struct TakeCompressed<'info> {
+    /// The participant taking the compressed NFT must be a signer
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the escrow, receives the payment and the escrow's rent
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the token expected to be received by the maker
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the taker paying mint_b to the maker
+    /// Not needed when mint_b is wrapped SOL, the taker then pays in lamports
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Associated token account of the maker receiving mint_b from the taker
+    /// Not needed when mint_b is wrapped SOL, the maker then receives lamports
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payment
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The escrow being taken, closed to the maker once the compressed NFT is released
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        has_one = merkle_tree @ EscrowError::AssetMismatch,
+        constraint = taker.key() != maker.key() @ EscrowError::SelfTake,
+        seeds = [b"compressed", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Box<Account<'info, CompressedEscrow>>,
+
+    /// CHECK: Bubblegum config of `merkle_tree`, checked by Bubblegum
+    pub tree_config: UncheckedAccount<'info>,
+
+    /// CHECK: Merkle tree holding the compressed NFT, checked against the escrow
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    /// CHECK: The Bubblegum program, checked by address
+    #[account(address = mpl_bubblegum::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Noop program Bubblegum logs leaves with, checked by address
+    #[account(address = SPL_NOOP_ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+
+    /// CHECK: The SPL Account Compression program owning the tree, checked by address
+    #[account(address = SPL_ACCOUNT_COMPRESSION_ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeCompressed<'info> {
+    /// Ensures the escrow can still be filled and `proof` points to its compressed NFT
+    pub fn check_fillable(&self, proof: &LeafProof) -> Result<()> {
+        require!(
+            !self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::EscrowExpired
+        );
+        require_keys_eq!(
+            get_asset_id(self.merkle_tree.key, proof.nonce),
+            self.escrow.asset_id,
+            EscrowError::AssetMismatch
+        );
+        Ok(())
+    }
+
+    /// Transfers `receive` of mint_b from the taker to the maker, minus the protocol fee which goes to the fee vault
+    pub fn deposit(&mut self) -> Result<()> {
+        let amount = self.escrow.receive;
+        let fee = self.config.fee(amount)?;
+        let proceeds = amount.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+
+        // Wrapped SOL is paid in lamports, so neither side has to wrap or unwrap it themselves
+        if is_native_mint(&self.mint_b.key()) {
+            if fee > 0 {
+                self.transfer_lamports(self.fee_vault.to_account_info(), fee)?;
+                self.sync_fee_vault()?;
+            }
+            return self.transfer_lamports(self.maker.to_account_info(), proceeds);
+        }
+
+        let maker_ata_b = self
+            .maker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?
+            .to_account_info();
+
+        if fee > 0 {
+            self.transfer_b(self.fee_vault.to_account_info(), fee)?;
+        }
+        self.transfer_b(maker_ata_b, proceeds)
+    }
+
+    /// Transfers `amount` lamports from the taker to `to`
+    fn transfer_lamports(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Syncs the wrapped SOL fee vault so lamports sent to it count towards its token balance
+    fn sync_fee_vault(&self) -> Result<()> {
+        let sync_accounts = SyncNative {
+            account: self.fee_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
+    }
+
+    /// Transfers `amount` of mint_b from the taker to the `to` token account
+    /// With a Token-2022 transfer fee, the taker sends enough for `to` to receive exactly `amount`
+    fn transfer_b(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let taker_ata_b = self
+            .taker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, &[])
+    }
+
+    /// Transfers the compressed NFT from the escrow PDA to the taker
+    pub fn withdraw(&self, proof: &LeafProof, proof_nodes: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"compressed",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        transfer_compressed(
+            &self.bubblegum_program.to_account_info(),
+            &self.tree_config.to_account_info(),
+            &self.escrow.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.merkle_tree.to_account_info(),
+            &self.log_wrapper.to_account_info(),
+            &self.compression_program.to_account_info(),
+            &self.system_program.to_account_info(),
+            proof,
+            proof_nodes,
+            &signer_seeds,
+        )?;
+
+        emit!(TakeCompressedEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            asset_id: self.escrow.asset_id,
+            receive: self.escrow.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+    },
+    system_program::{transfer, Transfer},
+};
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{
+        sync_native, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked,
+    },
+};
+
+use crate::{
+    helpers::{
+        gross_up_transfer_fee, is_native_mint, master_edition_supply, token_metadata,
+        transfer_checked_with_hook,
+    },
+    Config, EditionEscrow, EscrowError, PrintEditionEvent,
+};
+
+/// Token Metadata's `MintNewEditionFromMasterEditionViaToken` instruction
+const MINT_NEW_EDITION_VIA_TOKEN: u8 = 11;
+
+/// Defines the accounts needed for the `take_edition` instruction, buying a new edition printed from an escrowed master edition
+/// The taker creates `new_mint` and mints its one token to themselves beforehand, in the same transaction
+#[derive(Accounts)]
+pub struct TakeEdition<'info> {
+    /// The participant buying a print must be a signer, mint authority of the new edition's mint and payer for its accounts
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The maker of the escrow, receives the payment
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the token expected to be received by the maker
+    #[account(mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Associated token account of the taker paying mint_b to the maker
+    /// Not needed when mint_b is wrapped SOL, the taker then pays in lamports
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Associated token account of the maker receiving mint_b from the taker
+    /// Not needed when mint_b is wrapped SOL, the maker then receives lamports
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b
+    )]
+    pub maker_ata_b: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// The program's global config, holding the protocol fee taken from the taker's payment
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// Token account of the config PDA collecting the protocol fees paid in mint_b
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = config,
+        associated_token::token_program = token_program_b
+    )]
+    pub fee_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The escrow being taken, left open for the next print
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        has_one = master_mint @ EscrowError::MintMismatch,
+        constraint = taker.key() != maker.key() @ EscrowError::SelfTake,
+        seeds = [b"edition", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump
+    )]
+    pub escrow: Box<Account<'info, EditionEscrow>>,
+
+    /// The mint of the master edition
+    #[account(mint::token_program = token_program)]
+    pub master_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Vault holding the master edition's token, controlled by the escrow PDA
+    #[account(
+        associated_token::mint = master_mint,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: The metadata account of `master_mint`, checked by seeds
+    #[account(
+        seeds = [b"metadata", token_metadata::ID.as_ref(), master_mint.key().as_ref()],
+        seeds::program = token_metadata::ID,
+        bump
+    )]
+    pub master_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: The master edition account of `master_mint`, checked by seeds and deserialized in `check_fillable`
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata::ID.as_ref(), master_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata::ID,
+        bump
+    )]
+    pub master_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The mint of the new edition, holding the single token the taker minted, checked by Token Metadata
+    #[account(mut)]
+    pub new_mint: UncheckedAccount<'info>,
+
+    /// CHECK: The metadata account of `new_mint`, created by Token Metadata
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata::ID.as_ref(), new_mint.key().as_ref()],
+        seeds::program = token_metadata::ID,
+        bump
+    )]
+    pub new_metadata: UncheckedAccount<'info>,
+
+    /// CHECK: The edition account of `new_mint`, created by Token Metadata
+    #[account(
+        mut,
+        seeds = [b"metadata", token_metadata::ID.as_ref(), new_mint.key().as_ref(), b"edition"],
+        seeds::program = token_metadata::ID,
+        bump
+    )]
+    pub new_edition: UncheckedAccount<'info>,
+
+    /// CHECK: The edition marker of the printed edition number, checked by Token Metadata
+    #[account(mut)]
+    pub edition_marker: UncheckedAccount<'info>,
+
+    /// CHECK: The Token Metadata program, checked by address
+    #[account(address = token_metadata::ID)]
+    pub token_metadata_program: UncheckedAccount<'info>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning the master edition's mint and the new edition's mint
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The token program owning mint_b
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeEdition<'info> {
+    /// Ensures the escrow can still be filled, returning the number of the edition printed next
+    pub fn check_fillable(&self) -> Result<u64> {
+        require!(
+            !self.escrow.is_expired(Clock::get()?.unix_timestamp),
+            EscrowError::EscrowExpired
+        );
+        let (supply, max_supply) = master_edition_supply(&self.master_edition)?;
+        require!(max_supply != Some(supply), EscrowError::EditionsSoldOut);
+        supply
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow.into())
+    }
+
+    /// Transfers `receive` of mint_b from the taker to the maker, minus the protocol fee which goes to the fee vault
+    pub fn deposit(&mut self) -> Result<()> {
+        let amount = self.escrow.receive;
+        let fee = self.config.fee(amount)?;
+        let proceeds = amount.checked_sub(fee).ok_or(EscrowError::MathOverflow)?;
+
+        // Wrapped SOL is paid in lamports, so neither side has to wrap or unwrap it themselves
+        if is_native_mint(&self.mint_b.key()) {
+            if fee > 0 {
+                self.transfer_lamports(self.fee_vault.to_account_info(), fee)?;
+                self.sync_fee_vault()?;
+            }
+            return self.transfer_lamports(self.maker.to_account_info(), proceeds);
+        }
+
+        let maker_ata_b = self
+            .maker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?
+            .to_account_info();
+
+        if fee > 0 {
+            self.transfer_b(self.fee_vault.to_account_info(), fee)?;
+        }
+        self.transfer_b(maker_ata_b, proceeds)
+    }
+
+    /// Transfers `amount` lamports from the taker to `to`
+    fn transfer_lamports(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.taker.to_account_info(),
+            to,
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, amount)
+    }
+
+    /// Syncs the wrapped SOL fee vault so lamports sent to it count towards its token balance
+    fn sync_fee_vault(&self) -> Result<()> {
+        let sync_accounts = SyncNative {
+            account: self.fee_vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
+    }
+
+    /// Transfers `amount` of mint_b from the taker to the `to` token account
+    /// With a Token-2022 transfer fee, the taker sends enough for `to` to receive exactly `amount`
+    fn transfer_b(&self, to: AccountInfo<'info>, amount: u64) -> Result<()> {
+        let taker_ata_b = self
+            .taker_ata_b
+            .as_ref()
+            .ok_or(EscrowError::MissingTokenAccount)?;
+
+        let transfer_accounts = TransferChecked {
+            from: taker_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to,
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), amount)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, &[])
+    }
+
+    /// Prints edition number `edition` of the master edition to the taker, the escrow PDA signing as the master token's owner
+    /// The new edition's metadata is placed under the maker's update authority
+    pub fn print(&mut self, edition: u64) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"edition",
+            self.maker.to_account_info().key.as_ref(),
+            &self.escrow.seed.to_le_bytes()[..],
+            &[self.escrow.bump],
+        ]];
+
+        let mut data = vec![MINT_NEW_EDITION_VIA_TOKEN];
+        data.extend_from_slice(&edition.to_le_bytes());
+        let instruction = Instruction {
+            program_id: token_metadata::ID,
+            accounts: vec![
+                AccountMeta::new(self.new_metadata.key(), false),
+                AccountMeta::new(self.new_edition.key(), false),
+                AccountMeta::new(self.master_edition.key(), false),
+                AccountMeta::new(self.new_mint.key(), false),
+                AccountMeta::new(self.edition_marker.key(), false),
+                AccountMeta::new_readonly(self.taker.key(), true),
+                AccountMeta::new(self.taker.key(), true),
+                AccountMeta::new_readonly(self.escrow.key(), true),
+                AccountMeta::new_readonly(self.vault.key(), false),
+                AccountMeta::new_readonly(self.maker.key(), false),
+                AccountMeta::new_readonly(self.master_metadata.key(), false),
+                AccountMeta::new_readonly(self.token_program.key(), false),
+                AccountMeta::new_readonly(self.system_program.key(), false),
+            ],
+            data,
+        };
+        invoke_signed(
+            &instruction,
+            &[
+                self.new_metadata.to_account_info(),
+                self.new_edition.to_account_info(),
+                self.master_edition.to_account_info(),
+                self.new_mint.to_account_info(),
+                self.edition_marker.to_account_info(),
+                self.taker.to_account_info(),
+                self.escrow.to_account_info(),
+                self.vault.to_account_info(),
+                self.maker.to_account_info(),
+                self.master_metadata.to_account_info(),
+                self.token_program.to_account_info(),
+                self.system_program.to_account_info(),
+                self.token_metadata_program.to_account_info(),
+            ],
+            &signer_seeds,
+        )?;
+
+        self.escrow.printed = self
+            .escrow
+            .printed
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        emit!(PrintEditionEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            new_mint: self.new_mint.key(),
+            edition,
+            receive: self.escrow.receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
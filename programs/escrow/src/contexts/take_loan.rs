@@ -0,0 +1,105 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, transfer_checked_with_hook},
+    Config, EscrowError, Loan, TakeLoanEvent,
+};
+
+/// Defines the accounts needed for the `take_loan` instruction, where a lender pays the principal to the maker
+#[derive(Accounts)]
+pub struct TakeLoan<'info> {
+    /// The lender paying the principal, repaid or given the collateral later
+    #[account(mut)]
+    pub lender: Signer<'info>,
+
+    /// The maker borrowing the principal
+    pub maker: SystemAccount<'info>,
+
+    /// The mint of the principal
+    #[account(mint::token_program = token_program)]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The lender's token account for `mint_b`, paying the principal
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = lender,
+        associated_token::token_program = token_program
+    )]
+    pub lender_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// Associated token account of the maker receiving the principal
+    #[account(
+        init_if_needed,
+        payer = lender,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// The program's global config, which must not be paused for fills
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = !config.paused @ EscrowError::Paused
+    )]
+    pub config: Box<Account<'info, Config>>,
+
+    /// The loan being taken, which must not have a lender yet
+    #[account(
+        mut,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = mint_b @ EscrowError::MintMismatch,
+        constraint = loan.lender.is_none() @ EscrowError::LoanAlreadyTaken,
+        seeds = [b"loan", maker.key().as_ref(), loan.seed.to_le_bytes().as_ref()],
+        bump = loan.bump
+    )]
+    pub loan: Account<'info, Loan>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning `mint_b`
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeLoan<'info> {
+    /// Pays the principal to the maker and starts the repayment period
+    /// With a Token-2022 transfer fee, the lender sends enough for the maker to receive exactly the principal
+    pub fn lend(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let transfer_accounts = TransferChecked {
+            from: self.lender_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            to: self.maker_ata_b.to_account_info(),
+            authority: self.lender.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program.to_account_info(), transfer_accounts);
+        let amount = gross_up_transfer_fee(&self.mint_b.to_account_info(), self.loan.principal)?;
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals, hook_accounts)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        let deadline = now
+            .checked_add(self.loan.duration)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.loan.lender = Some(self.lender.key());
+        self.loan.deadline = deadline;
+
+        emit!(TakeLoanEvent {
+            loan: self.loan.key(),
+            lender: self.lender.key(),
+            principal: self.loan.principal,
+            deadline,
+            timestamp: now,
+        });
+        Ok(())
+    }
+}
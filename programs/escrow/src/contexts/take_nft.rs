@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::{
+    helpers::{reindex, release_vault, transfer_checked_with_hook, verified_collection},
+    Escrow, EscrowError, EscrowStatus, PairIndex, TakeEvent,
+};
+
+/// Defines the accounts needed for the `take_nft` instruction, paying a collection offer with one NFT of the collection
+#[derive(Accounts)]
+pub struct TakeNft<'info> {
+    /// The participant paying with their NFT must be a signer
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The original maker of the escrow, receives its rent once it is closed
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+
+    /// The owner of the maker's position, the maker unless it was transferred, receives the NFT
+    pub owner: SystemAccount<'info>,
+
+    /// The mint of the token deposited by the maker
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program_a
+    )]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    /// The mint of the NFT the taker pays with, which must be verified in the escrow's collection
+    #[account(mint::token_program = token_program_b)]
+    pub nft_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// CHECK: Token Metadata account of `nft_mint`, deserialized and checked against it in `check_nft`
+    pub nft_metadata: UncheckedAccount<'info>,
+
+    /// Associated token account of the taker holding the NFT
+    #[account(
+        mut,
+        associated_token::mint = nft_mint,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_b
+    )]
+    pub taker_nft_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Associated token account of the position's owner receiving the NFT
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = nft_mint,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_b
+    )]
+    pub owner_nft_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Associated token account of the taker receiving the deposit
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_a,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program_a
+    )]
+    pub taker_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The collection offer being taken, closed to the maker
+    /// Its `mint_b` is the mint of the collection the NFT must belong to
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker @ EscrowError::MakerMismatch,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = escrow.load()?.collection_offer() @ EscrowError::NotCollectionOffer,
+        constraint = taker.key() != maker.key() @ EscrowError::SelfTake,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault for the assets deposited by the maker
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The index of the escrow's mints, which the fill removes it from
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// Represents the SPL Associated Token program used for managing token accounts, especially helpful for operations like creating and managing token accounts in a standardized way
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The token program owning the NFT's mint
+    pub token_program_b: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeNft<'info> {
+    /// Ensures the escrow can still be filled by this taker, with an NFT verified in the escrow's collection
+    pub fn check_nft(&self) -> Result<()> {
+        let escrow = self.escrow.load()?;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!escrow.is_expired(now), EscrowError::EscrowExpired);
+        require!(!escrow.is_committed(now), EscrowError::CommitmentPending);
+        require!(escrow.template().is_none(), EscrowError::TemplatedEscrow);
+        require!(
+            escrow.gate().is_none() && escrow.gatekeeper_network().is_none(),
+            EscrowError::GatedEscrow
+        );
+        require!(
+            escrow.is_taker_allowed(&self.taker.key()),
+            EscrowError::UnauthorizedTaker
+        );
+        require!(
+            self.nft_mint.decimals == 0 && self.nft_mint.supply == 1,
+            EscrowError::NotInCollection
+        );
+
+        let collection =
+            verified_collection(&self.nft_metadata.to_account_info(), &self.nft_mint.key())?;
+        require!(
+            collection == Some(escrow.mint_b),
+            EscrowError::NotInCollection
+        );
+        Ok(())
+    }
+
+    /// Transfers the NFT to the owner of the maker's position, then releases the whole vault to the taker and closes it
+    /// NFT payments can't be split, so no protocol fee is taken
+    pub fn fill(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let escrow = *self.escrow.load()?;
+        require!(
+            self.vault.amount >= escrow.deposit,
+            EscrowError::VaultDrained
+        );
+        reindex(
+            &mut *self.escrow.load_mut()?,
+            self.pair_index.as_deref_mut(),
+            true,
+        )?;
+
+        let transfer_accounts = TransferChecked {
+            from: self.taker_nft_ata.to_account_info(),
+            mint: self.nft_mint.to_account_info(),
+            to: self.owner_nft_ata.to_account_info(),
+            authority: self.taker.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_b.to_account_info(), transfer_accounts);
+        transfer_checked_with_hook(cpi_ctx, 1, 0, hook_accounts)?;
+
+        let address_maker = escrow.address_maker();
+        let address_seed = escrow.address_seed();
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"escrow",
+            address_maker.as_ref(),
+            &address_seed[..],
+            &[escrow.bump],
+        ]];
+        let deposit = release_vault(
+            &self.token_program_a.to_account_info(),
+            &self.mint_a.to_account_info(),
+            &self.vault.to_account_info(),
+            &self.taker_ata_a.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.escrow.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+
+        emit!(TakeEvent {
+            escrow: self.escrow.key(),
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.nft_mint.key(),
+            deposit,
+            receive: 1,
+            remaining: 0,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{transfer, Transfer},
+};
+use anchor_spl::token_interface::{
+    sync_native, Mint, SyncNative, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    helpers::{gross_up_transfer_fee, is_native_mint, reindex, transfer_checked_with_hook},
+    Escrow, EscrowError, EscrowStatus, PairIndex, ResizeEvent,
+};
+
+/// Defines the accounts needed for the `top_up` instruction, letting the maker add to the deposit of an open escrow
+#[derive(Accounts)]
+pub struct TopUp<'info> {
+    /// The owner of the maker's position, the only one allowed to add to its deposit
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    /// The mint of the token deposited in the escrow
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+
+    /// The owner's token account for mint_a, funding the top-up
+    /// Optional when mint_a is wrapped SOL, the top-up is then wrapped straight from the owner's lamports
+    /// Owners holding data, such as program-owned PDAs, can't send lamports and top up wrapped SOL from it instead
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = owner,
+        associated_token::token_program = token_program_a
+    )]
+    pub owner_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// The open escrow being topped up
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        has_one = mint_a @ EscrowError::MintMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        constraint = !escrow.load()?.is_reserved(Clock::get()?.slot) @ EscrowError::EscrowReserved,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+
+    /// Vault holding the deposit
+    #[account(
+        mut,
+        token::mint = mint_a,
+        token::authority = escrow,
+        token::token_program = token_program_a,
+        address = escrow.load()?.vault_address(&escrow.key(), &token_program_a.key())? @ EscrowError::VaultMismatch
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The index of the escrow's mints, counting the added deposit
+    /// Required when the escrow is indexed
+    #[account(mut)]
+    pub pair_index: Option<Box<Account<'info, PairIndex>>>,
+
+    /// The token program owning mint_a
+    pub token_program_a: Interface<'info, TokenInterface>,
+
+    /// The basic Solana system program used for fundamental operations like creating accounts and transferring lamports
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TopUp<'info> {
+    /// Deposits `amount` more of mint_a into the vault and raises `deposit` and `receive` at the escrow's price
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn top_up(&mut self, amount: u64, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, EscrowError::ZeroDeposit);
+
+        let escrow = *self.escrow.load()?;
+        let deposit = escrow
+            .deposit
+            .checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        let receive = escrow.receive_for(deposit)?;
+
+        if is_native_mint(&self.mint_a.key()) && self.owner_ata_a.is_none() {
+            self.wrap_deposit(amount)?;
+        } else {
+            let owner_ata_a = self
+                .owner_ata_a
+                .as_ref()
+                .ok_or(EscrowError::MissingTokenAccount)?;
+
+            let transfer_accounts = TransferChecked {
+                from: owner_ata_a.to_account_info(),
+                mint: self.mint_a.to_account_info(),
+                to: self.vault.to_account_info(),
+                authority: self.owner.to_account_info(),
+            };
+            let cpi_ctx =
+                CpiContext::new(self.token_program_a.to_account_info(), transfer_accounts);
+
+            // With a Token-2022 transfer fee, the owner sends enough for the vault to receive exactly `amount`
+            let gross = gross_up_transfer_fee(&self.mint_a.to_account_info(), amount)?;
+            transfer_checked_with_hook(cpi_ctx, gross, self.mint_a.decimals, hook_accounts)?;
+        }
+
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.deposit = deposit;
+        escrow.receive = receive;
+        reindex(&mut escrow, self.pair_index.as_deref_mut(), false)?;
+
+        emit!(ResizeEvent {
+            escrow: self.escrow.key(),
+            maker: escrow.maker,
+            deposit,
+            receive,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Funds a wrapped SOL vault directly with the owner's lamports, so SOL doesn't need to be wrapped beforehand
+    fn wrap_deposit(&mut self, amount: u64) -> Result<()> {
+        let transfer_accounts = Transfer {
+            from: self.owner.to_account_info(),
+            to: self.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.system_program.to_account_info(), transfer_accounts);
+        transfer(cpi_ctx, amount)?;
+
+        let sync_accounts = SyncNative {
+            account: self.vault.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(self.token_program_a.to_account_info(), sync_accounts);
+        sync_native(cpi_ctx)
+    }
+}
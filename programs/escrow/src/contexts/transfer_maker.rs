@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError, EscrowStatus, TransferPositionEvent};
+
+/// Defines the accounts needed for the `transfer_maker` instruction, making another wallet the maker of an escrow
+#[derive(Accounts)]
+pub struct TransferMaker<'info> {
+    /// The owner of the maker's position, who must still be its maker
+    pub owner: Signer<'info>,
+
+    /// The open escrow handed to the new maker
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.maker == owner.key() @ EscrowError::MakerMismatch,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> TransferMaker<'info> {
+    /// Makes `new_maker` the maker and owner of the escrow, receiving its proceeds, refunds and rent from then on
+    /// Its address stays derived from the previous maker, kept as `address_maker`, and its chained escrow, if any,
+    /// stays with the previous maker as a standalone escrow
+    pub fn transfer_maker(&mut self, new_maker: Pubkey) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.address_maker = escrow.address_maker();
+        escrow.maker = new_maker;
+        escrow.owner = new_maker;
+        escrow.payout = Pubkey::default();
+        escrow.chained_seed = 0;
+
+        emit!(TransferPositionEvent {
+            escrow: self.escrow.key(),
+            from: self.owner.key(),
+            to: new_maker,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError, EscrowStatus, TransferPositionEvent};
+
+/// Defines the accounts needed for the `transfer_position` instruction, handing the maker's position to another wallet
+#[derive(Accounts)]
+pub struct TransferPosition<'info> {
+    /// The current owner of the maker's position
+    pub owner: Signer<'info>,
+
+    /// The open escrow whose maker position changes hands
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> TransferPosition<'info> {
+    /// Makes `new_owner` the recipient of the escrow's proceeds and refunds
+    /// The escrow's rent stays with the maker who paid for it, and the previous owner's payout account is dropped
+    pub fn transfer_position(&mut self, new_owner: Pubkey) -> Result<()> {
+        let mut escrow = self.escrow.load_mut()?;
+        escrow.owner = new_owner;
+        escrow.payout = Pubkey::default();
+
+        emit!(TransferPositionEvent {
+            escrow: self.escrow.key(),
+            from: self.owner.key(),
+            to: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
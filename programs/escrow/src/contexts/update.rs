@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowError, EscrowStatus, UpdateEvent};
+
+/// Defines the accounts needed for the `update_escrow` instruction, letting the maker amend the terms of an open escrow
+#[derive(Accounts)]
+pub struct Update<'info> {
+    /// The owner of the maker's position, the only one allowed to change its terms
+    pub owner: Signer<'info>,
+
+    /// The escrow account whose terms are being amended
+    /// The vault is left untouched, so no refund and re-make (and the rent it costs) is needed
+    #[account(
+        mut,
+        has_one = owner @ EscrowError::NotPositionOwner,
+        constraint = escrow.load()?.has_status(EscrowStatus::Open) @ EscrowError::EscrowNotOpen,
+        constraint = !escrow.load()?.is_private() @ EscrowError::PrivateTerms,
+        constraint = !escrow.load()?.is_reserved(Clock::get()?.slot) @ EscrowError::EscrowReserved,
+        seeds = [b"escrow", escrow.load()?.address_maker().as_ref(), escrow.load()?.address_seed().as_ref()],
+        bump = escrow.load()?.bump
+    )]
+    pub escrow: AccountLoader<'info, Escrow>,
+}
+
+impl<'info> Update<'info> {
+    /// Replaces the amount of mint_b the maker expects and the escrow's expiry (0 for none)
+    pub fn update_terms(&mut self, receive: u64, expiry: i64) -> Result<()> {
+        // Same rules as in `make`: something must be asked in return, and a deadline, if any, must still be in the future
+        require!(receive > 0, EscrowError::ZeroReceive);
+        if expiry != 0 {
+            require!(
+                expiry > Clock::get()?.unix_timestamp,
+                EscrowError::InvalidExpiry
+            );
+        }
+        // The timeout of a hashlocked escrow can only be pushed back, the taker relies on it to reveal the preimage in time
+        let mut escrow = self.escrow.load_mut()?;
+        if escrow.hashlock().is_some() {
+            require!(expiry >= escrow.expiry, EscrowError::InvalidExpiry);
+        }
+
+        escrow.set_receive(receive);
+        escrow.expiry = expiry;
+
+        // Lets takers detect that the escrow was repriced since they last looked at it
+        emit!(UpdateEvent {
+            escrow: self.escrow.key(),
+            maker: escrow.maker,
+            receive,
+            expiry,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+
+use crate::CounterOffer;
+
+/// Defines the accounts needed for the `withdraw_counter` instruction, letting a taker take back a pending counter-offer
+#[derive(Accounts)]
+pub struct WithdrawCounter<'info> {
+    /// The taker who made the counter-offer, receives its rent back
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The pending counter-offer, closed to the taker
+    /// Works even once the escrow itself has been settled or closed
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker,
+        seeds = [b"counter", counter_offer.escrow.as_ref(), taker.key().as_ref()],
+        bump = counter_offer.bump
+    )]
+    pub counter_offer: Account<'info, CounterOffer>,
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{helpers::transfer_checked_with_hook, Config, EscrowError, FeesWithdrawnEvent};
+
+/// Defines the accounts needed for the `withdraw_fees` instruction, sweeping the protocol fees collected in one mint
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    /// The admin of the config
+    pub admin: Signer<'info>,
+
+    /// The program's global config, owner of the fee vaults
+    #[account(
+        has_one = admin @ EscrowError::UnauthorizedAdmin,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// The mint the fees were collected in
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Token account of the config PDA holding the fees collected in `mint`, emptied into `treasury`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = config,
+        associated_token::token_program = token_program
+    )]
+    pub fee_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The treasury's token account for `mint`, any account the admin chooses
+    #[account(
+        mut,
+        token::mint = mint,
+        token::token_program = token_program
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    /// The token program owning `mint`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WithdrawFees<'info> {
+    /// Transfers the whole balance of the fee vault to the treasury
+    /// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+    pub fn withdraw_fees(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount = self.fee_vault.amount;
+        require!(amount > 0, EscrowError::NoFees);
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[b"config", &[self.config.bump]]];
+        let accounts = TransferChecked {
+            from: self.fee_vault.to_account_info(),
+            mint: self.mint.to_account_info(),
+            to: self.treasury.to_account_info(),
+            authority: self.config.to_account_info(),
+        };
+        let ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            accounts,
+            &signer_seeds,
+        );
+        // Any Token-2022 transfer fee on the way out is borne by the treasury
+        transfer_checked_with_hook(ctx, amount, self.mint.decimals, hook_accounts)?;
+
+        emit!(FeesWithdrawnEvent {
+            admin: self.admin.key(),
+            mint: self.mint.key(),
+            treasury: self.treasury.key(),
+            amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::{helpers::release_vault, RfqQuote};
+
+/// Defines the accounts needed for the `withdraw_quote` instruction, letting a taker take back a pending quote and its funds
+#[derive(Accounts)]
+pub struct WithdrawQuote<'info> {
+    /// The taker who posted the quote, receives its funds and rent back
+    #[account(mut)]
+    pub taker: Signer<'info>,
+
+    /// The mint of the quoted token
+    /// Writable so Token-2022 transfer fees withheld in the vault can be harvested to it before closing
+    #[account(
+        mut,
+        mint::token_program = token_program
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+
+    /// The taker's token account for `mint_b`, receiving the quoted amount back
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = taker,
+        associated_token::token_program = token_program
+    )]
+    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+
+    /// The pending quote, closed to the taker
+    /// Works even once the escrow itself has been settled or closed
+    #[account(
+        mut,
+        close = taker,
+        has_one = taker,
+        has_one = mint_b,
+        seeds = [b"rfq", quote.escrow.as_ref(), taker.key().as_ref()],
+        bump = quote.bump
+    )]
+    pub quote: Account<'info, RfqQuote>,
+
+    /// Vault holding the quoted mint_b
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = quote,
+        associated_token::token_program = token_program
+    )]
+    pub quote_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// The token program owning `mint_b`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> WithdrawQuote<'info> {
+    /// Releases the quote's vault back to the taker and closes it
+    pub fn withdraw_quote(&mut self, hook_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"rfq",
+            self.quote.escrow.as_ref(),
+            self.taker.to_account_info().key.as_ref(),
+            &[self.quote.bump],
+        ]];
+        release_vault(
+            &self.token_program.to_account_info(),
+            &self.mint_b.to_account_info(),
+            &self.quote_vault.to_account_info(),
+            &self.taker_ata_b.to_account_info(),
+            &self.taker.to_account_info(),
+            &self.quote.to_account_info(),
+            &signer_seeds,
+            hook_accounts,
+        )?;
+        Ok(())
+    }
+}
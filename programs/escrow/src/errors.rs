@@ -0,0 +1,272 @@
+use anchor_lang::prelude::*;
+
+/// Errors returned by the escrow program, so integrators can tell failure causes apart
+#[error_code]
+pub enum EscrowError {
+    #[msg("Amount must be greater than zero and within the escrow's remaining deposit")]
+    InvalidAmount,
+    #[msg("Expiry must be in the future, or 0 for an escrow that never expires")]
+    InvalidExpiry,
+    #[msg("The escrow has expired and can only be refunded")]
+    EscrowExpired,
+    #[msg("The signer is not the taker designated for this escrow")]
+    UnauthorizedTaker,
+    #[msg("The maker does not match the one recorded in the escrow")]
+    MakerMismatch,
+    #[msg("The mint does not match the one recorded in the escrow")]
+    MintMismatch,
+    #[msg("The vault holds less than the recorded deposit")]
+    VaultDrained,
+    #[msg("Arithmetic overflow in settlement math")]
+    MathOverflow,
+    #[msg("Fee can't exceed 10000 basis points")]
+    InvalidFee,
+    #[msg("A token account required for a non-native mint was not provided")]
+    MissingTokenAccount,
+    #[msg("mint_a and mint_b must be different mints")]
+    SameMint,
+    #[msg("Deposit amount must be greater than zero")]
+    ZeroDeposit,
+    #[msg("Receive amount must be greater than zero")]
+    ZeroReceive,
+    #[msg("The escrow has not expired yet")]
+    EscrowNotExpired,
+    #[msg("The escrow has already been filled or refunded")]
+    EscrowNotOpen,
+    #[msg("The escrow is still open, only settled receipts can be closed or pruned from an order book")]
+    EscrowStillOpen,
+    #[msg("Basket legs must be between 1 and 5 distinct mints, with 3 accounts passed per leg")]
+    InvalidLegs,
+    #[msg("The account passed as a vault is not the token account of the escrow, or basket leg, for that mint")]
+    VaultMismatch,
+    #[msg("Only the maker or the taker of the swap can do this")]
+    NotSwapParty,
+    #[msg("The taker has already locked their side of the swap")]
+    SwapAlreadyFunded,
+    #[msg("The taker has not locked their side of the swap yet")]
+    SwapNotFunded,
+    #[msg("The escrow has no arbiter to settle disputes")]
+    NoArbiter,
+    #[msg("Only the escrow's arbiter can resolve its dispute")]
+    UnauthorizedArbiter,
+    #[msg("Only the owner of the maker's position or the designated taker can dispute the escrow")]
+    NotEscrowParty,
+    #[msg("The escrow is not disputed")]
+    EscrowNotDisputed,
+    #[msg("The recipient doesn't match the side the dispute is resolved to")]
+    RecipientMismatch,
+    #[msg("The escrow is hashlocked, it can only be taken with its preimage")]
+    HashlockRequired,
+    #[msg("The preimage doesn't hash to the escrow's hashlock")]
+    InvalidPreimage,
+    #[msg("The escrow is not hashlocked")]
+    NotHashlocked,
+    #[msg("A hashlocked escrow can only be refunded after its timeout")]
+    HashlockNotExpired,
+    #[msg("Vesting needs a positive duration and a cliff between 0 and the duration")]
+    InvalidVesting,
+    #[msg("Escrows with a vesting schedule can only be taken in full")]
+    PartialVesting,
+    #[msg("Nothing has vested since the last claim")]
+    NothingToClaim,
+    #[msg("The program is paused, only refunds are allowed")]
+    Paused,
+    #[msg("Only the config's admin can do this")]
+    UnauthorizedAdmin,
+    #[msg("The mint is not allowed in new escrows")]
+    MintNotPermitted,
+    #[msg("The mint list is full")]
+    MintListFull,
+    #[msg("The mint is already in the list")]
+    MintAlreadyListed,
+    #[msg("The mint is not in the list")]
+    MintNotListed,
+    #[msg("Batch accounts don't match the offers, pass them in order for each offer")]
+    InvalidBatch,
+    #[msg("Only the owner of the maker's position can do this")]
+    NotPositionOwner,
+    #[msg("The auction must end in the future")]
+    InvalidAuctionEnd,
+    #[msg("Bidding on this auction has ended")]
+    AuctionEnded,
+    #[msg("Bidding on this auction has not ended yet")]
+    AuctionNotEnded,
+    #[msg("The bid must meet the reserve and beat the current highest bid")]
+    BidTooLow,
+    #[msg("The escrow's deposit or price changed since the taker built the transaction")]
+    TermsChanged,
+    #[msg("The NFT doesn't prove membership: it must be held by the taker or the position's owner and belong to the verified membership collection")]
+    InvalidMembership,
+    #[msg("The leaf proof or merkle tree doesn't point to the escrowed compressed NFT")]
+    AssetMismatch,
+    #[msg("The metadata account isn't the Token Metadata account of the NFT's mint")]
+    MetadataMismatch,
+    #[msg("The escrow is paid with any NFT of its collection, it can only be taken with take_nft")]
+    CollectionOfferRequiresNft,
+    #[msg("The escrow isn't paid with an NFT of a collection")]
+    NotCollectionOffer,
+    #[msg("The NFT isn't a verified member of the escrow's collection")]
+    NotInCollection,
+    #[msg("The escrow already uses the current layout")]
+    AlreadyMigrated,
+    #[msg("With a maker profile, the seed must be the profile's next offer counter")]
+    SeedMismatch,
+    #[msg("The rent can't be sent to the default public key")]
+    InvalidRentDestination,
+    #[msg("The escrow sends its rent to a treasury, which must be passed as rent_treasury")]
+    RentTreasuryMismatch,
+    #[msg("Another taker committed to take the escrow, it is reserved for them until the commitment's deadline")]
+    CommitmentPending,
+    #[msg("The revealed salt doesn't match the commitment for this taker and escrow")]
+    InvalidCommitment,
+    #[msg("The escrow has no pending commitment to reveal")]
+    NoCommitment,
+    #[msg("The fill would pay more mint_b, or buy less mint_a, than the taker's limit")]
+    SlippageExceeded,
+    #[msg("Milestone escrows need between 1 and 10 milestones with non-zero amounts, and an index within them")]
+    InvalidMilestones,
+    #[msg("The milestone was already approved")]
+    MilestoneNotPending,
+    #[msg("The milestone must be approved by the maker, and not claimed yet, to be claimed")]
+    MilestoneNotApproved,
+    #[msg("Loans need a collateral, a principal and a duration, and a repayment of at least the principal")]
+    InvalidLoanTerms,
+    #[msg("The loan has already been taken by a lender")]
+    LoanAlreadyTaken,
+    #[msg("The loan hasn't been taken by this lender")]
+    LoanNotTaken,
+    #[msg("The loan can only be liquidated once its repayment deadline has passed")]
+    LoanNotDefaulted,
+    #[msg("Schedules need an installment of at most the deposit and a positive interval, without vesting or a hashlock")]
+    InvalidSchedule,
+    #[msg("Scheduled escrows can only be taken one installment at a time, with take_installment")]
+    ScheduledEscrow,
+    #[msg("The escrow has no schedule, or its next installment isn't due yet")]
+    InstallmentNotDue,
+    #[msg("Quotes need a positive amount, on an escrow taken in full at once, and must match the escrow they are settled on")]
+    InvalidQuote,
+    #[msg("A losing quote offers more mint_b than the accepted one")]
+    QuoteNotBest,
+    #[msg("Only the position's owner, or the cancel authority they delegated to, can refund the escrow")]
+    UnauthorizedCanceller,
+    #[msg(
+        "Only escrows trading the same mints at the same price and on the same terms can be merged"
+    )]
+    TermsMismatch,
+    #[msg("The maker can't take their own escrow")]
+    SelfTake,
+    #[msg("The vault holds nothing beyond the escrow's deposit")]
+    NoSurplus,
+    #[msg("NonTransferable mints can't be escrowed, as they could never be paid out")]
+    NonTransferableMint,
+    #[msg("Mints with a permanent delegate, which could drain the vault, aren't permitted in new escrows")]
+    PermanentDelegateMint,
+    #[msg("A token account of the fill is frozen by its mint's freeze authority, see the account the error names")]
+    AccountFrozen,
+    #[msg("The mint freezes new token accounts by default, its issuer must thaw the account the error names before the fill")]
+    AccountNotThawed,
+    #[msg("Auto refunds need a time to execute from, the escrow's expiry when none is given")]
+    InvalidAutoRefund,
+    #[msg("The escrow's scheduled refund isn't due yet")]
+    AutoRefundNotDue,
+    #[msg("The fee override applies to another escrow or maker")]
+    FeeOverrideMismatch,
+    #[msg("The fee vault holds no fees to withdraw")]
+    NoFees,
+    #[msg("Lamport fees need a treasury to be paid to")]
+    InvalidFeeCurrency,
+    #[msg("The fee treasury isn't the one the config pays lamport fees to")]
+    FeeTreasuryMismatch,
+    #[msg("Fee tiers must be at most 4, sorted by increasing volume, with fees of at most 100%")]
+    InvalidFeeTiers,
+    #[msg("Reservations hold an escrow for 1 to 150 slots, or 9000 with reserve_fill, against a bond of at least 0.001 SOL per 150 slots")]
+    InvalidReservation,
+    #[msg("Another taker reserved the escrow, it is held for them until the reservation expires")]
+    EscrowReserved,
+    #[msg("The reservation is for another escrow or taker")]
+    ReservationMismatch,
+    #[msg("The reservation hasn't expired yet")]
+    ReservationActive,
+    #[msg("Committees have 1 to 16 distinct members and a threshold between 1 and their number")]
+    InvalidCommittee,
+    #[msg("Only a member of the escrow's arbiter committee can approve its resolution")]
+    NotCommitteeMember,
+    #[msg("The member already approved a resolution of this dispute")]
+    AlreadyApproved,
+    #[msg("Templates charge fees of at most 100% and can't have a negative expiry policy")]
+    InvalidTemplate,
+    #[msg(
+        "The template isn't the one the escrow was made from, or conflicts with the maker's terms"
+    )]
+    TemplateMismatch,
+    #[msg("The taker isn't on the template's allowlist")]
+    NotAllowlisted,
+    #[msg(
+        "Escrows made from a template can only be filled through take, which enforces its terms"
+    )]
+    TemplatedEscrow,
+    #[msg("Token gates need a mint and a non-zero amount")]
+    InvalidGate,
+    #[msg("The taker doesn't hold enough of the escrow's gate mint in the token account passed")]
+    GateNotMet,
+    #[msg(
+        "Token- or identity-gated escrows can only be filled through take, which checks the taker"
+    )]
+    GatedEscrow,
+    #[msg("The taker's gateway token isn't an active pass of the escrow's gatekeeper network")]
+    InvalidGatewayToken,
+    #[msg(
+        "The escrow's terms are private, they can only be revealed and filled with take_private"
+    )]
+    PrivateTerms,
+    #[msg("The terms supplied don't match the escrow's private terms hash")]
+    TermsHashMismatch,
+    #[msg("Sealed-bid auctions take bids through commit_bid and reveal_bid")]
+    SealedAuction,
+    #[msg(
+        "Sealed bids need a bond of at least 0.001 SOL, and reveals a bid matching its commitment"
+    )]
+    InvalidSealedBid,
+    #[msg("Sealed bids can only be revealed between the end of bidding and the end of the reveal window")]
+    NotRevealing,
+    #[msg("settle_fill only fills an escrow through the taker's reservation of it")]
+    NotReserved,
+    #[msg("Refunding a reserved escrow pays its taker the cancellation penalty, pass the reservation and their token account for mint_a")]
+    PenaltyOwed,
+    #[msg("Only open, public escrows paid in a token other than wrapped SOL can be chained, once, with a nonzero seed")]
+    InvalidChain,
+    #[msg("Chained escrows fund the escrow chained to them, only fills passing it and its vault can take them")]
+    ChainedEscrow,
+    #[msg("Stake accounts must be initialized, owned by the stake program, authorized to the maker and out of lockup")]
+    InvalidStakeAccount,
+    #[msg("Edition escrows must hold the token of a master edition that can still print editions")]
+    InvalidMasterEdition,
+    #[msg("Every edition the master edition may print has been printed")]
+    EditionsSoldOut,
+    #[msg("Payment options must list up to 4 distinct mints besides the escrow's mints, for an open public escrow that isn't a collection offer, chained or indexed")]
+    InvalidPaymentOptions,
+    #[msg("Payouts must be token accounts of the escrow's mint_b other than wrapped SOL, and escrows paying out to one can't be chained or paid in another mint")]
+    InvalidPayout,
+    #[msg("Proceeds must be paid to the payout account the owner designated, or else to their associated token account")]
+    PayoutMismatch,
+    #[msg("Escrows are listed in a page of the order book of their public mints, and must be passed with it once they are taken or refunded")]
+    OrderBookMismatch,
+    #[msg("Order book pages list at most 32 escrows, list the escrow in another page of its mint pair or add one")]
+    OrderBookPageFull,
+    #[msg("Indexed escrows must be passed with the index of their public mints whenever their deposit or status changes")]
+    IndexMismatch,
+    #[msg("The taker must pay from a mint_b token account they own or are the delegate of")]
+    TakerAccountMismatch,
+    #[msg("The taker can't cover the payment with its transfer fees, or the lamports it costs while staying rent-exempt")]
+    InsufficientTakerFunds,
+    #[msg("A maker that doesn't sign needs the maker authority PDA of the passed program to sign")]
+    UnauthorizedMaker,
+    #[msg("A seed id must not be zeroed, pass none to derive the escrow from its seed")]
+    InvalidSeedId,
+    #[msg(
+        "Batch fills are only charged fees in mint_b, fill escrows one at a time with take instead"
+    )]
+    BatchFeeCurrency,
+    #[msg("The escrow uses an older layout, migrate it with migrate_escrow first")]
+    EscrowNotMigrated,
+}
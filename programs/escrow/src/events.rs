@@ -0,0 +1,721 @@
+use anchor_lang::prelude::*;
+
+use crate::{
+    BasketLeg, FeeCurrency, FeeTier, MilestoneStatus, MintList, PaymentLeg, PermanentDelegatePolicy,
+};
+
+/// Emitted when a maker opens a new escrow and deposits into its vault
+#[event]
+pub struct MakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    pub expiry: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner chains an escrow to another, declaring the `receive` of `mint_b` it asks once funded
+#[event]
+pub struct ChainEvent {
+    pub escrow: Pubkey,
+    pub chained_escrow: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub expiry: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a fill's proceeds of `amount` fund the escrow chained to the one filled
+#[event]
+pub struct ChainFundedEvent {
+    pub escrow: Pubkey,
+    pub chained_escrow: Pubkey,
+    pub amount: u64,
+    pub deposit: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker amends the terms of an open escrow
+#[event]
+pub struct UpdateEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub receive: u64,
+    pub expiry: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted on every fill, `deposit` of mint_a went to the taker for `receive` of mint_b paid to the maker
+/// `remaining` is the deposit left in the vault, 0 once the escrow is fully filled and closed
+#[event]
+pub struct TakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker takes back the vault's tokens and the escrow is closed
+#[event]
+pub struct RefundEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker opens a basket escrow and deposits every leg into its vault
+#[event]
+pub struct MakeBasketEvent {
+    pub basket: Pubkey,
+    pub maker: Pubkey,
+    pub legs: Vec<BasketLeg>,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker pays `receive` of mint_b for every leg of a basket, which is then closed
+#[event]
+pub struct TakeBasketEvent {
+    pub basket: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker takes back every leg of a basket and it is closed
+#[event]
+pub struct RefundBasketEvent {
+    pub basket: Pubkey,
+    pub maker: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker proposes a two-sided swap and locks their side
+#[event]
+pub struct ProposeSwapEvent {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the taker locks their side of a swap, which can then be settled
+#[event]
+pub struct FundSwapEvent {
+    pub swap: Pubkey,
+    pub taker: Pubkey,
+    pub amount_b: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a swap is settled, both vaults having been released to the other side
+#[event]
+pub struct SettleSwapEvent {
+    pub swap: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when either party backs out of a swap, each side getting back what it locked
+#[event]
+pub struct CancelSwapEvent {
+    pub swap: Pubkey,
+    pub cancelled_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker or the designated taker contests an escrow with an arbiter
+#[event]
+pub struct DisputeEvent {
+    pub escrow: Pubkey,
+    pub opened_by: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the arbiter settles a dispute, `amount` of mint_a going to `recipient`
+#[event]
+pub struct ResolveEvent {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a hashlocked escrow is taken, revealing the preimage for the other leg of a cross-chain swap
+#[event]
+pub struct RevealEvent {
+    pub escrow: Pubkey,
+    pub preimage: Vec<u8>,
+    pub timestamp: i64,
+}
+
+/// Emitted when the taker claims `amount` of a vesting deposit, `claimed` is the total claimed so far
+#[event]
+pub struct ClaimEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub claimed: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner and the taker cancel a vesting escrow, `claimed` going to the taker and `refunded` to the owner
+#[event]
+pub struct CancelVestingEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub claimed: u64,
+    pub refunded: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin pauses or resumes new escrows and fills
+#[event]
+pub struct PauseEvent {
+    pub admin: Pubkey,
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin changes the share of the protocol fee paid to referrers
+#[event]
+pub struct ReferralEvent {
+    pub admin: Pubkey,
+    pub referral_bps: u16,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin changes the penalty paid to reserving takers of refunded escrows
+#[event]
+pub struct CancelPenaltyEvent {
+    pub admin: Pubkey,
+    pub cancel_penalty_bps: u16,
+    pub timestamp: i64,
+}
+
+/// Emitted when a refund pays the taker reserving the escrow the cancellation penalty
+#[event]
+pub struct PenaltyPaidEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin changes the membership collection or its discounted fee
+#[event]
+pub struct MembershipEvent {
+    pub admin: Pubkey,
+    pub collection: Option<Pubkey>,
+    pub member_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin adds a mint to, or removes it from, one of the config's mint lists
+#[event]
+pub struct MintListEvent {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub list: MintList,
+    pub listed: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin changes which mints with Token-2022 extensions new escrows may use
+#[event]
+pub struct MintPolicyEvent {
+    pub admin: Pubkey,
+    pub allow_non_transferable: bool,
+    pub permanent_delegate_policy: PermanentDelegatePolicy,
+    pub timestamp: i64,
+}
+
+/// Emitted when an escrow is made with a mint whose permanent delegate can move tokens out of its accounts
+/// Only allowed with `PermanentDelegatePolicy::Flag`, the escrow is flagged so takers and UIs can weigh the risk
+#[event]
+pub struct PermanentDelegateEvent {
+    pub escrow: Pubkey,
+    pub mint: Pubkey,
+    pub delegate: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker tops up or reduces the deposit of an open escrow, with its rescaled terms
+#[event]
+pub struct ResizeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner sweeps `amount` donated to an escrow's vault on top of its deposit
+#[event]
+pub struct SweepEvent {
+    pub escrow: Pubkey,
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the maker's position in an escrow changes hands
+#[event]
+pub struct TransferPositionEvent {
+    pub escrow: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a prospective taker proposes a counter-offer, and again when the position's owner accepts it
+#[event]
+pub struct CounterOfferEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub receive: u64,
+    pub accepted: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a prospective taker posts a funded quote, and again when the owner accepts it
+#[event]
+pub struct RfqQuoteEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub amount: u64,
+    pub accepted: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker opens an auction of their deposit
+#[event]
+pub struct MakeAuctionEvent {
+    pub auction: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64,
+    pub reserve: u64,
+    pub end: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a bid becomes the highest bid of an auction, the previous one having been refunded
+#[event]
+pub struct BidEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a bidder commits a sealed bid on an auction, locking `bond` until they reveal it
+#[event]
+pub struct CommitBidEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub bond: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a sealed bid is closed, its bond returned to the bidder once revealed, or `forfeited` to the maker
+#[event]
+pub struct SealedBidSettledEvent {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub bond: u64,
+    pub forfeited: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when an auction is settled, `winner` being `None` if it got no bids
+#[event]
+pub struct SettleAuctionEvent {
+    pub auction: Pubkey,
+    pub maker: Pubkey,
+    pub winner: Option<Pubkey>,
+    pub deposit: u64,
+    pub bid: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker escrows a compressed NFT
+#[event]
+pub struct MakeCompressedEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub asset_id: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a compressed NFT escrow is taken and the NFT transferred to the taker
+#[event]
+pub struct TakeCompressedEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub asset_id: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a compressed NFT is returned to its maker and the escrow closed
+#[event]
+pub struct RefundCompressedEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub asset_id: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a stake account is escrowed, the escrow PDA becoming its staker and withdrawer
+#[event]
+pub struct MakeStakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub stake_account: Pubkey,
+    pub lamports: u64,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a stake account escrow is taken and its authorities handed to the taker
+#[event]
+pub struct TakeStakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub stake_account: Pubkey,
+    pub lamports: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a stake account's authorities are returned to its maker and the escrow closed
+#[event]
+pub struct RefundStakeEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub stake_account: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner of an escrow sets the other mints it accepts as payment, or closes them (`legs` being empty)
+#[event]
+pub struct PaymentOptionsEvent {
+    pub escrow: Pubkey,
+    pub owner: Pubkey,
+    pub legs: Vec<PaymentLeg>,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker pays in one of an escrow's payment options, switching the escrow to it
+#[event]
+pub struct PaymentSelectedEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the owner of an escrow designates the token account its proceeds are paid to, or goes back to their own (`payout` being `None`)
+#[event]
+pub struct PayoutEvent {
+    pub escrow: Pubkey,
+    pub owner: Pubkey,
+    pub payout: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// Emitted when a page is added to the order book of a mint pair
+#[event]
+pub struct OrderBookPageEvent {
+    pub page: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub index: u32,
+    pub timestamp: i64,
+}
+
+/// Emitted when an escrow is listed in a page of its mint pair's order book (`listed`), or removed from it
+#[event]
+pub struct ListingEvent {
+    pub escrow: Pubkey,
+    pub page: Pubkey,
+    pub listed: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a master edition is escrowed to sell prints of it
+#[event]
+pub struct MakeEditionEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub master_mint: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub max_supply: Option<u64>,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker buys a new edition printed from an escrowed master edition
+#[event]
+pub struct PrintEditionEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub new_mint: Pubkey,
+    pub edition: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a master edition is returned to its maker and the escrow closed
+#[event]
+pub struct RefundEditionEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub master_mint: Pubkey,
+    pub printed: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a V1 escrow is rewritten in the current layout
+#[event]
+pub struct MigrateEvent {
+    pub escrow: Pubkey,
+    pub maker: Pubkey,
+    pub version: u8,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker commits to take an escrow, reserving it for them until `deadline`
+#[event]
+pub struct CommitEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker funds the milestones of a contract with a contractor
+#[event]
+pub struct MakeMilestonesEvent {
+    pub milestone_escrow: Pubkey,
+    pub maker: Pubkey,
+    pub contractor: Pubkey,
+    pub mint: Pubkey,
+    pub total: u64,
+    pub milestones: u8,
+    pub timestamp: i64,
+}
+
+/// Emitted when a milestone is approved by the maker, or claimed by the contractor
+#[event]
+pub struct MilestoneEvent {
+    pub milestone_escrow: Pubkey,
+    pub index: u8,
+    pub amount: u64,
+    pub status: MilestoneStatus,
+    pub timestamp: i64,
+}
+
+/// Emitted when a maker locks collateral to borrow against
+#[event]
+pub struct MakeLoanEvent {
+    pub loan: Pubkey,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub collateral: u64,
+    pub principal: u64,
+    pub repayment: u64,
+    pub duration: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a lender pays the principal of a loan, which must be repaid before `deadline`
+#[event]
+pub struct TakeLoanEvent {
+    pub loan: Pubkey,
+    pub lender: Pubkey,
+    pub principal: u64,
+    pub deadline: i64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a loan is closed: repaid, liquidated by the lender, or cancelled before any lender took it (`lender` being `None`)
+#[event]
+pub struct SettleLoanEvent {
+    pub loan: Pubkey,
+    pub maker: Pubkey,
+    pub lender: Option<Pubkey>,
+    pub repayment: u64,
+    pub liquidated: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when `merged` is folded into `escrow`, which now holds `deposit` for `receive`
+#[event]
+pub struct MergeEvent {
+    pub escrow: Pubkey,
+    pub merged: Pubkey,
+    pub maker: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner delegates the refund of their escrows to `authority`, or revokes it (`authority` being `None`)
+#[event]
+pub struct CancelAuthorityEvent {
+    pub owner: Pubkey,
+    pub authority: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// Emitted when an owner schedules the refund of an escrow, cancels it (`execute_after` being 0),
+/// or a keeper executes it (`keeper` being the one tipped)
+#[event]
+pub struct AutoRefundEvent {
+    pub escrow: Pubkey,
+    pub owner: Pubkey,
+    pub keeper: Option<Pubkey>,
+    pub execute_after: i64,
+    pub tip: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin sets the fee charged on the fills of an escrow or maker, or removes it (`fee_bps` being `None`)
+#[event]
+pub struct FeeOverrideEvent {
+    pub admin: Pubkey,
+    pub subject: Pubkey,
+    pub fee_bps: Option<u16>,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin sweeps the protocol fees collected in `mint` to a treasury token account
+#[event]
+pub struct FeesWithdrawnEvent {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin changes the currency `take` charges the protocol fee in
+#[event]
+pub struct FeeCurrencyEvent {
+    pub admin: Pubkey,
+    pub fee_currency: FeeCurrency,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin replaces the volume-based fee tiers, an empty table disabling them
+#[event]
+pub struct FeeTiersEvent {
+    pub admin: Pubkey,
+    pub fee_tiers: Vec<FeeTier>,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker reserves an escrow until slot `expires`, locking `bond` lamports
+#[event]
+pub struct ReserveEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub bond: u64,
+    pub expires: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a reservation is closed, its bond returned to the taker unless `forfeited` to the escrow's owner
+#[event]
+pub struct ReservationSettledEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub bond: u64,
+    pub forfeited: bool,
+    pub timestamp: i64,
+}
+
+/// Emitted when a committee of arbiters is created, escrows made with its address as arbiter being settled by `threshold` of its `members`
+#[event]
+pub struct CommitteeEvent {
+    pub committee: Pubkey,
+    pub creator: Pubkey,
+    pub threshold: u8,
+    pub members: Vec<Pubkey>,
+    pub timestamp: i64,
+}
+
+/// Emitted when a committee member approves releasing a disputed escrow to `recipient`, `approvals` of `threshold` now agreeing
+#[event]
+pub struct ApprovalEvent {
+    pub escrow: Pubkey,
+    pub member: Pubkey,
+    pub recipient: Pubkey,
+    pub approvals: u32,
+    pub threshold: u8,
+    pub timestamp: i64,
+}
+
+/// Emitted when the admin publishes a template of escrow terms makers can adopt
+#[event]
+pub struct TemplateEvent {
+    pub template: Pubkey,
+    pub admin: Pubkey,
+    pub fee_bps: u16,
+    pub max_duration: i64,
+    pub allowlist_root: [u8; 32],
+    pub arbiter: Pubkey,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker reveals the private terms of an escrow as they fill it
+#[event]
+pub struct TermsRevealedEvent {
+    pub escrow: Pubkey,
+    pub taker: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub timestamp: i64,
+}
+
+/// Emitted when a taker proves they are on a template's allowlist
+#[event]
+pub struct AllowlistEvent {
+    pub template: Pubkey,
+    pub taker: Pubkey,
+    pub timestamp: i64,
+}
@@ -0,0 +1,527 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            default_account_state::DefaultAccountState,
+            interest_bearing_mint::InterestBearingConfig,
+            non_transferable::NonTransferable,
+            permanent_delegate::PermanentDelegate,
+            transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+            BaseStateWithExtensions, StateWithExtensions,
+        },
+        onchain::invoke_transfer_checked,
+        state::AccountState,
+    },
+    token_interface::{
+        close_account, get_mint_extension_data, harvest_withheld_tokens_to_mint, CloseAccount,
+        HarvestWithheldTokensToMint, Mint, TokenAccount, TransferChecked,
+    },
+};
+
+use anchor_lang::solana_program::{
+    program::invoke_signed,
+    stake::{
+        self,
+        state::{Meta, StakeAuthorize, StakeStateV2},
+    },
+};
+use mpl_bubblegum::instructions::{TransferCpi, TransferCpiAccounts, TransferInstructionArgs};
+
+use crate::{
+    Config, Escrow, EscrowError, EscrowStatus, LeafProof, ListingEvent, OrderBookPage, PairIndex,
+    PermanentDelegateEvent, PermanentDelegatePolicy, RentDestination,
+};
+
+/// The Metaplex Token Metadata program, owning the metadata accounts of NFTs
+pub mod token_metadata {
+    anchor_lang::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+/// The Identity.com gateway program, issuing the gateway tokens of gatekeeper networks such as Civic Pass
+pub mod gateway {
+    anchor_lang::declare_id!("gatem74V238djXdzWnJf94Wo1DcnuGkfijbf3AuBhfs");
+}
+
+/// State of a gateway token the gateway program only accepts as a valid pass
+const GATEWAY_TOKEN_ACTIVE: u8 = 0;
+
+/// Key tagging a Token Metadata account as a `MetadataV1` account
+const METADATA_V1_KEY: u8 = 4;
+
+/// Key tagging a Token Metadata account as a `MasterEditionV2` account
+const MASTER_EDITION_V2_KEY: u8 = 6;
+
+/// Whether `mint` is the wrapped SOL mint of either the SPL Token or the Token-2022 program
+/// Legs in these mints are paid in lamports, wrapped and unwrapped by the program itself
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+/// Rejects `mint` if it has a Token-2022 extension that could keep an escrow from settling, unless the config permits it
+/// A NonTransferable deposit could never be paid out to the taker, nor a NonTransferable payment made to the maker,
+/// and a permanent delegate can drain the vault out from under the escrow
+pub fn check_mint_policy(config: &Config, mint: &AccountInfo) -> Result<()> {
+    if !config.allow_non_transferable {
+        require!(
+            get_mint_extension_data::<NonTransferable>(mint).is_err(),
+            EscrowError::NonTransferableMint
+        );
+    }
+    if config.permanent_delegate_policy == PermanentDelegatePolicy::Reject {
+        require!(
+            permanent_delegate(mint).is_none(),
+            EscrowError::PermanentDelegateMint
+        );
+    }
+    Ok(())
+}
+
+/// The Token-2022 permanent delegate of `mint`, `None` for mints without one, including legacy SPL mints
+pub fn permanent_delegate(mint: &AccountInfo) -> Option<Pubkey> {
+    get_mint_extension_data::<PermanentDelegate>(mint)
+        .ok()
+        .and_then(|extension| extension.delegate.into())
+}
+
+/// Emits a `PermanentDelegateEvent` for each of the mints of `escrow` with a permanent delegate
+/// Returns whether any of them has one, which the escrow is flagged with
+pub fn flag_permanent_delegates(escrow: Pubkey, mints: [&AccountInfo; 2], now: i64) -> bool {
+    let mut flagged = false;
+    for mint in mints {
+        if let Some(delegate) = permanent_delegate(mint) {
+            emit!(PermanentDelegateEvent {
+                escrow,
+                mint: mint.key(),
+                delegate,
+                timestamp: now,
+            });
+            flagged = true;
+        }
+    }
+    flagged
+}
+
+/// `amount` of `mint` in UI units, as wallets display it at `now`
+/// Interest-bearing mints add the interest accrued so far, while settlement always moves raw amounts
+pub fn ui_amount(mint: &InterfaceAccount<Mint>, amount: u64, now: i64) -> Result<String> {
+    match get_mint_extension_data::<InterestBearingConfig>(&mint.to_account_info()) {
+        Ok(config) => config
+            .amount_to_ui_amount(amount, mint.decimals, now)
+            .ok_or(EscrowError::MathOverflow.into()),
+        Err(_) => Ok(spl_token_2022::amount_to_ui_amount_string_trimmed(
+            amount,
+            mint.decimals,
+        )),
+    }
+}
+
+/// Error for a frozen token account of `mint`, telling apart accounts the mint froze on creation with its DefaultAccountState
+/// Those are typically RWA or KYC-gated tokens, whose issuer must thaw the account before it can be used
+pub fn frozen_account_error(mint: &AccountInfo) -> EscrowError {
+    match get_mint_extension_data::<DefaultAccountState>(mint) {
+        Ok(default) if default.state == AccountState::Frozen as u8 => EscrowError::AccountNotThawed,
+        _ => EscrowError::AccountFrozen,
+    }
+}
+
+/// Amount to send so that `net` arrives after the Token-2022 transfer fee of `mint`, the sender bears the fee
+/// Mints without the TransferFee extension, including legacy SPL mints, transfer `net` unchanged
+pub fn gross_up_transfer_fee(mint: &AccountInfo, net: u64) -> Result<u64> {
+    let Ok(config) = get_mint_extension_data::<TransferFeeConfig>(mint) else {
+        return Ok(net);
+    };
+    let fee = config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or(EscrowError::MathOverflow)?;
+    net.checked_add(fee).ok_or(EscrowError::MathOverflow.into())
+}
+
+/// Transfer fees withheld in a Token-2022 token account, which must be harvested before it can be closed
+pub fn withheld_transfer_fees(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map(|amount| u64::from(amount.withheld_amount))
+        .unwrap_or(0))
+}
+
+/// Moves any transfer fees withheld in `account` to its mint, so that the account can be closed
+/// Harvesting is permissionless, no signature from the account's owner is needed
+pub fn harvest_withheld_fees<'info>(
+    token_program: AccountInfo<'info>,
+    mint: AccountInfo<'info>,
+    account: AccountInfo<'info>,
+) -> Result<()> {
+    if withheld_transfer_fees(&account)? == 0 {
+        return Ok(());
+    }
+
+    let harvest_accounts = HarvestWithheldTokensToMint {
+        token_program_id: token_program.clone(),
+        mint,
+    };
+    let cpi_ctx = CpiContext::new(token_program, harvest_accounts);
+    harvest_withheld_tokens_to_mint(cpi_ctx, vec![account])
+}
+
+/// `transfer_checked` that also forwards the extra accounts required by a Token-2022 TransferHook on the mint
+/// `hook_accounts` are the instruction's remaining accounts: the hook program, its validation account and the extra accounts it lists
+/// Mints without a transfer hook ignore them, so the same call serves every mint
+pub fn transfer_checked_with_hook<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        hook_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
+
+/// Deserializes a mint passed as a remaining account, which must be owned by `token_program`
+pub fn load_mint(mint: &AccountInfo, token_program: &Pubkey) -> Result<Mint> {
+    require_keys_eq!(
+        *mint.owner,
+        *token_program,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    Mint::try_deserialize(&mut &mint.try_borrow_data()?[..])
+}
+
+/// Deserializes a token account passed as a remaining account, which must be owned by `token_program`
+pub fn load_token_account(account: &AccountInfo, token_program: &Pubkey) -> Result<TokenAccount> {
+    require_keys_eq!(
+        *account.owner,
+        *token_program,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    TokenAccount::try_deserialize(&mut &account.try_borrow_data()?[..])
+}
+
+/// Empties `vault` into `to` and closes it, sending its rent to `rent_to`
+/// `authority` is the PDA owning the vault, signing with `signer_seeds`; returns the amount released
+/// `hook_accounts` are forwarded to the transfer for mints with a Token-2022 TransferHook
+#[allow(clippy::too_many_arguments)]
+pub fn release_vault<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    rent_to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    hook_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let amount = load_token_account(vault, token_program.key)?.amount;
+
+    if amount > 0 {
+        let accounts = TransferChecked {
+            from: vault.clone(),
+            mint: mint.clone(),
+            to: to.clone(),
+            authority: authority.clone(),
+        };
+        let ctx = CpiContext::new_with_signer(token_program.clone(), accounts, signer_seeds);
+        let decimals = load_mint(mint, token_program.key)?.decimals;
+        transfer_checked_with_hook(ctx, amount, decimals, hook_accounts)?;
+    }
+
+    harvest_withheld_fees(token_program.clone(), mint.clone(), vault.clone())?;
+
+    let accounts = CloseAccount {
+        account: vault.clone(),
+        destination: rent_to.clone(),
+        authority: authority.clone(),
+    };
+    let ctx = CpiContext::new_with_signer(token_program.clone(), accounts, signer_seeds);
+    close_account(ctx)?;
+
+    Ok(amount)
+}
+
+/// Accounts receiving the rent of an escrow and of its vault once closed, following its `RentDestination`
+/// Under `Split` the escrow's rent goes to `maker` and the vault's to `beneficiary`, the taker or the refunded owner
+/// `taker` is `None` on refunds, and `treasury` must be the escrow's treasury when it sends its rent there
+pub fn rent_recipients<'info>(
+    destination: RentDestination,
+    mint_a: &Pubkey,
+    maker: &AccountInfo<'info>,
+    beneficiary: &AccountInfo<'info>,
+    taker: Option<&AccountInfo<'info>>,
+    treasury: Option<&AccountInfo<'info>>,
+) -> Result<(AccountInfo<'info>, AccountInfo<'info>)> {
+    let recipient = match (destination, taker) {
+        (RentDestination::Maker, _) => maker,
+        (RentDestination::Taker, Some(taker)) => taker,
+        (RentDestination::Treasury(key), _) => treasury
+            .filter(|treasury| treasury.key() == key)
+            .ok_or(EscrowError::RentTreasuryMismatch)?,
+        _ => return Ok((maker.clone(), beneficiary.clone())),
+    };
+
+    // Closing a wrapped SOL vault unwraps its balance along with the rent, so it always goes to the beneficiary
+    if is_native_mint(mint_a) {
+        return Ok((recipient.clone(), beneficiary.clone()));
+    }
+    Ok((recipient.clone(), recipient.clone()))
+}
+
+/// Brings the index of `escrow`'s mint pair up to date with it, counting its deposit while it is open and dropping it
+/// once it isn't, or is `closing`
+/// `index` is required if the escrow is indexed, and ignored otherwise
+pub fn reindex(
+    escrow: &mut Escrow,
+    index: Option<&mut Account<PairIndex>>,
+    closing: bool,
+) -> Result<()> {
+    if !escrow.is_indexed() {
+        return Ok(());
+    }
+    let index = index.ok_or(EscrowError::IndexMismatch)?;
+    require!(
+        index.mint_a == escrow.mint_a && index.mint_b == escrow.mint_b,
+        EscrowError::IndexMismatch
+    );
+
+    let deposit = match escrow.has_status(EscrowStatus::Open) && !closing {
+        true => escrow.deposit,
+        false => 0,
+    };
+    index.total_deposit = index
+        .total_deposit
+        .saturating_sub(escrow.indexed_deposit)
+        .checked_add(deposit)
+        .ok_or(EscrowError::MathOverflow)?;
+    if deposit == 0 {
+        index.open_escrows = index.open_escrows.saturating_sub(1);
+    }
+    escrow.indexed_deposit = deposit;
+    Ok(())
+}
+
+/// Removes `escrow` from the order book page listing it, once it is no longer open to takers
+/// `page` is required if the escrow is listed, and ignored otherwise
+pub fn delist(
+    escrow: &AccountLoader<Escrow>,
+    page: Option<&mut Account<OrderBookPage>>,
+) -> Result<()> {
+    let mut state = escrow.load_mut()?;
+    if state.order_book_page().is_none() {
+        return Ok(());
+    }
+    let page = page.ok_or(EscrowError::OrderBookMismatch)?;
+    require!(page.remove(&escrow.key()), EscrowError::OrderBookMismatch);
+    state.order_book_page = 0;
+
+    emit!(ListingEvent {
+        escrow: escrow.key(),
+        page: page.key(),
+        listed: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    Ok(())
+}
+
+/// Leading fields of a Token Metadata `MetadataV1` account, up to its collection
+/// Deserialized as a prefix, so the fields following the collection are ignored
+#[derive(AnchorDeserialize)]
+struct MetadataPrefix {
+    key: u8,
+    _update_authority: Pubkey,
+    mint: Pubkey,
+    _name: String,
+    _symbol: String,
+    _uri: String,
+    _seller_fee_basis_points: u16,
+    _creators: Option<Vec<(Pubkey, bool, u8)>>,
+    _primary_sale_happened: bool,
+    _is_mutable: bool,
+    _edition_nonce: Option<u8>,
+    _token_standard: Option<u8>,
+    collection: Option<(bool, Pubkey)>,
+}
+
+/// The collection `metadata` places `mint` in, only if the collection's authority has verified it
+/// `metadata` must be a Token Metadata account, and the one of `mint`
+pub fn verified_collection(metadata: &AccountInfo, mint: &Pubkey) -> Result<Option<Pubkey>> {
+    require_keys_eq!(
+        *metadata.owner,
+        token_metadata::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    let prefix = MetadataPrefix::deserialize(&mut &metadata.try_borrow_data()?[..])
+        .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+    require!(
+        prefix.key == METADATA_V1_KEY && prefix.mint == *mint,
+        EscrowError::MetadataMismatch
+    );
+
+    Ok(prefix
+        .collection
+        .and_then(|(verified, key)| verified.then_some(key)))
+}
+
+/// Leading fields of a Token Metadata `MasterEditionV2` account
+#[derive(AnchorDeserialize)]
+struct MasterEditionV2 {
+    key: u8,
+    supply: u64,
+    max_supply: Option<u64>,
+}
+
+/// The number of editions printed from `master_edition` and the most it may print, `None` if unlimited
+/// `master_edition` must be a Token Metadata `MasterEditionV2` account
+pub fn master_edition_supply(master_edition: &AccountInfo) -> Result<(u64, Option<u64>)> {
+    require_keys_eq!(
+        *master_edition.owner,
+        token_metadata::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    let edition = MasterEditionV2::deserialize(&mut &master_edition.try_borrow_data()?[..])
+        .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+    require!(
+        edition.key == MASTER_EDITION_V2_KEY,
+        EscrowError::InvalidMasterEdition
+    );
+    Ok((edition.supply, edition.max_supply))
+}
+
+/// A gateway program `GatewayToken` account, the identity pass a gatekeeper issued to a wallet
+#[derive(AnchorDeserialize)]
+struct GatewayToken {
+    _features: u8,
+    _parent_gateway_token: Option<Pubkey>,
+    owner_wallet: Pubkey,
+    _owner_identity: Option<Pubkey>,
+    gatekeeper_network: Pubkey,
+    _issuing_gatekeeper: Pubkey,
+    state: u8,
+    expire_time: Option<i64>,
+}
+
+/// Checks `gateway_token` is an active, unexpired pass `gatekeeper_network` issued to `wallet`
+/// `gateway_token` must be a gateway program account, frozen and revoked passes are rejected like missing ones
+pub fn check_gateway_token(
+    gateway_token: &AccountInfo,
+    wallet: &Pubkey,
+    gatekeeper_network: &Pubkey,
+    now: i64,
+) -> Result<()> {
+    require_keys_eq!(
+        *gateway_token.owner,
+        gateway::ID,
+        ErrorCode::AccountOwnedByWrongProgram
+    );
+    let token = GatewayToken::deserialize(&mut &gateway_token.try_borrow_data()?[..])
+        .map_err(|_| ErrorCode::AccountDidNotDeserialize)?;
+    require!(
+        token.owner_wallet == *wallet
+            && token.gatekeeper_network == *gatekeeper_network
+            && token.state == GATEWAY_TOKEN_ACTIVE
+            && token.expire_time.unwrap_or(i64::MAX) > now,
+        EscrowError::InvalidGatewayToken
+    );
+    Ok(())
+}
+
+/// Transfers a compressed NFT from `leaf_owner` to `new_leaf_owner` with Bubblegum, proving its current leaf with `proof`
+/// `proof_nodes` are the instruction's remaining accounts, the proof's path up to the tree's canopy
+/// `leaf_owner` signs, with `signer_seeds` when it is a PDA, and must also be the leaf's delegate
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_compressed<'info>(
+    bubblegum_program: &AccountInfo<'info>,
+    tree_config: &AccountInfo<'info>,
+    leaf_owner: &AccountInfo<'info>,
+    new_leaf_owner: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    log_wrapper: &AccountInfo<'info>,
+    compression_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    proof: &LeafProof,
+    proof_nodes: &[AccountInfo<'info>],
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let accounts = TransferCpiAccounts {
+        tree_config,
+        leaf_owner: (leaf_owner, true),
+        leaf_delegate: (leaf_owner, false),
+        new_leaf_owner,
+        merkle_tree,
+        log_wrapper,
+        compression_program,
+        system_program,
+    };
+    let args = TransferInstructionArgs {
+        root: proof.root,
+        data_hash: proof.data_hash,
+        creator_hash: proof.creator_hash,
+        nonce: proof.nonce,
+        index: proof.index,
+    };
+    let proof_nodes: Vec<_> = proof_nodes
+        .iter()
+        .map(|node| (node, false, false))
+        .collect();
+
+    TransferCpi::new(bubblegum_program, accounts, args)
+        .invoke_signed_with_remaining_accounts(signer_seeds, &proof_nodes)
+        .map_err(Into::into)
+}
+
+/// Reads the authorities and lockup of a stake account, failing unless it is an initialized account of the stake program
+pub fn stake_meta(stake_account: &AccountInfo) -> Result<Meta> {
+    require_keys_eq!(
+        *stake_account.owner,
+        stake::program::ID,
+        EscrowError::InvalidStakeAccount
+    );
+    let state = StakeStateV2::deserialize(&mut &stake_account.try_borrow_data()?[..])
+        .map_err(|_| EscrowError::InvalidStakeAccount)?;
+    state
+        .meta()
+        .ok_or_else(|| EscrowError::InvalidStakeAccount.into())
+}
+
+/// Hands both the staker and withdrawer authorities of `stake_account` from `authority` to `new_authority`
+/// `authority` signs, with `signer_seeds` when it is a PDA
+pub fn authorize_stake<'info>(
+    stake_program: &AccountInfo<'info>,
+    stake_account: &AccountInfo<'info>,
+    clock: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    new_authority: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    for stake_authorize in [StakeAuthorize::Staker, StakeAuthorize::Withdrawer] {
+        let instruction = stake::instruction::authorize(
+            stake_account.key,
+            authority.key,
+            new_authority,
+            stake_authorize,
+            None,
+        );
+        invoke_signed(
+            &instruction,
+            &[
+                stake_account.clone(),
+                clock.clone(),
+                authority.clone(),
+                stake_program.clone(),
+            ],
+            signer_seeds,
+        )?;
+    }
+    Ok(())
+}
@@ -1,3 +1,6 @@
+// The `cpi` wrappers `#[program]` generates take every argument of their instruction, like `make`'s
+#![allow(clippy::too_many_arguments)]
+
 use anchor_lang::prelude::*;
 
 declare_id!("F4AzBSfhz1ASmHzBX6ERbQJCK14GCEBzS6T6mv4CzXS1");
@@ -6,31 +9,1072 @@ pub mod state;
 pub use state::*;
 pub mod contexts;
 pub use contexts::*;
+pub mod errors;
+pub use errors::*;
+pub mod events;
+pub use events::*;
+pub mod helpers;
+pub mod pricing;
 
 #[program]
 pub mod escrow {
     use super::*;
 
+    /// Creates the program's global config, holding the admin and the protocol fee
+    /// Only callable once, by the program's upgrade authority
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16) -> Result<()> {
+        ctx.accounts.initialize_config(fee_bps, &ctx.bumps)
+    }
+
+    /// Pauses or resumes new escrows and fills, only callable by the config's admin
+    /// Refunds stay available while paused, so makers can always get their deposits back
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    /// Sets the share of the protocol fee diverted to the referrer of a fill, only callable by the config's admin
+    pub fn set_referral_bps(ctx: Context<SetReferralBps>, referral_bps: u16) -> Result<()> {
+        ctx.accounts.set_referral_bps(referral_bps)
+    }
+
+    /// Sets the NFT collection whose holders pay the discounted `member_fee_bps` on fills, only callable by the config's admin
+    pub fn set_membership(
+        ctx: Context<SetMembership>,
+        collection: Option<Pubkey>,
+        member_fee_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_membership(collection, member_fee_bps)
+    }
+
+    /// Adds `mint` to the config's allowlist or denylist, only callable by the config's admin
+    /// Once the allowlist has an entry, new escrows may only use allowed mints
+    pub fn add_mint(ctx: Context<ManageMints>, mint: Pubkey, list: MintList) -> Result<()> {
+        ctx.accounts.add_mint(mint, list)
+    }
+
+    /// Removes `mint` from the config's allowlist or denylist, only callable by the config's admin
+    pub fn remove_mint(ctx: Context<ManageMints>, mint: Pubkey, list: MintList) -> Result<()> {
+        ctx.accounts.remove_mint(mint, list)
+    }
+
+    /// Sweeps the protocol fees collected in a mint from the config's fee vault to a treasury token account
+    /// Only callable by the config's admin, the fee vaults being owned by the config PDA
+    pub fn withdraw_fees<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawFees<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_fees(ctx.remaining_accounts)
+    }
+
+    /// Sets the currency `take` charges the protocol fee in: mint_b, mint_a, or a flat lamport fee, only callable by the config's admin
+    /// Lets each deployment collect its fees in the asset it wants
+    pub fn set_fee_currency(ctx: Context<SetFeeCurrency>, fee_currency: FeeCurrency) -> Result<()> {
+        ctx.accounts.set_fee_currency(fee_currency)
+    }
+
+    /// Sets the fee tiers charged to takers by their cumulative volume in the mint they pay, only callable by the config's admin
+    /// Takers passing their trader stats to `take` are charged the rate of the highest tier they reached
+    pub fn set_fee_tiers(ctx: Context<SetFeeTiers>, fee_tiers: Vec<FeeTier>) -> Result<()> {
+        ctx.accounts.set_fee_tiers(fee_tiers)
+    }
+
+    /// Sets the share of the deposit `refund` pays the taker holding a reservation of the escrow, only callable by the config's admin
+    pub fn set_cancel_penalty(
+        ctx: Context<SetCancelPenalty>,
+        cancel_penalty_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_cancel_penalty(cancel_penalty_bps)
+    }
+
+    /// Charges `fee_bps` on the fills of `subject`, an escrow or a maker, instead of the global fee
+    /// Only callable by the config's admin, for pricing negotiated with institutions without redeploying
+    pub fn set_fee_override(
+        ctx: Context<SetFeeOverride>,
+        subject: Pubkey,
+        fee_bps: u16,
+    ) -> Result<()> {
+        ctx.accounts.set_fee_override(subject, fee_bps, &ctx.bumps)
+    }
+
+    /// Removes a fee override, returning its rent to the config's admin
+    pub fn remove_fee_override(ctx: Context<RemoveFeeOverride>) -> Result<()> {
+        ctx.accounts.remove_fee_override()
+    }
+
+    /// Sets which mints with Token-2022 extensions that could keep an escrow from settling new escrows may use
+    /// Only callable by the config's admin, e.g. to permit NonTransferable mints for burn-style flows,
+    /// or to let mints with a permanent delegate through, flagging the escrows made with them
+    pub fn set_mint_policy(
+        ctx: Context<SetMintPolicy>,
+        allow_non_transferable: bool,
+        permanent_delegate_policy: PermanentDelegatePolicy,
+    ) -> Result<()> {
+        ctx.accounts
+            .set_mint_policy(allow_non_transferable, permanent_delegate_policy)
+    }
+
+    /// Creates the program's on-chain event log, which `make`, `take` and `refund` record into when passed it
+    /// Only callable by the config's admin, for deployments whose indexers can't rely on the program's logs alone
+    pub fn initialize_event_log(ctx: Context<InitializeEventLog>) -> Result<()> {
+        ctx.accounts.initialize_event_log()
+    }
+
+    /// Publishes a template of escrow terms makers can adopt in `make`, only callable by the config's admin
+    /// Its escrows pay `fee_bps` on fills, expire within `max_duration` seconds (0 for no limit), only fill for takers on the
+    /// allowlist with `allowlist_root` if given, and are put under `arbiter` if given
+    pub fn create_template(
+        ctx: Context<CreateTemplate>,
+        seed: u64,
+        fee_bps: u16,
+        max_duration: i64,
+        allowlist_root: Option<[u8; 32]>,
+        arbiter: Option<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.create(
+            seed,
+            fee_bps,
+            max_duration,
+            allowlist_root,
+            arbiter,
+            &ctx.bumps,
+        )
+    }
+
+    /// Records that the taker is on a template's allowlist, proven by the merkle `proof` of their key
+    /// The pass is then passed to `take` to fill the template's escrows
+    pub fn join_allowlist(ctx: Context<JoinAllowlist>, proof: Vec<[u8; 32]>) -> Result<()> {
+        ctx.accounts.join(proof, &ctx.bumps)
+    }
+
+    /// Creates the maker's profile, whose offer counter then seeds the escrows they make with it
+    pub fn create_profile(ctx: Context<CreateProfile>) -> Result<()> {
+        ctx.accounts.create_profile(&ctx.bumps)
+    }
+
     /// Initiates the process of making an escrow
-    /// Takes a seed, deposit amount, and receive amount
+    /// Takes a seed (the maker profile's offer counter, when one is passed), an optional 32-byte seed id the escrow's address
+    /// is derived from instead, e.g. a hash of an integrator's order id, deposit amount, receive amount, an optional expiry (0 for none), an optional designated taker,
+    /// an optional arbiter for disputes, an optional SHA-256 hashlock (with `expiry` as its timeout),
+    /// an optional vesting schedule for the taker, an optional schedule of recurring installments, whether to keep the escrow as a receipt once it is settled
+    /// where the rent of the escrow and its vault goes once `take` or a refund closes them, an optional label for UIs (zeroed for none)
+    /// an optional token gate, a holding the taker must prove to fill the escrow, and an optional gatekeeper network
+    /// whose identity pass (e.g. a Civic Pass gateway token) the taker must hold to fill it, and a category (0 for none) clients can filter escrows by
+    /// A template passed with the accounts imposes its arbiter and expiry policy, and its fee and allowlist on fills
     /// Designed to deposit funds and set up the escrow conditions
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64) -> Result<()> {
-        ctx.accounts.deposit(deposit)?;
-        ctx.accounts.save_escrow(seed, receive, &ctx.bumps)
+    #[allow(clippy::too_many_arguments)]
+    pub fn make<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        seed_id: Option<[u8; 32]>,
+        deposit: u64,
+        receive: u64,
+        expiry: i64,
+        taker: Option<Pubkey>,
+        arbiter: Option<Pubkey>,
+        hashlock: Option<[u8; 32]>,
+        vesting: Option<Vesting>,
+        schedule: Option<Schedule>,
+        keep_receipt: bool,
+        rent_destination: RentDestination,
+        label: [u8; 32],
+        gate: Option<Gate>,
+        gatekeeper_network: Option<Pubkey>,
+        category: u16,
+    ) -> Result<()> {
+        ctx.accounts.validate_terms(deposit, receive)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        ctx.accounts.save_escrow(
+            seed,
+            seed_id,
+            deposit,
+            receive,
+            expiry,
+            taker,
+            arbiter,
+            hashlock,
+            vesting,
+            schedule,
+            keep_receipt,
+            rent_destination,
+            label,
+            gate,
+            gatekeeper_network,
+            category,
+            &ctx.bumps,
+        )
+    }
+
+    /// Opens an escrow paid with any single NFT verified in the collection whose mint is passed as `mint_b`
+    /// Taken with `take_nft`, trait-agnostic offers don't need an escrow per NFT of the collection
+    pub fn make_collection_offer<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        seed_id: Option<[u8; 32]>,
+        deposit: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        // Collection offers are filled through `take_nft`, which doesn't enforce template terms
+        require!(
+            ctx.accounts.template.is_none(),
+            EscrowError::TemplatedEscrow
+        );
+        ctx.accounts.validate_terms(deposit, 1)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        ctx.accounts.save_escrow(
+            seed,
+            seed_id,
+            deposit,
+            1,
+            expiry,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+            RentDestination::Split,
+            [0; 32],
+            None,
+            None,
+            0,
+            &ctx.bumps,
+        )?;
+        // The discriminator is only written on exit, so the escrow is still being initialized here
+        ctx.accounts.escrow.load_init()?.collection_offer = 1;
+        Ok(())
+    }
+
+    /// Opens a private escrow, recording only `terms_hash`, the hash of its mint_b, receive and a salt, see `Escrow::terms_hash`
+    /// The `mint_b` account passed is a placeholder, e.g. a widely held mint, as the real one is only checked once revealed
+    /// Taken with `take_private`, where the taker supplies the terms, so they stay confidential until execution
+    pub fn make_private<'info>(
+        ctx: Context<'_, '_, '_, 'info, Make<'info>>,
+        seed: u64,
+        seed_id: Option<[u8; 32]>,
+        deposit: u64,
+        expiry: i64,
+        taker: Option<Pubkey>,
+        terms_hash: [u8; 32],
+    ) -> Result<()> {
+        require!(terms_hash != [0; 32], EscrowError::TermsHashMismatch);
+        // Private escrows have no public mint_b to be listed or indexed under
+        require!(
+            ctx.accounts.order_book_page.is_none(),
+            EscrowError::OrderBookMismatch
+        );
+        require!(
+            ctx.accounts.pair_index.is_none(),
+            EscrowError::IndexMismatch
+        );
+        ctx.accounts.validate_terms(deposit, 1)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)?;
+        ctx.accounts.save_escrow(
+            seed,
+            seed_id,
+            deposit,
+            1,
+            expiry,
+            taker,
+            None,
+            None,
+            None,
+            None,
+            false,
+            RentDestination::Split,
+            [0; 32],
+            None,
+            None,
+            0,
+            &ctx.bumps,
+        )?;
+        // The discriminator is only written on exit, so the escrow is still being initialized here
+        let mut escrow = ctx.accounts.escrow.load_init()?;
+        escrow.mint_b = Pubkey::default();
+        escrow.receive = 0;
+        escrow.price_b = 0;
+        escrow.terms_hash = terms_hash;
+        Ok(())
+    }
+
+    /// Opens several escrows between the same mints in one instruction, amortizing overhead for market makers
+    /// Each escrow is passed as remaining accounts `[escrow, vault]`, with `offers` in the same order
+    pub fn batch_make<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchMake<'info>>,
+        offers: Vec<BatchOffer>,
+    ) -> Result<()> {
+        ctx.accounts.make_all(&offers, ctx.remaining_accounts)
+    }
+
+    /// Adds a page to the order book of a mint pair, creating the order book with its first page, callable by anyone
+    /// `make` lists escrows in a page with room when passed one, so clients discover the pair's offers by walking its pages
+    pub fn add_order_book_page(ctx: Context<AddOrderBookPage>) -> Result<()> {
+        ctx.accounts.add_page(&ctx.bumps)
+    }
+
+    /// Creates the index of a mint pair, counting the open escrows made with it and their total deposit, callable by anyone
+    /// Lets aggregators tell whether the pair has liquidity by reading a single account
+    pub fn create_pair_index(ctx: Context<CreatePairIndex>) -> Result<()> {
+        ctx.accounts.create_pair_index(&ctx.bumps)
+    }
+
+    /// Removes an escrow no longer open from the order book page listing it, callable by anyone
+    /// `take`, `batch_take`, `refund` and `execute_auto_refund` delist the escrows they settle, this cleans up after every other way an escrow is settled
+    pub fn prune_order_book(ctx: Context<PruneOrderBook>) -> Result<()> {
+        ctx.accounts.prune()
+    }
+
+    /// Amends the terms of an open escrow without refunding and re-making it
+    /// Only callable by the maker, emits an `UpdateEvent` so takers can detect repricing
+    pub fn update_escrow(ctx: Context<Update>, receive: u64, expiry: i64) -> Result<()> {
+        ctx.accounts.update_terms(receive, expiry)
+    }
+
+    /// Routes the proceeds of an open escrow to `payout`, any token account of its mint_b, e.g. of a treasury's custody wallet
+    /// Fills are then only accepted paying into it, until the owner sets another or goes back to their own without one
+    pub fn set_payout(ctx: Context<SetPayout>) -> Result<()> {
+        ctx.accounts.set_payout()
+    }
+
+    /// Accepts up to `PaymentOptions::MAX_LEGS` other mints as payment for an open escrow, each asking its own `receive`
+    /// for the escrow's whole current deposit, e.g. 100 USDC or 99 USDT; takers pay in one by passing it as mint_b
+    pub fn set_payment_options(
+        ctx: Context<SetPaymentOptions>,
+        legs: Vec<PaymentLeg>,
+    ) -> Result<()> {
+        ctx.accounts.set_payment_options(legs, &ctx.bumps)
+    }
+
+    /// Stops accepting an escrow's payment options, returning their rent to the owner who set them
+    pub fn close_payment_options(ctx: Context<ClosePaymentOptions>) -> Result<()> {
+        ctx.accounts.close_payment_options()
+    }
+
+    /// Adds `amount` of mint_a to an open escrow's vault, scaling `receive` so the price stays the same
+    /// Combined with partial fills, lets the maker keep a standing offer without re-making it
+    pub fn top_up<'info>(ctx: Context<'_, '_, '_, 'info, TopUp<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.top_up(amount, ctx.remaining_accounts)
+    }
+
+    /// Withdraws `amount` of mint_a from an open escrow back to the maker, scaling `receive` down at the same price
+    /// The escrow stays open with the rest of the deposit, a full withdrawal goes through `refund`
+    pub fn reduce_deposit<'info>(
+        ctx: Context<'_, '_, '_, 'info, ReduceDeposit<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.reduce_deposit(amount, ctx.remaining_accounts)
+    }
+
+    /// Moves `amount` of an open escrow's deposit into a new escrow made with `seed`, on the same terms
+    /// `receive` is split in proportion, so the new escrow can then be repriced on its own with `update`
+    pub fn split_escrow<'info>(
+        ctx: Context<'_, '_, '_, 'info, SplitEscrow<'info>>,
+        seed: u64,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .split(seed, amount, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    /// Returns anything donated to an open escrow's vault on top of its recorded deposit to the owner
+    /// Fills are settled against the recorded deposit, so such a surplus can't change the escrow's terms
+    pub fn sweep_surplus<'info>(
+        ctx: Context<'_, '_, '_, 'info, SweepSurplus<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.sweep(ctx.remaining_accounts)
+    }
+
+    /// Folds the open escrow `merged` into `escrow`, moving its vault over and closing it to recover its rent
+    /// Both escrows must trade the same mints at the same price and on the same terms, see `Escrow::has_same_terms`
+    pub fn merge_escrows<'info>(
+        ctx: Context<'_, '_, '_, 'info, MergeEscrows<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.merge(ctx.remaining_accounts)
+    }
+
+    /// Lets `authority`, e.g. an ops hot wallet or an automation program, refund the escrows whose position the owner holds
+    /// Refunds still go to the owner, so a cold-wallet owner can cancel without bringing their key online
+    pub fn set_cancel_authority(ctx: Context<SetCancelAuthority>, authority: Pubkey) -> Result<()> {
+        ctx.accounts.set_cancel_authority(authority, &ctx.bumps)
+    }
+
+    /// Revokes the owner's cancel authority, returning the delegation's rent to them
+    pub fn revoke_cancel_authority(ctx: Context<RevokeCancelAuthority>) -> Result<()> {
+        ctx.accounts.revoke_cancel_authority()
     }
 
     /// Refunds the assets deposited in the escrow and closes the escrow account
     /// This function is callble only under conditions where the escrow agreement is not met,
     /// allowing the maker to reclaim their deposited assets- for example, if the taker does
-    /// not fulfill their part of the agreement
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    /// not fulfill their part of the agreement or the escrow expired before being taken
+    /// Signed by the owner, or by the cancel authority they delegated it to with `set_cancel_authority`
+    pub fn refund<'info>(ctx: Context<'_, '_, '_, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.check_refundable()?;
+        ctx.accounts
+            .refund_and_close_vault(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Refunds an expired escrow to its maker and closes it, callable by anyone once the deadline has passed
+    /// Lets keepers clean up abandoned escrows without the maker having to sign
+    pub fn refund_expired<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundExpired<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_expired(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Schedules the refund of an open escrow from `execute_after`, its expiry when 0, executable by any keeper once due
+    /// `tip` lamports are set aside for the keeper, so expired escrows unwind without anyone watching them
+    pub fn schedule_auto_refund(
+        ctx: Context<ScheduleAutoRefund>,
+        execute_after: i64,
+        tip: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .schedule_auto_refund(execute_after, tip, &ctx.bumps)
+    }
+
+    /// Cancels an escrow's scheduled refund, returning its tip and rent to the owner who scheduled it
+    pub fn cancel_auto_refund(ctx: Context<CancelAutoRefund>) -> Result<()> {
+        ctx.accounts.cancel_auto_refund()
+    }
+
+    /// Refunds an escrow whose scheduled refund is due to its owner and closes it, callable by any keeper
+    /// The keeper is paid the schedule's tip, its rent going back to the owner
+    pub fn execute_auto_refund<'info>(
+        ctx: Context<'_, '_, '_, 'info, ExecuteAutoRefund<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.execute_auto_refund(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Prices a full fill of an escrow as `take` would settle it now, returned through return data
+    /// Read-only, meant to be simulated by UIs before sending `take`
+    pub fn quote(ctx: Context<GetQuote>) -> Result<Quote> {
+        ctx.accounts.quote()
+    }
+
+    /// Creates the token accounts `take` pays into, the taker's for mint_a, the owner's for mint_b and the fee vault
+    /// Idempotent, and paid for by `payer` so the accounts can be sponsored ahead of the fill
+    pub fn prepare_take(ctx: Context<PrepareTake>) -> Result<()> {
+        ctx.accounts.prepare()
     }
 
     /// Finalizes the escrow by transfering assets and closing the vault
-    /// Only callable if the escrow conditions are fully met
-    pub fn take(ctx: Context<Take>) -> Result<()> {
+    /// Only callable if the escrow conditions are fully met and still match the `expected_deposit` and `expected_receive` the taker saw
+    pub fn take<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Same as `take`, but keeps the escrow as a receipt of the trade rather than closing it, for accounting and tax reporting
+    /// The receipt records the taker and when it was settled next to its amounts, until the maker closes it with `close_receipt`
+    pub fn take_keep_receipt<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.keep_receipt()?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Fills the whole private escrow, revealing its terms: the mint_b passed, `receive` and the `salt` they were hashed with
+    /// Fails unless they match the hash `make_private` recorded, or the deposit no longer matches `expected_deposit`
+    pub fn take_private<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        receive: u64,
+        salt: [u8; 32],
+        expected_deposit: u64,
+    ) -> Result<()> {
+        ctx.accounts.reveal_terms(receive, &salt)?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts.check_terms(expected_deposit, receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Reserves an open escrow for the taker for `Escrow::COMMIT_WINDOW` seconds, so bots can't front-run their take
+    /// `hash` is `Escrow::commitment` of the escrow, the taker and a secret salt, revealed with `reveal_take`
+    pub fn commit_take(ctx: Context<CommitTake>, hash: [u8; 32]) -> Result<()> {
+        ctx.accounts.commit(hash)
+    }
+
+    /// Holds an open escrow exclusively for the taker for `slots` slots, at most `Reservation::MAX_SLOTS`, locking a `bond` in lamports
+    /// Filling it with `take` while passing the reservation returns the bond, abandoning it forfeits the bond to the escrow's owner
+    pub fn reserve(ctx: Context<Reserve>, slots: u64, bond: u64) -> Result<()> {
+        ctx.accounts
+            .reserve(slots, bond, Reservation::MAX_SLOTS, &ctx.bumps)
+    }
+
+    /// First half of a reserve-then-settle fill, holding an open escrow for the taker for up to `Reservation::MAX_FILL_SLOTS` slots
+    /// Gives takers time to source mint_b without being raced, the bond grows with the window, see `Reservation::min_bond`
+    pub fn reserve_fill(ctx: Context<Reserve>, slots: u64, bond: u64) -> Result<()> {
+        ctx.accounts
+            .reserve(slots, bond, Reservation::MAX_FILL_SLOTS, &ctx.bumps)
+    }
+
+    /// Second half of a reserve-then-settle fill, filling the whole escrow like `take` through the taker's reservation
+    /// The maker can't `update` the terms, `top_up` or `reduce_deposit` while the reservation holds the escrow
+    pub fn settle_fill<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.check_reservation()?;
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Closes a lapsed reservation, paying its bond to the escrow's owner if the escrow is still open, callable by anyone
+    /// The bond goes back to the taker if the escrow was refunded, expired or settled meanwhile, the rent always does
+    pub fn forfeit_reservation(ctx: Context<ForfeitReservation>) -> Result<()> {
+        ctx.accounts.forfeit()
+    }
+
+    /// Takes the whole escrow like `take`, revealing the `salt` of the taker's pending commitment
+    /// Only the committed taker can take the escrow until the commitment's deadline
+    pub fn reveal_take<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        salt: [u8; 32],
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(Some(&salt))?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Takes a hashlocked escrow by revealing the preimage of its hashlock before the timeout
+    /// The preimage is emitted in a `RevealEvent`, letting the maker claim the other leg of a cross-chain swap
+    /// Fails like `take` if the escrow's terms no longer match `expected_deposit` and `expected_receive`
+    pub fn take_hashlocked<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        preimage: Vec<u8>,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts
+            .escrow
+            .load()?
+            .check_preimage(Some(&preimage))?;
+        ctx.accounts.fill(ctx.remaining_accounts)?;
+        emit!(RevealEvent {
+            escrow: ctx.accounts.escrow.key(),
+            preimage,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        ctx.accounts.close_escrow()
+    }
+
+    /// Fills several escrows between the same mints in full, from one or several makers, in one instruction
+    /// Each escrow is passed as remaining accounts `[escrow, maker, owner, vault, owner_ata_b, owner_ata_a]`
+    /// A surplus in a vault goes back to its owner if `owner_ata_a` is passed, and to the taker if it is the program's ID, as on `take`
+    pub fn batch_take<'info>(ctx: Context<'_, '_, 'info, 'info, BatchTake<'info>>) -> Result<()> {
+        ctx.accounts.take_all(ctx.remaining_accounts)
+    }
+
+    /// Fills only `amount` of the deposited mint_a, paying the proportional share of `receive`
+    /// The escrow stays open with the remaining amounts so other takers can fill the rest
+    /// `expected_deposit` and `expected_receive` are the escrow's whole terms before the fill, as the taker read them
+    pub fn take_partial<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        amount: u64,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts.fill_partial(amount, ctx.remaining_accounts)
+    }
+
+    /// Fills the escrow for an exact amount of mint_a bought (`ExactOut`) or of mint_b spent (`ExactIn`), computing the other leg at its price
+    /// `limit` is the most mint_b paid or the least mint_a bought, filling the whole deposit completes the escrow
+    /// Also fails if the escrow was changed since the taker read its `expected_deposit` and `expected_receive`
+    pub fn take_exact<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        amount: FillAmount,
+        limit: u64,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.escrow.load()?.check_preimage(None)?;
+        ctx.accounts
+            .fill_exact(amount, limit, ctx.remaining_accounts)
+    }
+
+    /// Takes the next installment of a scheduled escrow, at most `Schedule::installment` of the deposit at its price
+    /// Installments are due every `Schedule::interval` seconds, the last one completes the escrow
+    /// `expected_deposit` and `expected_receive` guard the taker against the owner changing the escrow before it lands
+    pub fn take_installment<'info>(
+        ctx: Context<'_, '_, '_, 'info, Take<'info>>,
+        expected_deposit: u64,
+        expected_receive: u64,
+    ) -> Result<()> {
+        ctx.accounts.select_payment()?;
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.check_commitment(None)?;
+        ctx.accounts
+            .check_terms(expected_deposit, expected_receive)?;
+        ctx.accounts.fill_installment(ctx.remaining_accounts)
+    }
+
+    /// Takes a collection offer by paying with an NFT verified in its collection, releasing the whole vault to the taker
+    pub fn take_nft<'info>(ctx: Context<'_, '_, '_, 'info, TakeNft<'info>>) -> Result<()> {
+        ctx.accounts.check_nft()?;
+        ctx.accounts.fill(ctx.remaining_accounts)
+    }
+
+    /// Opens a basket escrow, depositing several mints at once for a single `receive` of mint_b
+    /// Each leg is passed as remaining accounts `[mint, maker_ata, vault]`, with `amounts` in the same order
+    pub fn make_basket<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeBasket<'info>>,
+        seed: u64,
+        amounts: Vec<u64>,
+        receive: u64,
+    ) -> Result<()> {
+        let legs = ctx
+            .accounts
+            .deposit_legs(&amounts, ctx.remaining_accounts)?;
+        ctx.accounts.save_basket(seed, legs, receive, &ctx.bumps)
+    }
+
+    /// Pays the basket's `receive` to the maker and releases every leg to the taker, closing the basket
+    /// Each leg is passed as remaining accounts `[mint, vault, taker_ata]`, in the basket's order
+    pub fn take_basket<'info>(ctx: Context<'_, '_, '_, 'info, TakeBasket<'info>>) -> Result<()> {
+        ctx.accounts.deposit()?;
+        ctx.accounts.withdraw_legs(ctx.remaining_accounts)
+    }
+
+    /// Returns every leg of a basket to its maker and closes the basket
+    /// Each leg is passed as remaining accounts `[mint, vault, maker_ata]`, in the basket's order
+    pub fn refund_basket<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundBasket<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.refund_legs(ctx.remaining_accounts)
+    }
+
+    /// Escrows the compressed NFT `proof` points to for `receive` of mint_b, the escrow PDA becoming its leaf owner
+    /// The proof nodes of the NFT's leaf are passed as remaining accounts
+    pub fn make_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeCompressed<'info>>,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+        proof: LeafProof,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_escrow(seed, receive, expiry, &proof, &ctx.bumps)?;
+        ctx.accounts.deposit(&proof, ctx.remaining_accounts)
+    }
+
+    /// Pays `receive` of mint_b to the maker and transfers the escrowed compressed NFT to the taker, closing the escrow
+    /// `proof` must be the NFT's current leaf proof, its nodes passed as remaining accounts
+    pub fn take_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, TakeCompressed<'info>>,
+        proof: LeafProof,
+    ) -> Result<()> {
+        ctx.accounts.check_fillable(&proof)?;
         ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+        ctx.accounts.withdraw(&proof, ctx.remaining_accounts)
+    }
+
+    /// Returns the escrowed compressed NFT to its maker and closes the escrow
+    /// `proof` must be the NFT's current leaf proof, its nodes passed as remaining accounts
+    pub fn refund_compressed<'info>(
+        ctx: Context<'_, '_, '_, 'info, RefundCompressed<'info>>,
+        proof: LeafProof,
+    ) -> Result<()> {
+        ctx.accounts.refund(&proof, ctx.remaining_accounts)
+    }
+
+    /// Escrows a native stake account for `receive` of mint_b, the escrow PDA becoming its staker and withdrawer
+    /// The stake stays delegated while escrowed, the maker must hold both authorities and the account be out of lockup
+    pub fn make_stake(ctx: Context<MakeStake>, seed: u64, receive: u64, expiry: i64) -> Result<()> {
+        ctx.accounts
+            .save_escrow(seed, receive, expiry, &ctx.bumps)?;
+        ctx.accounts.deposit()
+    }
+
+    /// Pays `receive` of mint_b to the maker and hands the escrowed stake account's authorities to the taker, closing the escrow
+    pub fn take_stake<'info>(ctx: Context<'_, '_, '_, 'info, TakeStake<'info>>) -> Result<()> {
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.deposit()?;
+        ctx.accounts.withdraw()
+    }
+
+    /// Returns the escrowed stake account's authorities to its maker and closes the escrow
+    pub fn refund_stake(ctx: Context<RefundStake>) -> Result<()> {
+        ctx.accounts.refund()
+    }
+
+    /// Escrows the token of a master edition to sell prints of it for `receive` of mint_b each
+    /// The escrow stays open between fills, until the master edition's max supply is printed or it is refunded
+    pub fn make_edition(
+        ctx: Context<MakeEdition>,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_escrow(seed, receive, expiry, &ctx.bumps)?;
+        ctx.accounts.deposit()
+    }
+
+    /// Pays `receive` of mint_b to the maker and prints the next edition of the escrowed master edition to the taker
+    pub fn take_edition<'info>(ctx: Context<'_, '_, '_, 'info, TakeEdition<'info>>) -> Result<()> {
+        let edition = ctx.accounts.check_fillable()?;
+        ctx.accounts.deposit()?;
+        ctx.accounts.print(edition)
+    }
+
+    /// Returns the escrowed master edition to its maker and closes the escrow
+    pub fn refund_edition(ctx: Context<RefundEdition>) -> Result<()> {
+        ctx.accounts.refund()
+    }
+
+    /// Proposes a two-sided swap with `taker`, locking `amount_a` of mint_a in its vault
+    /// The swap only settles once the taker has locked `amount_b` of mint_b with `fund_swap`
+    pub fn propose_swap<'info>(
+        ctx: Context<'_, '_, '_, 'info, ProposeSwap<'info>>,
+        seed: u64,
+        amount_a: u64,
+        amount_b: u64,
+        taker: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_swap(seed, amount_a, amount_b, taker, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    /// Locks the taker's side of a swap, after which either party can settle it
+    pub fn fund_swap<'info>(ctx: Context<'_, '_, '_, 'info, FundSwap<'info>>) -> Result<()> {
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    /// Atomically releases each side of a fully funded swap to the other party and closes it
+    pub fn settle_swap<'info>(ctx: Context<'_, '_, '_, 'info, SettleSwap<'info>>) -> Result<()> {
+        ctx.accounts.settle(ctx.remaining_accounts)
+    }
+
+    /// Lets either party back out of a swap before settlement, returning each side to whoever locked it
+    pub fn cancel_swap<'info>(ctx: Context<'_, '_, '_, 'info, CancelSwap<'info>>) -> Result<()> {
+        ctx.accounts.cancel(ctx.remaining_accounts)
+    }
+
+    /// Opens an English auction of `deposit` mint_a, taking bids in mint_b of at least `reserve` until `end`
+    pub fn make_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeAuction<'info>>,
+        seed: u64,
+        deposit: u64,
+        reserve: u64,
+        end: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_auction(seed, deposit, reserve, end, 0, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    /// Opens a sealed-bid auction of `deposit` mint_a, taking bid commitments until `end` and their reveals until `reveal_end`
+    /// The highest revealed bid of at least `reserve` wins once the reveal window closes
+    pub fn make_sealed_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeAuction<'info>>,
+        seed: u64,
+        deposit: u64,
+        reserve: u64,
+        end: i64,
+        reveal_end: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_auction(seed, deposit, reserve, end, reveal_end, &ctx.bumps)?;
+        ctx.accounts.deposit(ctx.remaining_accounts)
+    }
+
+    /// Chains an escrow made under the same maker with `seed` to the open escrow, declaring the `receive` of `chained_mint_b` it asks
+    /// for what the escrow asks, every fill of the escrow then deposits its proceeds into the chained escrow and opens it
+    /// until the chained escrow is refunded, filled or closed, which unchains the escrow at its next fill
+    pub fn make_chained(
+        ctx: Context<MakeChained>,
+        seed: u64,
+        receive: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        ctx.accounts.make_chained(seed, receive, expiry, &ctx.bumps)
+    }
+
+    /// Outbids the current highest bidder of an auction, refunding their bid in the same instruction
+    pub fn bid<'info>(ctx: Context<'_, '_, '_, 'info, Bid<'info>>, amount: u64) -> Result<()> {
+        ctx.accounts.bid(amount, ctx.remaining_accounts)
+    }
+
+    /// Commits a sealed bid on a sealed-bid auction, locking `bond` lamports until it is revealed
+    /// `hash` commits to the amount bid with a secret salt, see `Auction::bid_commitment`
+    pub fn commit_bid(ctx: Context<CommitBid>, hash: [u8; 32], bond: u64) -> Result<()> {
+        ctx.accounts.commit_bid(hash, bond, &ctx.bumps)
+    }
+
+    /// Reveals a sealed bid once bidding has ended, returning its bond and outbidding the highest bidder if it beats them
+    pub fn reveal_bid<'info>(
+        ctx: Context<'_, '_, '_, 'info, Bid<'info>>,
+        amount: u64,
+        salt: [u8; 32],
+    ) -> Result<()> {
+        ctx.accounts.reveal(amount, salt, ctx.remaining_accounts)
+    }
+
+    /// Forfeits the bond of a sealed bid left unrevealed to the auction's maker once the reveal window has closed
+    /// Permissionless, the bid's rent is returned to the bidder
+    pub fn forfeit_bid(ctx: Context<ForfeitBid>) -> Result<()> {
+        ctx.accounts.forfeit()
+    }
+
+    /// Settles an auction once bidding has ended, releasing the deposit to the winner and the bid to the maker
+    pub fn settle_auction<'info>(
+        ctx: Context<'_, '_, '_, 'info, SettleAuction<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.settle(ctx.remaining_accounts)
+    }
+
+    /// Funds a contract with `contractor` paid in stages, depositing the sum of the `milestones` upfront
+    /// Each milestone is released once the maker approves it with `approve_milestone` and the contractor claims it
+    pub fn make_milestones<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeMilestones<'info>>,
+        seed: u64,
+        milestones: Vec<MilestoneTerms>,
+    ) -> Result<()> {
+        let total = ctx.accounts.save_milestones(seed, milestones, &ctx.bumps)?;
+        ctx.accounts.deposit(total, ctx.remaining_accounts)
+    }
+
+    /// Approves the milestone at `index` once delivered, only callable by the maker
+    pub fn approve_milestone(ctx: Context<ApproveMilestone>, index: u8) -> Result<()> {
+        ctx.accounts.approve(index)
+    }
+
+    /// Pays an approved milestone to the contractor, closing the milestone escrow once every milestone is claimed
+    pub fn claim_milestone<'info>(
+        ctx: Context<'_, '_, '_, 'info, ClaimMilestone<'info>>,
+        index: u8,
+    ) -> Result<()> {
+        ctx.accounts.claim(index, ctx.remaining_accounts)
+    }
+
+    /// Opens a loan, locking `collateral` of mint_a to borrow `principal` of mint_b from any lender
+    /// The maker must pay back `repayment` within `duration` seconds of the loan being taken, or the lender can claim the collateral
+    pub fn make_loan<'info>(
+        ctx: Context<'_, '_, '_, 'info, MakeLoan<'info>>,
+        seed: u64,
+        collateral: u64,
+        principal: u64,
+        repayment: u64,
+        duration: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .save_loan(seed, collateral, principal, repayment, duration, &ctx.bumps)?;
+        ctx.accounts.deposit(collateral, ctx.remaining_accounts)
+    }
+
+    /// Lends the principal of an open loan to its maker, starting the repayment period
+    pub fn take_loan<'info>(ctx: Context<'_, '_, '_, 'info, TakeLoan<'info>>) -> Result<()> {
+        ctx.accounts.lend(ctx.remaining_accounts)
+    }
+
+    /// Pays back a taken loan to the lender, returning the collateral to the maker and closing the loan
+    pub fn repay<'info>(ctx: Context<'_, '_, '_, 'info, Repay<'info>>) -> Result<()> {
+        ctx.accounts.repay(ctx.remaining_accounts)
+    }
+
+    /// Releases the collateral of a loan not repaid by its deadline to the lender, closing the loan
+    pub fn liquidate_after_deadline<'info>(
+        ctx: Context<'_, '_, '_, 'info, LiquidateAfterDeadline<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.liquidate(ctx.remaining_accounts)
+    }
+
+    /// Withdraws a loan no lender has taken yet, returning the collateral to the maker
+    pub fn cancel_loan<'info>(ctx: Context<'_, '_, '_, 'info, CancelLoan<'info>>) -> Result<()> {
+        ctx.accounts.cancel(ctx.remaining_accounts)
+    }
+
+    /// Contests an open escrow with an arbiter, freezing it until the arbiter decides where the vault goes
+    /// Callable by the owner of the maker's position or the designated taker
+    pub fn open_dispute(ctx: Context<OpenDispute>) -> Result<()> {
+        ctx.accounts.open_dispute()
+    }
+
+    /// Settles a disputed escrow by releasing the vault back to the owner of the maker's position, only callable by the arbiter
+    pub fn resolve_to_maker<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+    ) -> Result<()> {
+        let owner = ctx.accounts.escrow.load()?.owner;
+        ctx.accounts.resolve(owner, ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow(EscrowStatus::Refunded)
+    }
+
+    /// Settles a disputed escrow by releasing the vault to the designated taker, only callable by the arbiter
+    pub fn resolve_to_taker<'info>(
+        ctx: Context<'_, '_, '_, 'info, ResolveDispute<'info>>,
+    ) -> Result<()> {
+        let taker = ctx
+            .accounts
+            .escrow
+            .load()?
+            .taker()
+            .ok_or(EscrowError::UnauthorizedTaker)?;
+        ctx.accounts.resolve(taker, ctx.remaining_accounts)?;
+        ctx.accounts.close_escrow(EscrowStatus::Filled)
+    }
+
+    /// Creates a committee of arbiters, `threshold` of the `members` having to agree to settle a dispute
+    /// Escrows are put under the committee by making them with its address as their arbiter
+    pub fn create_committee(
+        ctx: Context<CreateCommittee>,
+        seed: u64,
+        threshold: u8,
+        members: Vec<Pubkey>,
+    ) -> Result<()> {
+        ctx.accounts.create(seed, threshold, members, &ctx.bumps)
+    }
+
+    /// Approves releasing a disputed escrow to the designated taker if `to_taker`, to the owner of the maker's position otherwise
+    /// Only callable by members of the escrow's arbiter committee, the approval reaching its threshold settles the dispute
+    pub fn approve_resolution<'info>(
+        ctx: Context<'_, '_, '_, 'info, ApproveResolution<'info>>,
+        to_taker: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .approve(to_taker, &ctx.bumps, ctx.remaining_accounts)
+    }
+
+    /// Claims the part of a vesting deposit that has vested since the last claim, only callable by the taker
+    /// The vault and the escrow are closed once the whole deposit has been claimed
+    pub fn claim_vested<'info>(ctx: Context<'_, '_, '_, 'info, ClaimVested<'info>>) -> Result<()> {
+        ctx.accounts.claim(ctx.remaining_accounts)
+    }
+
+    /// Ends a vesting escrow early once both the owner and the taker sign for it
+    /// The taker gets what has vested so far, the owner gets the rest back, and the vault and the escrow are closed
+    pub fn cancel_vesting<'info>(
+        ctx: Context<'_, '_, '_, 'info, CancelVesting<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.cancel(ctx.remaining_accounts)
+    }
+
+    /// Proposes `receive` mint_b for the whole deposit of an open escrow, as a counter-offer to its price
+    /// Kept in a PDA per escrow and taker until accepted or withdrawn
+    pub fn propose_counter(ctx: Context<ProposeCounter>, receive: u64) -> Result<()> {
+        ctx.accounts.propose_counter(receive, &ctx.bumps)
+    }
+
+    /// Accepts a counter-offer, repricing the escrow and reserving it for the taker who made it
+    /// Only callable by the owner of the maker's position
+    pub fn accept_counter(ctx: Context<AcceptCounter>) -> Result<()> {
+        ctx.accounts.accept_counter()
+    }
+
+    /// Closes a taker's fill receipt once they no longer need it, returning its rent to them
+    pub fn close_fill_receipt(_ctx: Context<CloseFillReceipt>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Withdraws a pending counter-offer, returning its rent to the taker
+    pub fn withdraw_counter(_ctx: Context<WithdrawCounter>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Posts a quote of `amount` mint_b for the whole deposit of an open escrow, locking it in the quote's vault
+    /// Lets the owner price illiquid assets by accepting the best quote, kept in a PDA per escrow and taker until then
+    pub fn post_quote<'info>(
+        ctx: Context<'_, '_, '_, 'info, PostQuote<'info>>,
+        amount: u64,
+    ) -> Result<()> {
+        ctx.accounts.post_quote(amount, &ctx.bumps)?;
+        ctx.accounts.deposit(amount, ctx.remaining_accounts)
+    }
+
+    /// Settles the escrow against a quote, only callable by the owner of the maker's position
+    /// Losing quotes passed as remaining accounts must not beat it, and are refunded to their takers
+    pub fn accept_quote<'info>(
+        ctx: Context<'_, '_, 'info, 'info, AcceptQuote<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.check_fillable()?;
+        ctx.accounts.refund_losers(ctx.remaining_accounts)?;
+        ctx.accounts.settle()?;
+        ctx.accounts.close_escrow()
+    }
+
+    /// Withdraws a pending quote, returning its mint_b and rent to the taker
+    pub fn withdraw_quote<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawQuote<'info>>,
+    ) -> Result<()> {
+        ctx.accounts.withdraw_quote(ctx.remaining_accounts)
+    }
+
+    /// Hands the maker's position in an open escrow over to `new_owner`, only callable by its current owner
+    /// The new owner receives the proceeds and refunds, and manages the escrow from then on
+    pub fn transfer_position(ctx: Context<TransferPosition>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.transfer_position(new_owner)
+    }
+
+    /// Makes `new_maker` the maker of an open escrow, e.g. after a key rotation or a transfer between desks
+    /// Only callable by a maker who still owns their position, checked against the stored `owner` rather than
+    /// the address, which stays derived from the previous maker. `new_maker` then owns it, collects its rent
+    /// and can no longer take it
+    pub fn transfer_maker(ctx: Context<TransferMaker>, new_maker: Pubkey) -> Result<()> {
+        ctx.accounts.transfer_maker(new_maker)
+    }
+
+    /// Closes an escrow kept as a receipt once it has been filled, refunded or expired
+    /// Receipts keep the historical record for analytics and disputes until the maker reclaims their rent
+    pub fn close_receipt(_ctx: Context<CloseReceipt>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Rewrites an escrow made in an older layout in the current one, growing the account to fit it
+    /// Its terms are carried over unchanged, so anyone can migrate it and pay for the added rent
+    pub fn migrate_escrow(ctx: Context<MigrateEscrow>) -> Result<()> {
+        ctx.accounts.migrate()
     }
 }
@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Decimals past which a mint's amounts can't be normalized, `10^19` no longer fitting a u64
+pub const MAX_DECIMALS: u8 = 18;
+
+/// Which way an amount is rounded when it isn't a whole number of base units
+/// Always rounded in the maker's favour, so partial fills never sell below the agreed rate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+/// `amount` scaled by `numerator / denominator`, in u128 so intermediate products can't overflow
+/// Prices are ratios of raw amounts, `price_b` base units of mint_b for `price_a` base units of mint_a,
+/// so scaling by them never depends on either mint's decimals
+pub fn scale(amount: u64, numerator: u64, denominator: u64, rounding: Rounding) -> Result<u64> {
+    require!(denominator > 0, EscrowError::MathOverflow);
+    let product = amount as u128 * numerator as u128;
+    let scaled = match rounding {
+        Rounding::Down => product / denominator as u128,
+        Rounding::Up => product.div_ceil(denominator as u128),
+    };
+    u64::try_from(scaled).map_err(|_| EscrowError::MathOverflow.into())
+}
+
+/// Raw `amount` of a mint with `from_decimals` as the raw amount of the same UI value with `to_decimals`
+/// Used wherever a price is quoted outside the two mints' base units, like per whole token,
+/// so a 6-vs-9 decimal pair isn't mispriced by 10^3
+pub fn normalize(
+    amount: u64,
+    from_decimals: u8,
+    to_decimals: u8,
+    rounding: Rounding,
+) -> Result<u64> {
+    require!(
+        from_decimals <= MAX_DECIMALS && to_decimals <= MAX_DECIMALS,
+        EscrowError::MathOverflow
+    );
+    if from_decimals <= to_decimals {
+        return scale(
+            amount,
+            10u64.pow((to_decimals - from_decimals).into()),
+            1,
+            rounding,
+        );
+    }
+    scale(
+        amount,
+        1,
+        10u64.pow((from_decimals - to_decimals).into()),
+        rounding,
+    )
+}
+
+/// Price of an escrow as `(price_a, price_b)` raw amounts, from `ui_price` whole mint_b per whole mint_a
+/// `ui_price` is itself a raw amount with `price_decimals`, like an oracle quote
+pub fn raw_price(
+    ui_price: u64,
+    price_decimals: u8,
+    decimals_a: u8,
+    decimals_b: u8,
+) -> Result<(u64, u64)> {
+    require!(
+        decimals_a <= MAX_DECIMALS && ui_price > 0,
+        EscrowError::MathOverflow
+    );
+    let price_b = normalize(ui_price, price_decimals, decimals_b, Rounding::Up)?;
+    Ok((10u64.pow(decimals_a.into()), price_b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scale_rounds_as_asked() {
+        assert_eq!(scale(1, 2, 3, Rounding::Down).unwrap(), 0);
+        assert_eq!(scale(1, 2, 3, Rounding::Up).unwrap(), 1);
+        assert_eq!(scale(3, 2, 3, Rounding::Down).unwrap(), 2);
+        assert_eq!(scale(3, 2, 3, Rounding::Up).unwrap(), 2);
+        assert_eq!(
+            scale(u64::MAX, u64::MAX, u64::MAX, Rounding::Up).unwrap(),
+            u64::MAX
+        );
+        assert!(scale(u64::MAX, 2, 1, Rounding::Down).is_err());
+        assert!(scale(1, 1, 0, Rounding::Down).is_err());
+    }
+
+    #[test]
+    fn one_whole_token_normalizes_to_one_whole_token_for_0_to_18_decimals() {
+        for from in 0..=MAX_DECIMALS {
+            for to in 0..=MAX_DECIMALS {
+                let one = 10u64.pow(from.into());
+                for rounding in [Rounding::Down, Rounding::Up] {
+                    assert_eq!(
+                        normalize(one, from, to, rounding).unwrap(),
+                        10u64.pow(to.into()),
+                        "{from} to {to} decimals"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn normalizing_round_trips_without_losing_base_units_for_0_to_18_decimals() {
+        for from in 0..=MAX_DECIMALS {
+            for to in from..=MAX_DECIMALS {
+                // Up to 18 more decimals still fit a u64
+                let amount = 12;
+                let up = normalize(amount, from, to, Rounding::Down).unwrap();
+                assert_eq!(normalize(up, to, from, Rounding::Down).unwrap(), amount);
+            }
+        }
+    }
+
+    #[test]
+    fn normalizing_to_fewer_decimals_rounds_as_asked_for_0_to_18_decimals() {
+        for from in 1..=MAX_DECIMALS {
+            for to in 0..from {
+                // One base unit is a fraction of a base unit with fewer decimals
+                assert_eq!(normalize(1, from, to, Rounding::Down).unwrap(), 0);
+                assert_eq!(normalize(1, from, to, Rounding::Up).unwrap(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn normalizing_rejects_more_than_18_decimals_and_overflows() {
+        assert!(normalize(1, 19, 0, Rounding::Down).is_err());
+        assert!(normalize(1, 0, 19, Rounding::Down).is_err());
+        assert!(normalize(u64::MAX, 0, 1, Rounding::Down).is_err());
+        assert_eq!(normalize(u64::MAX, 18, 0, Rounding::Down).unwrap(), 18);
+    }
+
+    #[test]
+    fn six_and_nine_decimal_pairs_are_priced_per_whole_token() {
+        // 2.5 whole mint_b per whole mint_a, quoted with 2 decimals
+        assert_eq!(raw_price(250, 2, 6, 9).unwrap(), (1_000_000, 2_500_000_000));
+        assert_eq!(raw_price(250, 2, 9, 6).unwrap(), (1_000_000_000, 2_500_000));
+        // Half a whole mint_a asks 1.25 whole mint_b either way round
+        let (price_a, price_b) = raw_price(250, 2, 6, 9).unwrap();
+        assert_eq!(
+            scale(500_000, price_b, price_a, Rounding::Up).unwrap(),
+            1_250_000_000
+        );
+        let (price_a, price_b) = raw_price(250, 2, 9, 6).unwrap();
+        assert_eq!(
+            scale(500_000_000, price_b, price_a, Rounding::Up).unwrap(),
+            1_250_000
+        );
+    }
+
+    #[test]
+    fn raw_prices_keep_the_ui_price_for_0_to_18_decimals() {
+        for decimals_a in 0..=MAX_DECIMALS {
+            for decimals_b in 0..=MAX_DECIMALS {
+                // 3 whole mint_b per whole mint_a
+                let (price_a, price_b) = raw_price(3, 0, decimals_a, decimals_b).unwrap();
+                let one_a = 10u64.pow(decimals_a.into());
+                let one_b = 10u64.pow(decimals_b.into());
+                assert_eq!((price_a, price_b), (one_a, 3 * one_b));
+                // What a whole mint_a asks and what the payment buys back round trip
+                let receive = scale(one_a, price_b, price_a, Rounding::Up).unwrap();
+                assert_eq!(receive, 3 * one_b);
+                assert_eq!(
+                    scale(receive, price_a, price_b, Rounding::Down).unwrap(),
+                    one_a
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Defines a committee of arbiters settling disputes together, which includes:
+/// - the account that created it, and the seed it was created with,
+/// - the number of members who must approve an outcome before the vault is released,
+/// - the members, fixed once created so parties can vet the panel before trusting it,
+/// - and a bump seed for address generation security.
+///
+/// Escrows are put under a committee by making them with its address as their arbiter
+#[account]
+#[derive(InitSpace)]
+pub struct ArbiterCommittee {
+    pub creator: Pubkey, // account that created the committee
+    pub seed: u64,       // seed the committee was created with
+    pub threshold: u8,   // approvals needed to settle a dispute, at most the number of members
+    #[max_len(16)]
+    pub members: Vec<Pubkey>, // arbiters allowed to approve resolutions, each counted once
+    pub bump: u8,        // bump seed for the committee account
+}
+
+impl ArbiterCommittee {
+    /// Upper bound of `members`, must match the `max_len` the account is sized with and fit the approvals bitmask
+    pub const MAX_MEMBERS: usize = 16;
+
+    /// Position of `member` in the committee, `None` if they aren't a member
+    pub fn member_index(&self, member: &Pubkey) -> Option<usize> {
+        self.members.iter().position(|m| m == member)
+    }
+}
+
+/// Defines the approvals a committee collected on a dispute, which includes:
+/// - the disputed escrow,
+/// - which members approved releasing the vault to the maker's side, and which to the taker,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct DisputeResolution {
+    pub escrow: Pubkey,       // disputed escrow the approvals are for
+    pub maker_approvals: u16, // bitmask, by member index, of the approvals for the owner of the maker's position
+    pub taker_approvals: u16, // bitmask, by member index, of the approvals for the designated taker
+    pub bump: u8,             // bump seed for the dispute resolution account
+}
+
+impl DisputeResolution {
+    /// Records the approval of the member at `index` for the taker's side if `to_taker`, the maker's otherwise
+    /// Returns how many members approved that side, a member can only approve once whatever the side
+    pub fn approve(&mut self, index: usize, to_taker: bool) -> Result<u32> {
+        let bit = 1u16 << index;
+        require!(
+            (self.maker_approvals | self.taker_approvals) & bit == 0,
+            EscrowError::AlreadyApproved
+        );
+        let approvals = match to_taker {
+            true => &mut self.taker_approvals,
+            false => &mut self.maker_approvals,
+        };
+        *approvals |= bit;
+        Ok(approvals.count_ones())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn members_approve_once_and_sides_are_counted_apart() {
+        let mut resolution = DisputeResolution {
+            escrow: Pubkey::default(),
+            maker_approvals: 0,
+            taker_approvals: 0,
+            bump: 0,
+        };
+        assert_eq!(resolution.approve(0, true).unwrap(), 1);
+        assert_eq!(resolution.approve(15, false).unwrap(), 1);
+        assert_eq!(resolution.approve(3, true).unwrap(), 2);
+        assert_eq!(
+            resolution.approve(0, false).unwrap_err(),
+            EscrowError::AlreadyApproved.into()
+        );
+        assert_eq!(
+            resolution.approve(15, true).unwrap_err(),
+            EscrowError::AlreadyApproved.into()
+        );
+    }
+}
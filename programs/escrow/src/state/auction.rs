@@ -0,0 +1,97 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+/// Defines the data stored for an auction of a deposit, which includes:
+/// - a seed,
+/// - the maker's public key,
+/// - token types (`mint_a` auctioned by the maker and `mint_b` bid by the bidders),
+/// - the amount of mint_a auctioned and the lowest acceptable bid,
+/// - the time bidding ends,
+/// - the time sealed bids must be revealed by, for sealed-bid auctions,
+/// - the current highest bidder and their bid,
+/// - and a bump seed for address generation security.
+///
+/// Open auctions take English bids with `bid` until `end`, sealed-bid auctions take bid commitments with `commit_bid`
+/// until `end`, then reveals with `reveal_bid` until `reveal_end`
+#[account]
+#[derive(InitSpace)]
+pub struct Auction {
+    pub seed: u64,              // seed for the auction account
+    pub maker: Pubkey,          // maker of the auction, locks mint_a when opening it
+    pub mint_a: Pubkey,         // token being auctioned
+    pub mint_b: Pubkey,         // token bids are made in
+    pub deposit: u64,           // amount of mint_a locked in `vault_a`
+    pub reserve: u64,           // lowest acceptable bid in mint_b
+    pub end: i64,               // unix timestamp after which no bids are accepted
+    pub reveal_end: i64, // unix timestamp after which sealed bids can no longer be revealed, 0 for open auctions
+    pub bidder: Option<Pubkey>, // current highest bidder, if any
+    pub bid: u64,        // current highest bid, locked in `bid_vault`
+    pub bump: u8,        // bump seed for the auction account
+}
+
+impl Auction {
+    /// Whether bidding has closed at `now`
+    pub fn has_ended(&self, now: i64) -> bool {
+        now >= self.end
+    }
+
+    /// Whether bids are sealed, committed while bidding is open and revealed afterwards
+    pub fn is_sealed(&self) -> bool {
+        self.reveal_end != 0
+    }
+
+    /// Whether sealed bids can be revealed at `now`, between the end of bidding and `reveal_end`
+    pub fn is_revealing(&self, now: i64) -> bool {
+        self.is_sealed() && self.has_ended(now) && now < self.reveal_end
+    }
+
+    /// Whether the auction can be settled at `now`, once bidding and any reveal window have closed
+    pub fn is_settleable(&self, now: i64) -> bool {
+        now >= self.end.max(self.reveal_end)
+    }
+
+    /// Whether `amount` beats the current highest bid, or meets the reserve for the first bid
+    pub fn is_winning_bid(&self, amount: u64) -> bool {
+        match self.bidder {
+            Some(_) => amount > self.bid,
+            None => amount >= self.reserve,
+        }
+    }
+
+    /// Commitment binding `bidder` to bid `amount` on `auction`, hiding it behind their `salt` until they reveal it
+    pub fn bid_commitment(
+        auction: &Pubkey,
+        bidder: &Pubkey,
+        amount: u64,
+        salt: &[u8; 32],
+    ) -> [u8; 32] {
+        hashv(&[
+            auction.as_ref(),
+            bidder.as_ref(),
+            &amount.to_le_bytes(),
+            salt,
+        ])
+        .to_bytes()
+    }
+}
+
+/// Defines a bidder's sealed bid on an auction, which includes:
+/// - the auction bid on, and its maker, who is paid the bond if the bid is never revealed,
+/// - the bidder,
+/// - the commitment to the bid, see `Auction::bid_commitment`,
+/// - the bond locked in the account on top of its rent,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct SealedBid {
+    pub auction: Pubkey, // auction the bid is on
+    pub maker: Pubkey,   // maker of the auction, receiving the bond if the bid is never revealed
+    pub bidder: Pubkey,  // bidder, refunded the bond when they reveal the bid
+    pub hash: [u8; 32],  // commitment to the amount bid
+    pub bond: u64,       // lamports locked on top of the account's rent
+    pub bump: u8,        // bump seed for the sealed bid account
+}
+
+impl SealedBid {
+    /// Smallest bond a sealed bid locks, 0.001 SOL, so bids can't be committed and abandoned for free
+    pub const MIN_BOND: u64 = 1_000_000;
+}
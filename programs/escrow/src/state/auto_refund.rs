@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// Defines an escrow's scheduled refund, executed by any keeper once due, which includes:
+/// - the escrow it unwinds, and the owner of its position who scheduled it,
+/// - when keepers may execute it,
+/// - the tip paid to the keeper who does, held in the account on top of its rent,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct AutoRefund {
+    pub escrow: Pubkey,     // escrow refunded once due, one schedule per escrow
+    pub owner: Pubkey,      // owner who scheduled it, receiving the refund and the account's rent
+    pub execute_after: i64, // unix timestamp from which any keeper can execute the refund
+    pub tip: u64,           // lamports paid to the keeper executing the refund
+    pub bump: u8,           // bump seed for the auto refund account
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for a basket escrow, where the maker deposits several mints at once, which includes:
+/// - a seed,
+/// - maker's public key,
+/// - the legs deposited by the maker, each held in its own vault,
+/// - the token and amount the maker expects to receive for the whole basket,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct Basket {
+    pub seed: u64,      // seed for the basket account
+    pub maker: Pubkey,  // maker of the trade
+    pub mint_b: Pubkey, // token that the maker is expecting to receive
+    pub receive: u64,   // amount of mint_b that the maker is expecting to receive
+    #[max_len(5)]
+    pub legs: Vec<BasketLeg>, // mints and amounts deposited by the maker, one vault each
+    pub bump: u8,       // bump seed for the basket account
+}
+
+/// A single mint deposited into a basket, held in the basket's associated token account for `mint`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct BasketLeg {
+    pub mint: Pubkey, // token deposited by the maker
+    pub amount: u64,  // amount of `mint` held in the leg's vault
+}
+
+impl Basket {
+    /// Upper bound of `legs`, must match the `max_len` the account is sized with
+    pub const MAX_LEGS: usize = 5;
+
+    /// Number of remaining accounts passed per leg to the basket instructions
+    pub const ACCOUNTS_PER_LEG: usize = 3;
+}
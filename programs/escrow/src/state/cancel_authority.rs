@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Defines the delegation of an owner's right to cancel their escrows, which includes:
+/// - the owner of the maker positions it applies to,
+/// - the authority allowed to refund them on the owner's behalf, e.g. an ops hot wallet or an automation program,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct CancelAuthority {
+    pub owner: Pubkey,     // owner whose escrows can be refunded by `authority`
+    pub authority: Pubkey, // may call `refund` on the owner's escrows, refunds still go to the owner
+    pub bump: u8,          // bump seed for the cancel authority account
+}
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for an escrow of a compressed NFT, which the escrow PDA holds as leaf owner in its Bubblegum tree, which includes:
+/// - a seed,
+/// - maker's public key,
+/// - the merkle tree of the compressed NFT and its asset id,
+/// - the token and amount the maker expects to receive for it,
+/// - an optional deadline after which it can only be refunded,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct CompressedEscrow {
+    pub seed: u64,           // seed for the escrow account
+    pub maker: Pubkey,       // maker of the trade
+    pub merkle_tree: Pubkey, // Bubblegum tree the compressed NFT lives in
+    pub asset_id: Pubkey,    // asset id of the compressed NFT, derived from the tree and its nonce
+    pub mint_b: Pubkey,      // token that the maker is expecting to receive
+    pub receive: u64,        // amount of mint_b that the maker is expecting to receive
+    pub expiry: i64, // unix timestamp after which the escrow can no longer be taken, 0 if it never expires
+    pub bump: u8,    // bump seed for the escrow account
+}
+
+impl CompressedEscrow {
+    /// Whether the escrow's deadline has passed at `now`, escrows without an expiry never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+}
+
+/// Proof of a compressed NFT's current leaf, as returned by a DAS indexer for the asset
+/// The proof's nodes are passed as remaining accounts, the tree's root changing with every transfer in it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct LeafProof {
+    pub root: [u8; 32],         // current root of the merkle tree
+    pub data_hash: [u8; 32],    // hash of the asset's metadata
+    pub creator_hash: [u8; 32], // hash of the asset's creators
+    pub nonce: u64, // nonce the asset was minted with, from which its asset id is derived
+    pub index: u32, // index of the asset's leaf in the tree
+}
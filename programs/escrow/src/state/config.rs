@@ -0,0 +1,244 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Defines the global settings of the program, stored in a single PDA, which includes:
+/// - the admin allowed to manage the config,
+/// - the protocol fee taken from the taker's payment, in basis points,
+/// - the share of that fee paid to referrers, in basis points of the fee,
+/// - the NFT collection whose holders pay a discounted fee, and that fee,
+/// - whether new escrows and fills are paused,
+/// - the mints allowed and banned in new escrows,
+/// - whether new escrows may use mints with Token-2022 extensions that could keep them from settling,
+///   and whether mints with a permanent delegate are rejected or flagged,
+/// - the currency `take` charges the protocol fee in,
+/// - the penalty paid to a reserving taker out of the deposit when the maker cancels under them, in basis points,
+/// - and a bump seed for address generation security.
+///
+/// Fees are collected in `fee_vault` token accounts owned by this PDA, one per mint_b.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,                         // account allowed to manage the config
+    pub fee_bps: u16,                          // protocol fee charged on each fill, in basis points
+    pub referral_bps: u16,                     // share of the fee paid to a fill's referrer, in bps
+    pub membership_collection: Option<Pubkey>, // NFT collection whose holders get a discount
+    pub member_fee_bps: u16,                   // discounted protocol fee for members, in bps
+    pub paused: bool, // halts new escrows and fills, refunds stay available
+    #[max_len(16)]
+    pub allowed_mints: Vec<Pubkey>, // only mints new escrows may use, any mint if empty
+    #[max_len(16)]
+    pub banned_mints: Vec<Pubkey>, // mints new escrows may never use, e.g. scam or honeypot tokens
+    pub allow_non_transferable: bool, // permits NonTransferable mints in new escrows, for burn-style flows
+    pub permanent_delegate_policy: PermanentDelegatePolicy, // rejects or flags mints with a permanent delegate
+    pub fee_currency: FeeCurrency, // leg, or lamports, `take` charges the protocol fee in
+    #[max_len(4)]
+    pub fee_tiers: Vec<FeeTier>, // discounted rates for takers above a volume, by increasing volume
+    pub cancel_penalty_bps: u16, // share of the deposit paid to the reserving taker of a refunded escrow
+    pub bump: u8,                // bump seed for the config account
+}
+
+impl Config {
+    /// Upper bound of `fee_bps`, a fee of 100%
+    pub const MAX_FEE_BPS: u16 = 10_000;
+
+    /// Upper bound of `allowed_mints` and `banned_mints`, must match the `max_len` the account is sized with
+    pub const MAX_LISTED_MINTS: usize = 16;
+
+    /// Upper bound of `fee_tiers`, must match the `max_len` the account is sized with
+    pub const MAX_FEE_TIERS: usize = 4;
+
+    /// Protocol fee owed on a payment of `amount`, rounded down in favor of the maker
+    pub fn fee(&self, amount: u64) -> Result<u64> {
+        Self::bps_of(amount, self.fee_bps)
+    }
+
+    /// Discounted protocol fee owed by members on a payment of `amount`, never above the regular fee
+    pub fn member_fee(&self, amount: u64) -> Result<u64> {
+        self.member_fee_at(amount, self.fee_bps)
+    }
+
+    /// Protocol fee owed on a payment of `amount` at `fee_bps` instead of the regular rate, e.g. a `FeeOverride`'s
+    pub fn fee_at(&self, amount: u64, fee_bps: u16) -> Result<u64> {
+        Self::bps_of(amount, fee_bps)
+    }
+
+    /// Discounted protocol fee owed by members on a payment of `amount`, never above the rate of `fee_bps`
+    pub fn member_fee_at(&self, amount: u64, fee_bps: u16) -> Result<u64> {
+        Self::bps_of(amount, self.member_fee_bps.min(fee_bps))
+    }
+
+    /// Part of a protocol `fee` diverted to the referrer of a fill, rounded down in favor of the protocol
+    pub fn referral_share(&self, fee: u64) -> Result<u64> {
+        Self::bps_of(fee, self.referral_bps)
+    }
+
+    /// Penalty owed to the taker reserving an escrow refunded with `deposit` left of its recorded deposit, rounded down in favor of the maker
+    pub fn cancel_penalty(&self, deposit: u64) -> Result<u64> {
+        Self::bps_of(deposit, self.cancel_penalty_bps)
+    }
+
+    /// Rate of the highest fee tier a taker with `volume` reached, the regular `fee_bps` below the first tier
+    pub fn tier_fee_bps(&self, volume: u64) -> u16 {
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+            .map_or(self.fee_bps, |tier| tier.fee_bps)
+    }
+
+    /// `bps` basis points of `amount`, rounded down
+    /// Computed in u128 so `amount * bps` can't overflow, the result only exceeds `amount` if `bps` is above `MAX_FEE_BPS`
+    fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+        u64::try_from(amount as u128 * bps as u128 / Self::MAX_FEE_BPS as u128)
+            .map_err(|_| EscrowError::MathOverflow.into())
+    }
+
+    /// Whether new escrows may use `mint`: it must not be banned, and must be allowed if there is an allowlist
+    pub fn is_mint_permitted(&self, mint: &Pubkey) -> bool {
+        !self.banned_mints.contains(mint)
+            && (self.allowed_mints.is_empty() || self.allowed_mints.contains(mint))
+    }
+
+    /// The list of `list` mints
+    pub fn mints_mut(&mut self, list: MintList) -> &mut Vec<Pubkey> {
+        match list {
+            MintList::Allowed => &mut self.allowed_mints,
+            MintList::Banned => &mut self.banned_mints,
+        }
+    }
+}
+
+/// How new escrows treat mints with a Token-2022 permanent delegate, which can move tokens out of any account of the mint
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum PermanentDelegatePolicy {
+    /// Such mints can't be used in new escrows
+    #[default]
+    Reject,
+    /// Such mints are allowed, the escrow being flagged and a `PermanentDelegateEvent` emitted for each delegate
+    Flag,
+}
+
+/// Currency `take` charges the protocol fee in, chosen per deployment
+/// `batch_take` only settles in mint_b, and refuses to fill while the fee is charged in another currency
+/// Other settlement paths, such as auctions and swaps, always charge it in the leg they are paid in
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum FeeCurrency {
+    /// Out of the taker's payment, in the fee vault for mint_b, shared with the fill's referrer
+    #[default]
+    MintB,
+    /// Out of the deposit released to the taker, in the fee vault for mint_a
+    MintA,
+    /// A flat `lamports` fee per fill paid by the taker to `treasury`, on top of their payment
+    Lamports { lamports: u64, treasury: Pubkey },
+}
+
+impl FeeCurrency {
+    /// The account receiving lamport fees, `None` unless fees are charged in lamports
+    pub fn treasury(&self) -> Option<Pubkey> {
+        match self {
+            FeeCurrency::Lamports { treasury, .. } => Some(*treasury),
+            _ => None,
+        }
+    }
+}
+
+/// A rate of the config's fee table, charged to takers whose volume in the mint they pay reached `min_volume`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct FeeTier {
+    pub min_volume: u64, // volume, in raw units of mint_b, from which the tier applies
+    pub fee_bps: u16,    // protocol fee charged in the tier, in basis points
+}
+
+/// Which of the config's mint lists an admin instruction applies to
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MintList {
+    Allowed,
+    Banned,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(fee_bps: u16, referral_bps: u16) -> Config {
+        Config {
+            admin: Pubkey::default(),
+            fee_bps,
+            referral_bps,
+            membership_collection: None,
+            member_fee_bps: 0,
+            paused: false,
+            allowed_mints: vec![],
+            banned_mints: vec![],
+            allow_non_transferable: false,
+            permanent_delegate_policy: PermanentDelegatePolicy::Reject,
+            fee_currency: FeeCurrency::MintB,
+            fee_tiers: vec![],
+            cancel_penalty_bps: 0,
+            bump: 0,
+        }
+    }
+
+    #[test]
+    fn fees_handle_u64_max_payments() {
+        let full = config(Config::MAX_FEE_BPS, Config::MAX_FEE_BPS);
+        assert_eq!(full.fee(u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(full.referral_share(u64::MAX).unwrap(), u64::MAX);
+
+        let config = config(30, 5_000);
+        assert_eq!(config.fee(u64::MAX).unwrap(), 55_340_232_221_128_654);
+        assert_eq!(config.member_fee(u64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn fees_round_down_in_favor_of_the_maker() {
+        let config = config(30, 5_000);
+        assert_eq!(config.fee(333).unwrap(), 0);
+        assert_eq!(config.fee(334).unwrap(), 1);
+        assert_eq!(config.referral_share(1).unwrap(), 0);
+    }
+
+    #[test]
+    fn members_pay_the_lower_of_their_rate_and_a_fee_override() {
+        let mut config = config(30, 0);
+        config.member_fee_bps = 20;
+        assert_eq!(config.fee_at(10_000, 100).unwrap(), 100);
+        assert_eq!(config.member_fee_at(10_000, 100).unwrap(), 20);
+        assert_eq!(config.member_fee_at(10_000, 10).unwrap(), 10);
+    }
+
+    #[test]
+    fn takers_pay_the_rate_of_the_highest_tier_reached() {
+        let mut config = config(30, 0);
+        assert_eq!(config.tier_fee_bps(u64::MAX), 30);
+
+        config.fee_tiers = vec![
+            FeeTier {
+                min_volume: 1_000,
+                fee_bps: 20,
+            },
+            FeeTier {
+                min_volume: 10_000,
+                fee_bps: 10,
+            },
+        ];
+        assert_eq!(config.tier_fee_bps(999), 30);
+        assert_eq!(config.tier_fee_bps(1_000), 20);
+        assert_eq!(config.tier_fee_bps(9_999), 20);
+        assert_eq!(config.tier_fee_bps(u64::MAX), 10);
+    }
+
+    #[test]
+    fn fees_above_100_percent_fail_with_math_overflow() {
+        let config = config(u16::MAX, 0);
+        assert_eq!(
+            config.fee(u64::MAX).unwrap_err(),
+            EscrowError::MathOverflow.into()
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+/// Defines a counter-offer made by a prospective taker on an open escrow, which includes:
+/// - the escrow it is made on,
+/// - the prospective taker,
+/// - the amount of mint_b they offer for the whole deposit,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct CounterOffer {
+    pub escrow: Pubkey, // escrow the counter-offer is made on
+    pub taker: Pubkey,  // prospective taker proposing the price
+    pub receive: u64,   // amount of mint_b offered for the whole deposit
+    pub bump: u8,       // bump seed for the counter-offer account
+}
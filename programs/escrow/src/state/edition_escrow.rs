@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for an escrow selling prints of a master edition, whose token its vault holds, which includes:
+/// - a seed,
+/// - maker's public key,
+/// - the mint of the master edition,
+/// - the token and amount the maker expects to receive for each print,
+/// - an optional deadline after which it can only be refunded,
+/// - the number of editions sold through it,
+/// - and a bump seed for address generation security.
+///
+/// The escrow stays open between fills, until the master edition's supply runs out or the maker refunds it
+#[account]
+#[derive(InitSpace)]
+pub struct EditionEscrow {
+    pub seed: u64,           // seed for the escrow account
+    pub maker: Pubkey,       // maker of the trade
+    pub master_mint: Pubkey, // mint of the master edition the escrow prints from
+    pub mint_b: Pubkey,      // token that the maker is expecting to receive
+    pub receive: u64, // amount of mint_b that the maker is expecting to receive for each print
+    pub expiry: i64, // unix timestamp after which the escrow can no longer be taken, 0 if it never expires
+    pub printed: u64, // number of editions printed to takers
+    pub bump: u8,    // bump seed for the escrow account
+}
+
+impl EditionEscrow {
+    /// Whether the escrow's deadline has passed at `now`, escrows without an expiry never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+}
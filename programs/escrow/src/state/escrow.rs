@@ -1,18 +1,749 @@
-use anchor_lang::prelude::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::hash::{hash, hashv},
+};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::{
+    pricing::{self, Rounding},
+    EscrowError,
+};
 
 /// Defines the data stored for an escrow, which includes:
-/// - a seed,
-/// - maker's public key,
-/// - token types (`mint_a` and `mint_b`),
-/// - the expected receive amount,
-/// - and a bump seed for address generation security.
-#[account]
-#[derive(InitSpace)]
+/// - the version of its layout, and its lifecycle status,
+/// - whether it is kept as a receipt once settled, and whether it is a collection offer,
+/// - a seed, and a bump seed for address generation security,
+/// - the deposited and expected receive amounts, and the price fills are settled at,
+/// - an optional expiry deadline,
+/// - an optional vesting schedule releasing the deposit to the taker over time once taken,
+/// - maker's public key, and the owner of the maker's position, which can be transferred,
+/// - token types (`mint_a` and `mint_b`), `mint_b` being a collection for offers paid with any NFT of it,
+/// - an optional designated taker,
+/// - an optional arbiter settling disputes,
+/// - an optional SHA-256 hashlock, whose preimage must be revealed to take the escrow,
+/// - where the rent of the escrow and its vault goes once they are closed,
+/// - whether its vault is a token account at its own PDA rather than its associated token account,
+/// - a taker's pending commitment, reserving the escrow for them until its deadline,
+/// - an optional schedule of recurring installments, and when the last one was taken,
+/// - an optional label the maker tags the offer with, for UIs to display,
+/// - whether either mint has a permanent delegate, which the config let through,
+/// - the slot a taker's bonded reservation holds it until,
+/// - the template its terms were made from, if any,
+/// - the token gate a taker must pass to fill it, if any,
+/// - the gatekeeper network whose identity pass a taker must hold to fill it, if any,
+/// - the hash of its private terms, until a taker reveals them,
+/// - the seed of the escrow its proceeds fund, if chained with `make_chained`,
+/// - the token account its proceeds are paid to, if the owner designated one,
+/// - the page of its mint pair's order book listing it, if listed,
+/// - the deposit its mint pair's index counts, if indexed,
+/// - the category the maker filed the offer under, for discovery,
+/// - an optional 32-byte seed its address is derived from instead of `seed`,
+/// - the maker its address was derived from, if `transfer_maker` handed it to another one since,
+/// - and reserved space for fields added by later versions.
+///
+/// The account is zero-copy, so optional fields are zeroed when unset and read through the accessors below
+/// Fields are ordered so the layout contains no implicit padding, as required to cast it from the account data
+#[account(zero_copy)]
+#[derive(Default)]
 pub struct Escrow {
-    pub seed: u64,      // seed for the escrow account
-    pub maker: Pubkey,  // maker of the trade
-    pub mint_a: Pubkey, // token that the maker is expected to deposit
-    pub mint_b: Pubkey, // token that the maker is expecting to receive
-    pub receive: u64,   // amount of mint_b that the maker is expecting to receive
-    pub bump: u8,       // bump seed for the escrow account
+    pub version: u8,            // layout version, `Escrow::VERSION` once made or migrated
+    pub status: u8,             // `EscrowStatus` lifecycle stage, only open escrows can be taken
+    pub keep_receipt: u8,       // 1 to keep the account as a receipt once settled
+    pub collection_offer: u8,   // 1 if paid with any NFT verified in the `mint_b` collection
+    pub bump: u8,               // bump seed for the escrow account
+    pub rent_destination: u8,   // `RentDestination` kind receiving the rent on close
+    pub pda_vault: u8,          // 1 if the vault is the token account at `["vault", escrow]`
+    pub vault_bump: u8,         // bump seed of that vault, zero for associated token account vaults
+    pub seed: u64,              // seed for the escrow account
+    pub deposit: u64,           // amount of mint_a still held in the vault for takers
+    pub receive: u64,           // amount of mint_b that the maker is expecting to receive
+    pub expiry: i64,            // deadline (or hashlock timeout) to take it, 0 if none
+    pub vesting: Vesting,       // schedule the taker claims the deposit on, at once if zeroed
+    pub vesting_start: i64,     // unix timestamp vesting started at when taken, 0 until then
+    pub claimed: u64,           // amount of the deposit already claimed by the taker
+    pub maker: Pubkey,          // maker of the trade
+    pub owner: Pubkey,          // holder of the maker's position, gets proceeds and refunds
+    pub mint_a: Pubkey,         // token that the maker is expected to deposit
+    pub mint_b: Pubkey,         // token that the maker is expecting to receive
+    pub taker: Pubkey,          // only account allowed to take the escrow, anyone if zeroed
+    pub arbiter: Pubkey,        // settles disputes over the vault, no disputes if zeroed
+    pub hashlock: [u8; 32],     // SHA-256 hash of the preimage a taker must reveal, none if zeroed
+    pub rent_treasury: Pubkey,  // receives the rent on close with `RentDestination::Treasury`
+    pub commit_hash: [u8; 32],  // `Escrow::commitment` of the taker who committed to take it
+    pub commit_deadline: i64,   // until when only that taker can take it, 0 if never committed
+    pub price_a: u64,           // amount of mint_a the price is quoted for, see `Escrow::price`
+    pub price_b: u64,           // amount of mint_b asked for `price_a` of mint_a
+    pub schedule: Schedule,     // recurring installments the escrow is taken in, any fill if zeroed
+    pub last_fill_ts: i64,      // last installment or receipt settlement timestamp, 0 until then
+    pub label: [u8; 32],        // maker's tag for the offer, zero-padded UTF-8, none if zeroed
+    pub permanent_delegate: u8, // 1 if mint_a or mint_b has a PermanentDelegate able to drain it
+    pub padding: [u8; 7],       // keeps `reserved_until` aligned, zeroed
+    pub reserved_until: u64, // slot until which only the taker holding its reservation can fill it, 0 if never reserved
+    pub template: Pubkey,    // template the escrow's terms were made from, none if zeroed
+    pub gate: Gate,          // holding a taker needs to fill the escrow, none if its mint is zeroed
+    pub gatekeeper_network: Pubkey, // network whose gateway token a taker needs to fill the escrow, none if zeroed
+    pub terms_hash: [u8; 32], // hash of the private mint_b, receive and salt, see `Escrow::terms_hash`, public terms if zeroed
+    pub chained_seed: u64, // seed of the maker's escrow the proceeds fund, see `make_chained`, none if 0
+    pub payout: Pubkey, // mint_b token account the proceeds are paid to, the owner's associated token account if zeroed
+    pub order_book_page: u64, // one past the index of the order book page listing the escrow, unlisted if 0
+    pub indexed_deposit: u64, // deposit counted in its mint pair's index, not indexed if 0
+    pub category: u16, // kind of offer, e.g. NFT sale, OTC or service, set by the maker at a fixed offset, uncategorized if 0
+    pub category_padding: [u8; 6], // keeps `seed_id` aligned, zeroed
+    pub seed_id: [u8; 32], // caller-supplied seed of the address, e.g. a hash of an off-chain order id, see `address_seed`, `seed` if zeroed
+    pub address_maker: Pubkey, // maker the address was derived from, once `transfer_maker` moved the escrow, `maker` if zeroed
+    pub reserved: [u64; 5],    // room for fields added by later versions, zeroed until then
+}
+
+/// Lifecycle stage of an escrow, stored as its `u8` discriminant
+/// Escrows kept as receipts hold their final stage until closed with `close_receipt`
+/// Escrows made with `make_chained` stay chained, unfillable, until the proceeds of the escrow chained to them fund them
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+#[repr(u8)]
+pub enum EscrowStatus {
+    Open,
+    Filled,
+    Refunded,
+    Expired,
+    Disputed,
+    Vesting,
+    Chained,
+}
+
+/// Where the rent of an escrow and its vault goes once `take`, `refund` or `refund_expired` closes them
+/// Chosen by the maker at `make` time, escrows made before it was introduced use `Split`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RentDestination {
+    /// The escrow's rent goes to the maker, the vault's to the taker on a take and to the owner on a refund
+    #[default]
+    Split,
+    /// Both go to the maker
+    Maker,
+    /// Both go to the taker on a take, refunds fall back to `Split` as there is no taker
+    Taker,
+    /// Both go to the given account, e.g. a treasury that sponsored the rent
+    Treasury(Pubkey),
+}
+
+/// Cliff and linear vesting schedule of the deposit, relative to the time the escrow is taken
+/// Nothing can be claimed before `cliff`, then the deposit vests linearly until it is fully vested at `duration`
+/// Stored in place in `Escrow`, and passed to `make` as an instruction argument, so it is both Pod and Borsh
+/// Not `#[zero_copy]`, whose IDL implementation would conflict with the Borsh derive's
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    InitSpace,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+#[repr(C)]
+pub struct Vesting {
+    pub cliff: i64,    // seconds after the take before anything can be claimed
+    pub duration: i64, // seconds after the take at which the whole deposit is vested
+}
+
+/// Recurring installments of the deposit, for subscription-style escrows such as payroll or retainers
+/// Taken with `take_installment`, at most `installment` of the deposit every `interval` seconds
+/// Stored in place in `Escrow` and passed to `make` like `Vesting`, so it is both Pod and Borsh
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    InitSpace,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+#[repr(C)]
+pub struct Schedule {
+    pub installment: u64, // amount of the deposit released by each installment
+    pub interval: i64,    // seconds between two installments
+}
+
+/// Holding a taker must prove to fill the escrow, at least `amount` of `mint` in a token account they own
+/// Lets communities restrict offers to their members without maintaining an allowlist
+/// Stored in place in `Escrow` and passed to `make` like `Vesting`, so it is both Pod and Borsh
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    InitSpace,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+)]
+#[repr(C)]
+pub struct Gate {
+    pub mint: Pubkey, // mint the taker must hold
+    pub amount: u64,  // least amount of it the taker must hold, raw
+}
+
+impl Escrow {
+    /// Version of the layout above, bumped whenever a field is carved out of reserved space or the account grows
+    /// Version 1 is the original Borsh-serialized layout, version 3 added the rent destination, version 4 commitments,
+    /// version 5 the price, version 6 recurring installments, version 7 grew the account for the label,
+    /// version 8 flagged permanent delegates, version 9 reservations, using up the reserved space,
+    /// version 10 grew the account for templates and new reserved space, version 11 added token gates
+    /// version 12 gatekeeper networks, version 13 private terms, version 14 chained escrows, using up the reserved space,
+    /// version 15 grew the account for payout accounts and new reserved space, version 16 added order book listings,
+    /// version 17 mint pair indexes, version 18 categories, version 19 32-byte seeds and version 20 transferable makers
+    pub const VERSION: u8 = 20;
+
+    /// Seconds a taker's commitment reserves the escrow for them, to reveal it with `reveal_take`
+    pub const COMMIT_WINDOW: i64 = 30;
+
+    /// Space taken by the account, discriminator included
+    pub const SPACE: usize = 8 + std::mem::size_of::<Escrow>();
+
+    /// Space taken by escrows made in the layouts of versions 10 to 14, before the payout and the new reserved space were added
+    /// The payout starts where their zeroed `reserved: [u64; 2]` did
+    pub const V14_SPACE: usize = Escrow::SPACE - 32 - 128 + 16;
+
+    /// Space taken by escrows made in the layouts of versions 7 to 9, before the template and the new reserved space were added
+    pub const V9_SPACE: usize = Escrow::V14_SPACE - 32 - 128;
+
+    /// Space taken by escrows made in the zero-copy layouts of versions 2 to 6, before the label was added
+    pub const V6_SPACE: usize = Escrow::V9_SPACE - 32;
+
+    /// Reads an escrow made in the layouts of versions 2 to 6 from its data, discriminator excluded
+    /// The label sits where their zeroed `reserved` space started, so every field before it carries over as is
+    pub fn from_v6(data: &[u8]) -> Escrow {
+        let mut escrow = Escrow::default();
+        // Version 6 ended with `reserved: [u64; 2]`
+        let len = Escrow::V6_SPACE - 8 - 16;
+        bytemuck::bytes_of_mut(&mut escrow)[..len].copy_from_slice(&data[..len]);
+        escrow.version = Escrow::VERSION;
+        escrow
+    }
+
+    /// Reads an escrow made in the layouts of versions 7 to 9 from its data, discriminator excluded
+    /// The template and the reserved space are appended after every field they had, which carry over as is
+    pub fn from_v9(data: &[u8]) -> Escrow {
+        let mut escrow = Escrow::default();
+        let len = Escrow::V9_SPACE - 8;
+        bytemuck::bytes_of_mut(&mut escrow)[..len].copy_from_slice(&data[..len]);
+        escrow.version = Escrow::VERSION;
+        escrow
+    }
+
+    /// Reads an escrow made in the layouts of versions 10 to 14 from its data, discriminator excluded
+    /// Their zeroed reserved space becomes the start of an unset payout, so every field carries over as is
+    pub fn from_v14(data: &[u8]) -> Escrow {
+        let mut escrow = Escrow::default();
+        let len = Escrow::V14_SPACE - 8;
+        bytemuck::bytes_of_mut(&mut escrow)[..len].copy_from_slice(&data[..len]);
+        escrow.version = Escrow::VERSION;
+        escrow
+    }
+
+    /// Whether the escrow is at lifecycle stage `status`
+    pub fn has_status(&self, status: EscrowStatus) -> bool {
+        self.status == status as u8
+    }
+
+    /// Whether the escrow has reached its final stage, only settled receipts can be closed
+    pub fn is_settled(&self) -> bool {
+        !(self.has_status(EscrowStatus::Open)
+            || self.has_status(EscrowStatus::Disputed)
+            || self.has_status(EscrowStatus::Vesting)
+            || self.has_status(EscrowStatus::Chained))
+    }
+
+    /// Moves the escrow to lifecycle stage `status`
+    pub fn set_status(&mut self, status: EscrowStatus) {
+        self.status = status as u8;
+    }
+
+    /// The only account allowed to take the escrow, `None` if anyone can
+    pub fn taker(&self) -> Option<Pubkey> {
+        (self.taker != Pubkey::default()).then_some(self.taker)
+    }
+
+    /// Reserves the escrow for `taker`, or opens it to anyone with `None`
+    pub fn set_taker(&mut self, taker: Option<Pubkey>) {
+        self.taker = taker.unwrap_or_default();
+    }
+
+    /// Whether `taker` may take the escrow
+    pub fn is_taker_allowed(&self, taker: &Pubkey) -> bool {
+        self.taker().is_none_or(|allowed| allowed == *taker)
+    }
+
+    /// The arbiter settling disputes over the vault, `None` if the escrow can't be disputed
+    pub fn arbiter(&self) -> Option<Pubkey> {
+        (self.arbiter != Pubkey::default()).then_some(self.arbiter)
+    }
+
+    /// The template the escrow's terms were made from, `None` if it was made without one
+    pub fn template(&self) -> Option<Pubkey> {
+        (self.template != Pubkey::default()).then_some(self.template)
+    }
+
+    /// The SHA-256 hash of the preimage a taker must reveal, `None` if the escrow isn't hashlocked
+    pub fn hashlock(&self) -> Option<[u8; 32]> {
+        (self.hashlock != [0; 32]).then_some(self.hashlock)
+    }
+
+    /// The maker's label without its zero padding, `None` if unset or not valid UTF-8
+    pub fn label(&self) -> Option<&str> {
+        let len = self.label.iter().position(|&b| b == 0).unwrap_or(32);
+        std::str::from_utf8(&self.label[..len])
+            .ok()
+            .filter(|label| !label.is_empty())
+    }
+
+    /// Whether mint_a or mint_b had a permanent delegate when the escrow was made, able to move tokens out of its accounts
+    pub fn has_permanent_delegate(&self) -> bool {
+        self.permanent_delegate != 0
+    }
+
+    /// The schedule the taker claims the deposit on, `None` if it is released at once
+    pub fn vesting(&self) -> Option<Vesting> {
+        (self.vesting.duration != 0).then_some(self.vesting)
+    }
+
+    /// The token gate a taker must pass to fill the escrow, `None` if anyone can
+    pub fn gate(&self) -> Option<Gate> {
+        (self.gate.mint != Pubkey::default()).then_some(self.gate)
+    }
+
+    /// The gatekeeper network whose gateway token a taker must hold to fill the escrow, `None` if no identity pass is needed
+    pub fn gatekeeper_network(&self) -> Option<Pubkey> {
+        (self.gatekeeper_network != Pubkey::default()).then_some(self.gatekeeper_network)
+    }
+
+    /// The installments the escrow is taken in, `None` if it can be filled freely
+    pub fn schedule(&self) -> Option<Schedule> {
+        (self.schedule.interval != 0).then_some(self.schedule)
+    }
+
+    /// Whether the next installment can be taken at `now`, the first one being due right away
+    pub fn is_installment_due(&self, now: i64) -> bool {
+        self.schedule()
+            .is_some_and(|schedule| now >= self.last_fill_ts.saturating_add(schedule.interval))
+    }
+
+    /// Where the rent of the escrow and its vault goes once closed
+    pub fn rent_destination(&self) -> RentDestination {
+        match self.rent_destination {
+            1 => RentDestination::Maker,
+            2 => RentDestination::Taker,
+            3 => RentDestination::Treasury(self.rent_treasury),
+            _ => RentDestination::Split,
+        }
+    }
+
+    /// Sets where the rent of the escrow and its vault goes once closed
+    pub fn set_rent_destination(&mut self, destination: RentDestination) {
+        (self.rent_destination, self.rent_treasury) = match destination {
+            RentDestination::Split => (0, Pubkey::default()),
+            RentDestination::Maker => (1, Pubkey::default()),
+            RentDestination::Taker => (2, Pubkey::default()),
+            RentDestination::Treasury(treasury) => (3, treasury),
+        };
+    }
+
+    /// Whether the account is kept as a receipt once settled
+    pub fn keep_receipt(&self) -> bool {
+        self.keep_receipt != 0
+    }
+
+    /// The token account the owner designated for the proceeds, `None` if they go to the owner's associated token account
+    pub fn payout(&self) -> Option<Pubkey> {
+        (self.payout != Pubkey::default()).then_some(self.payout)
+    }
+
+    /// Index of the page of its mint pair's order book listing the escrow, `None` if it isn't listed
+    pub fn order_book_page(&self) -> Option<u32> {
+        self.order_book_page
+            .checked_sub(1)
+            .map(|index| index as u32)
+    }
+
+    /// Whether the escrow is counted in its mint pair's index, see `PairIndex`
+    pub fn is_indexed(&self) -> bool {
+        self.indexed_deposit != 0
+    }
+
+    /// Address of the token account fills pay the proceeds to: the payout if designated, else `owner`'s associated token account
+    /// for `mint_b` under `token_program`
+    pub fn payout_address(
+        &self,
+        owner: &Pubkey,
+        mint_b: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Pubkey {
+        self.payout().unwrap_or_else(|| {
+            get_associated_token_address_with_program_id(owner, mint_b, token_program)
+        })
+    }
+
+    /// Whether the vault is a token account at the escrow's `["vault", escrow]` PDA rather than its associated token account
+    pub fn pda_vault(&self) -> bool {
+        self.pda_vault != 0
+    }
+
+    /// Address of the vault of the escrow at `escrow`, whose mint_a is owned by `token_program`
+    pub fn vault_address(&self, escrow: &Pubkey, token_program: &Pubkey) -> Result<Pubkey> {
+        if self.pda_vault() {
+            return Pubkey::create_program_address(
+                &[b"vault", escrow.as_ref(), &[self.vault_bump]],
+                &crate::ID,
+            )
+            .map_err(|_| EscrowError::VaultMismatch.into());
+        }
+        Ok(get_associated_token_address_with_program_id(
+            escrow,
+            &self.mint_a,
+            token_program,
+        ))
+    }
+
+    /// Whether the escrow is paid with any NFT verified in the `mint_b` collection
+    pub fn collection_offer(&self) -> bool {
+        self.collection_offer != 0
+    }
+
+    /// Whether the escrow's deadline has passed at `now`, escrows without an expiry never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+
+    /// Amount of the deposit vested to the taker at `now`, the whole deposit for escrows without a vesting schedule
+    pub fn vested(&self, now: i64) -> Result<u64> {
+        let Some(vesting) = self.vesting() else {
+            return Ok(self.deposit);
+        };
+        let elapsed = now.saturating_sub(self.vesting_start);
+        if elapsed < vesting.cliff {
+            return Ok(0);
+        }
+        if elapsed >= vesting.duration {
+            return Ok(self.deposit);
+        }
+        u64::try_from(self.deposit as u128 * elapsed as u128 / vesting.duration as u128)
+            .map_err(|_| EscrowError::MathOverflow.into())
+    }
+
+    /// The price fills are settled at, as `(price_a, price_b)`: `price_b` of mint_b for every `price_a` of mint_a
+    /// Fixed when the escrow is priced, so rounding on partial fills doesn't drift it
+    /// Escrows made before the price was stored are priced at their current `deposit` and `receive`
+    pub fn price(&self) -> (u64, u64) {
+        if self.price_a == 0 {
+            return (self.deposit, self.receive);
+        }
+        (self.price_a, self.price_b)
+    }
+
+    /// Prices the escrow at `receive` of mint_b for its whole current deposit
+    pub fn set_receive(&mut self, receive: u64) {
+        self.receive = receive;
+        (self.price_a, self.price_b) = (self.deposit, receive);
+    }
+
+    /// Amount of mint_b asked for `deposit` of mint_a at the escrow's price
+    /// Rounded up so the maker never asks less than the agreed rate
+    pub fn receive_for(&self, deposit: u64) -> Result<u64> {
+        let (price_a, price_b) = self.price();
+        pricing::scale(deposit, price_b, price_a, Rounding::Up)
+    }
+
+    /// Amount of mint_a that `payment` of mint_b buys at the escrow's price
+    /// Rounded down so the maker never sells below the agreed rate
+    pub fn deposit_for(&self, payment: u64) -> Result<u64> {
+        let (price_a, price_b) = self.price();
+        pricing::scale(payment, price_a, price_b, Rounding::Down)
+    }
+
+    /// Whether `other` trades the same mints at the same price and on the same terms, so that both escrows can be merged
+    /// Prices are compared as ratios, and only the amounts, seed and commitment may differ
+    pub fn has_same_terms(&self, other: &Escrow) -> bool {
+        let (price_a, price_b) = self.price();
+        let (other_a, other_b) = other.price();
+        price_a as u128 * other_b as u128 == other_a as u128 * price_b as u128
+            && (self.maker, self.owner, self.mint_a, self.mint_b)
+                == (other.maker, other.owner, other.mint_a, other.mint_b)
+            && (self.taker, self.arbiter, self.hashlock)
+                == (other.taker, other.arbiter, other.hashlock)
+            && (self.vesting, self.schedule) == (other.vesting, other.schedule)
+            && self.expiry == other.expiry
+            && self.collection_offer == other.collection_offer
+            && self.rent_destination() == other.rent_destination()
+            && self.template == other.template
+            && self.gate == other.gate
+            && self.gatekeeper_network == other.gatekeeper_network
+            && self.terms_hash == other.terms_hash
+            && self.chained_seed == other.chained_seed
+    }
+
+    /// Commitment binding `taker` to take `escrow`, hiding their `salt` until they reveal it
+    pub fn commitment(escrow: &Pubkey, taker: &Pubkey, salt: &[u8; 32]) -> [u8; 32] {
+        hashv(&[escrow.as_ref(), taker.as_ref(), salt]).to_bytes()
+    }
+
+    /// Hash of the private terms of an escrow, binding the `mint_b` and `receive` only its maker and chosen takers know
+    /// The `salt` keeps the terms from being guessed by hashing likely mints and amounts
+    pub fn terms_hash(mint_b: &Pubkey, receive: u64, salt: &[u8; 32]) -> [u8; 32] {
+        hashv(&[mint_b.as_ref(), &receive.to_le_bytes(), salt]).to_bytes()
+    }
+
+    /// Seed the address of an escrow made with `seed` and `seed_id` is derived from, after `b"escrow"` and its maker
+    /// Its `seed_id` if it has one, so integrators can derive it from their own identifiers, else `seed` in little-endian
+    pub fn address_seed_of(seed: u64, seed_id: Option<[u8; 32]>) -> Vec<u8> {
+        match seed_id {
+            Some(seed_id) => seed_id.to_vec(),
+            None => seed.to_le_bytes().to_vec(),
+        }
+    }
+
+    /// Seed the escrow's address is derived from, see `address_seed_of`
+    pub fn address_seed(&self) -> Vec<u8> {
+        Escrow::address_seed_of(self.seed, self.seed_id())
+    }
+
+    /// Maker the escrow's address is derived from, after `b"escrow"`, which `transfer_maker` leaves as it was
+    pub fn address_maker(&self) -> Pubkey {
+        if self.address_maker == Pubkey::default() {
+            return self.maker;
+        }
+        self.address_maker
+    }
+
+    /// Caller-supplied 32-byte seed the escrow's address is derived from, `None` if derived from its `seed`
+    pub fn seed_id(&self) -> Option<[u8; 32]> {
+        (self.seed_id != [0; 32]).then_some(self.seed_id)
+    }
+
+    /// Seed of the maker's escrow funded by the proceeds of fills, `None` if they go to the owner
+    pub fn chained_seed(&self) -> Option<u64> {
+        (self.chained_seed != 0).then_some(self.chained_seed)
+    }
+
+    /// Whether the escrow's terms are private, its mint_b and receive only known by their hash until revealed
+    pub fn is_private(&self) -> bool {
+        self.terms_hash != [0; 32]
+    }
+
+    /// Whether a taker's commitment still reserves the escrow at `now`
+    pub fn is_committed(&self, now: i64) -> bool {
+        now < self.commit_deadline
+    }
+
+    /// Whether a taker's reservation still holds the escrow at `slot`
+    pub fn is_reserved(&self, slot: u64) -> bool {
+        slot < self.reserved_until
+    }
+
+    /// Checks a take of `escrow` by `taker` against any pending commitment
+    /// While one is pending only its taker can take the escrow, by revealing their `salt`, otherwise no salt is expected
+    pub fn check_commitment(
+        &self,
+        escrow: &Pubkey,
+        taker: &Pubkey,
+        salt: Option<&[u8; 32]>,
+        now: i64,
+    ) -> Result<()> {
+        match (self.is_committed(now), salt) {
+            (false, None) => Ok(()),
+            (true, Some(salt)) => {
+                require!(
+                    Escrow::commitment(escrow, taker, salt) == self.commit_hash,
+                    EscrowError::InvalidCommitment
+                );
+                Ok(())
+            }
+            (true, None) => err!(EscrowError::CommitmentPending),
+            (false, Some(_)) => err!(EscrowError::NoCommitment),
+        }
+    }
+
+    /// Checks `preimage` against the escrow's hashlock, it must be revealed if and only if the escrow is hashlocked
+    pub fn check_preimage(&self, preimage: Option<&[u8]>) -> Result<()> {
+        match (self.hashlock(), preimage) {
+            (None, None) => Ok(()),
+            (Some(hashlock), Some(preimage)) => {
+                require!(
+                    hash(preimage).to_bytes() == hashlock,
+                    EscrowError::InvalidPreimage
+                );
+                Ok(())
+            }
+            (Some(_), None) => err!(EscrowError::HashlockRequired),
+            (None, Some(_)) => err!(EscrowError::NotHashlocked),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escrow(deposit: u64, receive: u64) -> Escrow {
+        Escrow {
+            deposit,
+            receive,
+            price_a: deposit,
+            price_b: receive,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn receive_for_handles_u64_max_amounts() {
+        let escrow = escrow(u64::MAX, u64::MAX);
+        assert_eq!(escrow.receive_for(u64::MAX).unwrap(), u64::MAX);
+        assert_eq!(escrow.deposit_for(u64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn receive_for_rounds_up_and_deposit_for_rounds_down() {
+        let escrow = escrow(3, 2);
+        assert_eq!(escrow.receive_for(1).unwrap(), 1);
+        assert_eq!(escrow.deposit_for(1).unwrap(), 1);
+        assert_eq!(escrow.deposit_for(3).unwrap(), 4);
+    }
+
+    #[test]
+    fn pricing_past_u64_max_fails_with_math_overflow() {
+        assert_eq!(
+            escrow(1, 2).receive_for(u64::MAX).unwrap_err(),
+            EscrowError::MathOverflow.into()
+        );
+        assert_eq!(
+            escrow(2, 1).deposit_for(u64::MAX).unwrap_err(),
+            EscrowError::MathOverflow.into()
+        );
+    }
+
+    #[test]
+    fn vested_handles_a_u64_max_deposit() {
+        let escrow = Escrow {
+            vesting: Vesting {
+                cliff: 0,
+                duration: i64::MAX,
+            },
+            ..escrow(u64::MAX, 1)
+        };
+        assert_eq!(escrow.vested(i64::MAX - 1).unwrap(), u64::MAX - 3);
+        assert_eq!(escrow.vested(i64::MAX).unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn same_terms_compare_prices_as_ratios() {
+        let max = escrow(u64::MAX, u64::MAX);
+        assert!(max.has_same_terms(&escrow(1, 1)));
+        assert!(!max.has_same_terms(&escrow(u64::MAX, u64::MAX - 1)));
+    }
+
+    #[test]
+    fn terms_hashes_bind_every_private_term() {
+        let (mint_b, salt) = (Pubkey::new_unique(), [7; 32]);
+        let hash = Escrow::terms_hash(&mint_b, 100, &salt);
+        assert_eq!(hash, Escrow::terms_hash(&mint_b, 100, &salt));
+        assert_ne!(hash, Escrow::terms_hash(&Pubkey::new_unique(), 100, &salt));
+        assert_ne!(hash, Escrow::terms_hash(&mint_b, 101, &salt));
+        assert_ne!(hash, Escrow::terms_hash(&mint_b, 100, &[8; 32]));
+    }
+
+    #[test]
+    fn address_seeds_fall_back_to_the_u64_seed() {
+        let mut escrow = escrow(u64::MAX, 1);
+        escrow.seed = 42;
+        assert_eq!(escrow.seed_id(), None);
+        assert_eq!(escrow.address_seed(), 42u64.to_le_bytes().to_vec());
+
+        escrow.seed_id = [7; 32];
+        assert_eq!(escrow.seed_id(), Some([7; 32]));
+        assert_eq!(escrow.address_seed(), vec![7; 32]);
+    }
+
+    #[test]
+    fn address_makers_fall_back_to_the_maker() {
+        let mut escrow = escrow(u64::MAX, 1);
+        escrow.maker = Pubkey::new_unique();
+        assert_eq!(escrow.address_maker(), escrow.maker);
+
+        let previous = escrow.maker;
+        escrow.address_maker = escrow.address_maker();
+        escrow.maker = Pubkey::new_unique();
+        assert_eq!(escrow.address_maker(), previous);
+    }
+
+    #[test]
+    fn v6_escrows_carry_over_with_an_empty_label() {
+        let v6 = Escrow {
+            version: 6,
+            last_fill_ts: i64::MAX,
+            ..escrow(u64::MAX, 1)
+        };
+        // A V6 account ends with the zeroed reserved space where the label now starts
+        let data = &bytemuck::bytes_of(&v6)[..Escrow::V6_SPACE - 8];
+
+        let escrow = Escrow::from_v6(data);
+        assert_eq!(escrow.version, Escrow::VERSION);
+        assert_eq!(escrow.label, [0; 32]);
+        assert_eq!(
+            bytemuck::bytes_of(&escrow)[1..],
+            bytemuck::bytes_of(&Escrow {
+                version: Escrow::VERSION,
+                ..v6
+            })[1..]
+        );
+    }
+
+    #[test]
+    fn v9_escrows_carry_over_without_a_template() {
+        let v9 = Escrow {
+            version: 9,
+            reserved_until: u64::MAX,
+            label: [7; 32],
+            ..escrow(u64::MAX, 1)
+        };
+        let data = &bytemuck::bytes_of(&v9)[..Escrow::V9_SPACE - 8];
+
+        let escrow = Escrow::from_v9(data);
+        assert_eq!(escrow.template(), None);
+        assert_eq!(
+            bytemuck::bytes_of(&escrow),
+            bytemuck::bytes_of(&Escrow {
+                version: Escrow::VERSION,
+                ..v9
+            })
+        );
+    }
+
+    #[test]
+    fn v14_escrows_carry_over_without_a_payout() {
+        let v14 = Escrow {
+            version: 14,
+            chained_seed: u64::MAX,
+            terms_hash: [7; 32],
+            ..escrow(u64::MAX, 1)
+        };
+        // A V14 account ends with the zeroed reserved space where the payout now starts
+        let data = &bytemuck::bytes_of(&v14)[..Escrow::V14_SPACE - 8];
+
+        let escrow = Escrow::from_v14(data);
+        assert_eq!(escrow.payout(), None);
+        assert_eq!(
+            bytemuck::bytes_of(&escrow),
+            bytemuck::bytes_of(&Escrow {
+                version: Escrow::VERSION,
+                ..v14
+            })
+        );
+    }
 }
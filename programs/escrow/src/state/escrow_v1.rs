@@ -0,0 +1,137 @@
+use anchor_lang::prelude::*;
+
+use crate::{Escrow, EscrowStatus, Vesting};
+
+/// The baseline, Borsh-serialized layout of `Escrow`, before makers could attach any term to it
+/// Its deposit was the balance of its vault, the escrow's associated token account, so `migrate_escrow` reads it from there
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct EscrowV0 {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub receive: u64,
+    pub bump: u8,
+}
+
+impl EscrowV0 {
+    /// Space taken by a baseline account, discriminator included
+    pub const SPACE: usize = 8 + EscrowV0::INIT_SPACE;
+
+    /// Carries the baseline terms over with the `deposit` its vault holds, the maker owning the position of an open escrow
+    pub fn into_escrow(self, deposit: u64) -> Escrow {
+        Escrow {
+            version: Escrow::VERSION,
+            status: EscrowStatus::Open as u8,
+            bump: self.bump,
+            seed: self.seed,
+            deposit,
+            receive: self.receive,
+            maker: self.maker,
+            owner: self.maker,
+            mint_a: self.mint_a,
+            mint_b: self.mint_b,
+            ..Default::default()
+        }
+    }
+}
+
+/// The original, Borsh-serialized layout of `Escrow`, before it was made zero-copy
+/// Only read by `migrate_escrow`, which rewrites V1 accounts in the current layout
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct EscrowV1 {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub owner: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64,
+    pub receive: u64,
+    pub expiry: i64,
+    pub taker: Option<Pubkey>,
+    pub arbiter: Option<Pubkey>,
+    pub hashlock: Option<[u8; 32]>,
+    pub vesting: Option<Vesting>,
+    pub vesting_start: i64,
+    pub claimed: u64,
+    pub status: EscrowStatus,
+    pub keep_receipt: bool,
+    pub collection_offer: bool,
+    pub bump: u8,
+}
+
+impl EscrowV1 {
+    /// Space taken by a V1 account, discriminator included
+    /// V1 accounts shared the `Escrow` discriminator, so they are told apart by their size
+    pub const SPACE: usize = 8 + EscrowV1::INIT_SPACE;
+}
+
+impl From<EscrowV1> for Escrow {
+    /// Carries the V1 terms over as they are, fields added since then start zeroed
+    fn from(v1: EscrowV1) -> Self {
+        Escrow {
+            version: Escrow::VERSION,
+            status: v1.status as u8,
+            keep_receipt: v1.keep_receipt.into(),
+            collection_offer: v1.collection_offer.into(),
+            bump: v1.bump,
+            seed: v1.seed,
+            deposit: v1.deposit,
+            receive: v1.receive,
+            expiry: v1.expiry,
+            vesting: v1.vesting.unwrap_or_default(),
+            vesting_start: v1.vesting_start,
+            claimed: v1.claimed,
+            maker: v1.maker,
+            owner: v1.owner,
+            mint_a: v1.mint_a,
+            mint_b: v1.mint_b,
+            taker: v1.taker.unwrap_or_default(),
+            arbiter: v1.arbiter.unwrap_or_default(),
+            hashlock: v1.hashlock.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn baseline_escrows_are_told_apart_by_their_size() {
+        assert_eq!(EscrowV0::SPACE, 121);
+        assert_ne!(EscrowV0::SPACE, EscrowV1::SPACE);
+        assert_ne!(EscrowV0::SPACE, Escrow::V6_SPACE);
+        assert_ne!(EscrowV0::SPACE, Escrow::SPACE);
+    }
+
+    #[test]
+    fn baseline_escrows_carry_their_terms_over() {
+        let maker = Pubkey::new_unique();
+        let v0 = EscrowV0 {
+            seed: 7,
+            maker,
+            mint_a: Pubkey::new_unique(),
+            mint_b: Pubkey::new_unique(),
+            receive: 20,
+            bump: 254,
+        };
+        let mut data = Vec::new();
+        v0.serialize(&mut data).unwrap();
+        assert_eq!(data.len(), EscrowV0::SPACE - 8);
+
+        let escrow = EscrowV0::deserialize(&mut &data[..])
+            .unwrap()
+            .into_escrow(10);
+        assert_eq!(escrow.version, Escrow::VERSION);
+        assert!(escrow.has_status(EscrowStatus::Open));
+        assert_eq!((escrow.seed, escrow.bump), (7, 254));
+        assert_eq!((escrow.deposit, escrow.receive), (10, 20));
+        assert_eq!((escrow.maker, escrow.owner), (maker, maker));
+        assert_eq!((escrow.mint_a, escrow.mint_b), (v0.mint_a, v0.mint_b));
+        assert_eq!(escrow.address_maker(), maker);
+        assert_eq!(escrow.address_seed(), 7u64.to_le_bytes().to_vec());
+        assert!(!escrow.pda_vault());
+    }
+}
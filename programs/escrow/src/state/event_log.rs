@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Defines the on-chain log of the program's latest lifecycle events, stored in a single PDA, which includes:
+/// - the number of events recorded so far, the sequence number of the latest one,
+/// - and the last `EventLog::LEN` events, in a ring indexed by their sequence number.
+///
+/// Indexers that missed the program's logs, truncated or lost in a websocket gap, can catch up by reading this one account
+/// Only instructions passed the log record into it, and every one that does write-locks it
+#[account(zero_copy)]
+pub struct EventLog {
+    pub sequence: u64, // sequence number of the latest event, 0 before the first
+    pub entries: [LogEntry; EventLog::LEN], // latest events, the one numbered `n` at `n % LEN`
+}
+
+/// One event of the `EventLog`, a zeroed entry being a slot never written to
+#[zero_copy]
+#[derive(Debug, Default)]
+pub struct LogEntry {
+    pub sequence: u64, // position of the event in the log, increasing from 1 without gaps
+    pub timestamp: i64, // unix timestamp the event happened at
+    pub amount: u64,   // amount of mint_a deposited, taken or refunded
+    pub escrow: Pubkey, // escrow the event happened on
+    pub actor: Pubkey, // signer who caused it: the maker, the taker, or whoever refunded it
+    pub kind: u8,      // `LogKind` of the event
+    pub padding: [u8; 7], // keeps entries aligned, zeroed
+}
+
+/// Kind of an `EventLog` entry, stored as its `u8` discriminant
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LogKind {
+    Make = 1,
+    Take,
+    Refund,
+}
+
+impl EventLog {
+    /// Number of events the log keeps, the oldest being overwritten by each new one
+    pub const LEN: usize = 32;
+
+    /// Space of the account, discriminator included
+    pub const SPACE: usize = 8 + std::mem::size_of::<EventLog>();
+
+    /// Records an event of `kind` on `escrow`, numbered one past the latest
+    pub fn record(
+        &mut self,
+        kind: LogKind,
+        escrow: Pubkey,
+        actor: Pubkey,
+        amount: u64,
+        timestamp: i64,
+    ) -> Result<()> {
+        self.sequence = self
+            .sequence
+            .checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
+        self.entries[self.sequence as usize % Self::LEN] = LogEntry {
+            sequence: self.sequence,
+            timestamp,
+            amount,
+            escrow,
+            actor,
+            kind: kind as u8,
+            padding: [0; 7],
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_oldest_events_are_overwritten_once_the_log_is_full() {
+        let mut log: EventLog = bytemuck::Zeroable::zeroed();
+        for i in 0..EventLog::LEN as u64 + 2 {
+            log.record(LogKind::Take, Pubkey::default(), Pubkey::default(), i, 0)
+                .unwrap();
+        }
+
+        let mut sequences: Vec<u64> = log.entries.iter().map(|e| e.sequence).collect();
+        sequences.sort();
+        assert_eq!(
+            sequences,
+            (3..=EventLog::LEN as u64 + 2).collect::<Vec<_>>()
+        );
+        assert_eq!(log.sequence, EventLog::LEN as u64 + 2);
+    }
+}
@@ -0,0 +1,15 @@
+use anchor_lang::prelude::*;
+
+/// Defines a protocol fee negotiated by the admin in place of the global one, which includes:
+/// - the escrow, or the maker of every escrow, it applies to,
+/// - the fee charged on their fills, in basis points,
+/// - and a bump seed for address generation security.
+///
+/// Takers opt in by passing it to `take`, so in practice an override can only lower the fee a fill pays
+#[account]
+#[derive(InitSpace)]
+pub struct FeeOverride {
+    pub subject: Pubkey, // escrow, or maker of the escrows, whose fills are charged `fee_bps`
+    pub fee_bps: u16,    // protocol fee charged instead of the config's, in basis points
+    pub bump: u8,        // bump seed for the fee override account
+}
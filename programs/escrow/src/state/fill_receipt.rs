@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+/// Defines the record of a taker's fills of an escrow, kept for accounting, airdrops and dispute evidence, which includes:
+/// - the escrow it records fills of, which may since have been closed,
+/// - the taker who filled it,
+/// - the total mint_a received and mint_b paid over every fill,
+/// - the number of fills, and the time of the first and the last one,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct FillReceipt {
+    pub escrow: Pubkey,  // escrow the fills were made on
+    pub taker: Pubkey,   // taker who made the fills, the only one able to close the receipt
+    pub deposit: u64,    // total amount of mint_a received by the taker
+    pub receive: u64,    // total amount of mint_b paid by the taker
+    pub fills: u32,      // number of fills recorded
+    pub first_fill: i64, // unix timestamp of the first fill
+    pub last_fill: i64,  // unix timestamp of the latest fill
+    pub bump: u8,        // bump seed for the fill receipt account
+}
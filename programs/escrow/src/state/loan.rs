@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for a collateralized loan, where the maker borrows mint_b against mint_a, which includes:
+/// - a seed,
+/// - the maker's public key, borrowing against the collateral they lock,
+/// - token types (`mint_a` locked as collateral and `mint_b` lent),
+/// - the collateral locked, the principal asked for and the amount to repay,
+/// - how long the maker has to repay once the loan is taken,
+/// - the lender and the repayment deadline, once the loan is taken,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct Loan {
+    pub seed: u64,              // seed for the loan account
+    pub maker: Pubkey,          // borrower, locks mint_a when opening the loan
+    pub mint_a: Pubkey,         // token locked as collateral
+    pub mint_b: Pubkey,         // token lent to the maker and repaid to the lender
+    pub collateral: u64,        // amount of mint_a locked in the vault
+    pub principal: u64,         // amount of mint_b the lender pays the maker
+    pub repayment: u64, // amount of mint_b the maker owes the lender to get the collateral back
+    pub duration: i64,  // seconds the maker has to repay once the loan is taken
+    pub lender: Option<Pubkey>, // lender who took the loan, if any
+    pub deadline: i64,  // unix timestamp after which the lender can liquidate, set once taken
+    pub bump: u8,       // bump seed for the loan account
+}
+
+impl Loan {
+    /// Whether the loan is past its repayment deadline at `now`, letting the lender claim the collateral
+    pub fn is_defaulted(&self, now: i64) -> bool {
+        self.lender.is_some() && now > self.deadline
+    }
+}
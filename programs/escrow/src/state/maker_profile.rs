@@ -0,0 +1,13 @@
+use anchor_lang::prelude::*;
+
+/// Defines the optional profile of a maker, which includes:
+/// - the maker it belongs to,
+/// - the number of escrows made through it, which is also the seed of the next one,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct MakerProfile {
+    pub maker: Pubkey, // maker the profile belongs to
+    pub offers: u64,   // escrows made through the profile, seeded 0 to `offers - 1`
+    pub bump: u8,      // bump seed for the profile account
+}
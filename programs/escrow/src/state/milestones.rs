@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for a milestone escrow, releasing a deposit to a contractor in stages, which includes:
+/// - a seed,
+/// - the maker's public key, who funds the milestones and approves each of them,
+/// - the contractor paid as milestones are approved,
+/// - the token the milestones are paid in,
+/// - the milestones, in the order they are expected to be delivered,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct MilestoneEscrow {
+    pub seed: u64,          // seed for the milestone escrow account
+    pub maker: Pubkey,      // funds the milestones and approves their release
+    pub contractor: Pubkey, // claims each milestone once approved
+    pub mint: Pubkey,       // token the milestones are paid in
+    #[max_len(10)]
+    pub milestones: Vec<Milestone>, // stages of the work, held together in one vault
+    pub bump: u8,           // bump seed for the milestone escrow account
+}
+
+/// A stage of the work, paid `amount` from the vault once the maker approves it
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct Milestone {
+    pub amount: u64,                // amount of `mint` released for this stage
+    pub description_hash: [u8; 32], // hash of the off-chain description of the deliverable
+    pub status: MilestoneStatus,    // progress of the stage's payment
+}
+
+/// Progress of a milestone's payment
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum MilestoneStatus {
+    Pending,
+    Approved,
+    Claimed,
+}
+
+/// Terms of a milestone, as passed to `make_milestones`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MilestoneTerms {
+    pub amount: u64,                // amount of `mint` released for this stage
+    pub description_hash: [u8; 32], // hash of the off-chain description of the deliverable
+}
+
+impl MilestoneEscrow {
+    /// Upper bound of `milestones`, must match the `max_len` the account is sized with
+    pub const MAX_MILESTONES: usize = 10;
+
+    /// Whether every milestone has been paid out, after which the escrow can be closed
+    pub fn is_complete(&self) -> bool {
+        self.milestones
+            .iter()
+            .all(|milestone| milestone.status == MilestoneStatus::Claimed)
+    }
+}
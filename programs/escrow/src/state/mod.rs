@@ -1,2 +1,80 @@
+// Older bytemuck derives emit padding checks that trip `dead_code` on their Pod structs, outside the structs' own lint scope
+#[allow(dead_code)]
 pub mod escrow;
 pub use escrow::*;
+
+pub mod escrow_v1;
+pub use escrow_v1::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod basket;
+pub use basket::*;
+
+pub mod swap;
+pub use swap::*;
+
+pub mod counter_offer;
+pub use counter_offer::*;
+
+pub mod auction;
+pub use auction::*;
+
+pub mod compressed_escrow;
+pub use compressed_escrow::*;
+
+pub mod maker_profile;
+pub use maker_profile::*;
+
+pub mod milestones;
+pub use milestones::*;
+
+pub mod loan;
+pub use loan::*;
+
+pub mod rfq_quote;
+pub use rfq_quote::*;
+
+pub mod fill_receipt;
+pub use fill_receipt::*;
+
+pub mod cancel_authority;
+pub use cancel_authority::*;
+
+pub mod auto_refund;
+pub use auto_refund::*;
+
+#[allow(dead_code)] // see `escrow`
+pub mod event_log;
+pub use event_log::*;
+
+pub mod fee_override;
+pub use fee_override::*;
+
+pub mod trader_stats;
+pub use trader_stats::*;
+
+pub mod reservation;
+pub use reservation::*;
+
+pub mod arbiter_committee;
+pub use arbiter_committee::*;
+
+pub mod template;
+pub use template::*;
+
+pub mod stake_escrow;
+pub use stake_escrow::*;
+
+pub mod edition_escrow;
+pub use edition_escrow::*;
+
+pub mod payment_options;
+pub use payment_options::*;
+
+pub mod order_book;
+pub use order_book::*;
+
+pub mod pair_index;
+pub use pair_index::*;
@@ -0,0 +1,94 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+/// Defines the head of the order book of a mint pair, which includes:
+/// - the mint its escrows offer and the mint they ask for,
+/// - the number of pages it spans,
+/// - and a bump seed for address generation security.
+///
+/// Clients discover the pair's open escrows by walking its pages, at `["order_book_page", mint_a, mint_b, index]` for every index below `pages`
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBook {
+    pub mint_a: Pubkey, // token the listed escrows offer
+    pub mint_b: Pubkey, // token the listed escrows ask for
+    pub pages: u32,     // number of pages added, indexed from 0
+    pub bump: u8,       // bump seed for the order book account
+}
+
+/// Defines a page of the order book of a mint pair, which includes:
+/// - the mints of the pair,
+/// - its index in the order book,
+/// - the escrows it lists, in no particular order,
+/// - and a bump seed for address generation security.
+///
+/// `make` lists an escrow in a page with room, and `take` and `refund` remove it once it is no longer open
+#[account]
+#[derive(InitSpace)]
+pub struct OrderBookPage {
+    pub mint_a: Pubkey, // token the listed escrows offer
+    pub mint_b: Pubkey, // token the listed escrows ask for
+    pub index: u32,     // position of the page in the order book
+    #[max_len(32)]
+    pub escrows: Vec<Pubkey>, // open escrows listed in the page
+    pub bump: u8,       // bump seed for the order book page account
+}
+
+impl OrderBookPage {
+    /// Upper bound of `escrows`, must match the `max_len` the account is sized with
+    pub const LEN: usize = 32;
+
+    /// Lists `escrow` in the page, failing once it is full
+    pub fn insert(&mut self, escrow: Pubkey) -> Result<()> {
+        require!(
+            self.escrows.len() < Self::LEN,
+            EscrowError::OrderBookPageFull
+        );
+        if !self.escrows.contains(&escrow) {
+            self.escrows.push(escrow);
+        }
+        Ok(())
+    }
+
+    /// Removes `escrow` from the page, returning whether it was listed in it
+    /// The last escrow takes its place, so removals don't shift the whole page
+    pub fn remove(&mut self, escrow: &Pubkey) -> bool {
+        match self.escrows.iter().position(|listed| listed == escrow) {
+            Some(i) => {
+                self.escrows.swap_remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_fill_up_and_free_slots_as_escrows_are_delisted() {
+        let mut page = OrderBookPage {
+            mint_a: Pubkey::new_unique(),
+            mint_b: Pubkey::new_unique(),
+            index: 0,
+            escrows: Vec::new(),
+            bump: 0,
+        };
+        let escrows: Vec<Pubkey> = (0..OrderBookPage::LEN)
+            .map(|_| Pubkey::new_unique())
+            .collect();
+        for escrow in &escrows {
+            page.insert(*escrow).unwrap();
+        }
+        assert!(page.insert(Pubkey::new_unique()).is_err());
+
+        assert!(page.remove(&escrows[0]));
+        assert!(!page.remove(&escrows[0]));
+        assert_eq!(page.escrows[0], escrows[OrderBookPage::LEN - 1]);
+        page.insert(escrows[0]).unwrap();
+        assert_eq!(page.escrows.len(), OrderBookPage::LEN);
+    }
+}
@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+/// Defines the index of a mint pair, which includes:
+/// - the mint its escrows offer and the mint they ask for,
+/// - the number of its indexed escrows still open,
+/// - the total deposit they hold,
+/// - and a bump seed for address generation security.
+///
+/// Aggregators read this one account to tell whether the pair has liquidity before any heavier query
+/// Escrows are indexed when made with it, every later change to their deposit or status must then be passed it
+#[account]
+#[derive(InitSpace)]
+pub struct PairIndex {
+    pub mint_a: Pubkey,     // token the indexed escrows offer
+    pub mint_b: Pubkey,     // token the indexed escrows ask for
+    pub open_escrows: u64,  // number of indexed escrows still open
+    pub total_deposit: u64, // amount of mint_a they hold for takers
+    pub bump: u8,           // bump seed for the pair index account
+}
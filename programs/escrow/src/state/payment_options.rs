@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+/// Defines the other mints the owner of an escrow accepts as payment for it, which includes:
+/// - the escrow they apply to, and the owner who set them and paid for the account,
+/// - the amount of mint_a the legs are priced for, the escrow's deposit when they were set,
+/// - each accepted mint and the amount of it asked for that much mint_a,
+/// - and a bump seed for address generation security.
+///
+/// Takers pay in any of them by passing it as mint_b, `take` then switches the escrow to that leg
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentOptions {
+    pub escrow: Pubkey, // escrow the options apply to
+    pub owner: Pubkey,  // owner of the escrow's position who set them, gets the rent back on close
+    pub price_a: u64,   // amount of mint_a each leg's `receive` is asked for
+    #[max_len(4)]
+    pub legs: Vec<PaymentLeg>, // other mints accepted, each with its own price
+    pub bump: u8,       // bump seed for the payment options account
+}
+
+/// A mint accepted as payment for an escrow besides its mint_b, and the amount of it asked for `PaymentOptions::price_a` of mint_a
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct PaymentLeg {
+    pub mint: Pubkey, // token accepted as payment
+    pub receive: u64, // amount of `mint` asked for `price_a` of mint_a
+}
+
+impl PaymentOptions {
+    /// Upper bound of `legs`, must match the `max_len` the account is sized with
+    pub const MAX_LEGS: usize = 4;
+
+    /// Index of the leg paying in `mint`, `None` if it isn't accepted
+    pub fn leg(&self, mint: &Pubkey) -> Option<usize> {
+        self.legs.iter().position(|leg| leg.mint == *mint)
+    }
+}
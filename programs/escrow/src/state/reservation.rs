@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+/// Defines a taker's bonded reservation of an escrow, holding it exclusively for them for a few slots, which includes:
+/// - the escrow it holds, and its maker,
+/// - the taker holding it, the only one able to fill the escrow until it expires,
+/// - the bond locked in the account on top of its rent,
+/// - the slot the reservation expires at,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct Reservation {
+    pub escrow: Pubkey, // escrow held by the reservation
+    pub maker: Pubkey, // maker of the escrow, the bond of an abandoned reservation going to its position's owner
+    pub taker: Pubkey, // taker holding the reservation, refunded the bond when they fill the escrow
+    pub bond: u64,     // lamports locked on top of the account's rent
+    pub expires: u64,  // slot from which the escrow can be filled by anyone again
+    pub bump: u8,      // bump seed for the reservation account
+}
+
+impl Reservation {
+    /// Smallest bond a reservation locks, 0.001 SOL, so holding offers without filling them has a cost
+    pub const MIN_BOND: u64 = 1_000_000;
+
+    /// Most slots a reservation can hold an escrow for, about a minute
+    pub const MAX_SLOTS: u64 = 150;
+
+    /// Most slots `reserve_fill` can hold an escrow for, about an hour, for takers sourcing mint_b before settling
+    pub const MAX_FILL_SLOTS: u64 = 9_000;
+
+    /// Smallest bond holding an escrow for `slots` slots, `MIN_BOND` for every `MAX_SLOTS` started
+    pub fn min_bond(slots: u64) -> u64 {
+        Self::MIN_BOND.saturating_mul(slots.div_ceil(Self::MAX_SLOTS))
+    }
+}
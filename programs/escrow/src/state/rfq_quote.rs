@@ -0,0 +1,17 @@
+use anchor_lang::prelude::*;
+
+/// Defines a funded quote posted by a prospective taker on an open escrow, which includes:
+/// - the escrow it is posted on,
+/// - the prospective taker,
+/// - the mint of the token quoted, the escrow's `mint_b`,
+/// - the amount of mint_b they'd pay for the whole deposit, locked in the quote's vault,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct RfqQuote {
+    pub escrow: Pubkey, // escrow the quote is posted on
+    pub taker: Pubkey,  // prospective taker who posted and funded the quote
+    pub mint_b: Pubkey, // token the quote is paid in
+    pub amount: u64,    // amount of mint_b offered for the whole deposit
+    pub bump: u8,       // bump seed for the quote account
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for an escrow of a native stake account, whose staker and withdrawer authorities the escrow PDA holds, which includes:
+/// - a seed,
+/// - maker's public key,
+/// - the stake account escrowed,
+/// - the token and amount the maker expects to receive for it,
+/// - an optional deadline after which it can only be refunded,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct StakeEscrow {
+    pub seed: u64,             // seed for the escrow account
+    pub maker: Pubkey,         // maker of the trade
+    pub stake_account: Pubkey, // stake account whose authorities the escrow holds
+    pub mint_b: Pubkey,        // token that the maker is expecting to receive
+    pub receive: u64,          // amount of mint_b that the maker is expecting to receive
+    pub expiry: i64, // unix timestamp after which the escrow can no longer be taken, 0 if it never expires
+    pub bump: u8,    // bump seed for the escrow account
+}
+
+impl StakeEscrow {
+    /// Whether the escrow's deadline has passed at `now`, escrows without an expiry never expire
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expiry != 0 && now >= self.expiry
+    }
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+/// Defines the data stored for a two-sided swap, where both parties lock their side before settling, which includes:
+/// - a seed,
+/// - the maker's and the taker's public keys,
+/// - token types (`mint_a` from the maker and `mint_b` from the taker),
+/// - the amount each side has to lock,
+/// - whether the taker has locked their side yet,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct Swap {
+    pub seed: u64,      // seed for the swap account
+    pub maker: Pubkey,  // maker of the trade, locks mint_a when proposing it
+    pub taker: Pubkey,  // counterparty, locks mint_b to accept it
+    pub mint_a: Pubkey, // token locked by the maker
+    pub mint_b: Pubkey, // token locked by the taker
+    pub amount_a: u64,  // amount of mint_a the maker locks in `vault_a`
+    pub amount_b: u64,  // amount of mint_b the taker locks in `vault_b`
+    pub funded: bool,   // whether the taker has locked their side, the swap can then be settled
+    pub bump: u8,       // bump seed for the swap account
+}
+
+impl Swap {
+    /// Whether `key` is one of the two parties of the swap
+    pub fn is_party(&self, key: &Pubkey) -> bool {
+        *key == self.maker || *key == self.taker
+    }
+}
@@ -0,0 +1,97 @@
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+
+/// Defines a reusable set of escrow terms the admin publishes, which includes:
+/// - the seed the template was created with,
+/// - the fee charged on fills of escrows made from it, in basis points,
+/// - its expiry policy, the longest an escrow made from it may stay open,
+/// - the merkle root of the takers allowed to fill its escrows, if restricted,
+/// - the arbiter every escrow made from it is put under, if any,
+/// - and a bump seed for address generation security.
+///
+/// Makers reference a template in `make` to adopt its terms, which `take` then enforces
+#[account]
+#[derive(InitSpace)]
+pub struct Template {
+    pub seed: u64,                // seed the template was created with
+    pub fee_bps: u16, // fee on fills of its escrows, replacing the config's and tiered fees
+    pub max_duration: i64, // seconds after make its escrows must expire within, no limit if 0
+    pub allowlist_root: [u8; 32], // merkle root of the takers allowed to fill its escrows, anyone if zeroed
+    pub arbiter: Pubkey,          // arbiter its escrows are put under, none if zeroed
+    pub bump: u8,                 // bump seed for the template account
+}
+
+impl Template {
+    /// The arbiter escrows made from the template are put under, `None` if they have none
+    pub fn arbiter(&self) -> Option<Pubkey> {
+        (self.arbiter != Pubkey::default()).then_some(self.arbiter)
+    }
+
+    /// Whether takers must be on the template's allowlist to fill its escrows
+    pub fn is_restricted(&self) -> bool {
+        self.allowlist_root != [0; 32]
+    }
+
+    /// Whether `proof` shows `taker` is on the allowlist
+    /// Leaves hash the taker's key, and each level hashes the sorted pair of nodes so proofs need no directions
+    pub fn is_allowlisted(&self, taker: &Pubkey, proof: &[[u8; 32]]) -> bool {
+        let leaf = hashv(&[taker.as_ref()]).to_bytes();
+        let root = proof.iter().fold(leaf, |node, sibling| {
+            let (left, right) = match node <= *sibling {
+                true => (node, *sibling),
+                false => (*sibling, node),
+            };
+            hashv(&[&left, &right]).to_bytes()
+        });
+        root == self.allowlist_root
+    }
+}
+
+/// Defines a taker's proven place on a template's allowlist, which includes:
+/// - the template whose allowlist the taker is on,
+/// - the taker,
+/// - and a bump seed for address generation security.
+///
+/// Proving membership once keeps the merkle proof out of every `take`
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistPass {
+    pub template: Pubkey, // template whose allowlist the taker proved membership of
+    pub taker: Pubkey,    // taker allowed to fill the template's escrows
+    pub bump: u8,         // bump seed for the allowlist pass account
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        match left <= right {
+            true => hashv(&[&left, &right]).to_bytes(),
+            false => hashv(&[&right, &left]).to_bytes(),
+        }
+    }
+
+    #[test]
+    fn allowlisted_takers_prove_membership_in_any_position() {
+        let takers = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let leaves = takers.map(|taker| hashv(&[taker.as_ref()]).to_bytes());
+        let template = Template {
+            seed: 0,
+            fee_bps: 0,
+            max_duration: 0,
+            allowlist_root: node(node(leaves[0], leaves[1]), leaves[2]),
+            arbiter: Pubkey::default(),
+            bump: 0,
+        };
+
+        assert!(template.is_allowlisted(&takers[0], &[leaves[1], leaves[2]]));
+        assert!(template.is_allowlisted(&takers[1], &[leaves[0], leaves[2]]));
+        assert!(template.is_allowlisted(&takers[2], &[node(leaves[0], leaves[1])]));
+        assert!(!template.is_allowlisted(&Pubkey::new_unique(), &[leaves[1], leaves[2]]));
+        assert!(!template.is_allowlisted(&takers[2], &[leaves[0]]));
+    }
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+/// Defines the cumulative fill volume of a taker in a mint, which the config's fee tiers are applied on, which includes:
+/// - the taker it tracks,
+/// - the mint the volume is paid in, tiers only comparing amounts of the same mint,
+/// - the total amount of that mint paid and the number of fills,
+/// - and a bump seed for address generation security.
+#[account]
+#[derive(InitSpace)]
+pub struct TraderStats {
+    pub trader: Pubkey, // taker whose fills are tracked
+    pub mint: Pubkey,   // mint_b of the tracked fills, the volume is in its raw units
+    pub volume: u64,    // total amount of `mint` paid over every tracked fill
+    pub fills: u64,     // number of tracked fills
+    pub bump: u8,       // bump seed for the trader stats account
+}
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        extension::{
+            transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+        },
+        state::Account as SplTokenAccount,
+    },
+    token_interface::{Mint, TokenAccount},
+};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList,
+};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+
+declare_id!("CQoDJQzsCRXDzddwsfBKHv1VRRALFm5QzQkeXioMeTFQ");
+
+/// Token-2022 transfer hook counting the transfers of a mint in a PDA, which it requires as an extra account
+/// Only used by the tests, to check the escrow forwards the accounts of hooked mints to their transfers
+#[program]
+pub mod transfer_hook {
+    use super::*;
+
+    /// Creates the mint's counter and the account listing it as the extra account of every transfer
+    pub fn initialize_extra_account_meta_list(
+        ctx: Context<InitializeExtraAccountMetaList>,
+    ) -> Result<()> {
+        ctx.accounts.counter.bump = ctx.bumps.counter;
+
+        // `[b"counter", mint]`, the mint being the second account of `Execute`
+        let metas = [ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: b"counter".to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )?];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &metas,
+        )?;
+        Ok(())
+    }
+
+    /// Counts a transfer, only callable by Token-2022 while it transfers
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        let source = ctx.accounts.source.to_account_info();
+        let data = source.try_borrow_data()?;
+        let account = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+        require!(
+            bool::from(account.get_extension::<TransferHookAccount>()?.transferring),
+            HookError::NotTransferring
+        );
+
+        ctx.accounts.counter.transfers += 1;
+        Ok(())
+    }
+
+    /// Token-2022 calls `Execute` with the discriminator of the transfer hook interface rather than Anchor's
+    pub fn fallback<'info>(
+        program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        match TransferHookInstruction::unpack(data)? {
+            TransferHookInstruction::Execute { amount } => {
+                __private::__global::transfer_hook(program_id, accounts, &amount.to_le_bytes())
+            }
+            _ => Err(ProgramError::InvalidInstructionData.into()),
+        }
+    }
+}
+
+/// Defines the accounts needed to set up the hook of a mint
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Initialized as the `ExtraAccountMetaList` of `mint`, at the address Token-2022 derives it at
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(1)?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// The hooked mint
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Counts the transfers of `mint`
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump
+    )]
+    pub counter: Account<'info, Counter>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Defines the accounts of `Execute`, in the order Token-2022 passes them
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    #[account(token::mint = mint)]
+    pub source: InterfaceAccount<'info, TokenAccount>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(token::mint = mint)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: The owner or delegate of `source`, already checked by Token-2022
+    pub authority: UncheckedAccount<'info>,
+
+    /// CHECK: The `ExtraAccountMetaList` of `mint`, only checked by its address
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump = counter.bump
+    )]
+    pub counter: Account<'info, Counter>,
+}
+
+/// Number of transfers of a mint
+#[account]
+#[derive(InitSpace)]
+pub struct Counter {
+    pub transfers: u64,
+    pub bump: u8,
+}
+
+#[error_code]
+pub enum HookError {
+    #[msg("The hook can only be called by Token-2022 while transferring")]
+    NotTransferring,
+}
@@ -1,23 +1,45 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program, BN } from "@coral-xyz/anchor";
 import { Escrow } from "../target/types/escrow";
+import { TransferHook } from "../target/types/transfer_hook";
 import {
+	Authorized,
 	Keypair,
 	LAMPORTS_PER_SOL,
+	Lockup,
 	PublicKey,
+	StakeProgram,
 	SystemProgram,
 	Transaction,
+	TransactionInstruction,
 } from "@solana/web3.js";
 import {
+	AccountState,
+	ExtensionType,
 	MINT_SIZE,
+	NATIVE_MINT,
 	TOKEN_2022_PROGRAM_ID,
+	TOKEN_PROGRAM_ID,
+	createApproveInstruction,
 	createAssociatedTokenAccountIdempotentInstruction,
+	createFreezeAccountInstruction,
+	createInitializeAccount3Instruction,
+	createInitializeDefaultAccountStateInstruction,
 	createInitializeMint2Instruction,
+	createInitializeNonTransferableMintInstruction,
+	createInitializePermanentDelegateInstruction,
+	createInitializeTransferFeeConfigInstruction,
+	createInitializeTransferHookInstruction,
 	createMintToInstruction,
+	createThawAccountInstruction,
+	createTransferCheckedInstruction,
+	getAccountLen,
 	getAssociatedTokenAddressSync,
 	getMinimumBalanceForRentExemptMint,
+	getMintLen,
 } from "@solana/spl-token";
-import { randomBytes } from "crypto";
+import { keccak_256 } from "@noble/hashes/sha3";
+import { createHash, randomBytes } from "crypto";
 import { expect } from "chai";
 
 describe("escrow", () => {
@@ -25,6 +47,8 @@ describe("escrow", () => {
 	const provider = anchor.AnchorProvider.env();
 	anchor.setProvider(provider);
 	const program = anchor.workspace.AnchorEscrow as Program<Escrow>;
+	// Token-2022 transfer hook counting the transfers of the hooked mint the tests escrow
+	const hookProgram = anchor.workspace.TransferHook as Program<TransferHook>;
 
 	const connection = provider.connection;
 
@@ -47,6 +71,10 @@ describe("escrow", () => {
 		return signature;
 	};
 
+	// Escrow labels are 32 zero-padded bytes, and most escrows made here go without one
+	const label = (text: string): number[] => [...Buffer.concat([Buffer.from(text), Buffer.alloc(32)]).subarray(0, 32)];
+	const noLabel = label("");
+
 	const seed = new BN(randomBytes(8));
 
 	// Generate the key pairs for the maker, taker, and two mints
@@ -100,8 +128,22 @@ describe("escrow", () => {
 		tokenProgram // programId
 	);
 
+	// The program's global config PDA and the token account collecting the protocol fees paid in `mintB`
+	const config = PublicKey.findProgramAddressSync(
+		[Buffer.from("config")],
+		program.programId
+	)[0];
+	const feeVault = getAssociatedTokenAddressSync(
+		mintB.publicKey,
+		config,
+		true,
+		tokenProgram
+	);
+
 	const accounts = {
 		maker: maker.publicKey,
+		// The maker keeps their position in the escrow, so they also receive its proceeds and refunds
+		owner: maker.publicKey,
 		taker: taker.publicKey,
 		mintA: mintA.publicKey,
 		mintB: mintB.publicKey,
@@ -109,11 +151,89 @@ describe("escrow", () => {
 		makerAtaB,
 		takerAtaA,
 		takerAtaB,
+		ownerAtaA: makerAtaA,
+		ownerAtaB: makerAtaB,
 		escrow,
 		vault,
-		tokenProgram,
+		config,
+		feeVault,
+		// No frontend routed these fills, so the whole protocol fee goes to the fee vault
+		referrer: null,
+		referrerAtaB: null,
+		// Neither side proves membership, so the regular protocol fee applies
+		memberNft: null,
+		memberMetadata: null,
+		// Escrows are made with a random seed rather than through the maker's profile
+		makerProfile: null,
+		// No escrow sends its rent to a treasury
+		rentTreasury: null,
+		// Fills aren't recorded in a fill receipt unless a test passes one
+		fillReceipt: null,
+		// The maker refunds their escrows themselves rather than through a cancel authority
+		cancelAuthority: null,
+		// Vaults are the escrow's associated token account unless a test makes one at the escrow's PDA
+		pdaVault: null,
+		// Nothing is recorded in the on-chain event log unless a test passes it
+		eventLog: null,
+		// Fills are charged the config's fee unless a test passes a fee override
+		feeOverride: null,
+		// Fees are charged in mint_b unless a test switches the config's fee currency
+		feeVaultA: null,
+		feeTreasury: null,
+		// Fills are charged the config's fee rather than the taker's fee tier unless a test passes their stats
+		traderStats: null,
+		// Escrows aren't reserved unless a test reserves one
+		reservation: null,
+		// Refunds of escrows no one reserved owe no cancellation penalty
+		reserverAtaA: null,
+		// Proceeds go to the owner unless a test chains an escrow to the one filled
+		chainedEscrow: null,
+		chainedVault: null,
+		// Escrows are made on their own terms rather than from a template unless a test passes one
+		template: null,
+		allowlistPass: null,
+		// Escrows aren't token-gated unless a test gates one
+		gateAccount: null,
+		// Escrows don't require an identity pass unless a test names a gatekeeper network
+		gatewayToken: null,
+		// Auction bids are open unless a test seals them
+		sealedBid: null,
+		// Escrows aren't listed in an order book unless a test lists one
+		orderBookPage: null,
+		pairIndex: null,
+		// Makers sign for themselves unless a test makes an escrow for a PDA maker
+		makerAuthority: null,
+		makerProgram: null,
+		tokenProgramA: tokenProgram,
+		tokenProgramB: tokenProgram,
 	};
 
+	// Makes an escrow of `deposit` mint_a for `receive` mint_b under a fresh seed, returning it and its vault
+	async function makeEscrow(deposit: number, receive: number, rentDestination: object = { split: {} }) {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		await program.methods
+			.make(seed, null, new BN(deposit), new BN(receive), new BN(0), null, null, null, null, null, false, rentDestination, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+		return { escrow, vault };
+	}
+
+	// Remaining accounts `batch_take` fills an escrow of the maker with, `[escrow, maker, owner, vault, owner_ata_b, owner_ata_a]`
+	// Without `ownerAtaA` the program id is passed in its place, and a surplus in the vault goes to the taker
+	const batchGroup = ({ escrow, vault, ownerAtaA = program.programId }: { escrow: PublicKey; vault: PublicKey; ownerAtaA?: PublicKey }) =>
+		[escrow, maker.publicKey, maker.publicKey, vault, makerAtaB, ownerAtaA].map((pubkey) => ({
+			pubkey,
+			isSigner: false,
+			isWritable: !pubkey.equals(program.programId),
+		}));
+
 	async function tokenBalances(accounts: { [label: string]: PublicKey }) {
 		let balances: { [label: string]: number } = {};
 		for (const [label, publicKey] of Object.entries(accounts)) {
@@ -246,21 +366,85 @@ describe("escrow", () => {
 		expect(balances.takerAtaB).to.equal(1000);
 	});
 
+	it("Initialize config: the upgrade authority creates the global config with no protocol fee", async () => {
+		const programData = PublicKey.findProgramAddressSync(
+			[program.programId.toBuffer()],
+			new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+		)[0];
+
+		await program.methods
+			.initializeConfig(0)
+			.accounts({
+				admin: provider.publicKey,
+				config,
+				program: program.programId,
+				programData,
+			})
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const configAccount = await program.account.config.fetch(config);
+		expect(configAccount.admin.toBase58()).to.equal(
+			provider.publicKey.toBase58()
+		);
+		expect(configAccount.feeBps).to.equal(0);
+	});
+
+	it("Maker profile: starts the maker's offer counter at 0, the only seed make then accepts with it", async () => {
+		const makerProfile = PublicKey.findProgramAddressSync(
+			[Buffer.from("profile"), maker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+
+		await program.methods
+			.createProfile()
+			.accounts({ maker: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const profileAccount = await program.account.makerProfile.fetch(
+			makerProfile
+		);
+		expect(profileAccount.offers.toNumber()).to.equal(0);
+
+		try {
+			await program.methods
+				.make(seed, null, new BN(1e6), new BN(1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, makerProfile, payer: maker.publicKey })
+				.signers([maker])
+				.rpc();
+			expect.fail("make should reject a seed other than the profile's counter");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("SeedMismatch");
+		}
+	});
+
 	it("Make: deposits deposit amount of mint_a from the maker and receives receive amount of mint_b", async () => {
 		const deposit = new BN(100 * 1e6);
 		const receive = new BN(200 * 1e6);
+		const makerLamports = await connection.getBalance(maker.publicKey);
 
 		await program.methods
-			.make(seed, deposit, receive)
-			.accounts({ ...accounts })
+			// Once closed, the rent of the escrow and its vault goes back to the maker rather than being split with the taker
+			.make(seed, null, deposit, receive, new BN(0), null, null, null, null, null, false, { maker: {} }, label("OTC desk #42"), null, null, 2)
+			// A sponsor covers the rent of the escrow and its vault, so the maker's lamports are untouched
+			.accounts({ ...accounts, payer: provider.publicKey })
 			.signers([maker])
 			.rpc()
 			.then(confirm)
 			.then(log);
 
+		expect(await connection.getBalance(maker.publicKey)).to.equal(makerLamports);
+
 		const escrowAccount = await program.account.escrow.fetch(escrow);
 		console.log("\tEscrow account:");
 		console.log(escrowAccount);
+		expect(escrowAccount.label).to.deep.equal(label("OTC desk #42"));
+		// Filed under a category indexers can filter escrows by without deserializing them
+		expect(escrowAccount.category).to.equal(2);
 
 		const balances = await tokenBalances({
 			makerAtaA,
@@ -278,14 +462,41 @@ describe("escrow", () => {
 		expect(balances.takerAtaB).to.equal(1000);
 	});
 
-	xit("Refund: refunds the deposited mint_a tokens to the maker and closes the escrow- might be used in case the taker doesn't fulfill their part of the agreement", async () => {
+	it("Make: a maker that doesn't sign must be vouched for by its maker authority, a PDA of the passed program", async () => {
+		const pdaMaker = Keypair.generate().publicKey;
+		const pdaSeed = new BN(randomBytes(8));
+		const pdaEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), pdaMaker.toBuffer(), pdaSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+
+		// The taker isn't the `["maker_authority", maker]` PDA of the program, so their signature doesn't vouch for the maker
 		try {
 			await program.methods
-				.refund()
+				.make(pdaSeed, null, new BN(1e6), new BN(1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
 				.accounts({
 					...accounts,
-					tokenProgram: tokenProgram,
+					maker: pdaMaker,
+					payer: maker.publicKey,
+					makerAtaA: null,
+					escrow: pdaEscrow,
+					vault: getAssociatedTokenAddressSync(mintA.publicKey, pdaEscrow, true, tokenProgram),
+					makerAuthority: taker.publicKey,
+					makerProgram: program.programId,
 				})
+				.signers([maker, taker])
+				.rpc();
+			expect.fail("make should reject a maker authority that isn't the PDA of the passed program");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedMaker");
+		}
+	});
+
+	xit("Refund: refunds the deposited mint_a tokens to the maker and closes the escrow- might be used in case the taker doesn't fulfill their part of the agreement", async () => {
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...accounts, authority: maker.publicKey })
 				.signers([maker])
 				.rpc()
 				.then(confirm)
@@ -295,10 +506,230 @@ describe("escrow", () => {
 		}
 	});
 
+	it("Update: the maker amends the escrow terms without refunding it", async () => {
+		const receive = new BN(200 * 1e6);
+		const expiry = new BN(Math.floor(Date.now() / 1000) + 3600);
+
+		await program.methods
+			.updateEscrow(receive, expiry)
+			.accounts({ owner: maker.publicKey, escrow })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(escrowAccount.receive.toNumber()).to.equal(200 * 1e6);
+		expect(escrowAccount.expiry.toNumber()).to.equal(expiry.toNumber());
+	});
+
+	it("Migrate escrow: escrows made in the current layout are left as they are", async () => {
+		try {
+			await program.methods
+				.migrateEscrow()
+				.accounts({ payer: maker.publicKey, escrow, vault: null })
+				.signers([maker])
+				.rpc();
+			expect.fail("migrate_escrow should reject an escrow in the current layout");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AlreadyMigrated");
+		}
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(escrowAccount.version).to.equal(20);
+	});
+
+	it("Quote: prices a full fill of the escrow without changing it", async () => {
+		const quote = await program.methods
+			.quote()
+			.accounts({ escrow, mintA: mintA.publicKey, mintB: mintB.publicKey, config, feeOverride: null, traderStats: null, template: null })
+			.view();
+
+		expect(quote.deposit.toNumber()).to.equal(100 * 1e6);
+		expect(quote.receive.toNumber()).to.equal(200 * 1e6);
+		// Neither mint bears interest, so the UI amounts are the raw ones at 6 decimals
+		expect(quote.depositUi).to.equal("100");
+		expect(quote.receiveUi).to.equal("200");
+		// The config charges no protocol fee, so the taker pays exactly what the owner receives
+		expect(quote.fee.toNumber()).to.equal(0);
+		expect(quote.proceeds.toNumber()).to.equal(200 * 1e6);
+		expect(quote.takerPays.toNumber()).to.equal(200 * 1e6);
+		expect(quote.expired).to.equal(false);
+		expect(quote.fillable).to.equal(true);
+	});
+
+	it("Prepare take: a sponsor creates the token accounts the fills pay into", async () => {
+		// Idempotent, so sending it twice leaves the accounts as they are
+		for (let i = 0; i < 2; i++) {
+			await program.methods
+				.prepareTake()
+				.accounts({ ...accounts, payer: provider.publicKey })
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+
+		const balances = await tokenBalances({ takerAtaA, makerAtaB });
+		expect(balances.takerAtaA).to.equal(0);
+		expect(balances.makerAtaB).to.equal(0);
+		expect(await connection.getAccountInfo(feeVault)).to.not.be.null;
+	});
+
+	it("Take partial: fills part of the vault for the pro-rata share of mint_b and keeps the escrow open", async () => {
+		const fillReceipt = PublicKey.findProgramAddressSync(
+			[Buffer.from("fill_receipt"), escrow.toBuffer(), taker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+
+		// The maker can't fill their own escrow
+		try {
+			await program.methods
+				.takePartial(new BN(25 * 1e6), new BN(100 * 1e6), new BN(200 * 1e6))
+				.accounts({ ...accounts, taker: maker.publicKey, takerAtaA: makerAtaA, takerAtaB: makerAtaB })
+				.signers([maker])
+				.rpc();
+			expect.fail("take_partial should reject the escrow's maker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("SelfTake");
+		}
+
+		// Nor can a taker who read terms the escrow no longer has
+		try {
+			await program.methods
+				.takePartial(new BN(25 * 1e6), new BN(100 * 1e6), new BN(100 * 1e6))
+				.accounts({ ...accounts })
+				.signers([taker])
+				.rpc();
+			expect.fail("take_partial should reject terms that changed");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("TermsChanged");
+		}
+
+		await program.methods
+			.takePartial(new BN(25 * 1e6), new BN(100 * 1e6), new BN(200 * 1e6))
+			// The taker keeps a receipt of the fill, e.g. for their accounting
+			.accounts({ ...accounts, fillReceipt })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(escrowAccount.deposit.toNumber()).to.equal(75 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(150 * 1e6);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Take partial':");
+		console.log(balances);
+
+		expect(balances.makerAtaB).to.equal(50);
+		expect(balances.takerAtaA).to.equal(25);
+		expect(balances.takerAtaB).to.equal(950);
+
+		const receiptAccount = await program.account.fillReceipt.fetch(fillReceipt);
+		expect(receiptAccount.taker.toBase58()).to.equal(taker.publicKey.toBase58());
+		expect(receiptAccount.deposit.toNumber()).to.equal(25 * 1e6);
+		expect(receiptAccount.receive.toNumber()).to.equal(50 * 1e6);
+		expect(receiptAccount.fills).to.equal(1);
+
+		await program.methods
+			.closeFillReceipt()
+			.accounts({ taker: taker.publicKey, fillReceipt })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(fillReceipt)).to.be.null;
+	});
+
+	it("Take exact: spends an exact amount of mint_b, buying the mint_a it pays for at the escrow's price", async () => {
+		// Buying less than the taker's limit of mint_a is rejected
+		try {
+			await program.methods
+				.takeExact({ exactIn: { payment: new BN(30 * 1e6) } }, new BN(16 * 1e6), new BN(75 * 1e6), new BN(150 * 1e6))
+				.accounts({ ...accounts })
+				.signers([taker])
+				.rpc();
+			expect.fail("take_exact should reject a fill below the taker's limit");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("SlippageExceeded");
+		}
+
+		await program.methods
+			.takeExact({ exactIn: { payment: new BN(30 * 1e6) } }, new BN(15 * 1e6), new BN(75 * 1e6), new BN(150 * 1e6))
+			.accounts({ ...accounts })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(escrowAccount.deposit.toNumber()).to.equal(60 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(120 * 1e6);
+
+		const balances = await tokenBalances({ makerAtaB, takerAtaA });
+		expect(balances.makerAtaB).to.equal(80);
+		expect(balances.takerAtaA).to.equal(40);
+	});
+
+	// Secret salt of the taker's commitment, revealed by the final take
+	const salt = randomBytes(32);
+
+	it("Commit take: reserves the escrow for the committed taker, who can then only take it by revealing their salt", async () => {
+		const hash = createHash("sha256")
+			.update(escrow.toBuffer())
+			.update(taker.publicKey.toBuffer())
+			.update(salt)
+			.digest();
+
+		await program.methods
+			.commitTake([...hash])
+			.accounts({ taker: taker.publicKey, escrow })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(Buffer.from(escrowAccount.commitHash).equals(hash)).to.equal(true);
+
+		// While the commitment is pending, plain takes are rejected
+		try {
+			await program.methods
+				.takePartial(new BN(25 * 1e6), new BN(60 * 1e6), new BN(120 * 1e6))
+				.accounts({ ...accounts })
+				.signers([taker])
+				.rpc();
+			expect.fail("take_partial should be rejected while a commitment is pending");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("CommitmentPending");
+		}
+
+		// And so is a reveal that doesn't match the commitment
+		try {
+			await program.methods
+				.revealTake([...randomBytes(32)], new BN(60 * 1e6), new BN(120 * 1e6))
+				.accounts({ ...accounts })
+				.signers([taker])
+				.rpc();
+			expect.fail("reveal_take should reject a salt that doesn't match the commitment");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidCommitment");
+		}
+	});
+
 	it("Take: deposits receive amount of mint_b from the taker (fulfilling the agreement) and withdraws the deposited mint_a tokens to the taker, then finalizes the escrow", async () => {
+		const takerLamports = await connection.getBalance(taker.publicKey);
+
 		try {
+			// The escrow is reserved by the taker's commitment, so it is taken by revealing it
 			await program.methods
-				.take()
+				.revealTake([...salt], new BN(60 * 1e6), new BN(120 * 1e6))
 				.accounts({ ...accounts })
 				.signers([taker])
 				.rpc()
@@ -346,6 +777,5456 @@ describe("escrow", () => {
 		expect(balances.makerAtaB).to.equal(200);
 		expect(balances.takerAtaA).to.equal(100);
 		expect(balances.takerAtaB).to.equal(800);
+
+		// The maker chose to get the vault's rent back too, so the taker's lamports are untouched
+		expect(await connection.getBalance(taker.publicKey)).to.equal(takerLamports);
+	});
+
+	it("Make basket: deposits several mints into one vault each for a single amount of mint_b", async () => {
+		// A second leg minted by the maker, alongside the mint_a tokens left after the escrow above
+		const mintC = Keypair.generate();
+		const makerAtaC = getAssociatedTokenAddressSync(
+			mintC.publicKey,
+			maker.publicKey,
+			false,
+			tokenProgram
+		);
+		const lamports = await getMinimumBalanceForRentExemptMint(connection);
+		const tx = new Transaction().add(
+			SystemProgram.createAccount({
+				fromPubkey: provider.publicKey,
+				newAccountPubkey: mintC.publicKey,
+				lamports,
+				space: MINT_SIZE,
+				programId: tokenProgram,
+			}),
+			createInitializeMint2Instruction(
+				mintC.publicKey,
+				0,
+				maker.publicKey,
+				null,
+				tokenProgram
+			),
+			createAssociatedTokenAccountIdempotentInstruction(
+				provider.publicKey,
+				makerAtaC,
+				maker.publicKey,
+				mintC.publicKey,
+				tokenProgram
+			),
+			createMintToInstruction(
+				mintC.publicKey,
+				makerAtaC,
+				maker.publicKey,
+				2,
+				undefined,
+				tokenProgram
+			)
+		);
+		await provider.sendAndConfirm(tx, [mintC, maker]).then(log);
+
+		const basketSeed = new BN(randomBytes(8));
+		const basket = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("basket"),
+				maker.publicKey.toBuffer(),
+				basketSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const legs = [
+			{ mint: mintA.publicKey, makerAta: makerAtaA },
+			{ mint: mintC.publicKey, makerAta: makerAtaC },
+		].map((leg) => ({
+			...leg,
+			vault: getAssociatedTokenAddressSync(
+				leg.mint,
+				basket,
+				true,
+				tokenProgram
+			),
+			takerAta: getAssociatedTokenAddressSync(
+				leg.mint,
+				taker.publicKey,
+				false,
+				tokenProgram
+			),
+		}));
+
+		await program.methods
+			.makeBasket(
+				basketSeed,
+				[new BN(100 * 1e6), new BN(2)],
+				new BN(300 * 1e6)
+			)
+			.accounts({ ...accounts, basket })
+			.remainingAccounts(
+				legs.flatMap((leg) =>
+					[leg.mint, leg.makerAta, leg.vault].map((pubkey) => ({
+						pubkey,
+						isSigner: false,
+						isWritable: true,
+					}))
+				)
+			)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const basketAccount = await program.account.basket.fetch(basket);
+		expect(basketAccount.legs.length).to.equal(2);
+		expect(basketAccount.receive.toNumber()).to.equal(300 * 1e6);
+
+		await program.methods
+			.takeBasket()
+			.accounts({ ...accounts, basket })
+			.remainingAccounts(
+				legs.flatMap((leg) =>
+					[leg.mint, leg.vault, leg.takerAta].map((pubkey) => ({
+						pubkey,
+						isSigner: false,
+						isWritable: true,
+					}))
+				)
+			)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			makerAtaC,
+			takerAtaA,
+			takerAtaB,
+			takerAtaC: legs[1].takerAta,
+		});
+		console.log("\n\tBalances after 'Take basket':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(800);
+		expect(balances.makerAtaB).to.equal(500);
+		expect(balances.makerAtaC).to.equal(0);
+		expect(balances.takerAtaA).to.equal(200);
+		expect(balances.takerAtaB).to.equal(500);
+		expect(balances.takerAtaC).to.equal(2);
+	});
+
+	it("Swap: both sides lock their tokens first, then either of them settles the swap atomically", async () => {
+		const swapSeed = new BN(randomBytes(8));
+		const swap = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("swap"),
+				maker.publicKey.toBuffer(),
+				swapSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const [vaultA, vaultB] = [mintA, mintB].map((m) =>
+			getAssociatedTokenAddressSync(m.publicKey, swap, true, tokenProgram)
+		);
+		const swapAccounts = { ...accounts, swap, vaultA, vaultB };
+
+		await program.methods
+			.proposeSwap(
+				swapSeed,
+				new BN(100 * 1e6),
+				new BN(100 * 1e6),
+				taker.publicKey
+			)
+			.accounts(swapAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.fundSwap()
+			.accounts(swapAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const swapAccount = await program.account.swap.fetch(swap);
+		expect(swapAccount.funded).to.equal(true);
+
+		await program.methods
+			.settleSwap()
+			.accounts({ ...swapAccounts, signer: taker.publicKey })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Settle swap':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(700);
+		expect(balances.makerAtaB).to.equal(600);
+		expect(balances.takerAtaA).to.equal(300);
+		expect(balances.takerAtaB).to.equal(400);
+	});
+
+	it("Auction: each bid refunds the previous one, and the highest bid wins the deposit once bidding ends", async () => {
+		const auctionSeed = new BN(randomBytes(8));
+		const auction = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("auction"),
+				maker.publicKey.toBuffer(),
+				auctionSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const [vaultA, bidVault] = [mintA, mintB].map((m) =>
+			getAssociatedTokenAddressSync(m.publicKey, auction, true, tokenProgram)
+		);
+		const auctionAccounts = {
+			...accounts,
+			auction,
+			vaultA,
+			bidVault,
+			bidder: taker.publicKey,
+			bidderAtaB: takerAtaB,
+			recipient: taker.publicKey,
+			recipientAtaA: takerAtaA,
+		};
+		const end = Math.floor(Date.now() / 1000) + 5;
+
+		await program.methods
+			.makeAuction(auctionSeed, new BN(100 * 1e6), new BN(50 * 1e6), new BN(end))
+			.accounts(auctionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.bid(new BN(60 * 1e6))
+			.accounts({ ...auctionAccounts, previousBidderAtaB: null })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.bid(new BN(80 * 1e6))
+			.accounts({ ...auctionAccounts, previousBidderAtaB: takerAtaB })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const auctionAccount = await program.account.auction.fetch(auction);
+		expect(auctionAccount.bidder.toBase58()).to.equal(taker.publicKey.toBase58());
+		expect(auctionAccount.bid.toNumber()).to.equal(80 * 1e6);
+
+		await new Promise((resolve) =>
+			setTimeout(resolve, Math.max(0, end * 1000 - Date.now()) + 2000)
+		);
+
+		await program.methods
+			.settleAuction()
+			.accounts({ ...auctionAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Settle auction':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(600);
+		expect(balances.makerAtaB).to.equal(680);
+		expect(balances.takerAtaA).to.equal(400);
+		expect(balances.takerAtaB).to.equal(320);
+	});
+
+	it("Milestones: the maker funds a contract upfront and releases it to the contractor one approved milestone at a time", async () => {
+		const milestoneSeed = new BN(randomBytes(8));
+		const milestoneEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("milestones"),
+				maker.publicKey.toBuffer(),
+				milestoneSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const milestoneAccounts = {
+			maker: maker.publicKey,
+			contractor: taker.publicKey,
+			mint: mintA.publicKey,
+			makerAta: makerAtaA,
+			contractorAta: takerAtaA,
+			config,
+			milestoneEscrow,
+			vault: getAssociatedTokenAddressSync(
+				mintA.publicKey,
+				milestoneEscrow,
+				true,
+				tokenProgram
+			),
+			tokenProgram,
+		};
+		const milestones = ["design", "delivery"].map((deliverable, i) => ({
+			amount: new BN((i + 1) * 50 * 1e6),
+			descriptionHash: [...createHash("sha256").update(deliverable).digest()],
+		}));
+
+		await program.methods
+			.makeMilestones(milestoneSeed, milestones)
+			.accounts(milestoneAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The contractor can't claim a milestone the maker hasn't approved
+		try {
+			await program.methods
+				.claimMilestone(0)
+				.accounts(milestoneAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("claim_milestone should reject a milestone that isn't approved");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("MilestoneNotApproved");
+		}
+
+		for (const index of [0, 1]) {
+			await program.methods
+				.approveMilestone(index)
+				.accounts(milestoneAccounts)
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+
+			await program.methods
+				.claimMilestone(index)
+				.accounts(milestoneAccounts)
+				.signers([taker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+
+			const balances = await tokenBalances({ makerAtaA, takerAtaA });
+			expect(balances.makerAtaA).to.equal(450);
+			expect(balances.takerAtaA).to.equal(index == 0 ? 450 : 550);
+		}
+
+		// Once every milestone is claimed, the milestone escrow is closed
+		expect(await connection.getAccountInfo(milestoneEscrow)).to.be.null;
+	});
+
+	it("Loan: the maker borrows mint_b against mint_a collateral, and gets the collateral back by repaying the lender", async () => {
+		const loanSeed = new BN(randomBytes(8));
+		const loan = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("loan"),
+				maker.publicKey.toBuffer(),
+				loanSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const loanAccounts = {
+			...accounts,
+			lender: taker.publicKey,
+			lenderAtaA: takerAtaA,
+			lenderAtaB: takerAtaB,
+			loan,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, loan, true, tokenProgram),
+			tokenProgram,
+		};
+
+		await program.methods
+			.makeLoan(loanSeed, new BN(100 * 1e6), new BN(50 * 1e6), new BN(60 * 1e6), new BN(60))
+			.accounts(loanAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.takeLoan()
+			.accounts(loanAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The lender can't claim the collateral before the repayment deadline
+		try {
+			await program.methods
+				.liquidateAfterDeadline()
+				.accounts(loanAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("liquidate_after_deadline should reject a loan that isn't past its deadline");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("LoanNotDefaulted");
+		}
+
+		await program.methods
+			.repay()
+			.accounts(loanAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Repay':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(450);
+		expect(balances.makerAtaB).to.equal(670);
+		expect(balances.takerAtaA).to.equal(550);
+		expect(balances.takerAtaB).to.equal(330);
+		expect(await connection.getAccountInfo(loan)).to.be.null;
+	});
+
+	it("Recurring: the taker takes one installment of the deposit per interval, at the escrow's price", async () => {
+		const recurringSeed = new BN(randomBytes(8));
+		const recurringEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				recurringSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const recurringAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: recurringEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, recurringEscrow, true, tokenProgram),
+		};
+		const schedule = { installment: new BN(40 * 1e6), interval: new BN(3600) };
+
+		await program.methods
+			.make(recurringSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), taker.publicKey, null, null, null, schedule, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(recurringAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Scheduled escrows can't be filled outside of their installments
+		try {
+			await program.methods
+				.takePartial(new BN(10 * 1e6), new BN(100 * 1e6), new BN(50 * 1e6))
+				.accounts(recurringAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take_partial should reject a scheduled escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ScheduledEscrow");
+		}
+
+		await program.methods
+			.takeInstallment(new BN(100 * 1e6), new BN(50 * 1e6))
+			.accounts(recurringAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(recurringEscrow);
+		expect(escrowAccount.deposit.toNumber()).to.equal(60 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(30 * 1e6);
+		expect(escrowAccount.lastFillTs.toNumber()).to.be.greaterThan(0);
+
+		// The next installment is only due an interval after the last one
+		try {
+			await program.methods
+				.takeInstallment(new BN(60 * 1e6), new BN(30 * 1e6))
+				.accounts(recurringAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take_installment should reject an installment that isn't due");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InstallmentNotDue");
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...recurringAccounts, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Recurring':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(410);
+		expect(balances.makerAtaB).to.equal(690);
+		expect(balances.takerAtaA).to.equal(590);
+		expect(balances.takerAtaB).to.equal(310);
+	});
+
+	it("Recurring: an installment whose payment rounds up to the whole receive completes the escrow", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		// Half the deposit is worth half a raw unit of mint_b, rounded up to the whole receive
+		const schedule = { installment: new BN(5), interval: new BN(3600) };
+		await program.methods
+			.make(seed, null, new BN(10), new BN(1), new BN(0), null, null, null, null, schedule, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+
+		const before = await connection.getTokenAccountBalance(takerAtaA);
+		await program.methods
+			.takeInstallment(new BN(10), new BN(1))
+			.accounts({ ...accounts, escrow, vault })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await connection.getTokenAccountBalance(takerAtaA);
+		expect(Number(after.value.amount) - Number(before.value.amount)).to.equal(10);
+		expect(await connection.getAccountInfo(escrow)).to.be.null;
+	});
+
+	it("RFQ: takers post funded quotes, the owner settles against the best one and the losing quotes are refunded", async () => {
+		const rfqSeed = new BN(randomBytes(8));
+		const rfqEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				rfqSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		// The maker's wallet stands in for a second prospective taker
+		const [takerQuote, makerQuote] = [taker, maker].map(
+			(quoter) =>
+				PublicKey.findProgramAddressSync(
+					[Buffer.from("rfq"), rfqEscrow.toBuffer(), quoter.publicKey.toBuffer()],
+					program.programId
+				)[0]
+		);
+		const [takerQuoteVault, makerQuoteVault] = [takerQuote, makerQuote].map((quote) =>
+			getAssociatedTokenAddressSync(mintB.publicKey, quote, true, tokenProgram)
+		);
+		const rfqAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: rfqEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, rfqEscrow, true, tokenProgram),
+			tokenProgram,
+		};
+
+		await program.methods
+			.make(rfqSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(rfqAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		for (const [quoter, quote, quoteVault, amount] of [
+			[maker, makerQuote, makerQuoteVault, 20],
+			[taker, takerQuote, takerQuoteVault, 30],
+		] as const) {
+			await program.methods
+				.postQuote(new BN(amount * 1e6))
+				.accounts({
+					...rfqAccounts,
+					taker: quoter.publicKey,
+					takerAtaB: quoter == maker ? makerAtaB : takerAtaB,
+					quote,
+					quoteVault,
+				})
+				.signers([quoter])
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+
+		const losingQuote = [makerQuote, makerQuoteVault, maker.publicKey, makerAtaB].map(
+			(pubkey) => ({ pubkey, isSigner: false, isWritable: true })
+		);
+
+		// The losing quote must not beat the accepted one
+		try {
+			await program.methods
+				.acceptQuote()
+				.accounts({
+					...rfqAccounts,
+					taker: maker.publicKey,
+					takerAtaA: makerAtaA,
+					quote: makerQuote,
+					quoteVault: makerQuoteVault,
+				})
+				.remainingAccounts(
+					[takerQuote, takerQuoteVault, taker.publicKey, takerAtaB].map((pubkey) => ({
+						pubkey,
+						isSigner: false,
+						isWritable: true,
+					}))
+				)
+				.signers([maker])
+				.rpc();
+			expect.fail("accept_quote should reject a losing quote offering more");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("QuoteNotBest");
+		}
+
+		await program.methods
+			.acceptQuote()
+			.accounts({ ...rfqAccounts, quote: takerQuote, quoteVault: takerQuoteVault })
+			.remainingAccounts(losingQuote)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Accept quote':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(310);
+		expect(balances.makerAtaB).to.equal(720);
+		expect(balances.takerAtaA).to.equal(690);
+		expect(balances.takerAtaB).to.equal(280);
+		for (const closed of [rfqEscrow, takerQuote, makerQuote]) {
+			expect(await connection.getAccountInfo(closed)).to.be.null;
+		}
+	});
+
+	it("Cancel authority: the maker delegates refunds to another key, which can then refund their escrows to them", async () => {
+		const cancelAuthority = PublicKey.findProgramAddressSync(
+			[Buffer.from("cancel_authority"), maker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		const delegatedSeed = new BN(randomBytes(8));
+		const delegatedEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				delegatedSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const delegatedAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: delegatedEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, delegatedEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(delegatedSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(delegatedAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Without a delegation, only the owner can refund the escrow
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...delegatedAccounts, authority: taker.publicKey })
+				.signers([taker])
+				.rpc();
+			expect.fail("refund should reject a signer the owner didn't delegate to");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedCanceller");
+		}
+
+		await program.methods
+			.setCancelAuthority(taker.publicKey)
+			.accounts({ owner: maker.publicKey, cancelAuthority })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.refund()
+			.accounts({ ...delegatedAccounts, authority: taker.publicKey, cancelAuthority })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.revokeCancelAuthority()
+			.accounts({ owner: maker.publicKey, cancelAuthority })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Cancel authority':");
+		console.log(balances);
+
+		// The refund went back to the maker, not to the authority who signed it
+		expect(balances.makerAtaA).to.equal(310);
+		expect(balances.takerAtaA).to.equal(690);
+		for (const closed of [delegatedEscrow, cancelAuthority]) {
+			expect(await connection.getAccountInfo(closed)).to.be.null;
+		}
+	});
+
+	it("Split: the maker moves part of an open escrow into a new one, both keeping the original price", async () => {
+		const escrowAt = (seed: BN) => {
+			const address = PublicKey.findProgramAddressSync(
+				[
+					Buffer.from("escrow"),
+					maker.publicKey.toBuffer(),
+					seed.toArrayLike(Buffer, "le", 8),
+				],
+				program.programId
+			)[0];
+			return {
+				address,
+				vault: getAssociatedTokenAddressSync(mintA.publicKey, address, true, tokenProgram),
+			};
+		};
+		const splitSeed = new BN(randomBytes(8));
+		const newSeed = new BN(randomBytes(8));
+		const original = escrowAt(splitSeed);
+		const split = escrowAt(newSeed);
+		const originalAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: original.address,
+			vault: original.vault,
+		};
+
+		await program.methods
+			.make(splitSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(originalAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.splitEscrow(newSeed, new BN(40 * 1e6))
+			.accounts({ ...originalAccounts, newEscrow: split.address, newVault: split.vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const originalEscrow = await program.account.escrow.fetch(original.address);
+		const splitEscrow = await program.account.escrow.fetch(split.address);
+		expect(originalEscrow.deposit.toNumber()).to.equal(60 * 1e6);
+		expect(originalEscrow.receive.toNumber()).to.equal(30 * 1e6);
+		expect(splitEscrow.deposit.toNumber()).to.equal(40 * 1e6);
+		expect(splitEscrow.receive.toNumber()).to.equal(20 * 1e6);
+		expect(splitEscrow.owner.toBase58()).to.equal(maker.publicKey.toBase58());
+
+		for (const { address, vault } of [original, split]) {
+			await program.methods
+				.refund()
+				.accounts({ ...originalAccounts, escrow: address, vault })
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Split':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(310);
+		expect(balances.takerAtaA).to.equal(690);
+	});
+
+	it("Merge: the maker folds an escrow into another one at the same price, closing it", async () => {
+		const [target, merged] = [new BN(randomBytes(8)), new BN(randomBytes(8))].map((seed) => {
+			const address = PublicKey.findProgramAddressSync(
+				[
+					Buffer.from("escrow"),
+					maker.publicKey.toBuffer(),
+					seed.toArrayLike(Buffer, "le", 8),
+				],
+				program.programId
+			)[0];
+			return {
+				seed,
+				address,
+				vault: getAssociatedTokenAddressSync(mintA.publicKey, address, true, tokenProgram),
+			};
+		});
+		const mergeAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: target.address,
+			vault: target.vault,
+			merged: merged.address,
+			mergedVault: merged.vault,
+		};
+
+		for (const [{ seed, address, vault }, deposit, receive] of [
+			[target, 60, 30],
+			[merged, 40, 20],
+		] as const) {
+			await program.methods
+				.make(seed, null, new BN(deposit * 1e6), new BN(receive * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...mergeAccounts, escrow: address, vault })
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+
+		await program.methods
+			.mergeEscrows()
+			.accounts(mergeAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(target.address);
+		expect(escrowAccount.deposit.toNumber()).to.equal(100 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(50 * 1e6);
+		for (const closed of [merged.address, merged.vault]) {
+			expect(await connection.getAccountInfo(closed)).to.be.null;
+		}
+
+		await program.methods
+			.refund()
+			.accounts(mergeAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Merge':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(310);
+		expect(balances.takerAtaA).to.equal(690);
+	});
+
+	it("Sweep surplus: tokens donated to a vault go back to the owner, and never change what the taker is settled", async () => {
+		const sweepSeed = new BN(randomBytes(8));
+		const sweepEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				sweepSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const sweepVault = getAssociatedTokenAddressSync(mintA.publicKey, sweepEscrow, true, tokenProgram);
+		const sweepAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: sweepEscrow,
+			vault: sweepVault,
+		};
+		// The taker sends 5 mint_a straight to the vault, outside of the program
+		const donate = () =>
+			provider.sendAndConfirm(
+				new Transaction().add(
+					createTransferCheckedInstruction(
+						takerAtaA,
+						mintA.publicKey,
+						sweepVault,
+						taker.publicKey,
+						5 * 1e6,
+						6,
+						[],
+						tokenProgram
+					)
+				),
+				[taker]
+			);
+
+		await program.methods
+			.make(sweepSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(sweepAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await donate();
+		await program.methods
+			.sweepSurplus()
+			.accounts(sweepAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Nothing is left to sweep once the vault only holds the deposit
+		try {
+			await program.methods.sweepSurplus().accounts(sweepAccounts).signers([maker]).rpc();
+			expect.fail("sweep_surplus should reject a vault without surplus");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NoSurplus");
+		}
+
+		// A surplus left in the vault goes back to the owner on a full take, the taker only gets the deposit
+		await donate();
+		await program.methods
+			.take(new BN(100 * 1e6), new BN(50 * 1e6))
+			.accounts(sweepAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balances = await tokenBalances({
+			makerAtaA,
+			makerAtaB,
+			takerAtaA,
+			takerAtaB,
+		});
+		console.log("\n\tBalances after 'Sweep surplus':");
+		console.log(balances);
+
+		expect(balances.makerAtaA).to.equal(220);
+		expect(balances.makerAtaB).to.equal(770);
+		expect(balances.takerAtaA).to.equal(780);
+		expect(balances.takerAtaB).to.equal(230);
+	});
+
+	it("PDA vault: the maker makes an escrow whose vault is a token account at its own PDA, usable like any vault", async () => {
+		const pdaSeed = new BN(randomBytes(8));
+		const pdaEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				pdaSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const pdaVault = PublicKey.findProgramAddressSync(
+			[Buffer.from("vault"), pdaEscrow.toBuffer()],
+			program.programId
+		)[0];
+		const pdaAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: pdaEscrow,
+		};
+
+		await program.methods
+			.make(pdaSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...pdaAccounts, vault: null, pdaVault })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(pdaEscrow);
+		expect(escrowAccount.pdaVault).to.equal(1);
+
+		// The escrow's associated token account isn't its vault
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...pdaAccounts, vault: getAssociatedTokenAddressSync(mintA.publicKey, pdaEscrow, true, tokenProgram) })
+				.signers([maker])
+				.rpc();
+			expect.fail("refund should reject an account other than the escrow's vault");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.be.oneOf(["VaultMismatch", "AccountNotInitialized"]);
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...pdaAccounts, vault: pdaVault })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		expect(await connection.getAccountInfo(pdaVault)).to.be.null;
+	});
+
+	it("Mint policy: NonTransferable mints are rejected by make unless the admin permits them", async () => {
+		const soulbound = Keypair.generate();
+		const space = getMintLen([ExtensionType.NonTransferable]);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: soulbound.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializeNonTransferableMintInstruction(soulbound.publicKey, tokenProgram),
+					createInitializeMint2Instruction(soulbound.publicKey, 6, taker.publicKey, null, tokenProgram)
+				),
+				[soulbound]
+			)
+			.then(log);
+
+		const policySeed = new BN(randomBytes(8));
+		const policyEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				policySeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const policyAccounts = {
+			...accounts,
+			mintB: soulbound.publicKey,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: policyEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, policyEscrow, true, tokenProgram),
+		};
+		const make = () =>
+			program.methods
+				.make(policySeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(policyAccounts)
+				.signers([maker])
+				.rpc();
+		const setMintPolicy = (allowNonTransferable: boolean) =>
+			program.methods
+				.setMintPolicy(allowNonTransferable, { reject: {} })
+				.accounts({ admin: provider.publicKey, config })
+				.rpc()
+				.then(confirm)
+				.then(log);
+
+		// Nothing the taker holds could ever be paid to the maker
+		try {
+			await make();
+			expect.fail("make should reject a NonTransferable mint");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NonTransferableMint");
+		}
+
+		await setMintPolicy(true);
+		await make().then(confirm).then(log);
+		expect((await program.account.escrow.fetch(policyEscrow)).mintB.toBase58()).to.equal(soulbound.publicKey.toBase58());
+
+		await program.methods
+			.refund()
+			.accounts(policyAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await setMintPolicy(false);
+	});
+
+	it("Mint policy: mints with a permanent delegate are rejected by make, or flagged if the admin lets them through", async () => {
+		const delegated = Keypair.generate();
+		const space = getMintLen([ExtensionType.PermanentDelegate]);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: delegated.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializePermanentDelegateInstruction(delegated.publicKey, taker.publicKey, tokenProgram),
+					createInitializeMint2Instruction(delegated.publicKey, 6, taker.publicKey, null, tokenProgram)
+				),
+				[delegated]
+			)
+			.then(log);
+
+		const delegateSeed = new BN(randomBytes(8));
+		const delegateEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				delegateSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const delegateAccounts = {
+			...accounts,
+			mintB: delegated.publicKey,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: delegateEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, delegateEscrow, true, tokenProgram),
+		};
+		const make = () =>
+			program.methods
+				.make(delegateSeed, null, new BN(100 * 1e6), new BN(50 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(delegateAccounts)
+				.signers([maker])
+				.rpc();
+		const setMintPolicy = (permanentDelegatePolicy: object) =>
+			program.methods
+				.setMintPolicy(false, permanentDelegatePolicy)
+				.accounts({ admin: provider.publicKey, config })
+				.rpc()
+				.then(confirm)
+				.then(log);
+
+		try {
+			await make();
+			expect.fail("make should reject a mint with a permanent delegate");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("PermanentDelegateMint");
+		}
+
+		await setMintPolicy({ flag: {} });
+		await make().then(confirm).then(log);
+		expect((await program.account.escrow.fetch(delegateEscrow)).permanentDelegate).to.equal(1);
+
+		await program.methods
+			.refund()
+			.accounts(delegateAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await setMintPolicy({ reject: {} });
+	});
+
+	it("Frozen accounts: a fill into a frozen token account fails upfront, naming the frozen account", async () => {
+		// A mint whose freeze authority, here the taker, can freeze the owner's account for it
+		const freezable = Keypair.generate();
+		const ownerAtaFreezable = getAssociatedTokenAddressSync(freezable.publicKey, maker.publicKey, false, tokenProgram);
+		const takerAtaFreezable = getAssociatedTokenAddressSync(freezable.publicKey, taker.publicKey, false, tokenProgram);
+		const frozenFeeVault = getAssociatedTokenAddressSync(freezable.publicKey, config, true, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: freezable.publicKey,
+						lamports: await getMinimumBalanceForRentExemptMint(connection),
+						space: MINT_SIZE,
+						programId: tokenProgram,
+					}),
+					createInitializeMint2Instruction(freezable.publicKey, 6, taker.publicKey, taker.publicKey, tokenProgram),
+					...[
+						{ ata: ownerAtaFreezable, owner: maker.publicKey },
+						{ ata: takerAtaFreezable, owner: taker.publicKey },
+						{ ata: frozenFeeVault, owner: config },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, freezable.publicKey, tokenProgram)
+					),
+					createMintToInstruction(freezable.publicKey, takerAtaFreezable, taker.publicKey, 100 * 1e6, undefined, tokenProgram),
+					createFreezeAccountInstruction(ownerAtaFreezable, freezable.publicKey, taker.publicKey, undefined, tokenProgram)
+				),
+				[freezable, taker]
+			)
+			.then(log);
+
+		const frozenSeed = new BN(randomBytes(8));
+		const frozenEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				frozenSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const frozenAccounts = {
+			...accounts,
+			mintB: freezable.publicKey,
+			takerAtaB: takerAtaFreezable,
+			ownerAtaB: ownerAtaFreezable,
+			feeVault: frozenFeeVault,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: frozenEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, frozenEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(frozenSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(frozenAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts(frozenAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a fill paying into a frozen account");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AccountFrozen");
+			expect(e.error?.origin).to.equal("owner_ata_b");
+		}
+
+		await program.methods
+			.refund()
+			.accounts(frozenAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Default frozen accounts: a fill into an account its mint froze on creation asks for it to be thawed", async () => {
+		// An RWA-style mint, whose issuer (here the taker) thaws each new account once it is cleared
+		const gated = Keypair.generate();
+		const space = getMintLen([ExtensionType.DefaultAccountState]);
+		const ownerAtaGated = getAssociatedTokenAddressSync(gated.publicKey, maker.publicKey, false, tokenProgram);
+		const takerAtaGated = getAssociatedTokenAddressSync(gated.publicKey, taker.publicKey, false, tokenProgram);
+		const gatedFeeVault = getAssociatedTokenAddressSync(gated.publicKey, config, true, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: gated.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializeDefaultAccountStateInstruction(gated.publicKey, AccountState.Frozen, tokenProgram),
+					createInitializeMint2Instruction(gated.publicKey, 6, taker.publicKey, taker.publicKey, tokenProgram),
+					...[
+						{ ata: ownerAtaGated, owner: maker.publicKey },
+						{ ata: takerAtaGated, owner: taker.publicKey },
+						{ ata: gatedFeeVault, owner: config },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, gated.publicKey, tokenProgram)
+					),
+					// Only the taker's account has been cleared so far
+					createThawAccountInstruction(takerAtaGated, gated.publicKey, taker.publicKey, undefined, tokenProgram),
+					createMintToInstruction(gated.publicKey, takerAtaGated, taker.publicKey, 100 * 1e6, undefined, tokenProgram)
+				),
+				[gated, taker]
+			)
+			.then(log);
+
+		const gatedSeed = new BN(randomBytes(8));
+		const gatedEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				gatedSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const gatedAccounts = {
+			...accounts,
+			mintB: gated.publicKey,
+			takerAtaB: takerAtaGated,
+			ownerAtaB: ownerAtaGated,
+			feeVault: gatedFeeVault,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: gatedEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, gatedEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(gatedSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(gatedAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts(gatedAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a fill paying into an account its mint froze on creation");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AccountNotThawed");
+			expect(e.error?.origin).to.equal("owner_ata_b");
+		}
+
+		await program.methods
+			.refund()
+			.accounts(gatedAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Take keep receipt: fills the escrow and keeps it as a receipt recording the taker and when it settled", async () => {
+		const receiptSeed = new BN(randomBytes(8));
+		const receiptEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				receiptSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const receiptAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: receiptEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, receiptEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(receiptSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(receiptAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.takeKeepReceipt(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(receiptAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The maker didn't ask for a receipt, the taker's fill kept one anyway
+		const receipt = await program.account.escrow.fetch(receiptEscrow);
+		expect(receipt.status).to.equal(1);
+		expect(receipt.taker.toBase58()).to.equal(taker.publicKey.toBase58());
+		expect(receipt.lastFillTs.toNumber()).to.be.greaterThan(0);
+		expect(receipt.deposit.toNumber()).to.equal(10 * 1e6);
+		expect(receipt.receive.toNumber()).to.equal(5 * 1e6);
+
+		await program.methods
+			.closeReceipt()
+			.accounts(receiptAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(receiptEscrow)).to.be.null;
+	});
+
+	it("Refund to a custody account: the owner gets the refund in any of their token accounts, not only their associated one", async () => {
+		// A token account of the maker at an arbitrary address, as exchanges and custodians use
+		const custody = Keypair.generate();
+		const space = getAccountLen([]);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: custody.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializeAccount3Instruction(custody.publicKey, mintA.publicKey, maker.publicKey, tokenProgram)
+				),
+				[custody]
+			)
+			.then(log);
+
+		const custodySeed = new BN(randomBytes(8));
+		const custodyEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				custodySeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const custodyAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: custodyEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, custodyEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(custodySeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(custodyAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The refund can't go to a token account the owner doesn't own
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...custodyAccounts, ownerAtaA: takerAtaA })
+				.signers([maker])
+				.rpc();
+			expect.fail("refund should reject a token account of someone else");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ConstraintTokenOwner");
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...custodyAccounts, ownerAtaA: custody.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const balance = await connection.getTokenAccountBalance(custody.publicKey);
+		expect(Number(balance.value.amount)).to.equal(10 * 1e6);
+	});
+
+	it("Auto-refund: the owner schedules the refund of an escrow, which a keeper executes once due for a tip", async () => {
+		const autoSeed = new BN(randomBytes(8));
+		const autoEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				autoSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const autoRefund = PublicKey.findProgramAddressSync(
+			[Buffer.from("auto_refund"), autoEscrow.toBuffer()],
+			program.programId
+		)[0];
+		const autoAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			// The taker runs the keeper here, any key can
+			keeper: taker.publicKey,
+			escrow: autoEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, autoEscrow, true, tokenProgram),
+			autoRefund,
+		};
+		const tip = 5000;
+
+		await program.methods
+			.make(autoSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(autoAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Without an expiry, the refund needs an explicit time to run from
+		try {
+			await program.methods
+				.scheduleAutoRefund(new BN(0), new BN(tip))
+				.accounts(autoAccounts)
+				.signers([maker])
+				.rpc();
+			expect.fail("schedule_auto_refund should need a time for an escrow that never expires");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidAutoRefund");
+		}
+
+		// A schedule set a day out can't be executed yet, and the owner can change their mind
+		await program.methods
+			.scheduleAutoRefund(new BN(Math.floor(Date.now() / 1000) + 86400), new BN(tip))
+			.accounts(autoAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		try {
+			await program.methods
+				.executeAutoRefund()
+				.accounts(autoAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("execute_auto_refund should wait for the schedule to be due");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AutoRefundNotDue");
+		}
+		await program.methods
+			.cancelAutoRefund()
+			.accounts(autoAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Rescheduled for a time already past, the keeper can run it straight away
+		await program.methods
+			.scheduleAutoRefund(new BN(1), new BN(tip))
+			.accounts(autoAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const makerBalance = await connection.getTokenAccountBalance(makerAtaA);
+		const keeperLamports = await connection.getBalance(taker.publicKey);
+
+		await program.methods
+			.executeAutoRefund()
+			.accounts(autoAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The deposit went back to the maker, and the keeper, whose fees the provider paid, got exactly the tip
+		const refunded = await connection.getTokenAccountBalance(makerAtaA);
+		expect(Number(refunded.value.amount) - Number(makerBalance.value.amount)).to.equal(10 * 1e6);
+		expect(await connection.getBalance(taker.publicKey)).to.equal(keeperLamports + tip);
+		for (const closed of [autoEscrow, autoRefund]) {
+			expect(await connection.getAccountInfo(closed)).to.be.null;
+		}
+	});
+
+	it("Multisig makers: the vault can be created before make, whose rent a separate payer covers", async () => {
+		const multisigSeed = new BN(randomBytes(8));
+		const multisigEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				multisigSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const multisigVault = getAssociatedTokenAddressSync(mintA.publicKey, multisigEscrow, true, tokenProgram);
+		const multisigAccounts = {
+			...accounts,
+			// The taker sponsors the rent here, as a multisig member executing the proposal would
+			payer: taker.publicKey,
+			authority: maker.publicKey,
+			escrow: multisigEscrow,
+			vault: multisigVault,
+		};
+
+		// Anyone can create the vault while the proposal awaits its approvals, which used to block make
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					createAssociatedTokenAccountIdempotentInstruction(
+						provider.publicKey,
+						multisigVault,
+						multisigEscrow,
+						mintA.publicKey,
+						tokenProgram
+					)
+				)
+			)
+			.then(log);
+
+		const makerLamports = await connection.getBalance(maker.publicKey);
+		await program.methods
+			.make(multisigSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(multisigAccounts)
+			.signers([maker, taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The maker only approved the deposit, the provider paid the fees and the taker the escrow's rent
+		expect(await connection.getBalance(maker.publicKey)).to.equal(makerLamports);
+		const vaultBalance = await connection.getTokenAccountBalance(multisigVault);
+		expect(Number(vaultBalance.value.amount)).to.equal(10 * 1e6);
+
+		await program.methods
+			.refund()
+			.accounts(multisigAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Event log: make, take and refund record their events in order in the log account when passed it", async () => {
+		const eventLog = PublicKey.findProgramAddressSync([Buffer.from("event_log")], program.programId)[0];
+		await program.methods
+			.initializeEventLog()
+			.accounts({ admin: provider.publicKey, config, eventLog })
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const logged = Array.from({ length: 2 }, () => new BN(randomBytes(8))).map((logSeed) => {
+			const logEscrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), logSeed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			return {
+				seed: logSeed,
+				accounts: {
+					...accounts,
+					payer: maker.publicKey,
+					authority: maker.publicKey,
+					escrow: logEscrow,
+					vault: getAssociatedTokenAddressSync(mintA.publicKey, logEscrow, true, tokenProgram),
+					eventLog,
+				},
+			};
+		});
+		for (const { seed: logSeed, accounts: logAccounts } of logged) {
+			await program.methods
+				.make(logSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(logAccounts)
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(logged[0].accounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.refund()
+			.accounts(logged[1].accounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Events are numbered from 1, each stored at its number modulo the log's length
+		const eventLogAccount = await program.account.eventLog.fetch(eventLog);
+		expect(eventLogAccount.sequence.toNumber()).to.equal(4);
+		const recorded = eventLogAccount.entries.slice(1, 5).map((entry) => ({
+			sequence: entry.sequence.toNumber(),
+			kind: entry.kind,
+			escrow: entry.escrow.toBase58(),
+			actor: entry.actor.toBase58(),
+		}));
+		expect(recorded).to.deep.equal([
+			{ sequence: 1, kind: 1, escrow: logged[0].accounts.escrow.toBase58(), actor: maker.publicKey.toBase58() },
+			{ sequence: 2, kind: 1, escrow: logged[1].accounts.escrow.toBase58(), actor: maker.publicKey.toBase58() },
+			{ sequence: 3, kind: 2, escrow: logged[0].accounts.escrow.toBase58(), actor: taker.publicKey.toBase58() },
+			{ sequence: 4, kind: 3, escrow: logged[1].accounts.escrow.toBase58(), actor: maker.publicKey.toBase58() },
+		]);
+	});
+
+	it("Fee override: the admin negotiates a fee for an escrow, charged instead of the config's when passed", async () => {
+		const overrideSeed = new BN(randomBytes(8));
+		const overrideEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				overrideSeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const feeOverrideOf = (subject: PublicKey) =>
+			PublicKey.findProgramAddressSync([Buffer.from("fee_override"), subject.toBuffer()], program.programId)[0];
+		const feeOverride = feeOverrideOf(overrideEscrow);
+		const overrideAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: overrideEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, overrideEscrow, true, tokenProgram),
+			feeOverride,
+		};
+
+		await program.methods
+			.make(overrideSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(overrideAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// 10% on this escrow, and on every escrow of the taker as a maker
+		for (const subject of [overrideEscrow, taker.publicKey]) {
+			await program.methods
+				.setFeeOverride(subject, 1000)
+				.accounts({ admin: provider.publicKey, config, feeOverride: feeOverrideOf(subject) })
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+
+		// An override negotiated for someone else can't be applied to this escrow
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...overrideAccounts, feeOverride: feeOverrideOf(taker.publicKey) })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject the fee override of another maker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("FeeOverrideMismatch");
+		}
+
+		const before = await tokenBalances({ makerAtaB, feeVaultAtaB: feeVault });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(overrideAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB, feeVaultAtaB: feeVault });
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(4.5, 1e-9);
+		expect(after.feeVaultAtaB - before.feeVaultAtaB).to.be.closeTo(0.5, 1e-9);
+
+		for (const subject of [overrideEscrow, taker.publicKey]) {
+			await program.methods
+				.removeFeeOverride()
+				.accounts({ admin: provider.publicKey, config, feeOverride: feeOverrideOf(subject) })
+				.rpc()
+				.then(confirm)
+				.then(log);
+			expect(await connection.getAccountInfo(feeOverrideOf(subject))).to.be.null;
+		}
+	});
+
+	it("Batch take: fills several escrows in one instruction, returning surpluses like take", async () => {
+		const escrows = [await makeEscrow(10 * 1e6, 5 * 1e6), await makeEscrow(4 * 1e6, 2 * 1e6), await makeEscrow(6 * 1e6, 3 * 1e6)];
+		// 1 mint_a is sent straight to the vaults of the last two escrows, outside of the program
+		await provider.sendAndConfirm(
+			new Transaction().add(
+				...escrows
+					.slice(1)
+					.map(({ vault }) =>
+						createTransferCheckedInstruction(takerAtaA, mintA.publicKey, vault, taker.publicKey, 1e6, 6, [], tokenProgram)
+					)
+			),
+			[taker]
+		);
+
+		const before = await tokenBalances({ makerAtaA, makerAtaB, takerAtaA, takerAtaB });
+		await program.methods
+			.batchTake()
+			.accounts(accounts)
+			.remainingAccounts([
+				...batchGroup(escrows[0]),
+				...batchGroup(escrows[1]),
+				// The owner's mint_a account is passed for the last escrow only
+				...batchGroup({ ...escrows[2], ownerAtaA: makerAtaA }),
+			])
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const after = await tokenBalances({ makerAtaA, makerAtaB, takerAtaA, takerAtaB });
+		// The surplus goes to the owner when their mint_a account is passed, to the taker otherwise
+		expect(after.makerAtaA - before.makerAtaA).to.be.closeTo(1, 1e-9);
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(21, 1e-9);
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(10, 1e-9);
+		expect(before.takerAtaB - after.takerAtaB).to.be.closeTo(10, 1e-9);
+		for (const { escrow, vault } of escrows) {
+			expect(await provider.connection.getAccountInfo(escrow)).to.equal(null);
+			expect(await provider.connection.getAccountInfo(vault)).to.equal(null);
+		}
+	});
+
+	it("Batch take: rejects escrows sending their rent to different treasuries, and malformed groups", async () => {
+		const [treasuryA, treasuryB] = [Keypair.generate().publicKey, Keypair.generate().publicKey];
+		const escrows = [
+			await makeEscrow(2 * 1e6, 1e6, { treasury: { 0: treasuryA } }),
+			await makeEscrow(2 * 1e6, 1e6, { treasury: { 0: treasuryB } }),
+		];
+		const batchTake = (remainingAccounts: ReturnType<typeof batchGroup>) =>
+			program.methods
+				.batchTake()
+				.accounts({ ...accounts, rentTreasury: treasuryA })
+				.remainingAccounts(remainingAccounts)
+				.signers([taker])
+				.rpc();
+
+		// A single rent_treasury is passed, so the second escrow's rent has nowhere to go
+		try {
+			await batchTake(escrows.flatMap((escrow) => batchGroup(escrow)));
+			expect.fail("batch_take should reject escrows sending their rent to different treasuries");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("RentTreasuryMismatch");
+		}
+		// The whole batch was rolled back, the first escrow included
+		expect(await provider.connection.getAccountInfo(escrows[0].escrow)).to.not.equal(null);
+
+		// Groups are six accounts each, and there must be at least one
+		for (const remainingAccounts of [batchGroup(escrows[0]).slice(0, 5), []]) {
+			try {
+				await batchTake(remainingAccounts);
+				expect.fail("batch_take should reject a malformed batch");
+			} catch (e) {
+				expect(e.error?.errorCode?.code).to.equal("InvalidBatch");
+			}
+		}
+
+		// Each fills once its own treasury is passed
+		await batchTake(batchGroup(escrows[0])).then(confirm).then(log);
+		expect(await provider.connection.getBalance(treasuryA)).to.be.greaterThan(0);
+		await program.methods
+			.batchTake()
+			.accounts({ ...accounts, rentTreasury: treasuryB })
+			.remainingAccounts(batchGroup(escrows[1]))
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Batch fees: batch fills are charged like takes, and refused while the fee is charged in another currency", async () => {
+		const escrows = [await makeEscrow(10 * 1e6, 5 * 1e6), await makeEscrow(4 * 1e6, 2 * 1e6)];
+		const feeOverride = PublicKey.findProgramAddressSync(
+			[Buffer.from("fee_override"), maker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		const batchAccounts = { ...accounts, feeOverride };
+		const setFeeCurrency = (feeCurrency: object) =>
+			program.methods.setFeeCurrency(feeCurrency).accounts({ admin: provider.publicKey, config }).rpc();
+
+		// 10% on every escrow of the maker
+		await program.methods
+			.setFeeOverride(maker.publicKey, 1000)
+			.accounts({ admin: provider.publicKey, config, feeOverride })
+			.rpc()
+			.then(confirm);
+
+		// A batch can't dodge a fee charged in mint_a by paying it in mint_b
+		await setFeeCurrency({ mintA: {} }).then(confirm);
+		try {
+			await program.methods
+				.batchTake()
+				.accounts(batchAccounts)
+				.remainingAccounts(escrows.flatMap((escrow) => batchGroup(escrow)))
+				.signers([taker])
+				.rpc();
+			expect.fail("batch_take should refuse to fill while the fee is charged in mint_a");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("BatchFeeCurrency");
+		}
+		await setFeeCurrency({ mintB: {} }).then(confirm);
+
+		const before = await tokenBalances({ makerAtaB, feeVaultAtaB: feeVault });
+		await program.methods
+			.batchTake()
+			.accounts(batchAccounts)
+			.remainingAccounts(escrows.flatMap((escrow) => batchGroup(escrow)))
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// The maker's override applies to both fills, as it would to takes
+		const after = await tokenBalances({ makerAtaB, feeVaultAtaB: feeVault });
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(6.3, 1e-9);
+		expect(after.feeVaultAtaB - before.feeVaultAtaB).to.be.closeTo(0.7, 1e-9);
+
+		await program.methods
+			.removeFeeOverride()
+			.accounts({ admin: provider.publicKey, config, feeOverride })
+			.rpc()
+			.then(confirm);
+	});
+
+	it("Refund expired: anyone refunds an escrow to its owner once expired, not before", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		const expiry = Math.floor(Date.now() / 1000) + 2;
+		await program.methods
+			.make(seed, null, new BN(3 * 1e6), new BN(1e6), new BN(expiry), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+
+		// The taker, a keeper here, triggers the refund without the maker signing
+		const refundExpired = () =>
+			program.methods
+				.refundExpired()
+				.accounts({ ...accounts, payer: taker.publicKey, escrow, vault })
+				.signers([taker])
+				.rpc();
+		try {
+			await refundExpired();
+			expect.fail("refund_expired should reject an escrow before its expiry");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowNotExpired");
+		}
+
+		await new Promise((resolve) => setTimeout(resolve, Math.max(0, expiry * 1000 - Date.now()) + 2000));
+		const before = await tokenBalances({ makerAtaA, takerAtaA });
+		await refundExpired().then(confirm).then(log);
+		const after = await tokenBalances({ makerAtaA, takerAtaA });
+
+		// The deposit goes back to the owner, the keeper gets nothing from it
+		expect(after.makerAtaA - before.makerAtaA).to.be.closeTo(3, 1e-9);
+		expect(after.takerAtaA).to.equal(before.takerAtaA);
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+		expect(await connection.getAccountInfo(vault)).to.equal(null);
+	});
+
+	it("Disputes: only a party opens a dispute, and only the arbiter resolves it to either side", async () => {
+		// The provider's wallet arbitrates, with the taker designated on both escrows
+		const makeDisputable = async () => {
+			const seed = new BN(randomBytes(8));
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+			await program.methods
+				.make(seed, null, new BN(4 * 1e6), new BN(2 * 1e6), new BN(0), taker.publicKey, provider.publicKey, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+				.signers([maker])
+				.rpc()
+				.then(confirm);
+			return { escrow, vault };
+		};
+		const [toMaker, toTaker] = [await makeDisputable(), await makeDisputable()];
+
+		// Escrows made without an arbiter can't be disputed
+		const undisputable = await makeEscrow(2 * 1e6, 1e6);
+		try {
+			await program.methods
+				.openDispute()
+				.accounts({ signer: maker.publicKey, escrow: undisputable.escrow })
+				.signers([maker])
+				.rpc();
+			expect.fail("open_dispute should reject an escrow without an arbiter");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NoArbiter");
+		}
+		// The arbiter isn't a party to the trade
+		try {
+			await program.methods.openDispute().accounts({ signer: provider.publicKey, escrow: toMaker.escrow }).rpc();
+			expect.fail("open_dispute should reject anyone but the owner and the designated taker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotEscrowParty");
+		}
+		await program.methods
+			.openDispute()
+			.accounts({ signer: maker.publicKey, escrow: toMaker.escrow })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.openDispute()
+			.accounts({ signer: taker.publicKey, escrow: toTaker.escrow })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// Both are `Disputed`
+		for (const { escrow } of [toMaker, toTaker]) {
+			expect((await program.account.escrow.fetch(escrow)).status).to.equal(4);
+		}
+
+		// A disputed escrow can no longer be taken
+		try {
+			await program.methods
+				.take(new BN(4 * 1e6), new BN(2 * 1e6))
+				.accounts({ ...accounts, ...toTaker })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a disputed escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowNotOpen");
+		}
+
+		const resolveAccounts = (recipient: PublicKey, recipientAtaA: PublicKey, { escrow, vault }: typeof toMaker) => ({
+			...accounts,
+			arbiter: provider.publicKey,
+			recipient,
+			recipientAtaA,
+			escrow,
+			vault,
+		});
+		try {
+			await program.methods
+				.resolveToTaker()
+				.accounts({ ...resolveAccounts(taker.publicKey, takerAtaA, toTaker), arbiter: taker.publicKey })
+				.signers([taker])
+				.rpc();
+			expect.fail("resolve_to_taker should reject anyone but the arbiter");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedArbiter");
+		}
+
+		const before = await tokenBalances({ makerAtaA, takerAtaA });
+		await program.methods.resolveToMaker().accounts(resolveAccounts(maker.publicKey, makerAtaA, toMaker)).rpc().then(confirm).then(log);
+		await program.methods.resolveToTaker().accounts(resolveAccounts(taker.publicKey, takerAtaA, toTaker)).rpc().then(confirm).then(log);
+		const after = await tokenBalances({ makerAtaA, takerAtaA });
+
+		expect(after.makerAtaA - before.makerAtaA).to.be.closeTo(4, 1e-9);
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(4, 1e-9);
+		for (const { escrow, vault } of [toMaker, toTaker]) {
+			expect(await connection.getAccountInfo(escrow)).to.equal(null);
+			expect(await connection.getAccountInfo(vault)).to.equal(null);
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...undisputable, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+	});
+
+	it("Vesting: the taker pays upfront and claims the deposit once it has vested", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		const makeVesting = (vesting: { cliff: BN; duration: BN }) =>
+			program.methods
+				.make(seed, null, new BN(6 * 1e6), new BN(3 * 1e6), new BN(0), null, null, null, vesting, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+				.signers([maker])
+				.rpc();
+
+		// The cliff can't come after the whole deposit has vested
+		try {
+			await makeVesting({ cliff: new BN(5), duration: new BN(2) });
+			expect.fail("make should reject a cliff past the vesting duration");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidVesting");
+		}
+		await makeVesting({ cliff: new BN(1), duration: new BN(2) }).then(confirm);
+
+		const before = await tokenBalances({ makerAtaB, takerAtaA });
+		await program.methods
+			.take(new BN(6 * 1e6), new BN(3 * 1e6))
+			.accounts({ ...accounts, escrow, vault })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// The maker is paid in full at the take, while the deposit stays in the vault
+		const taken = await tokenBalances({ makerAtaB, takerAtaA });
+		expect(taken.makerAtaB - before.makerAtaB).to.be.closeTo(3, 1e-9);
+		expect(taken.takerAtaA).to.equal(before.takerAtaA);
+		expect((await program.account.escrow.fetch(escrow)).status).to.equal(5);
+
+		const claimAccounts = { ...accounts, escrow, vault };
+		try {
+			await program.methods
+				.claimVested()
+				.accounts({ ...claimAccounts, taker: maker.publicKey, takerAtaA: makerAtaA })
+				.signers([maker])
+				.rpc();
+			expect.fail("claim_vested should reject anyone but the taker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedTaker");
+		}
+
+		await new Promise((resolve) => setTimeout(resolve, 4000));
+		await program.methods.claimVested().accounts(claimAccounts).signers([taker]).rpc().then(confirm).then(log);
+		const after = await tokenBalances({ takerAtaA });
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(6, 1e-9);
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+		expect(await connection.getAccountInfo(vault)).to.equal(null);
+	});
+
+	it("Pause: the admin halts new escrows and fills, while refunds stay available", async () => {
+		const open = await makeEscrow(2 * 1e6, 1e6);
+		const setPaused = (paused: boolean) => program.methods.setPaused(paused).accounts({ admin: provider.publicKey, config }).rpc();
+		try {
+			await program.methods.setPaused(true).accounts({ admin: maker.publicKey, config }).signers([maker]).rpc();
+			expect.fail("set_paused should reject anyone but the admin");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedAdmin");
+		}
+		await setPaused(true).then(confirm);
+
+		try {
+			await makeEscrow(2 * 1e6, 1e6);
+			expect.fail("make should reject new escrows while paused");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("Paused");
+		}
+		try {
+			await program.methods
+				.take(new BN(2 * 1e6), new BN(1e6))
+				.accounts({ ...accounts, ...open })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject fills while paused");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("Paused");
+		}
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...open, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(open.escrow)).to.equal(null);
+
+		// Escrows are made and filled again once resumed
+		await setPaused(false).then(confirm);
+		const resumed = await makeEscrow(2 * 1e6, 1e6);
+		await program.methods
+			.take(new BN(2 * 1e6), new BN(1e6))
+			.accounts({ ...accounts, ...resumed })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Mint lists: the admin bans mints and restricts new escrows to allowed mints", async () => {
+		const manage = (method: "addMint" | "removeMint", mint: PublicKey, list: object) =>
+			program.methods[method](mint, list).accounts({ admin: provider.publicKey, config }).rpc();
+		const expectNotPermitted = async () => {
+			try {
+				await makeEscrow(2 * 1e6, 1e6);
+				expect.fail("make should reject a mint that isn't permitted");
+			} catch (e) {
+				expect(e.error?.errorCode?.code).to.equal("MintNotPermitted");
+			}
+		};
+
+		try {
+			await program.methods
+				.addMint(mintA.publicKey, { banned: {} })
+				.accounts({ admin: maker.publicKey, config })
+				.signers([maker])
+				.rpc();
+			expect.fail("add_mint should reject anyone but the admin");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedAdmin");
+		}
+
+		// A banned mint_a can't be escrowed, and is only listed once
+		await manage("addMint", mintA.publicKey, { banned: {} }).then(confirm);
+		await expectNotPermitted();
+		try {
+			await manage("addMint", mintA.publicKey, { banned: {} });
+			expect.fail("add_mint should reject a mint already listed");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("MintAlreadyListed");
+		}
+		await manage("removeMint", mintA.publicKey, { banned: {} }).then(confirm);
+		try {
+			await manage("removeMint", mintA.publicKey, { banned: {} });
+			expect.fail("remove_mint should reject a mint that isn't listed");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("MintNotListed");
+		}
+
+		// Once the allowlist has an entry, both mints must be on it
+		await manage("addMint", mintB.publicKey, { allowed: {} }).then(confirm);
+		await expectNotPermitted();
+		await manage("addMint", mintA.publicKey, { allowed: {} }).then(confirm);
+		const allowed = await makeEscrow(2 * 1e6, 1e6);
+		expect((await program.account.config.fetch(config)).allowedMints).to.have.length(2);
+
+		for (const mint of [mintA.publicKey, mintB.publicKey]) {
+			await manage("removeMint", mint, { allowed: {} }).then(confirm);
+		}
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...allowed, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+	});
+
+	it("Top up: the owner adds to an open escrow's deposit at its price, which a take then fills in full", async () => {
+		const topped = await makeEscrow(4 * 1e6, 2 * 1e6);
+		const topUpAccounts = { ...accounts, ...topped };
+		try {
+			await program.methods
+				.topUp(new BN(1e6))
+				.accounts({ ...topUpAccounts, owner: taker.publicKey, ownerAtaA: takerAtaA })
+				.signers([taker])
+				.rpc();
+			expect.fail("top_up should reject anyone but the owner");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotPositionOwner");
+		}
+		try {
+			await program.methods.topUp(new BN(0)).accounts(topUpAccounts).signers([maker]).rpc();
+			expect.fail("top_up should reject an empty top-up");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ZeroDeposit");
+		}
+
+		await program.methods.topUp(new BN(2 * 1e6)).accounts(topUpAccounts).signers([maker]).rpc().then(confirm).then(log);
+		// Still 2 mint_a for each mint_b
+		const escrowAccount = await program.account.escrow.fetch(topped.escrow);
+		expect(escrowAccount.deposit.toNumber()).to.equal(6 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(3 * 1e6);
+		expect((await connection.getTokenAccountBalance(topped.vault)).value.uiAmount).to.equal(6);
+
+		const before = await tokenBalances({ takerAtaA });
+		await program.methods
+			.take(new BN(6 * 1e6), new BN(3 * 1e6))
+			.accounts(topUpAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaA });
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(6, 1e-9);
+	});
+
+	it("Reduce deposit: the owner withdraws part of an open escrow at its price, keeping it open", async () => {
+		const reduced = await makeEscrow(10 * 1e6, 5 * 1e6);
+		const reduceAccounts = { ...accounts, ...reduced };
+		// Withdrawing the whole deposit goes through refund
+		try {
+			await program.methods.reduceDeposit(new BN(10 * 1e6)).accounts(reduceAccounts).signers([maker]).rpc();
+			expect.fail("reduce_deposit should reject withdrawing the whole deposit");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidAmount");
+		}
+		try {
+			await program.methods
+				.reduceDeposit(new BN(1e6))
+				.accounts({ ...reduceAccounts, owner: taker.publicKey, ownerAtaA: takerAtaA })
+				.signers([taker])
+				.rpc();
+			expect.fail("reduce_deposit should reject anyone but the owner");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotPositionOwner");
+		}
+
+		const before = await tokenBalances({ makerAtaA });
+		await program.methods.reduceDeposit(new BN(4 * 1e6)).accounts(reduceAccounts).signers([maker]).rpc().then(confirm).then(log);
+		const after = await tokenBalances({ makerAtaA });
+
+		expect(after.makerAtaA - before.makerAtaA).to.be.closeTo(4, 1e-9);
+		const escrowAccount = await program.account.escrow.fetch(reduced.escrow);
+		expect(escrowAccount.deposit.toNumber()).to.equal(6 * 1e6);
+		expect(escrowAccount.receive.toNumber()).to.equal(3 * 1e6);
+		expect((await connection.getTokenAccountBalance(reduced.vault)).value.uiAmount).to.equal(6);
+
+		await program.methods
+			.take(new BN(6 * 1e6), new BN(3 * 1e6))
+			.accounts(reduceAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Batch make: opens several escrows in one instruction, each taken like any other", async () => {
+		const offers = [
+			{ seed: new BN(randomBytes(8)), deposit: new BN(3 * 1e6), receive: new BN(1e6), expiry: new BN(0) },
+			{ seed: new BN(randomBytes(8)), deposit: new BN(5 * 1e6), receive: new BN(2 * 1e6), expiry: new BN(0) },
+		];
+		const made = offers.map(({ seed }) => {
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			return { escrow, vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram) };
+		});
+		const batchMake = (remainingAccounts: { escrow: PublicKey; vault: PublicKey }[]) =>
+			program.methods
+				.batchMake(offers)
+				.accounts({ ...accounts })
+				.remainingAccounts(
+					remainingAccounts.flatMap(({ escrow, vault }) =>
+						[escrow, vault].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+					)
+				)
+				.signers([maker])
+				.rpc();
+
+		// Every offer needs its pair of accounts, at the addresses make would derive
+		for (const remainingAccounts of [made.slice(0, 1), [made[1], made[0]]]) {
+			try {
+				await batchMake(remainingAccounts);
+				expect.fail("batch_make should reject accounts that don't match the offers");
+			} catch (e) {
+				expect(e.error?.errorCode?.code).to.equal("InvalidBatch");
+			}
+		}
+
+		const before = await tokenBalances({ makerAtaA });
+		await batchMake(made).then(confirm).then(log);
+		const after = await tokenBalances({ makerAtaA });
+		expect(before.makerAtaA - after.makerAtaA).to.be.closeTo(8, 1e-9);
+
+		for (const [i, { escrow, vault }] of made.entries()) {
+			const escrowAccount = await program.account.escrow.fetch(escrow);
+			expect(escrowAccount.deposit.eq(offers[i].deposit)).to.equal(true);
+			expect(escrowAccount.receive.eq(offers[i].receive)).to.equal(true);
+			await program.methods
+				.take(offers[i].deposit, offers[i].receive)
+				.accounts({ ...accounts, escrow, vault })
+				.signers([taker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+		}
+	});
+
+	it("Streaming: the taker claims the deposit as it streams, and cancelling with the owner splits what's left", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		// Streams linearly over an hour, without a cliff
+		await program.methods
+			.make(seed, null, new BN(6 * 1e6), new BN(3 * 1e6), new BN(0), null, null, null, { cliff: new BN(0), duration: new BN(3600) }, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+		const streamAccounts = { ...accounts, escrow, vault };
+		await program.methods
+			.take(new BN(6 * 1e6), new BN(3 * 1e6))
+			.accounts(streamAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const before = await tokenBalances({ makerAtaA, takerAtaA });
+		await new Promise((resolve) => setTimeout(resolve, 2000));
+		await program.methods.claimVested().accounts(streamAccounts).signers([taker]).rpc().then(confirm).then(log);
+		// Only the part streamed so far is claimed
+		const claimed = (await tokenBalances({ takerAtaA })).takerAtaA - before.takerAtaA;
+		expect(claimed).to.be.greaterThan(0);
+		expect(claimed).to.be.lessThan(6);
+		expect((await program.account.escrow.fetch(escrow)).claimed.toNumber()).to.equal(Math.round(claimed * 1e6));
+
+		// Both sides of the agreement must sign its cancellation
+		try {
+			await program.methods
+				.cancelVesting()
+				.accounts({ ...streamAccounts, taker: provider.publicKey, takerAtaA: getAssociatedTokenAddressSync(mintA.publicKey, provider.publicKey, false, tokenProgram) })
+				.signers([maker])
+				.rpc();
+			expect.fail("cancel_vesting should reject anyone but the taker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedTaker");
+		}
+		await program.methods
+			.cancelVesting()
+			.accounts(streamAccounts)
+			.signers([maker, taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The taker keeps what streamed until the cancellation, the owner gets the unstreamed rest back
+		const after = await tokenBalances({ makerAtaA, takerAtaA });
+		const streamed = after.takerAtaA - before.takerAtaA;
+		expect(streamed).to.be.at.least(claimed);
+		expect(streamed + after.makerAtaA - before.makerAtaA).to.be.closeTo(6, 1e-9);
+		expect(after.makerAtaA - before.makerAtaA).to.be.greaterThan(5);
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+		expect(await connection.getAccountInfo(vault)).to.equal(null);
+	});
+
+	it("Expiry: an escrow can't be made already expired, nor taken past its deadline, but is still refunded", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		const makeExpiring = (expiry: number) =>
+			program.methods
+				.make(seed, null, new BN(2 * 1e6), new BN(1e6), new BN(expiry), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+				.signers([maker])
+				.rpc();
+
+		try {
+			await makeExpiring(Math.floor(Date.now() / 1000) - 60);
+			expect.fail("make should reject a deadline in the past");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidExpiry");
+		}
+		const expiry = Math.floor(Date.now() / 1000) + 2;
+		await makeExpiring(expiry).then(confirm);
+		expect((await program.account.escrow.fetch(escrow)).expiry.toNumber()).to.equal(expiry);
+
+		await new Promise((resolve) => setTimeout(resolve, Math.max(0, expiry * 1000 - Date.now()) + 2000));
+		try {
+			await program.methods
+				.take(new BN(2 * 1e6), new BN(1e6))
+				.accounts({ ...accounts, escrow, vault })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject an escrow past its deadline");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowExpired");
+		}
+
+		const before = await tokenBalances({ makerAtaA });
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, escrow, vault, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaA });
+		expect(after.makerAtaA - before.makerAtaA).to.be.closeTo(2, 1e-9);
+	});
+
+	it("Designated taker: only the counterparty the maker named can take a private OTC escrow", async () => {
+		const makeDesignated = async (designated: PublicKey) => {
+			const seed = new BN(randomBytes(8));
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+			await program.methods
+				.make(seed, null, new BN(2 * 1e6), new BN(1e6), new BN(0), designated, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+				.signers([maker])
+				.rpc()
+				.then(confirm);
+			return { escrow, vault };
+		};
+		const take = ({ escrow, vault }: { escrow: PublicKey; vault: PublicKey }) =>
+			program.methods
+				.take(new BN(2 * 1e6), new BN(1e6))
+				.accounts({ ...accounts, escrow, vault })
+				.signers([taker])
+				.rpc();
+
+		// Reserved for the provider's wallet, so the taker can't fill it
+		const elsewhere = await makeDesignated(provider.publicKey);
+		expect((await program.account.escrow.fetch(elsewhere.escrow)).taker.equals(provider.publicKey)).to.equal(true);
+		try {
+			await take(elsewhere);
+			expect.fail("take should reject anyone but the designated taker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedTaker");
+		}
+
+		const designated = await makeDesignated(taker.publicKey);
+		await take(designated).then(confirm).then(log);
+		expect(await connection.getAccountInfo(designated.escrow)).to.equal(null);
+
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...elsewhere, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+	});
+
+	it("Recorded deposit: fills settle against the deposit recorded at make, whatever was donated to the vault", async () => {
+		const donated = await makeEscrow(10 * 1e6, 5 * 1e6);
+		// The taker sends 10 mint_a straight to the vault, doubling its balance
+		await provider.sendAndConfirm(
+			new Transaction().add(
+				createTransferCheckedInstruction(takerAtaA, mintA.publicKey, donated.vault, taker.publicKey, 10 * 1e6, 6, [], tokenProgram)
+			),
+			[taker]
+		);
+		expect((await program.account.escrow.fetch(donated.escrow)).deposit.toNumber()).to.equal(10 * 1e6);
+
+		// Half the recorded deposit still costs half of receive
+		const before = await tokenBalances({ makerAtaA, makerAtaB, takerAtaA });
+		await program.methods
+			.takePartial(new BN(5 * 1e6), new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...accounts, ...donated })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const taken = await tokenBalances({ makerAtaA, makerAtaB, takerAtaA });
+		expect(taken.makerAtaB - before.makerAtaB).to.be.closeTo(2.5, 1e-9);
+		expect(taken.takerAtaA - before.takerAtaA).to.be.closeTo(5, 1e-9);
+		expect((await program.account.escrow.fetch(donated.escrow)).deposit.toNumber()).to.equal(5 * 1e6);
+
+		// Refunds empty the whole vault, the donation included
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...donated, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaA });
+		expect(after.makerAtaA - taken.makerAtaA).to.be.closeTo(15, 1e-9);
+	});
+
+	it("Errors: take and refund fail with an error code naming the cause", async () => {
+		const failing = await makeEscrow(4 * 1e6, 2 * 1e6);
+		const expectCode = async (call: Promise<string>, code: string) => {
+			try {
+				await call;
+				expect.fail(`expected ${code}`);
+			} catch (e) {
+				expect(e.error?.errorCode?.code).to.equal(code);
+			}
+		};
+
+		// A maker that isn't the escrow's
+		await expectCode(
+			program.methods
+				.take(new BN(4 * 1e6), new BN(2 * 1e6))
+				.accounts({ ...accounts, ...failing, maker: provider.publicKey })
+				.signers([taker])
+				.rpc(),
+			"MakerMismatch"
+		);
+		// A partial fill of nothing, or of the whole deposit
+		for (const amount of [0, 4 * 1e6]) {
+			await expectCode(
+				program.methods
+					.takePartial(new BN(amount), new BN(4 * 1e6), new BN(2 * 1e6))
+					.accounts({ ...accounts, ...failing })
+					.signers([taker])
+					.rpc(),
+				"InvalidAmount"
+			);
+		}
+		// A refund signed by someone the owner never delegated to
+		await expectCode(
+			program.methods
+				.refund()
+				.accounts({ ...accounts, ...failing, authority: taker.publicKey })
+				.signers([taker])
+				.rpc(),
+			"UnauthorizedCanceller"
+		);
+
+		// None of them changed the escrow, which is still taken as made
+		await program.methods
+			.take(new BN(4 * 1e6), new BN(2 * 1e6))
+			.accounts({ ...accounts, ...failing })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Events: make, take and refund emit events describing the escrow, read from the transaction logs", async () => {
+		const eventParser = new anchor.EventParser(program.programId, program.coder);
+		const events = async (signature: string) => {
+			const transaction = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+			return [...eventParser.parseLogs(transaction.meta.logMessages)];
+		};
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		const make = (await program.methods
+			.make(seed, null, new BN(4 * 1e6), new BN(2 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(events)).find(({ name }) => name === "makeEvent");
+		expect(make.data.escrow.equals(escrow)).to.equal(true);
+		expect(make.data.maker.equals(maker.publicKey)).to.equal(true);
+		expect(make.data.mintA.equals(mintA.publicKey)).to.equal(true);
+		expect(make.data.mintB.equals(mintB.publicKey)).to.equal(true);
+		expect(make.data.deposit.toNumber()).to.equal(4 * 1e6);
+		expect(make.data.receive.toNumber()).to.equal(2 * 1e6);
+
+		// A partial fill reports what is left, the refund what went back
+		const take = (await program.methods
+			.takePartial(new BN(1e6), new BN(4 * 1e6), new BN(2 * 1e6))
+			.accounts({ ...accounts, escrow, vault })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(events)).find(({ name }) => name === "takeEvent");
+		expect(take.data.taker.equals(taker.publicKey)).to.equal(true);
+		expect(take.data.deposit.toNumber()).to.equal(1e6);
+		expect(take.data.receive.toNumber()).to.equal(0.5 * 1e6);
+		expect(take.data.remaining.toNumber()).to.equal(3 * 1e6);
+
+		const refund = (await program.methods
+			.refund()
+			.accounts({ ...accounts, escrow, vault, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(events)).find(({ name }) => name === "refundEvent");
+		expect(refund.data.escrow.equals(escrow)).to.equal(true);
+		expect(refund.data.amount.toNumber()).to.equal(3 * 1e6);
+		expect(refund.data.timestamp.toNumber()).to.be.greaterThan(0);
+	});
+
+	it("Native SOL: either leg can be SOL, wrapped and unwrapped by the program so neither side handles wSOL", async () => {
+		const [takerWsol, feeVaultWsol] = [taker.publicKey, config].map((owner) =>
+			getAssociatedTokenAddressSync(NATIVE_MINT, owner, true, TOKEN_PROGRAM_ID)
+		);
+		// The take still names the taker's wSOL account, left empty as the deposit is unwrapped, and fees in SOL go to the config's
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					...[
+						{ ata: takerWsol, owner: taker.publicKey },
+						{ ata: feeVaultWsol, owner: config },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, NATIVE_MINT, TOKEN_PROGRAM_ID)
+					)
+				)
+			)
+			.then(log);
+		const escrowOf = (seed: BN, mint: PublicKey, mintProgram: PublicKey) => {
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			return { escrow, vault: getAssociatedTokenAddressSync(mint, escrow, true, mintProgram) };
+		};
+
+		// 0.5 SOL deposited straight from the maker's lamports, for 1 mint_b
+		const solSeed = new BN(randomBytes(8));
+		const solForB = escrowOf(solSeed, NATIVE_MINT, TOKEN_PROGRAM_ID);
+		const solAccounts = { ...accounts, ...solForB, mintA: NATIVE_MINT, makerAtaA: null, tokenProgramA: TOKEN_PROGRAM_ID };
+		await program.methods
+			.make(solSeed, null, new BN(0.5 * LAMPORTS_PER_SOL), new BN(1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...solAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await connection.getTokenAccountBalance(solForB.vault)).value.amount).to.equal(String(0.5 * LAMPORTS_PER_SOL));
+
+		const takerLamports = await connection.getBalance(taker.publicKey);
+		await program.methods
+			.take(new BN(0.5 * LAMPORTS_PER_SOL), new BN(1e6))
+			.accounts({ ...solAccounts, takerAtaA: takerWsol, ownerAtaA: null })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// The vault is unwrapped into the taker's lamports
+		expect((await connection.getBalance(taker.publicKey)) - takerLamports).to.be.at.least(0.5 * LAMPORTS_PER_SOL);
+		expect((await connection.getTokenAccountBalance(takerWsol)).value.amount).to.equal("0");
+
+		// 2 mint_a for 0.25 SOL, paid from the taker's lamports
+		const bSeed = new BN(randomBytes(8));
+		const aForSol = escrowOf(bSeed, mintA.publicKey, tokenProgram);
+		const forSolAccounts = { ...accounts, ...aForSol, mintB: NATIVE_MINT, tokenProgramB: TOKEN_PROGRAM_ID };
+		await program.methods
+			.make(bSeed, null, new BN(2 * 1e6), new BN(0.25 * LAMPORTS_PER_SOL), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...forSolAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const makerLamports = await connection.getBalance(maker.publicKey);
+		const before = await tokenBalances({ takerAtaA });
+		await program.methods
+			.take(new BN(2 * 1e6), new BN(0.25 * LAMPORTS_PER_SOL))
+			.accounts({ ...forSolAccounts, takerAtaB: null, ownerAtaB: null, feeVault: feeVaultWsol })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaA });
+		// The maker is paid in lamports, along with the escrow's rent
+		expect((await connection.getBalance(maker.publicKey)) - makerLamports).to.be.at.least(0.25 * LAMPORTS_PER_SOL);
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(2, 1e-9);
+	});
+
+	it("Transfer fees: deposits and payments are grossed up so the vault and the owner get the agreed amounts", async () => {
+		// A Token-2022 mint charging 1% on every transfer, held by both sides
+		const feeMint = Keypair.generate();
+		const space = getMintLen([ExtensionType.TransferFeeConfig]);
+		const [makerAtaFee, takerAtaFee, feeVaultFee] = [maker.publicKey, taker.publicKey, config].map((owner) =>
+			getAssociatedTokenAddressSync(feeMint.publicKey, owner, true, tokenProgram)
+		);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: feeMint.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializeTransferFeeConfigInstruction(feeMint.publicKey, null, null, 100, BigInt(1e12), tokenProgram),
+					createInitializeMint2Instruction(feeMint.publicKey, 6, provider.publicKey, null, tokenProgram),
+					...[
+						{ ata: makerAtaFee, owner: maker.publicKey },
+						{ ata: takerAtaFee, owner: taker.publicKey },
+						{ ata: feeVaultFee, owner: config },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, feeMint.publicKey, tokenProgram)
+					),
+					...[makerAtaFee, takerAtaFee].map((ata) =>
+						createMintToInstruction(feeMint.publicKey, ata, provider.publicKey, 100 * 1e6, undefined, tokenProgram)
+					)
+				),
+				[feeMint]
+			)
+			.then(log);
+		const escrowOf = (seed: BN, mint: PublicKey) => {
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			return { escrow, vault: getAssociatedTokenAddressSync(mint, escrow, true, tokenProgram) };
+		};
+
+		// Depositing 10 of the fee mint leaves exactly 10 in the vault, the maker bearing the fee
+		const depositSeed = new BN(randomBytes(8));
+		const feeDeposit = escrowOf(depositSeed, feeMint.publicKey);
+		const depositAccounts = {
+			...accounts,
+			...feeDeposit,
+			mintA: feeMint.publicKey,
+			makerAtaA: makerAtaFee,
+			takerAtaA: takerAtaFee,
+			ownerAtaA: makerAtaFee,
+		};
+		const beforeDeposit = await tokenBalances({ makerAtaFee });
+		await program.methods
+			.make(depositSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...depositAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await connection.getTokenAccountBalance(feeDeposit.vault)).value.amount).to.equal(String(10 * 1e6));
+		expect(beforeDeposit.makerAtaFee - (await tokenBalances({ makerAtaFee })).makerAtaFee).to.be.greaterThan(10);
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(depositAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Asking 5 of the fee mint pays the owner exactly 5, the taker bearing the fee
+		const paymentSeed = new BN(randomBytes(8));
+		const feePayment = escrowOf(paymentSeed, mintA.publicKey);
+		const paymentAccounts = {
+			...accounts,
+			...feePayment,
+			mintB: feeMint.publicKey,
+			makerAtaB: makerAtaFee,
+			takerAtaB: takerAtaFee,
+			ownerAtaB: makerAtaFee,
+			feeVault: feeVaultFee,
+		};
+		await program.methods
+			.make(paymentSeed, null, new BN(2 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...paymentAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const before = await tokenBalances({ makerAtaFee, takerAtaFee });
+		await program.methods
+			.take(new BN(2 * 1e6), new BN(5 * 1e6))
+			.accounts(paymentAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaFee, takerAtaFee });
+		expect(after.makerAtaFee - before.makerAtaFee).to.be.closeTo(5, 1e-9);
+		expect(before.takerAtaFee - after.takerAtaFee).to.be.greaterThan(5);
+	});
+
+	it("Transfer hooks: the accounts a hooked mint needs are forwarded to every transfer of it", async () => {
+		// A Token-2022 mint whose every transfer bumps a counter, which the hook requires as an extra account
+		const hookMint = Keypair.generate();
+		const space = getMintLen([ExtensionType.TransferHook]);
+		const [makerAtaHook, takerAtaHook] = [maker.publicKey, taker.publicKey].map((owner) =>
+			getAssociatedTokenAddressSync(hookMint.publicKey, owner, false, tokenProgram)
+		);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: hookMint.publicKey,
+						lamports: await connection.getMinimumBalanceForRentExemption(space),
+						space,
+						programId: tokenProgram,
+					}),
+					createInitializeTransferHookInstruction(hookMint.publicKey, provider.publicKey, hookProgram.programId, tokenProgram),
+					createInitializeMint2Instruction(hookMint.publicKey, 6, provider.publicKey, null, tokenProgram),
+					...[
+						{ ata: makerAtaHook, owner: maker.publicKey },
+						{ ata: takerAtaHook, owner: taker.publicKey },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, hookMint.publicKey, tokenProgram)
+					),
+					createMintToInstruction(hookMint.publicKey, makerAtaHook, provider.publicKey, 10 * 1e6, undefined, tokenProgram)
+				),
+				[hookMint]
+			)
+			.then(log);
+		const [extraAccountMetaList, counter] = ["extra-account-metas", "counter"].map(
+			(prefix) =>
+				PublicKey.findProgramAddressSync([Buffer.from(prefix), hookMint.publicKey.toBuffer()], hookProgram.programId)[0]
+		);
+		await hookProgram.methods
+			.initializeExtraAccountMetaList()
+			.accounts({ payer: provider.publicKey, extraAccountMetaList, mint: hookMint.publicKey, counter })
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const transfers = async () => (await hookProgram.account.counter.fetch(counter)).transfers.toNumber();
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const hookAccounts = {
+			...accounts,
+			escrow,
+			vault: getAssociatedTokenAddressSync(hookMint.publicKey, escrow, true, tokenProgram),
+			mintA: hookMint.publicKey,
+			makerAtaA: makerAtaHook,
+			takerAtaA: takerAtaHook,
+			ownerAtaA: makerAtaHook,
+		};
+		const remainingAccounts = [
+			{ pubkey: extraAccountMetaList, isSigner: false, isWritable: false },
+			{ pubkey: counter, isSigner: false, isWritable: true },
+			{ pubkey: hookProgram.programId, isSigner: false, isWritable: false },
+		];
+		const make = (remainingAccounts: { pubkey: PublicKey; isSigner: boolean; isWritable: boolean }[]) =>
+			program.methods
+				.make(seed, null, new BN(4 * 1e6), new BN(2 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...hookAccounts, payer: maker.publicKey })
+				.remainingAccounts(remainingAccounts)
+				.signers([maker])
+				.rpc();
+
+		// Without the hook's accounts the deposit can't be transferred
+		expect(await make([]).then(() => true, () => false)).to.equal(false);
+		expect(await transfers()).to.equal(0);
+
+		await make(remainingAccounts).then(confirm).then(log);
+		expect(await transfers()).to.equal(1);
+		await program.methods
+			.take(new BN(4 * 1e6), new BN(2 * 1e6))
+			.accounts(hookAccounts)
+			.remainingAccounts(remainingAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// The vault's release to the taker ran the hook too
+		expect(await transfers()).to.equal(2);
+		expect((await connection.getTokenAccountBalance(takerAtaHook)).value.uiAmount).to.equal(4);
+	});
+
+	it("Input validation: make rejects swapping a mint for itself and zero amounts", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const make = (deposit: number, receive: number, mint: PublicKey) =>
+			program.methods
+				.make(seed, null, new BN(deposit), new BN(receive), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({
+					...accounts,
+					escrow,
+					vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+					mintB: mint,
+					payer: maker.publicKey,
+				})
+				.signers([maker])
+				.rpc();
+
+		for (const [deposit, receive, mint, code] of [
+			[1e6, 1e6, mintA.publicKey, "SameMint"],
+			[0, 1e6, mintB.publicKey, "ZeroDeposit"],
+			[1e6, 0, mintB.publicKey, "ZeroReceive"],
+		] as const) {
+			try {
+				await make(deposit, receive, mint);
+				expect.fail(`make should reject with ${code}`);
+			} catch (e) {
+				expect(e.error?.errorCode?.code).to.equal(code);
+			}
+		}
+		// None of them left an escrow behind
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+	});
+
+	it("Hashlock: an HTLC escrow is only taken with its preimage, and only refunded after its timeout", async () => {
+		const preimage = randomBytes(32);
+		const hashlock = [...createHash("sha256").update(preimage).digest()];
+		const makeHashlocked = async (expiry: number) => {
+			const seed = new BN(randomBytes(8));
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+			await program.methods
+				.make(seed, null, new BN(2 * 1e6), new BN(1e6), new BN(expiry), null, null, hashlock, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+				.signers([maker])
+				.rpc()
+				.then(confirm);
+			return { escrow, vault };
+		};
+
+		try {
+			await makeHashlocked(0);
+			expect.fail("make should reject a hashlock without a timeout");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidExpiry");
+		}
+
+		const locked = await makeHashlocked(Math.floor(Date.now() / 1000) + 3600);
+		const lockedAccounts = { ...accounts, ...locked };
+		try {
+			await program.methods.take(new BN(2 * 1e6), new BN(1e6)).accounts(lockedAccounts).signers([taker]).rpc();
+			expect.fail("take should require the preimage of a hashlocked escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("HashlockRequired");
+		}
+		try {
+			await program.methods
+				.takeHashlocked(randomBytes(32), new BN(2 * 1e6), new BN(1e6))
+				.accounts(lockedAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take_hashlocked should reject a wrong preimage");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidPreimage");
+		}
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...lockedAccounts, authority: maker.publicKey })
+				.signers([maker])
+				.rpc();
+			expect.fail("refund should wait for the hashlock's timeout");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("HashlockNotExpired");
+		}
+
+		const before = await tokenBalances({ takerAtaA });
+		const signature = await program.methods
+			.takeHashlocked(preimage, new BN(2 * 1e6), new BN(1e6))
+			.accounts(lockedAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm);
+		const after = await tokenBalances({ takerAtaA });
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(2, 1e-9);
+		// The preimage is revealed for the other leg of the swap
+		const transaction = await connection.getTransaction(signature, { commitment: "confirmed", maxSupportedTransactionVersion: 0 });
+		const events = [...new anchor.EventParser(program.programId, program.coder).parseLogs(transaction.meta.logMessages)];
+		const reveal = events.find((event) => event.name === "revealEvent");
+		expect(Buffer.from(reveal.data.preimage).equals(preimage)).to.equal(true);
+
+		// Once the timeout passes the maker gets an untaken deposit back
+		const expiry = Math.floor(Date.now() / 1000) + 2;
+		const expiring = await makeHashlocked(expiry);
+		await new Promise((resolve) => setTimeout(resolve, Math.max(0, expiry * 1000 - Date.now()) + 2000));
+		const beforeRefund = await tokenBalances({ makerAtaA });
+		await program.methods
+			.refund()
+			.accounts({ ...accounts, ...expiring, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const afterRefund = await tokenBalances({ makerAtaA });
+		expect(afterRefund.makerAtaA - beforeRefund.makerAtaA).to.be.closeTo(2, 1e-9);
+	});
+
+	it("Counter-offers: a taker proposes another price, which only the owner can accept, reserving the escrow for them", async () => {
+		const offered = await makeEscrow(4 * 1e6, 2 * 1e6);
+		const counterOffer = PublicKey.findProgramAddressSync(
+			[Buffer.from("counter"), offered.escrow.toBuffer(), taker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		const propose = (receive: number) =>
+			program.methods
+				.proposeCounter(new BN(receive))
+				.accounts({ taker: taker.publicKey, escrow: offered.escrow, counterOffer })
+				.signers([taker])
+				.rpc()
+				.then(confirm);
+
+		// A withdrawn counter-offer can't be accepted
+		await propose(1e6);
+		expect((await program.account.counterOffer.fetch(counterOffer)).receive.toNumber()).to.equal(1e6);
+		await program.methods
+			.withdrawCounter()
+			.accounts({ taker: taker.publicKey, counterOffer })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(counterOffer)).to.equal(null);
+
+		await propose(1.5 * 1e6);
+		try {
+			await program.methods
+				.acceptCounter()
+				.accounts({ owner: taker.publicKey, taker: taker.publicKey, escrow: offered.escrow, counterOffer })
+				.signers([taker])
+				.rpc();
+			expect.fail("accept_counter should reject anyone but the owner");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotPositionOwner");
+		}
+		await program.methods
+			.acceptCounter()
+			.accounts({ owner: maker.publicKey, taker: taker.publicKey, escrow: offered.escrow, counterOffer })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const escrowAccount = await program.account.escrow.fetch(offered.escrow);
+		expect(escrowAccount.receive.toNumber()).to.equal(1.5 * 1e6);
+		expect(escrowAccount.taker.equals(taker.publicKey)).to.equal(true);
+		expect(await connection.getAccountInfo(counterOffer)).to.equal(null);
+
+		const before = await tokenBalances({ makerAtaB });
+		await program.methods
+			.take(new BN(4 * 1e6), new BN(1.5 * 1e6))
+			.accounts({ ...accounts, ...offered })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB });
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(1.5, 1e-9);
+	});
+
+	it("Reserve: nothing is reserved while paused, a reservation holds the deposit, and a forfeited bond goes to the position's owner", async () => {
+		const bond = new BN(0.01 * LAMPORTS_PER_SOL);
+		const reservationOf = (escrow: PublicKey) =>
+			PublicKey.findProgramAddressSync([Buffer.from("reservation"), escrow.toBuffer()], program.programId)[0];
+		const reserve = (reserveAccounts: object, slots: number) =>
+			program.methods.reserve(new BN(slots), bond).accounts(reserveAccounts).signers([taker]).rpc();
+
+		const held = await makeEscrow(10 * 1e6, 5 * 1e6);
+		const heldAccounts = { ...accounts, ...held, reservation: reservationOf(held.escrow) };
+		const setPaused = (paused: boolean) => program.methods.setPaused(paused).accounts({ admin: provider.publicKey, config }).rpc();
+		await setPaused(true).then(confirm);
+		try {
+			await reserve(heldAccounts, 150);
+			expect.fail("reserve should be rejected while the program is paused");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("Paused");
+		} finally {
+			await setPaused(false).then(confirm);
+		}
+
+		// The reserved taker relies on the deposit staying as it was
+		await reserve(heldAccounts, 150).then(confirm);
+		try {
+			await program.methods.topUp(new BN(1e6)).accounts(heldAccounts).signers([maker]).rpc();
+			expect.fail("top_up should be rejected while a reservation holds the escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowReserved");
+		}
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(heldAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Once the position changes hands, an abandoned reservation's bond goes to its new owner rather than the maker
+		const abandoned = await makeEscrow(10 * 1e6, 5 * 1e6);
+		const abandonedAccounts = { ...accounts, ...abandoned, reservation: reservationOf(abandoned.escrow) };
+		const newOwner = Keypair.generate().publicKey;
+		await program.methods
+			.transferPosition(newOwner)
+			.accounts({ owner: maker.publicKey, escrow: abandoned.escrow })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+		await reserve(abandonedAccounts, 1).then(confirm);
+		await new Promise((resolve) => setTimeout(resolve, 1000));
+		try {
+			await program.methods.forfeitReservation().accounts(abandonedAccounts).rpc();
+			expect.fail("forfeit_reservation should pay the bond to the escrow's current owner only");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotPositionOwner");
+		}
+		await program.methods
+			.forfeitReservation()
+			.accounts({ ...abandonedAccounts, owner: newOwner })
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getBalance(newOwner)).to.equal(bond.toNumber());
+	});
+
+	it("Withdraw fees: the admin sweeps the fees collected in a mint to a treasury token account", async () => {
+		const treasury = Keypair.generate();
+		const treasuryAtaB = getAssociatedTokenAddressSync(mintB.publicKey, treasury.publicKey, false, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					createAssociatedTokenAccountIdempotentInstruction(
+						provider.publicKey,
+						treasuryAtaB,
+						treasury.publicKey,
+						mintB.publicKey,
+						tokenProgram
+					)
+				)
+			)
+			.then(log);
+		const withdrawAccounts = {
+			admin: provider.publicKey,
+			config,
+			mint: mintB.publicKey,
+			feeVault,
+			treasury: treasuryAtaB,
+			tokenProgram,
+		};
+
+		// Only the admin can move the fees out of the config's vault
+		try {
+			await program.methods
+				.withdrawFees()
+				.accounts({ ...withdrawAccounts, admin: taker.publicKey })
+				.signers([taker])
+				.rpc();
+			expect.fail("withdraw_fees should reject anyone but the admin");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("UnauthorizedAdmin");
+		}
+
+		const collected = await connection.getTokenAccountBalance(feeVault);
+		expect(Number(collected.value.amount)).to.be.greaterThan(0);
+		await program.methods.withdrawFees().accounts(withdrawAccounts).rpc().then(confirm).then(log);
+
+		const balances = await tokenBalances({ feeVaultAtaB: feeVault, treasuryAtaB });
+		expect(balances.feeVaultAtaB).to.equal(0);
+		expect(balances.treasuryAtaB).to.equal(Number(collected.value.uiAmount));
+
+		// An empty vault has nothing to withdraw
+		try {
+			await program.methods.withdrawFees().accounts(withdrawAccounts).rpc();
+			expect.fail("withdraw_fees should reject an empty fee vault");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NoFees");
+		}
+	});
+
+	it("Fee currency: the admin charges a flat lamport fee per fill, paid by the taker to a treasury", async () => {
+		const currencySeed = new BN(randomBytes(8));
+		const currencyEscrow = PublicKey.findProgramAddressSync(
+			[
+				Buffer.from("escrow"),
+				maker.publicKey.toBuffer(),
+				currencySeed.toArrayLike(Buffer, "le", 8),
+			],
+			program.programId
+		)[0];
+		const currencyAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: currencyEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, currencyEscrow, true, tokenProgram),
+		};
+		const setFeeCurrency = (feeCurrency: any) =>
+			program.methods.setFeeCurrency(feeCurrency).accounts({ admin: provider.publicKey, config }).rpc();
+
+		// Lamports need somewhere to go
+		try {
+			await setFeeCurrency({ lamports: { lamports: new BN(5000), treasury: PublicKey.default } });
+			expect.fail("set_fee_currency should reject a lamport fee without a treasury");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidFeeCurrency");
+		}
+
+		// The treasury is funded so the fee doesn't have to cover its rent
+		const treasury = Keypair.generate();
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.transfer({
+						fromPubkey: provider.publicKey,
+						toPubkey: treasury.publicKey,
+						lamports: 0.01 * LAMPORTS_PER_SOL,
+					})
+				)
+			)
+			.then(log);
+		await setFeeCurrency({ lamports: { lamports: new BN(5000), treasury: treasury.publicKey } })
+			.then(confirm)
+			.then(log);
+
+		await program.methods
+			.make(currencySeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(currencyAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The fee goes to the treasury set in the config, and nowhere else
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...currencyAccounts, feeTreasury: maker.publicKey })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a lamport fee sent anywhere but the config's treasury");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("FeeTreasuryMismatch");
+		}
+
+		const before = await tokenBalances({ makerAtaB });
+		const treasuryBefore = await connection.getBalance(treasury.publicKey);
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...currencyAccounts, feeTreasury: treasury.publicKey })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// The owner receives the whole price, the fee being paid on top of it
+		const after = await tokenBalances({ makerAtaB });
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(5, 1e-9);
+		expect((await connection.getBalance(treasury.publicKey)) - treasuryBefore).to.equal(5000);
+
+		// Later fills are charged in mint_b again
+		await setFeeCurrency({ mintB: {} }).then(confirm).then(log);
+	});
+
+	it("Fee tiers: takers passing their stats are charged the rate of the volume tier they reached", async () => {
+		const traderStats = PublicKey.findProgramAddressSync(
+			[Buffer.from("trader_stats"), taker.publicKey.toBuffer(), mintB.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		const setFeeTiers = (feeTiers: { minVolume: BN; feeBps: number }[]) =>
+			program.methods.setFeeTiers(feeTiers).accounts({ admin: provider.publicKey, config }).rpc();
+
+		// Tiers must be sorted by volume so the highest one reached is unambiguous
+		try {
+			await setFeeTiers([
+				{ minVolume: new BN(5 * 1e6), feeBps: 500 },
+				{ minVolume: new BN(0), feeBps: 1000 },
+			]);
+			expect.fail("set_fee_tiers should reject unsorted tiers");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidFeeTiers");
+		}
+		await setFeeTiers([
+			{ minVolume: new BN(0), feeBps: 1000 },
+			{ minVolume: new BN(5 * 1e6), feeBps: 500 },
+		])
+			.then(confirm)
+			.then(log);
+
+		// The first fill is charged the entry tier, and its volume moves the taker up to the next one
+		for (const expectedFee of [0.5, 0.25]) {
+			const tierSeed = new BN(randomBytes(8));
+			const tierEscrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), tierSeed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const tierAccounts = {
+				...accounts,
+				payer: maker.publicKey,
+				escrow: tierEscrow,
+				vault: getAssociatedTokenAddressSync(mintA.publicKey, tierEscrow, true, tokenProgram),
+				traderStats,
+			};
+			await program.methods
+				.make(tierSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(tierAccounts)
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+
+			const before = await tokenBalances({ feeVaultAtaB: feeVault });
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts(tierAccounts)
+				.signers([taker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+			const after = await tokenBalances({ feeVaultAtaB: feeVault });
+			expect(after.feeVaultAtaB - before.feeVaultAtaB).to.be.closeTo(expectedFee, 1e-9);
+		}
+
+		const stats = await program.account.traderStats.fetch(traderStats);
+		expect(stats.trader.toBase58()).to.equal(taker.publicKey.toBase58());
+		expect(stats.volume.toNumber()).to.equal(10 * 1e6);
+		expect(stats.fills.toNumber()).to.equal(2);
+
+		// Later fills are charged the config's fee again
+		await setFeeTiers([]).then(confirm).then(log);
+	});
+
+	it("Reserve: a taker bonds a hold on an escrow, returned when they fill it and forfeited to the owner if they don't", async () => {
+		const reserveSeed = new BN(randomBytes(8));
+		const reserveEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), reserveSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const reservation = PublicKey.findProgramAddressSync(
+			[Buffer.from("reservation"), reserveEscrow.toBuffer()],
+			program.programId
+		)[0];
+		const reserveAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: reserveEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, reserveEscrow, true, tokenProgram),
+			reservation,
+		};
+		const bond = new BN(0.01 * LAMPORTS_PER_SOL);
+
+		await program.methods
+			.make(reserveSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(reserveAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// A reservation must cost something
+		try {
+			await program.methods.reserve(new BN(10), new BN(0)).accounts(reserveAccounts).signers([taker]).rpc();
+			expect.fail("reserve should reject a reservation without a bond");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidReservation");
+		}
+
+		// A reservation the taker lets lapse while the escrow is still open forfeits its bond to the owner, here the maker
+		await program.methods.reserve(new BN(1), bond).accounts(reserveAccounts).signers([taker]).rpc().then(confirm).then(log);
+		await new Promise((resolve) => setTimeout(resolve, 1000));
+		const makerBefore = await connection.getBalance(maker.publicKey);
+		await program.methods.forfeitReservation().accounts(reserveAccounts).rpc().then(confirm).then(log);
+		expect((await connection.getBalance(maker.publicKey)) - makerBefore).to.equal(bond.toNumber());
+		expect(await connection.getAccountInfo(reservation)).to.be.null;
+
+		// While reserved, the escrow can only be filled by passing the reservation
+		await program.methods.reserve(new BN(150), bond).accounts(reserveAccounts).signers([taker]).rpc().then(confirm).then(log);
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...reserveAccounts, reservation: null })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a reserved escrow filled without its reservation");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowReserved");
+		}
+		try {
+			await program.methods.forfeitReservation().accounts(reserveAccounts).rpc();
+			expect.fail("forfeit_reservation should reject a reservation that hasn't expired");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ReservationActive");
+		}
+
+		// Filling it settles the reservation, returning the bond with the rent
+		const reservationLamports = await connection.getBalance(reservation);
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(reserveAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(reservationLamports).to.be.greaterThan(bond.toNumber());
+		expect(await connection.getAccountInfo(reservation)).to.be.null;
+		expect(await connection.getAccountInfo(reserveEscrow)).to.be.null;
+	});
+
+	it("Arbiter committee: a dispute is only settled once 2 of the 3 members approve the same side", async () => {
+		const [second, third] = [Keypair.generate(), Keypair.generate()];
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.transfer({
+						fromPubkey: provider.publicKey,
+						toPubkey: second.publicKey,
+						lamports: 0.1 * LAMPORTS_PER_SOL,
+					})
+				)
+			)
+			.then(log);
+		const committeeSeed = new BN(randomBytes(8));
+		const committee = PublicKey.findProgramAddressSync(
+			[Buffer.from("committee"), provider.publicKey.toBuffer(), committeeSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		await program.methods
+			.createCommittee(committeeSeed, 2, [provider.publicKey, second.publicKey, third.publicKey])
+			.accounts({ creator: provider.publicKey, committee })
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const disputeSeed = new BN(randomBytes(8));
+		const disputeEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), disputeSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const disputeVault = getAssociatedTokenAddressSync(mintA.publicKey, disputeEscrow, true, tokenProgram);
+		await program.methods
+			.make(disputeSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), taker.publicKey, committee, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow: disputeEscrow, vault: disputeVault })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.openDispute()
+			.accounts({ signer: taker.publicKey, escrow: disputeEscrow })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const approveAccounts = {
+			...accounts,
+			member: provider.publicKey,
+			recipient: taker.publicKey,
+			recipientAtaA: takerAtaA,
+			committee,
+			resolution: PublicKey.findProgramAddressSync(
+				[Buffer.from("resolution"), disputeEscrow.toBuffer()],
+				program.programId
+			)[0],
+			escrow: disputeEscrow,
+			vault: disputeVault,
+		};
+
+		// Only members vote, and each of them once
+		try {
+			await program.methods
+				.approveResolution(true)
+				.accounts({ ...approveAccounts, member: maker.publicKey })
+				.signers([maker])
+				.rpc();
+			expect.fail("approve_resolution should reject anyone outside the committee");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotCommitteeMember");
+		}
+		const before = await tokenBalances({ takerAtaA });
+		await program.methods.approveResolution(true).accounts(approveAccounts).rpc().then(confirm).then(log);
+		try {
+			await program.methods.approveResolution(true).accounts(approveAccounts).rpc();
+			expect.fail("approve_resolution should reject a second approval from the same member");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AlreadyApproved");
+		}
+		expect(await connection.getAccountInfo(disputeEscrow)).to.not.be.null;
+
+		// The second approval reaches the threshold and releases the vault to the taker
+		await program.methods
+			.approveResolution(true)
+			.accounts({ ...approveAccounts, member: second.publicKey })
+			.signers([second])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaA });
+		expect(after.takerAtaA - before.takerAtaA).to.equal(10);
+		expect(await connection.getAccountInfo(disputeEscrow)).to.be.null;
+		expect(await connection.getAccountInfo(approveAccounts.resolution)).to.be.null;
+	});
+
+	it("Templates: escrows made from a template charge its fee, follow its expiry policy and only fill for its allowlist", async () => {
+		const sha256 = (...parts: Buffer[]) => createHash("sha256").update(Buffer.concat(parts)).digest();
+		const leaves = [taker.publicKey, provider.publicKey].map((key) => sha256(key.toBuffer()));
+		const root = sha256(...[...leaves].sort(Buffer.compare));
+
+		const templateSeed = new BN(randomBytes(8));
+		const template = PublicKey.findProgramAddressSync(
+			[Buffer.from("template"), templateSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		await program.methods
+			.createTemplate(templateSeed, 1000, new BN(3600), [...root], null)
+			.accounts({ admin: provider.publicKey, config, template })
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowSeed = new BN(randomBytes(8));
+		const templateEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), escrowSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const templateAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: templateEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, templateEscrow, true, tokenProgram),
+			template,
+		};
+		const make = (expiry: number) =>
+			program.methods
+				.make(escrowSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(expiry), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(templateAccounts)
+				.signers([maker])
+				.rpc();
+
+		// The template's escrows must expire within an hour of being made
+		try {
+			await make(0);
+			expect.fail("make should reject an escrow outliving its template's expiry policy");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidExpiry");
+		}
+		await make(Math.floor(Date.now() / 1000) + 600).then(confirm).then(log);
+		expect((await program.account.escrow.fetch(templateEscrow)).template.toBase58()).to.equal(template.toBase58());
+
+		// Its terms can't be dodged by leaving the template out, nor filled by a taker off its allowlist
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...templateAccounts, template: null })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a templated escrow filled without its template");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("TemplateMismatch");
+		}
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts(templateAccounts).signers([taker]).rpc();
+			expect.fail("take should reject a taker without an allowlist pass");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotAllowlisted");
+		}
+		const passOf = (key: PublicKey) =>
+			PublicKey.findProgramAddressSync(
+				[Buffer.from("allowlist_pass"), template.toBuffer(), key.toBuffer()],
+				program.programId
+			)[0];
+		try {
+			await program.methods
+				.joinAllowlist([[...leaves[1]]])
+				.accounts({ taker: maker.publicKey, template, allowlistPass: passOf(maker.publicKey) })
+				.signers([maker])
+				.rpc();
+			expect.fail("join_allowlist should reject a taker the proof doesn't place on the allowlist");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotAllowlisted");
+		}
+
+		// Once on the allowlist, the taker fills the escrow at the template's 10% fee
+		await program.methods
+			.joinAllowlist([[...leaves[1]]])
+			.accounts({ taker: taker.publicKey, template, allowlistPass: passOf(taker.publicKey) })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const before = await tokenBalances({ feeVaultAtaB: feeVault });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...templateAccounts, allowlistPass: passOf(taker.publicKey) })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ feeVaultAtaB: feeVault });
+		expect(after.feeVaultAtaB - before.feeVaultAtaB).to.be.closeTo(0.5, 1e-9);
+	});
+
+	it("Token gate: only takers holding enough of the gate mint fill a gated escrow", async () => {
+		const gatedSeed = new BN(randomBytes(8));
+		const gatedEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), gatedSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const gatedAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: gatedEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, gatedEscrow, true, tokenProgram),
+		};
+
+		// Members are recognized by their mint_b holding, which the taker has
+		const gate = { mint: mintB.publicKey, amount: new BN(1e6) };
+		try {
+			await program.methods
+				.make(gatedSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, { ...gate, amount: new BN(0) }, null, 0)
+				.accounts(gatedAccounts)
+				.signers([maker])
+				.rpc();
+			expect.fail("make should reject a gate without an amount");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidGate");
+		}
+		await program.methods
+			.make(gatedSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, gate, null, 0)
+			.accounts(gatedAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts(gatedAccounts).signers([taker]).rpc();
+			expect.fail("take should reject a gated escrow filled without proving the holding");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("GateNotMet");
+		}
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...gatedAccounts, gateAccount: makerAtaB })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a holding the taker doesn't own");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ConstraintTokenOwner");
+		}
+
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...gatedAccounts, gateAccount: takerAtaB })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(gatedEscrow)).to.be.null;
+	});
+
+	it("Gatekeeper network: an escrow requiring an identity pass can't be filled without a gateway token", async () => {
+		const kycSeed = new BN(randomBytes(8));
+		const kycEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), kycSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const kycAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: kycEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, kycEscrow, true, tokenProgram),
+		};
+		const gatekeeperNetwork = Keypair.generate().publicKey;
+		await program.methods
+			.make(kycSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, gatekeeperNetwork, 0)
+			.accounts(kycAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await program.account.escrow.fetch(kycEscrow)).gatekeeperNetwork.toBase58()).to.equal(gatekeeperNetwork.toBase58());
+
+		// The gateway program isn't deployed on the local validator, so only the rejections are exercised
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts(kycAccounts).signers([taker]).rpc();
+			expect.fail("take should reject a taker without a gateway token");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidGatewayToken");
+		}
+		try {
+			await program.methods
+				.take(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...kycAccounts, gatewayToken: takerAtaB })
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject a gateway token the gateway program doesn't own");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AccountOwnedByWrongProgram");
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...kycAccounts, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Private terms: an escrow recording only the hash of its terms is filled by the taker revealing them", async () => {
+		const privateSeed = new BN(randomBytes(8));
+		const privateEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), privateSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const privateAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: privateEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, privateEscrow, true, tokenProgram),
+		};
+		const salt = randomBytes(32);
+		const receive = new BN(5 * 1e6);
+		const termsHash = createHash("sha256")
+			.update(Buffer.concat([mintB.publicKey.toBuffer(), receive.toArrayLike(Buffer, "le", 8), salt]))
+			.digest();
+
+		await program.methods
+			.makePrivate(privateSeed, null, new BN(10 * 1e6), new BN(0), null, [...termsHash])
+			.accounts(privateAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const made = await program.account.escrow.fetch(privateEscrow);
+		expect(made.mintB.toBase58()).to.equal(PublicKey.default.toBase58());
+		expect(made.receive.toNumber()).to.equal(0);
+
+		// Only the plaintext terms fill it
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(0)).accounts(privateAccounts).signers([taker]).rpc();
+			expect.fail("take should reject an escrow whose terms weren't revealed");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("PrivateTerms");
+		}
+		try {
+			await program.methods
+				.takePrivate(new BN(1e6), [...salt], new BN(10 * 1e6))
+				.accounts(privateAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take_private should reject terms that don't match the hash");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("TermsHashMismatch");
+		}
+
+		const before = await tokenBalances({ makerAtaB, takerAtaA });
+		await program.methods
+			.takePrivate(receive, [...salt], new BN(10 * 1e6))
+			.accounts(privateAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB, takerAtaA });
+		expect(after.makerAtaB - before.makerAtaB).to.equal(5);
+		expect(after.takerAtaA - before.takerAtaA).to.equal(10);
+		expect(await connection.getAccountInfo(privateEscrow)).to.be.null;
+	});
+
+	it("Sealed-bid auction: bids are committed while bidding is open, and the highest reveal wins", async () => {
+		const auctionSeed = new BN(randomBytes(8));
+		const auction = PublicKey.findProgramAddressSync(
+			[Buffer.from("auction"), maker.publicKey.toBuffer(), auctionSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const sealedBid = PublicKey.findProgramAddressSync(
+			[Buffer.from("sealed_bid"), auction.toBuffer(), taker.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		const [vaultA, bidVault] = [mintA, mintB].map((m) =>
+			getAssociatedTokenAddressSync(m.publicKey, auction, true, tokenProgram)
+		);
+		const auctionAccounts = {
+			...accounts,
+			auction,
+			vaultA,
+			bidVault,
+			bidder: taker.publicKey,
+			bidderAtaB: takerAtaB,
+			previousBidderAtaB: null,
+			recipient: taker.publicKey,
+			recipientAtaA: takerAtaA,
+		};
+		const end = Math.floor(Date.now() / 1000) + 5;
+		const revealEnd = end + 6;
+
+		await program.methods
+			.makeSealedAuction(auctionSeed, new BN(10 * 1e6), new BN(5 * 1e6), new BN(end), new BN(revealEnd))
+			.accounts(auctionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods.bid(new BN(6 * 1e6)).accounts(auctionAccounts).signers([taker]).rpc();
+			expect.fail("bid should reject a sealed-bid auction");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("SealedAuction");
+		}
+
+		const salt = randomBytes(32);
+		const amount = new BN(7 * 1e6);
+		const hash = createHash("sha256")
+			.update(Buffer.concat([auction.toBuffer(), taker.publicKey.toBuffer(), amount.toArrayLike(Buffer, "le", 8), salt]))
+			.digest();
+		await program.methods
+			.commitBid([...hash], new BN(1e6))
+			.accounts({ ...auctionAccounts, sealedBid })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods
+				.revealBid(amount, [...salt])
+				.accounts({ ...auctionAccounts, sealedBid })
+				.signers([taker])
+				.rpc();
+			expect.fail("reveal_bid should wait for bidding to end");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotRevealing");
+		}
+
+		await new Promise((resolve) => setTimeout(resolve, Math.max(0, end * 1000 - Date.now()) + 2000));
+		await program.methods
+			.revealBid(amount, [...salt])
+			.accounts({ ...auctionAccounts, sealedBid })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const revealed = await program.account.auction.fetch(auction);
+		expect(revealed.bidder.toBase58()).to.equal(taker.publicKey.toBase58());
+		expect(revealed.bid.toNumber()).to.equal(7 * 1e6);
+		expect(await connection.getAccountInfo(sealedBid)).to.be.null;
+
+		await new Promise((resolve) => setTimeout(resolve, Math.max(0, revealEnd * 1000 - Date.now()) + 2000));
+		const before = await tokenBalances({ makerAtaB, takerAtaA });
+		await program.methods
+			.settleAuction()
+			.accounts({ ...auctionAccounts, payer: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB, takerAtaA });
+		expect(after.makerAtaB - before.makerAtaB).to.equal(7);
+		expect(after.takerAtaA - before.takerAtaA).to.equal(10);
+	});
+
+	it("Reserve then settle: a taker holds an escrow while sourcing mint_b, and settles through the reservation", async () => {
+		const fillSeed = new BN(randomBytes(8));
+		const fillEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), fillSeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const reservation = PublicKey.findProgramAddressSync(
+			[Buffer.from("reservation"), fillEscrow.toBuffer()],
+			program.programId
+		)[0];
+		const fillAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: fillEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, fillEscrow, true, tokenProgram),
+			reservation,
+		};
+
+		await program.methods
+			.make(fillSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(fillAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Longer holds need a bigger bond, 0.001 SOL for every 150 slots
+		try {
+			await program.methods.reserveFill(new BN(3000), new BN(1e6)).accounts(fillAccounts).signers([taker]).rpc();
+			expect.fail("reserve_fill should reject a bond too small for the window");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidReservation");
+		}
+		await program.methods
+			.reserveFill(new BN(3000), new BN(20 * 1e6))
+			.accounts(fillAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The maker can't move the terms while the taker sources mint_b
+		try {
+			await program.methods
+				.updateEscrow(new BN(6 * 1e6), new BN(0))
+				.accounts({ owner: maker.publicKey, escrow: fillEscrow })
+				.signers([maker])
+				.rpc();
+			expect.fail("update_escrow should reject a reserved escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowReserved");
+		}
+		try {
+			await program.methods
+				.settleFill(new BN(10 * 1e6), new BN(5 * 1e6))
+				.accounts({ ...fillAccounts, reservation: null })
+				.signers([taker])
+				.rpc();
+			expect.fail("settle_fill should require the reservation");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotReserved");
+		}
+
+		await program.methods
+			.settleFill(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(fillAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(reservation)).to.be.null;
+		expect(await connection.getAccountInfo(fillEscrow)).to.be.null;
+	});
+
+	it("Cancellation penalty: refunding an escrow from under the taker reserving it pays them part of the deposit", async () => {
+		const penaltySeed = new BN(randomBytes(8));
+		const penaltyEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), penaltySeed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const penaltyAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			authority: maker.publicKey,
+			escrow: penaltyEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, penaltyEscrow, true, tokenProgram),
+			reservation: PublicKey.findProgramAddressSync(
+				[Buffer.from("reservation"), penaltyEscrow.toBuffer()],
+				program.programId
+			)[0],
+			keeper: maker.publicKey,
+			autoRefund: PublicKey.findProgramAddressSync(
+				[Buffer.from("auto_refund"), penaltyEscrow.toBuffer()],
+				program.programId
+			)[0],
+		};
+		const setCancelPenalty = (bps: number) =>
+			program.methods.setCancelPenalty(bps).accounts({ admin: provider.publicKey, config }).rpc();
+
+		await setCancelPenalty(1000).then(confirm);
+		await program.methods
+			.make(penaltySeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(penaltyAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.reserve(new BN(150), new BN(0.01 * LAMPORTS_PER_SOL))
+			.accounts(penaltyAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The penalty can't be dodged by leaving the reservation out
+		try {
+			await program.methods
+				.refund()
+				.accounts({ ...penaltyAccounts, reservation: null })
+				.signers([maker])
+				.rpc();
+			expect.fail("refund should require the reservation while a penalty is owed");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("PenaltyOwed");
+		}
+
+		// Nor by scheduling an auto-refund due right away and running it as the keeper
+		await program.methods
+			.scheduleAutoRefund(new BN(1), new BN(0))
+			.accounts(penaltyAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+		try {
+			await program.methods.executeAutoRefund().accounts(penaltyAccounts).signers([maker]).rpc();
+			expect.fail("execute_auto_refund should wait for the reservation to lapse");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EscrowReserved");
+		}
+		await program.methods.cancelAutoRefund().accounts(penaltyAccounts).signers([maker]).rpc().then(confirm);
+
+		const before = await tokenBalances({ makerAtaA, takerAtaA });
+		await program.methods
+			.refund()
+			.accounts({ ...penaltyAccounts, reserverAtaA: takerAtaA })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaA, takerAtaA });
+		expect(after.takerAtaA - before.takerAtaA).to.equal(1);
+		expect(after.makerAtaA - before.makerAtaA).to.equal(9);
+
+		await setCancelPenalty(0).then(confirm);
+	});
+
+	it("Chained escrow: a fill's proceeds fund the escrow chained to it, which opens at its declared price", async () => {
+		const [parentSeed, chainedSeed] = [new BN(randomBytes(8)), new BN(randomBytes(8))];
+		const [parent, chainedEscrow] = [parentSeed, chainedSeed].map(
+			(seed) =>
+				PublicKey.findProgramAddressSync(
+					[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+					program.programId
+				)[0]
+		);
+		const chainedVault = getAssociatedTokenAddressSync(mintB.publicKey, chainedEscrow, true, tokenProgram);
+		const parentAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: parent,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, parent, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(parentSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(parentAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// Sells the 5 mint_b the escrow asks for back into 12 mint_a
+		await program.methods
+			.makeChained(chainedSeed, new BN(12 * 1e6), new BN(0))
+			.accounts({
+				...parentAccounts,
+				chainedMintB: mintA.publicKey,
+				chainedEscrow,
+				chainedVault,
+				chainedTokenProgram: tokenProgram,
+			})
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The proceeds can't be routed to the owner once chained
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts(parentAccounts).signers([taker]).rpc();
+			expect.fail("take should require the chained escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ChainedEscrow");
+		}
+
+		const before = await tokenBalances({ makerAtaB });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...parentAccounts, chainedEscrow, chainedVault })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await tokenBalances({ makerAtaB })).makerAtaB).to.equal(before.makerAtaB);
+
+		const chained = await program.account.escrow.fetch(chainedEscrow);
+		expect(chained.status).to.equal(0);
+		expect(chained.deposit.toNumber()).to.equal(5 * 1e6);
+		expect(chained.receive.toNumber()).to.equal(12 * 1e6);
+		expect(chained.mintA.toBase58()).to.equal(mintB.publicKey.toBase58());
+	});
+
+	it("Chained escrow: once the chained escrow is refunded, a fill unchains the escrow and pays its owner", async () => {
+		const [parentSeed, chainedSeed] = [new BN(randomBytes(8)), new BN(randomBytes(8))];
+		const [parent, chainedEscrow] = [parentSeed, chainedSeed].map(
+			(seed) =>
+				PublicKey.findProgramAddressSync(
+					[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+					program.programId
+				)[0]
+		);
+		const chainedVault = getAssociatedTokenAddressSync(mintB.publicKey, chainedEscrow, true, tokenProgram);
+		const parentAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: parent,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, parent, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(parentSeed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(parentAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.makeChained(chainedSeed, new BN(12 * 1e6), new BN(0))
+			.accounts({
+				...parentAccounts,
+				chainedMintB: mintA.publicKey,
+				chainedEscrow,
+				chainedVault,
+				chainedTokenProgram: tokenProgram,
+			})
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The chained escrow trades the escrow's mint_b, refunded before any fill funded it
+		await program.methods
+			.refund()
+			.accounts({
+				...accounts,
+				authority: maker.publicKey,
+				mintA: mintB.publicKey,
+				ownerAtaA: makerAtaB,
+				escrow: chainedEscrow,
+				vault: chainedVault,
+			})
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(chainedEscrow)).to.be.null;
+
+		const before = await tokenBalances({ makerAtaB });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...parentAccounts, chainedEscrow, chainedVault })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await tokenBalances({ makerAtaB })).makerAtaB).to.be.greaterThan(before.makerAtaB);
+		expect(await connection.getAccountInfo(chainedEscrow)).to.be.null;
+	});
+
+	it("Seed id: an escrow made with a 32-byte seed id lives at the address derived from it, and is taken like any other", async () => {
+		// E.g. the hash of an integrator's own order id
+		const seedId = createHash("sha256").update("order-42").digest();
+		const idEscrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seedId],
+			program.programId
+		)[0];
+		const idAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow: idEscrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, idEscrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(new BN(0), [...seedId], new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(idAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const escrowAccount = await program.account.escrow.fetch(idEscrow);
+		expect(Buffer.from(escrowAccount.seedId).equals(seedId)).to.equal(true);
+
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(idAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(idEscrow)).to.be.null;
+	});
+
+	it("Transfer maker: the new maker takes over the escrow at its address, collecting its split rent and unable to take it", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+
+		// Split rent, which would otherwise still pay the escrow's rent to the rotated-out key
+		await program.methods
+			.make(seed, null, new BN(1e6), new BN(1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts({ ...accounts, payer: maker.publicKey, escrow, vault })
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+
+		// The taker's wallet stands in for the maker's new key
+		await program.methods
+			.transferMaker(taker.publicKey)
+			.accounts({ owner: maker.publicKey, escrow })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const escrowAccount = await program.account.escrow.fetch(escrow);
+		expect(escrowAccount.maker.equals(taker.publicKey)).to.equal(true);
+		expect(escrowAccount.owner.equals(taker.publicKey)).to.equal(true);
+		// The address stays derived from the previous maker
+		expect(escrowAccount.addressMaker.equals(maker.publicKey)).to.equal(true);
+
+		// The previous maker no longer owns it, so can't hand it over again
+		try {
+			await program.methods
+				.transferMaker(maker.publicKey)
+				.accounts({ owner: maker.publicKey, escrow })
+				.signers([maker])
+				.rpc();
+			expect.fail("transfer_maker should reject a previous maker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotPositionOwner");
+		}
+
+		const transferredAccounts = {
+			...accounts,
+			maker: taker.publicKey,
+			owner: taker.publicKey,
+			ownerAtaA: takerAtaA,
+			ownerAtaB: takerAtaB,
+			escrow,
+			vault,
+		};
+
+		// Self-fills are checked against the new maker
+		try {
+			await program.methods
+				.take(new BN(1e6), new BN(1e6))
+				.accounts(transferredAccounts)
+				.signers([taker])
+				.rpc();
+			expect.fail("take should reject the new maker taking their own escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("SelfTake");
+		}
+
+		// Refunding pays the new maker both the escrow's rent and the vault's, none of it to the previous maker
+		const makerLamports = await connection.getBalance(maker.publicKey);
+		const newMakerLamports = await connection.getBalance(taker.publicKey);
+		const rent = (await connection.getBalance(escrow)) + (await connection.getBalance(vault));
+		await program.methods
+			.refund()
+			.accounts({ ...transferredAccounts, authority: taker.publicKey })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		expect(await connection.getBalance(maker.publicKey)).to.equal(makerLamports);
+		expect(await connection.getBalance(taker.publicKey)).to.equal(newMakerLamports + rent);
+		expect(await connection.getAccountInfo(escrow)).to.be.null;
+	});
+
+	it("Stake escrow: the taker pays mint_b for the staker and withdrawer authorities of an escrowed stake account", async () => {
+		const seed = new BN(randomBytes(8));
+		const stakeAccount = Keypair.generate();
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("stake"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const authorities = async () => {
+			const info = await connection.getParsedAccountInfo(stakeAccount.publicKey);
+			const { staker, withdrawer } = (info.value.data as any).parsed.info.meta.authorized;
+			return { staker, withdrawer };
+		};
+
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					StakeProgram.createAccount({
+						fromPubkey: maker.publicKey,
+						stakePubkey: stakeAccount.publicKey,
+						authorized: new Authorized(maker.publicKey, maker.publicKey),
+						lockup: new Lockup(0, 0, PublicKey.default),
+						lamports: LAMPORTS_PER_SOL,
+					})
+				),
+				[maker, stakeAccount]
+			)
+			.then(log);
+
+		const stakeAccounts = {
+			maker: maker.publicKey,
+			taker: taker.publicKey,
+			mintB: mintB.publicKey,
+			takerAtaB,
+			makerAtaB,
+			config,
+			feeVault,
+			escrow,
+			stakeAccount: stakeAccount.publicKey,
+			clock: anchor.web3.SYSVAR_CLOCK_PUBKEY,
+			stakeProgram: StakeProgram.programId,
+			tokenProgramB: tokenProgram,
+		};
+		await program.methods
+			.makeStake(seed, new BN(5 * 1e6), new BN(0))
+			.accounts(stakeAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await authorities()).to.deep.equal({ staker: escrow.toBase58(), withdrawer: escrow.toBase58() });
+
+		const before = await tokenBalances({ makerAtaB });
+		await program.methods
+			.takeStake()
+			.accounts(stakeAccounts)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB });
+
+		const taker58 = taker.publicKey.toBase58();
+		expect(await authorities()).to.deep.equal({ staker: taker58, withdrawer: taker58 });
+		expect(after.makerAtaB).to.be.greaterThan(before.makerAtaB);
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+	});
+
+	// Bubblegum and the programs it stores compressed NFTs with, cloned from mainnet by the test validator
+	const bubblegumProgram = new PublicKey("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+	const compressionProgram = new PublicKey("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+	const logWrapper = new PublicKey("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+	// A depth 3 tree of the maker's compressed NFTs, mirrored off-chain to compute the leaf proofs a DAS indexer would return
+	const treeDepth = 3;
+	const merkleTree = Keypair.generate();
+	const treeConfig = PublicKey.findProgramAddressSync([merkleTree.publicKey.toBuffer()], bubblegumProgram)[0];
+	const leaves: Buffer[] = Array.from({ length: 2 ** treeDepth }, () => Buffer.alloc(32));
+	// Concurrent merkle tree header, then its sequence number, active index and buffer size, then its changelogs
+	const treeHeader = 56 + 24;
+	const changelogSize = 32 * treeDepth + 40;
+	const keccak = (...parts: Buffer[]) => Buffer.from(keccak_256(Buffer.concat(parts)));
+	const u32 = (value: number) => {
+		const buffer = Buffer.alloc(4);
+		buffer.writeUInt32LE(value);
+		return buffer;
+	};
+	const borshString = (text: string) => Buffer.concat([u32(text.length), Buffer.from(text)]);
+	// Bubblegum `MetadataArgs` of the NFTs minted here, without royalties, collection, uses or creators
+	const cnftMetadata = Buffer.concat([
+		borshString("Escrowed cNFT"),
+		borshString("CNFT"),
+		borshString("https://example.com/cnft.json"),
+		Buffer.from([0, 0]), // seller_fee_basis_points
+		Buffer.from([0, 1]), // primary_sale_happened, is_mutable
+		Buffer.from([0]), // edition_nonce: None
+		Buffer.from([1, 0]), // token_standard: Some(NonFungible)
+		Buffer.from([0, 0]), // collection, uses: None
+		Buffer.from([0]), // token_program_version: Original
+		Buffer.alloc(4), // creators: []
+	]);
+	const dataHash = keccak(keccak(cnftMetadata), Buffer.alloc(2));
+	const creatorHash = keccak();
+	const assetId = (nonce: number) =>
+		PublicKey.findProgramAddressSync(
+			[Buffer.from("asset"), merkleTree.publicKey.toBuffer(), new BN(nonce).toArrayLike(Buffer, "le", 8)],
+			bubblegumProgram
+		)[0];
+	// Bubblegum's V1 leaf of the NFT minted with `nonce`, owned and delegated to `owner` as every transfer leaves it
+	const setLeaf = (nonce: number, owner: PublicKey) => {
+		leaves[nonce] = keccak(
+			Buffer.from([1]),
+			assetId(nonce).toBuffer(),
+			owner.toBuffer(),
+			owner.toBuffer(),
+			new BN(nonce).toArrayLike(Buffer, "le", 8),
+			dataHash,
+			creatorHash
+		);
+	};
+	// The `LeafProof` argument of the NFT minted with `nonce`, and its proof nodes as remaining accounts
+	const leafProof = (nonce: number, forgedDataHash?: Buffer) => {
+		let nodes = leaves;
+		const proofNodes = [];
+		for (let index = nonce; nodes.length > 1; index >>= 1) {
+			proofNodes.push({ pubkey: new PublicKey(nodes[index ^ 1]), isSigner: false, isWritable: false });
+			nodes = Array.from({ length: nodes.length / 2 }, (_, i) => keccak(nodes[2 * i], nodes[2 * i + 1]));
+		}
+		const proof = {
+			root: [...nodes[0]],
+			dataHash: [...(forgedDataHash ?? dataHash)],
+			creatorHash: [...creatorHash],
+			nonce: new BN(nonce),
+			index: nonce,
+		};
+		return { proof, proofNodes };
+	};
+	const compressedAccounts = (seed: BN) => ({
+		maker: maker.publicKey,
+		taker: taker.publicKey,
+		mintB: mintB.publicKey,
+		takerAtaB,
+		makerAtaB,
+		config,
+		feeVault,
+		escrow: PublicKey.findProgramAddressSync(
+			[Buffer.from("compressed"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0],
+		treeConfig,
+		merkleTree: merkleTree.publicKey,
+		bubblegumProgram,
+		logWrapper,
+		compressionProgram,
+		tokenProgramB: tokenProgram,
+	});
+
+	it("Compressed NFT: the maker mints two cNFTs into a Bubblegum tree of their own", async () => {
+		// A buffer of 8 changelogs and the rightmost path, the same size as one, without a canopy
+		const space = treeHeader + 8 * changelogSize + changelogSize;
+		// mint_v1 to the maker, nonces counting up from 0 as the tree mints them
+		const mintV1 = () =>
+			new TransactionInstruction({
+				programId: bubblegumProgram,
+				keys: [
+					{ pubkey: treeConfig, isSigner: false, isWritable: true },
+					{ pubkey: maker.publicKey, isSigner: false, isWritable: false },
+					{ pubkey: maker.publicKey, isSigner: false, isWritable: false },
+					{ pubkey: merkleTree.publicKey, isSigner: false, isWritable: true },
+					{ pubkey: maker.publicKey, isSigner: true, isWritable: false },
+					{ pubkey: maker.publicKey, isSigner: true, isWritable: false },
+					{ pubkey: logWrapper, isSigner: false, isWritable: false },
+					{ pubkey: compressionProgram, isSigner: false, isWritable: false },
+					{ pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+				],
+				data: Buffer.concat([Buffer.from([145, 98, 192, 118, 184, 147, 118, 104]), cnftMetadata]),
+			});
+
+		const tx = new Transaction().add(
+			SystemProgram.createAccount({
+				fromPubkey: maker.publicKey,
+				newAccountPubkey: merkleTree.publicKey,
+				lamports: await connection.getMinimumBalanceForRentExemption(space),
+				space,
+				programId: compressionProgram,
+			}),
+			new TransactionInstruction({
+				programId: bubblegumProgram,
+				keys: [
+					{ pubkey: treeConfig, isSigner: false, isWritable: true },
+					{ pubkey: merkleTree.publicKey, isSigner: false, isWritable: true },
+					{ pubkey: maker.publicKey, isSigner: true, isWritable: true },
+					{ pubkey: maker.publicKey, isSigner: true, isWritable: false },
+					{ pubkey: logWrapper, isSigner: false, isWritable: false },
+					{ pubkey: compressionProgram, isSigner: false, isWritable: false },
+					{ pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+				],
+				// create_tree_config(max_depth: 3, max_buffer_size: 8, public: None)
+				data: Buffer.concat([Buffer.from([165, 83, 136, 142, 89, 202, 47, 220]), u32(treeDepth), u32(8), Buffer.from([0])]),
+			}),
+			mintV1(),
+			mintV1()
+		);
+		await provider.sendAndConfirm(tx, [maker, merkleTree]).then(log);
+		[0, 1].forEach((nonce) => setLeaf(nonce, maker.publicKey));
+	});
+
+	it("Compressed NFT: the taker pays mint_b for an escrowed cNFT, which only its current leaf proof releases", async () => {
+		const seed = new BN(randomBytes(8));
+		const compressed = compressedAccounts(seed);
+
+		const made = leafProof(0);
+		await program.methods
+			.makeCompressed(seed, new BN(5 * 1e6), new BN(0), made.proof)
+			.accounts(compressed)
+			.remainingAccounts(made.proofNodes)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		setLeaf(0, compressed.escrow);
+
+		const escrowAccount = await program.account.compressedEscrow.fetch(compressed.escrow);
+		expect(escrowAccount.assetId.toBase58()).to.equal(assetId(0).toBase58());
+		expect(escrowAccount.merkleTree.toBase58()).to.equal(merkleTree.publicKey.toBase58());
+
+		// The proof of another of the maker's cNFTs doesn't point to the escrowed one
+		const other = leafProof(1);
+		try {
+			await program.methods
+				.takeCompressed(other.proof)
+				.accounts(compressed)
+				.remainingAccounts(other.proofNodes)
+				.signers([taker])
+				.rpc();
+			expect.fail("take_compressed should reject the proof of another asset");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("AssetMismatch");
+		}
+
+		// Nor does a proof of the escrowed cNFT with forged metadata, whose leaf Bubblegum can't verify against the tree
+		const forged = leafProof(0, keccak(Buffer.from("forged")));
+		let rejected = false;
+		try {
+			await program.methods
+				.takeCompressed(forged.proof)
+				.accounts(compressed)
+				.remainingAccounts(forged.proofNodes)
+				.signers([taker])
+				.rpc();
+		} catch (e) {
+			rejected = true;
+		}
+		expect(rejected, "take_compressed should reject a forged proof").to.equal(true);
+
+		const before = await tokenBalances({ makerAtaB });
+		const current = leafProof(0);
+		await program.methods
+			.takeCompressed(current.proof)
+			.accounts(compressed)
+			.remainingAccounts(current.proofNodes)
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		setLeaf(0, taker.publicKey);
+		const after = await tokenBalances({ makerAtaB });
+
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(5, 1e-6);
+		expect(await connection.getAccountInfo(compressed.escrow)).to.equal(null);
+	});
+
+	it("Compressed NFT: only the maker can refund an escrowed cNFT, back to the leaf they owned", async () => {
+		const seed = new BN(randomBytes(8));
+		const compressed = compressedAccounts(seed);
+
+		const made = leafProof(1);
+		await program.methods
+			.makeCompressed(seed, new BN(5 * 1e6), new BN(0), made.proof)
+			.accounts(compressed)
+			.remainingAccounts(made.proofNodes)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		setLeaf(1, compressed.escrow);
+
+		const current = leafProof(1);
+		try {
+			await program.methods
+				.refundCompressed(current.proof)
+				.accounts({ ...compressed, maker: taker.publicKey })
+				.remainingAccounts(current.proofNodes)
+				.signers([taker])
+				.rpc();
+			expect.fail("refund_compressed should reject anyone but the maker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("MakerMismatch");
+		}
+
+		await program.methods
+			.refundCompressed(current.proof)
+			.accounts(compressed)
+			.remainingAccounts(current.proofNodes)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		setLeaf(1, maker.publicKey);
+		expect(await connection.getAccountInfo(compressed.escrow)).to.equal(null);
+
+		// Every transfer above landed in the tree as mirrored, the leaves being back with the maker and the taker
+		const tree = (await connection.getAccountInfo(merkleTree.publicKey)).data;
+		const activeIndex = Number(tree.readBigUInt64LE(56 + 8));
+		const root = tree.subarray(treeHeader + activeIndex * changelogSize, treeHeader + activeIndex * changelogSize + 32);
+		expect([...root]).to.deep.equal(leafProof(0).proof.root);
+	});
+
+	// Token Metadata, cloned from mainnet by the test validator, whose NFTs are classic SPL Token mints
+	const tokenMetadataProgram = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+	const metadataAddress = (mint: PublicKey, ...suffix: Buffer[]) =>
+		PublicKey.findProgramAddressSync(
+			[Buffer.from("metadata"), tokenMetadataProgram.toBuffer(), mint.toBuffer(), ...suffix],
+			tokenMetadataProgram
+		)[0];
+	const editionAddress = (mint: PublicKey) => metadataAddress(mint, Buffer.from("edition"));
+	const u64Option = (value: number | null) =>
+		value === null ? Buffer.from([0]) : Buffer.concat([Buffer.from([1]), new BN(value).toArrayLike(Buffer, "le", 8)]);
+
+	// Instructions minting the one token of `mint` to `owner` as an NFT, with its Token Metadata metadata and master edition
+	// The master edition prints up to `maxSupply` editions, `null` if unlimited, and the NFT is placed in an unverified `collection`
+	const nftInstructions = async (
+		owner: PublicKey,
+		mint: PublicKey,
+		{ maxSupply = 0, collection }: { maxSupply?: number | null; collection?: PublicKey } = {}
+	) => {
+		const metadata = metadataAddress(mint);
+		return [
+			SystemProgram.createAccount({
+				fromPubkey: owner,
+				newAccountPubkey: mint,
+				lamports: await getMinimumBalanceForRentExemptMint(connection),
+				space: MINT_SIZE,
+				programId: TOKEN_PROGRAM_ID,
+			}),
+			createInitializeMint2Instruction(mint, 0, owner, owner, TOKEN_PROGRAM_ID),
+			createAssociatedTokenAccountIdempotentInstruction(
+				owner,
+				getAssociatedTokenAddressSync(mint, owner, false, TOKEN_PROGRAM_ID),
+				owner,
+				mint,
+				TOKEN_PROGRAM_ID
+			),
+			createMintToInstruction(
+				mint,
+				getAssociatedTokenAddressSync(mint, owner, false, TOKEN_PROGRAM_ID),
+				owner,
+				1,
+				undefined,
+				TOKEN_PROGRAM_ID
+			),
+			// CreateMetadataAccountV3 without creators or uses, mutable and not itself a sized collection
+			new TransactionInstruction({
+				programId: tokenMetadataProgram,
+				keys: [
+					{ pubkey: metadata, isSigner: false, isWritable: true },
+					{ pubkey: mint, isSigner: false, isWritable: false },
+					{ pubkey: owner, isSigner: true, isWritable: false },
+					{ pubkey: owner, isSigner: true, isWritable: true },
+					{ pubkey: owner, isSigner: true, isWritable: false },
+					{ pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+				],
+				data: Buffer.concat([
+					Buffer.from([33]),
+					borshString("Escrowed NFT"),
+					borshString("NFT"),
+					borshString("https://example.com/nft.json"),
+					Buffer.from([0, 0]), // seller_fee_basis_points
+					Buffer.from([0]), // creators: None
+					collection
+						? Buffer.concat([Buffer.from([1, 0]), collection.toBuffer()]) // collection: Some({ verified: false, key })
+						: Buffer.from([0]),
+					Buffer.from([0]), // uses: None
+					Buffer.from([1]), // is_mutable
+					Buffer.from([0]), // collection_details: None
+				]),
+			}),
+			// CreateMasterEditionV3, which takes over the mint's authorities
+			new TransactionInstruction({
+				programId: tokenMetadataProgram,
+				keys: [
+					{ pubkey: editionAddress(mint), isSigner: false, isWritable: true },
+					{ pubkey: mint, isSigner: false, isWritable: true },
+					{ pubkey: owner, isSigner: true, isWritable: false },
+					{ pubkey: owner, isSigner: true, isWritable: false },
+					{ pubkey: owner, isSigner: true, isWritable: true },
+					{ pubkey: metadata, isSigner: false, isWritable: true },
+					{ pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+					{ pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+				],
+				data: Buffer.concat([Buffer.from([17]), u64Option(maxSupply)]),
+			}),
+		];
+	};
+
+	it("Edition escrow: takers buy prints of an escrowed master edition until its max supply runs out", async () => {
+		const seed = new BN(randomBytes(8));
+		const masterMint = Keypair.generate();
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(...(await nftInstructions(maker.publicKey, masterMint.publicKey, { maxSupply: 1 }))),
+				[maker, masterMint]
+			)
+			.then(log);
+
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("edition"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const editionAccounts = {
+			maker: maker.publicKey,
+			taker: taker.publicKey,
+			mintB: mintB.publicKey,
+			takerAtaB,
+			makerAtaB,
+			config,
+			feeVault,
+			escrow,
+			masterMint: masterMint.publicKey,
+			makerAta: getAssociatedTokenAddressSync(masterMint.publicKey, maker.publicKey, false, TOKEN_PROGRAM_ID),
+			vault: getAssociatedTokenAddressSync(masterMint.publicKey, escrow, true, TOKEN_PROGRAM_ID),
+			masterMetadata: metadataAddress(masterMint.publicKey),
+			masterEdition: editionAddress(masterMint.publicKey),
+			tokenMetadataProgram,
+			tokenProgram: TOKEN_PROGRAM_ID,
+			tokenProgramB: tokenProgram,
+		};
+		await program.methods
+			.makeEdition(seed, new BN(2 * 1e6), new BN(0))
+			.accounts(editionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The taker mints the one token of the print's mint to themselves in the same transaction, `edition` being its number
+		const takeEdition = async (edition: number) => {
+			const newMint = Keypair.generate();
+			const [createMint, initializeMint, createAta, mintTo] = await nftInstructions(taker.publicKey, newMint.publicKey);
+			return program.methods
+				.takeEdition()
+				.accounts({
+					...editionAccounts,
+					newMint: newMint.publicKey,
+					newMetadata: metadataAddress(newMint.publicKey),
+					newEdition: editionAddress(newMint.publicKey),
+					editionMarker: metadataAddress(
+						masterMint.publicKey,
+						Buffer.from("edition"),
+						Buffer.from(String(Math.floor(edition / 248)))
+					),
+				})
+				.preInstructions([createMint, initializeMint, createAta, mintTo])
+				.signers([taker, newMint])
+				.rpc()
+				.then(confirm)
+				.then(log)
+				.then(() => newMint.publicKey);
+		};
+
+		const before = await tokenBalances({ makerAtaB });
+		const print = await takeEdition(1);
+		const after = await tokenBalances({ makerAtaB });
+
+		expect(after.makerAtaB - before.makerAtaB).to.be.closeTo(2, 1e-6);
+		const escrowAccount = await program.account.editionEscrow.fetch(escrow);
+		expect(escrowAccount.printed.toNumber()).to.equal(1);
+		// The print is an edition of Token Metadata, its token left with the taker
+		const printEdition = await connection.getAccountInfo(editionAddress(print));
+		expect(printEdition.owner.toBase58()).to.equal(tokenMetadataProgram.toBase58());
+		const printAta = getAssociatedTokenAddressSync(print, taker.publicKey, false, TOKEN_PROGRAM_ID);
+		expect((await connection.getTokenAccountBalance(printAta)).value.amount).to.equal("1");
+
+		// The master edition's only print is sold, so the escrow can't be taken again
+		try {
+			await takeEdition(2);
+			expect.fail("take_edition should reject prints past the master edition's max supply");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("EditionsSoldOut");
+		}
+
+		// Once refunded, a sold-out master edition can't be escrowed again
+		await program.methods
+			.refundEdition()
+			.accounts(editionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		try {
+			await program.methods
+				.makeEdition(seed, new BN(2 * 1e6), new BN(0))
+				.accounts(editionAccounts)
+				.signers([maker])
+				.rpc();
+			expect.fail("make_edition should reject a master edition with no prints left");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InvalidMasterEdition");
+		}
+	});
+
+	it("Edition escrow: the maker refunds the master edition's token along with the escrow and vault rent", async () => {
+		const seed = new BN(randomBytes(8));
+		const masterMint = Keypair.generate();
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(...(await nftInstructions(maker.publicKey, masterMint.publicKey, { maxSupply: null }))),
+				[maker, masterMint]
+			)
+			.then(log);
+
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("edition"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const makerAta = getAssociatedTokenAddressSync(masterMint.publicKey, maker.publicKey, false, TOKEN_PROGRAM_ID);
+		const vault = getAssociatedTokenAddressSync(masterMint.publicKey, escrow, true, TOKEN_PROGRAM_ID);
+		const editionAccounts = {
+			maker: maker.publicKey,
+			mintB: mintB.publicKey,
+			config,
+			escrow,
+			masterMint: masterMint.publicKey,
+			makerAta,
+			vault,
+			masterEdition: editionAddress(masterMint.publicKey),
+			tokenProgram: TOKEN_PROGRAM_ID,
+		};
+		await program.methods
+			.makeEdition(seed, new BN(2 * 1e6), new BN(0))
+			.accounts(editionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect((await connection.getTokenAccountBalance(vault)).value.amount).to.equal("1");
+
+		// Only the maker refunds, the master edition's token going back to an account they own
+		try {
+			await program.methods
+				.refundEdition()
+				.accounts({ ...editionAccounts, maker: taker.publicKey })
+				.signers([taker])
+				.rpc();
+			expect.fail("refund_edition should reject anyone but the maker");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("ConstraintTokenOwner");
+		}
+
+		await program.methods
+			.refundEdition()
+			.accounts(editionAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		expect((await connection.getTokenAccountBalance(makerAta)).value.amount).to.equal("1");
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+		expect(await connection.getAccountInfo(vault)).to.equal(null);
+	});
+
+	it("Collection offer: the taker pays with an NFT verified in the collection, not with one merely claiming it", async () => {
+		const [collectionMint, member, outsider] = [Keypair.generate(), Keypair.generate(), Keypair.generate()];
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(...(await nftInstructions(maker.publicKey, collectionMint.publicKey))),
+				[maker, collectionMint]
+			)
+			.then(log);
+		// Both NFTs of the taker name the collection, but only the maker, its update authority, verifies the member
+		for (const nft of [member, outsider]) {
+			await provider
+				.sendAndConfirm(
+					new Transaction().add(...(await nftInstructions(taker.publicKey, nft.publicKey, { collection: collectionMint.publicKey }))),
+					[taker, nft]
+				)
+				.then(log);
+		}
+		// VerifyCollection, for collections that aren't sized
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					new TransactionInstruction({
+						programId: tokenMetadataProgram,
+						keys: [
+							{ pubkey: metadataAddress(member.publicKey), isSigner: false, isWritable: true },
+							{ pubkey: maker.publicKey, isSigner: true, isWritable: true },
+							{ pubkey: maker.publicKey, isSigner: true, isWritable: true },
+							{ pubkey: collectionMint.publicKey, isSigner: false, isWritable: false },
+							{ pubkey: metadataAddress(collectionMint.publicKey), isSigner: false, isWritable: false },
+							{ pubkey: editionAddress(collectionMint.publicKey), isSigner: false, isWritable: false },
+						],
+						data: Buffer.from([18]),
+					})
+				),
+				[maker]
+			)
+			.then(log);
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const vault = getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram);
+		// The offer's mint_b is the collection's mint
+		await program.methods
+			.makeCollectionOffer(seed, null, new BN(5 * 1e6), new BN(0))
+			.accounts({
+				...accounts,
+				payer: maker.publicKey,
+				escrow,
+				vault,
+				mintB: collectionMint.publicKey,
+				tokenProgramB: TOKEN_PROGRAM_ID,
+			})
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const takeNft = (nft: PublicKey, nftMetadata = metadataAddress(nft)) =>
+			program.methods
+				.takeNft()
+				.accounts({
+					...accounts,
+					escrow,
+					vault,
+					nftMint: nft,
+					nftMetadata,
+					takerNftAta: getAssociatedTokenAddressSync(nft, taker.publicKey, false, TOKEN_PROGRAM_ID),
+					ownerNftAta: getAssociatedTokenAddressSync(nft, maker.publicKey, false, TOKEN_PROGRAM_ID),
+					tokenProgramB: TOKEN_PROGRAM_ID,
+				})
+				.signers([taker])
+				.rpc();
+
+		// The outsider's collection was never verified
+		try {
+			await takeNft(outsider.publicKey);
+			expect.fail("take_nft should reject an NFT not verified in the collection");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("NotInCollection");
+		}
+		// Nor can it borrow the member's metadata
+		try {
+			await takeNft(outsider.publicKey, metadataAddress(member.publicKey));
+			expect.fail("take_nft should reject metadata of another mint");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("MetadataMismatch");
+		}
+
+		const before = await tokenBalances({ takerAtaA });
+		await takeNft(member.publicKey).then(confirm).then(log);
+		const after = await tokenBalances({ takerAtaA });
+
+		expect(after.takerAtaA - before.takerAtaA).to.be.closeTo(5, 1e-6);
+		const ownerNftAta = getAssociatedTokenAddressSync(member.publicKey, maker.publicKey, false, TOKEN_PROGRAM_ID);
+		expect((await connection.getTokenAccountBalance(ownerNftAta)).value.amount).to.equal("1");
+		expect(await connection.getAccountInfo(escrow)).to.equal(null);
+	});
+
+	it("Payment options: the taker pays in another mint the owner accepts, at that mint's own price", async () => {
+		// A third mint held by the taker, which the maker accepts besides mint_b
+		const mintC = Keypair.generate();
+		const [takerAtaC, makerAtaC, feeVaultC] = [taker.publicKey, maker.publicKey, config].map((owner) =>
+			getAssociatedTokenAddressSync(mintC.publicKey, owner, true, tokenProgram)
+		);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					SystemProgram.createAccount({
+						fromPubkey: provider.publicKey,
+						newAccountPubkey: mintC.publicKey,
+						lamports: await getMinimumBalanceForRentExemptMint(connection),
+						space: MINT_SIZE,
+						programId: tokenProgram,
+					}),
+					createInitializeMint2Instruction(mintC.publicKey, 6, taker.publicKey, null, tokenProgram),
+					...[
+						{ ata: takerAtaC, owner: taker.publicKey },
+						{ ata: makerAtaC, owner: maker.publicKey },
+						{ ata: feeVaultC, owner: config },
+					].map(({ ata, owner }) =>
+						createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, ata, owner, mintC.publicKey, tokenProgram)
+					),
+					createMintToInstruction(mintC.publicKey, takerAtaC, taker.publicKey, 100 * 1e6, undefined, tokenProgram)
+				),
+				[mintC, taker]
+			)
+			.then(log);
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const paymentOptions = PublicKey.findProgramAddressSync(
+			[Buffer.from("payment_options"), escrow.toBuffer()],
+			program.programId
+		)[0];
+		const escrowAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(escrowAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		// 5 of mint_b or 4 of mint_c for the whole deposit
+		await program.methods
+			.setPaymentOptions([{ mint: mintC.publicKey, receive: new BN(4 * 1e6) }])
+			.accounts({ ...escrowAccounts, paymentOptions })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const before = await tokenBalances({ takerAtaC, makerAtaC });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(4 * 1e6))
+			.accounts({
+				...escrowAccounts,
+				mintB: mintC.publicKey,
+				takerAtaB: takerAtaC,
+				ownerAtaB: makerAtaC,
+				feeVault: feeVaultC,
+				paymentOptions,
+			})
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaC, makerAtaC });
+
+		expect(before.takerAtaC - after.takerAtaC).to.equal(4);
+		expect(after.makerAtaC).to.be.greaterThan(before.makerAtaC);
+
+		// The mint_b the escrow was switched away from is accepted in its place
+		const options = await program.account.paymentOptions.fetch(paymentOptions);
+		expect(options.legs.map((leg) => leg.mint.toBase58())).to.deep.equal([mintB.publicKey.toBase58()]);
+		expect(options.legs[0].receive.toNumber()).to.equal(5 * 1e6);
+
+		await program.methods
+			.closePaymentOptions()
+			.accounts({ owner: maker.publicKey, paymentOptions })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		expect(await connection.getAccountInfo(paymentOptions)).to.equal(null);
+	});
+
+	it("Payout: the owner routes the proceeds to a custody token account, which fills must pay into", async () => {
+		// A mint_b token account of another wallet, standing in for the owner's custody wallet
+		const custody = Keypair.generate();
+		const payout = getAssociatedTokenAddressSync(mintB.publicKey, custody.publicKey, false, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, payout, custody.publicKey, mintB.publicKey, tokenProgram)
+				)
+			)
+			.then(log);
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const escrowAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(escrowAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		await program.methods
+			.setPayout()
+			.accounts({ owner: maker.publicKey, escrow, payout })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// The owner's own token account no longer receives the proceeds
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts(escrowAccounts).signers([taker]).rpc();
+			expect.fail("take should pay into the payout account");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("PayoutMismatch");
+		}
+
+		const before = await tokenBalances({ makerAtaB, payoutAta: payout });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...escrowAccounts, ownerAtaB: payout })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ makerAtaB, payoutAta: payout });
+
+		expect(after.makerAtaB).to.equal(before.makerAtaB);
+		expect(after.payoutAta).to.be.greaterThan(before.payoutAta);
+	});
+
+	it("Receiving account: the taker is paid into a mint_a token account of another wallet they designate", async () => {
+		// A mint_a token account of another wallet, standing in for the taker's cold storage
+		const coldStorage = Keypair.generate();
+		const receiver = getAssociatedTokenAddressSync(mintA.publicKey, coldStorage.publicKey, false, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, receiver, coldStorage.publicKey, mintA.publicKey, tokenProgram)
+				)
+			)
+			.then(log);
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const escrowAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+		};
+
+		await program.methods
+			.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(escrowAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const before = await tokenBalances({ takerAtaA, receiverAta: receiver });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...escrowAccounts, takerAtaA: receiver })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaA, receiverAta: receiver });
+
+		expect(after.takerAtaA).to.equal(before.takerAtaA);
+		expect(after.receiverAta - before.receiverAta).to.equal(10);
+	});
+
+	it("Source account: the taker pays from a mint_b token account they are the delegate of", async () => {
+		// A mint_b token account of another wallet, standing in for a custody account the taker is delegated
+		const custody = Keypair.generate();
+		const source = getAssociatedTokenAddressSync(mintB.publicKey, custody.publicKey, false, tokenProgram);
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(
+					createAssociatedTokenAccountIdempotentInstruction(provider.publicKey, source, custody.publicKey, mintB.publicKey, tokenProgram),
+					createMintToInstruction(mintB.publicKey, source, taker.publicKey, 10 * 1e6, undefined, tokenProgram)
+				),
+				[taker]
+			)
+			.then(log);
+
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const escrowAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+		};
+		await program.methods
+			.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(escrowAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		// Until the custody wallet delegates to the taker, its account isn't theirs to pay from
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(5 * 1e6)).accounts({ ...escrowAccounts, takerAtaB: source }).signers([taker]).rpc();
+			expect.fail("take should reject a mint_b account the taker neither owns nor is the delegate of");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("TakerAccountMismatch");
+		}
+
+		await provider
+			.sendAndConfirm(
+				new Transaction().add(createApproveInstruction(source, taker.publicKey, custody.publicKey, 5 * 1e6, [], tokenProgram)),
+				[custody]
+			)
+			.then(log);
+
+		const before = await tokenBalances({ takerAtaB, sourceAta: source });
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts({ ...escrowAccounts, takerAtaB: source })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		const after = await tokenBalances({ takerAtaB, sourceAta: source });
+
+		expect(after.takerAtaB).to.equal(before.takerAtaB);
+		expect(before.sourceAta - after.sourceAta).to.equal(5);
+	});
+
+	it("Take: an underfunded taker is rejected before any transfer", async () => {
+		const seed = new BN(randomBytes(8));
+		const escrow = PublicKey.findProgramAddressSync(
+			[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+			program.programId
+		)[0];
+		const escrowAccounts = {
+			...accounts,
+			payer: maker.publicKey,
+			escrow,
+			vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+		};
+		// Asks for more mint_b than was ever minted to the taker
+		await program.methods
+			.make(seed, null, new BN(10 * 1e6), new BN(1_000_000 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+			.accounts(escrowAccounts)
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		try {
+			await program.methods.take(new BN(10 * 1e6), new BN(1_000_000 * 1e6)).accounts(escrowAccounts).signers([taker]).rpc();
+			expect.fail("take should reject a taker who can't pay the escrow's receive");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("InsufficientTakerFunds");
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...escrowAccounts, authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+	});
+
+	it("Order book: escrows listed at make are delisted by take, batch_take, refund and execute_auto_refund", async () => {
+		// No other test lists its escrows, so the pair's first page only lists this test's
+		const orderBookPage = PublicKey.findProgramAddressSync(
+			[Buffer.from("order_book_page"), mintA.publicKey.toBuffer(), mintB.publicKey.toBuffer(), new BN(0).toArrayLike(Buffer, "le", 4)],
+			program.programId
+		)[0];
+		await program.methods
+			.addOrderBookPage()
+			.accounts({ payer: provider.publicKey, mintA: mintA.publicKey, mintB: mintB.publicKey, orderBookPage })
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const listed = [];
+		for (let i = 0; i < 4; i++) {
+			const seed = new BN(randomBytes(8));
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const escrowAccounts = {
+				...accounts,
+				payer: maker.publicKey,
+				escrow,
+				vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+				orderBookPage,
+				keeper: taker.publicKey,
+				autoRefund: PublicKey.findProgramAddressSync([Buffer.from("auto_refund"), escrow.toBuffer()], program.programId)[0],
+			};
+			await program.methods
+				.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(escrowAccounts)
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+			listed.push(escrowAccounts);
+		}
+		let page = await program.account.orderBookPage.fetch(orderBookPage);
+		expect(page.escrows.map((escrow) => escrow.toBase58())).to.have.members(listed.map(({ escrow }) => escrow.toBase58()));
+
+		// Settling a listed escrow without its page would leave it listed
+		try {
+			await program.methods.refund().accounts({ ...listed[0], authority: maker.publicKey, orderBookPage: null }).signers([maker]).rpc();
+			expect.fail("refund should require the page listing the escrow");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("OrderBookMismatch");
+		}
+
+		await program.methods
+			.refund()
+			.accounts({ ...listed[0], authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		page = await program.account.orderBookPage.fetch(orderBookPage);
+		expect(page.escrows.map((escrow) => escrow.toBase58())).to.have.members(listed.slice(1).map(({ escrow }) => escrow.toBase58()));
+
+		await program.methods
+			.take(new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(listed[1])
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		page = await program.account.orderBookPage.fetch(orderBookPage);
+		expect(page.escrows.map((escrow) => escrow.toBase58())).to.have.members(listed.slice(2).map(({ escrow }) => escrow.toBase58()));
+
+		// Batch fills delist the escrows they fill, and record them in the event log like takes
+		const eventLog = PublicKey.findProgramAddressSync([Buffer.from("event_log")], program.programId)[0];
+		await program.methods
+			.batchTake()
+			.accounts({ ...accounts, orderBookPage, eventLog })
+			.remainingAccounts(batchGroup(listed[2]))
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		page = await program.account.orderBookPage.fetch(orderBookPage);
+		expect(page.escrows.map((escrow) => escrow.toBase58())).to.deep.equal([listed[3].escrow.toBase58()]);
+		let eventLogAccount = await program.account.eventLog.fetch(eventLog);
+		let latest = eventLogAccount.entries[eventLogAccount.sequence.toNumber() % eventLogAccount.entries.length];
+		expect(latest.kind).to.equal(2);
+		expect(latest.escrow.equals(listed[2].escrow)).to.equal(true);
+
+		// As do scheduled refunds, recorded as refunds by their keeper
+		await program.methods
+			.scheduleAutoRefund(new BN(1), new BN(0))
+			.accounts(listed[3])
+			.signers([maker])
+			.rpc()
+			.then(confirm);
+		await program.methods
+			.executeAutoRefund()
+			.accounts({ ...listed[3], eventLog })
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		page = await program.account.orderBookPage.fetch(orderBookPage);
+		expect(page.escrows).to.be.empty;
+		eventLogAccount = await program.account.eventLog.fetch(eventLog);
+		latest = eventLogAccount.entries[eventLogAccount.sequence.toNumber() % eventLogAccount.entries.length];
+		expect(latest.kind).to.equal(3);
+		expect(latest.escrow.equals(listed[3].escrow)).to.equal(true);
+		expect(latest.actor.equals(taker.publicKey)).to.equal(true);
+	});
+
+	it("Pair index: counts open indexed escrows and their deposit through fills and refunds", async () => {
+		// No other test indexes its escrows, so the pair's index only counts this test's
+		const pairIndex = PublicKey.findProgramAddressSync(
+			[Buffer.from("pair_index"), mintA.publicKey.toBuffer(), mintB.publicKey.toBuffer()],
+			program.programId
+		)[0];
+		await program.methods
+			.createPairIndex()
+			.accounts({ payer: provider.publicKey, mintA: mintA.publicKey, mintB: mintB.publicKey, pairIndex })
+			.rpc()
+			.then(confirm)
+			.then(log);
+
+		const indexed = [];
+		for (let i = 0; i < 2; i++) {
+			const seed = new BN(randomBytes(8));
+			const escrow = PublicKey.findProgramAddressSync(
+				[Buffer.from("escrow"), maker.publicKey.toBuffer(), seed.toArrayLike(Buffer, "le", 8)],
+				program.programId
+			)[0];
+			const escrowAccounts = {
+				...accounts,
+				payer: maker.publicKey,
+				escrow,
+				vault: getAssociatedTokenAddressSync(mintA.publicKey, escrow, true, tokenProgram),
+				pairIndex,
+			};
+			await program.methods
+				.make(seed, null, new BN(10 * 1e6), new BN(5 * 1e6), new BN(0), null, null, null, null, null, false, { split: {} }, noLabel, null, null, 0)
+				.accounts(escrowAccounts)
+				.signers([maker])
+				.rpc()
+				.then(confirm)
+				.then(log);
+			indexed.push(escrowAccounts);
+		}
+		let index = await program.account.pairIndex.fetch(pairIndex);
+		expect(index.openEscrows.toNumber()).to.equal(2);
+		expect(index.totalDeposit.toNumber()).to.equal(20 * 1e6);
+
+		// Changing an indexed escrow without its index would leave the index stale
+		try {
+			await program.methods.takePartial(new BN(4 * 1e6), new BN(10 * 1e6), new BN(5 * 1e6)).accounts({ ...indexed[0], pairIndex: null }).signers([taker]).rpc();
+			expect.fail("take_partial should require the index of the escrow's mints");
+		} catch (e) {
+			expect(e.error?.errorCode?.code).to.equal("IndexMismatch");
+		}
+
+		await program.methods
+			.takePartial(new BN(4 * 1e6), new BN(10 * 1e6), new BN(5 * 1e6))
+			.accounts(indexed[0])
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		index = await program.account.pairIndex.fetch(pairIndex);
+		expect(index.openEscrows.toNumber()).to.equal(2);
+		expect(index.totalDeposit.toNumber()).to.equal(16 * 1e6);
+
+		await program.methods
+			.refund()
+			.accounts({ ...indexed[1], authority: maker.publicKey })
+			.signers([maker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		index = await program.account.pairIndex.fetch(pairIndex);
+		expect(index.openEscrows.toNumber()).to.equal(1);
+		expect(index.totalDeposit.toNumber()).to.equal(6 * 1e6);
+
+		await program.methods
+			.take(new BN(6 * 1e6), new BN(3 * 1e6))
+			.accounts(indexed[0])
+			.signers([taker])
+			.rpc()
+			.then(confirm)
+			.then(log);
+		index = await program.account.pairIndex.fetch(pairIndex);
+		expect(index.openEscrows.toNumber()).to.equal(0);
+		expect(index.totalDeposit.toNumber()).to.equal(0);
 	});
 
 	after(async () => {